@@ -27,6 +27,12 @@ pub struct Chip {
     /// [`ChipFamily::flash_algorithms`]: crate::ChipFamily::flash_algorithms
     #[serde(default)]
     pub flash_algorithms: Vec<String>,
+    /// The reset used for the chip, unless a different reset is selected when resetting.
+    #[serde(default)]
+    pub default_reset_type: ResetType,
+    /// The JTAG scan chain of the chip, for chips with more than one TAP.
+    #[serde(default)]
+    pub jtag: Option<Jtag>,
 }
 
 impl Chip {
@@ -44,10 +50,37 @@ impl Chip {
             }],
             memory_map: vec![],
             flash_algorithms: vec![],
+            default_reset_type: ResetType::default(),
+            jtag: None,
         }
     }
 }
 
+/// The JTAG scan chain of a chip.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jtag {
+    /// The TAPs of the scan chain, starting with the one closest to TDO.
+    ///
+    /// The scan chain is detected by the probe if it is not given.
+    #[serde(default)]
+    pub scan_chain: Option<Vec<ScanChainElement>>,
+    /// The index of the TAP used to debug the chip, counted from TDO.
+    ///
+    /// A known debug TAP is selected if it is not given.
+    #[serde(default)]
+    pub tap: Option<usize>,
+}
+
+/// A TAP in a JTAG scan chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanChainElement {
+    /// The name of the TAP, only used to document the chain.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The length of the instruction register of the TAP.
+    pub ir_len: u8,
+}
+
 /// An individual core inside a chip
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Core {
@@ -63,6 +96,47 @@ pub struct Core {
     pub core_access_options: CoreAccessOptions,
 }
 
+/// The way a target is reset.
+///
+/// Not every reset type is supported by every core. Some boards only recover with a hardware reset,
+/// while others lose e.g. their RTT connection with a hardware reset.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetType {
+    /// The reset defined by the debug sequence of the chip. Unless the chip vendor defines a different
+    /// sequence, this is a system reset on Cortex-M cores, a core warm reset on Cortex-A cores
+    /// and a hart reset on RISC-V cores.
+    #[default]
+    Default,
+    /// A reset of the whole system except for the debug logic, using `AIRCR.SYSRESETREQ` on
+    /// Cortex-M cores and `ndmreset` on RISC-V cores.
+    System,
+    /// A reset of the core only, using `AIRCR.VECTRESET`. Only supported by ARMv7-M cores.
+    VectReset,
+    /// A reset of the core only, using `VECTRESET` on ARMv7-M cores, a core warm reset on
+    /// Cortex-A cores and `hartreset` on RISC-V cores.
+    Core,
+    /// A reset using the nRST pin of the debug connector.
+    Hardware,
+}
+
+impl std::str::FromStr for ResetType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_ascii_lowercase()[..] {
+            "default" => Ok(ResetType::Default),
+            "system" | "sysresetreq" => Ok(ResetType::System),
+            "vect_reset" | "vectreset" => Ok(ResetType::VectReset),
+            "core" => Ok(ResetType::Core),
+            "hardware" | "nrst" => Ok(ResetType::Hardware),
+            _ => Err(format!(
+                "'{s}' is not a valid reset type. Can be one of 'default', 'system', 'vect_reset', 'core' or 'hardware'."
+            )),
+        }
+    }
+}
+
 /// The data required to access a core
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CoreAccessOptions {
@@ -77,6 +151,11 @@ pub enum CoreAccessOptions {
 pub struct ArmCoreAccessOptions {
     /// The access port number to access the core
     pub ap: u8,
+    /// The base address of the access port to access the core, for ADIv6 debug ports.
+    ///
+    /// ADIv6 access ports are not numbered, so this is used instead of `ap` if it is set.
+    #[serde(default, serialize_with = "hex_option")]
+    pub ap_address: Option<u64>,
     /// The port select number to access the core
     pub psel: u32,
     /// The base address of the debug registers for the core.
@@ -119,13 +119,6 @@ pub enum BaseaddrFormat {
     ADIv5 = 1,
 }
 
-#[derive(Debug, Primitive, Clone, Copy, PartialEq, Eq, Default)]
-pub enum DebugEntryState {
-    #[default]
-    NotPresent = 0,
-    Present = 1,
-}
-
 define_ap_register!(
     type: MemoryAp,
     /// Base register
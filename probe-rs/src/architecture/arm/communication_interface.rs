@@ -3,7 +3,9 @@ use super::{
         valid_access_ports, AccessPort, ApAccess, ApClass, BaseaddrFormat, GenericAp, MemoryAp,
         BASE, BASE2, CFG, CSW, IDR,
     },
-    dp::{Abort, Ctrl, DebugPortVersion, DpAccess, Select, DPIDR},
+    dp::{
+        adi_v6, Abort, Ctrl, DebugPortVersion, DpAccess, Select, Select1, BASEPTR0, BASEPTR1, DPIDR,
+    },
     memory::{
         adi_v5_memory_interface::{ADIMemoryInterface, ArmProbe},
         Component,
@@ -13,7 +15,7 @@ use super::{
 };
 use crate::{
     architecture::arm::ap::DataSize, CoreStatus, DebugProbe, DebugProbeError,
-    Error as ProbeRsError, Probe,
+    Error as ProbeRsError, Probe, UartAccess,
 };
 use jep106::JEP106Code;
 
@@ -26,7 +28,7 @@ use std::{
 
 /// An error in the communication with an access port or
 /// debug port.
-#[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
+#[derive(Debug, thiserror::Error, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum DapError {
     /// An error occurred during SWD communication.
     #[error("An error occurred in the SWD communication between probe and device.")]
@@ -91,8 +93,26 @@ pub trait ArmProbeInterface: DapAccess + SwdSequence + SwoAccess + Send {
         dp: DpAddress,
     ) -> Result<Option<ArmChipInfo>, ArmError>;
 
+    /// Returns the access port at the given base address of an ADIv6 debug port.
+    ///
+    /// ADIv6 access ports are numbered in the order in which they are listed in the ROM tables
+    /// of the debug port, and this number is used as [`ApAddress::ap`].
+    fn access_port_at_address(
+        &mut self,
+        dp: DpAddress,
+        address: u64,
+    ) -> Result<GenericAp, ArmError> {
+        let _ = dp;
+        Err(ArmError::NoApAtAddress(address))
+    }
+
     /// Closes the interface and returns back the generic probe it consumed.
     fn close(self: Box<Self>) -> Probe;
+
+    /// Returns the interface to the UART of the target, if the probe bridges it.
+    fn uart_interface(&mut self) -> Option<&mut dyn UartAccess> {
+        None
+    }
 }
 
 // TODO: Rename trait!
@@ -159,13 +179,21 @@ impl ArmDebugState for Initialized {}
 
 #[derive(Debug)]
 pub(crate) struct DpState {
-    pub _debug_port_version: DebugPortVersion,
+    pub debug_port_version: DebugPortVersion,
 
     pub current_dpbanksel: u8,
 
     pub current_apsel: u8,
     pub current_apbanksel: u8,
 
+    /// The address selected with SELECT and SELECT1 on a DPv3, without the lowest four bits.
+    ///
+    /// `None` if the selected address is not known.
+    pub current_address: Option<u64>,
+
+    /// The base addresses of the APs of a DPv3, in the order of discovery.
+    pub ap_base_addresses: Vec<u64>,
+
     /// Information about the APs of the target.
     /// APs are identified by a number, starting from zero.
     pub ap_information: Vec<ApInformation>,
@@ -174,13 +202,29 @@ pub(crate) struct DpState {
 impl DpState {
     pub fn new() -> Self {
         Self {
-            _debug_port_version: DebugPortVersion::Unsupported(0xFF),
+            debug_port_version: DebugPortVersion::Unsupported(0xFF),
             current_dpbanksel: 0,
             current_apsel: 0,
             current_apbanksel: 0,
+            current_address: None,
+            ap_base_addresses: Vec::new(),
             ap_information: Vec::new(),
         }
     }
+
+    /// The value of the SELECT register for the cached selection.
+    fn select(&self) -> Select {
+        if self.debug_port_version == DebugPortVersion::DPv3 {
+            let address = self.current_address.unwrap_or(0) as u32;
+            Select(address | u32::from(self.current_dpbanksel))
+        } else {
+            let mut select = Select(0);
+            select.set_ap_sel(self.current_apsel);
+            select.set_ap_bank_sel(self.current_apbanksel);
+            select.set_dp_bank_sel(self.current_dpbanksel);
+            select
+        }
+    }
 }
 
 /// Information about an access port. Can be used for target discovery.
@@ -350,6 +394,24 @@ impl ArmProbeInterface for ArmCommunicationInterface<Initialized> {
         ArmCommunicationInterface::read_chip_info_from_rom_table(self, dp)
     }
 
+    fn access_port_at_address(
+        &mut self,
+        dp: DpAddress,
+        address: u64,
+    ) -> Result<GenericAp, ArmError> {
+        let state = self.select_dp(dp)?;
+
+        let ap = state
+            .ap_base_addresses
+            .iter()
+            .position(|base| *base == address)
+            .ok_or(ArmError::NoApAtAddress(address))?;
+
+        let ap = u8::try_from(ap).map_err(|_| ArmError::TooManyAccessPorts(ap + 1))?;
+
+        Ok(GenericAp::new(ApAddress { dp, ap }))
+    }
+
     fn num_access_ports(&mut self, dp: DpAddress) -> Result<usize, ArmError> {
         ArmCommunicationInterface::num_access_ports(self, dp)
     }
@@ -357,6 +419,10 @@ impl ArmProbeInterface for ArmCommunicationInterface<Initialized> {
     fn close(self: Box<Self>) -> Probe {
         Probe::from_attached_probe(RawDapAccess::into_probe(self.probe))
     }
+
+    fn uart_interface(&mut self) -> Option<&mut dyn UartAccess> {
+        self.probe.get_uart_interface_mut()
+    }
 }
 
 impl<S: ArmDebugState> SwdSequence for ArmCommunicationInterface<S> {
@@ -421,14 +487,6 @@ impl UninitializedArmProbe for ArmCommunicationInterface<Uninitialized> {
     }
 }
 
-impl<S: ArmDebugState> ArmCommunicationInterface<S> {
-    fn _get_debug_port_version(&mut self) -> Result<DebugPortVersion, ArmError> {
-        let dpidr = DPIDR(self.probe.raw_read_register(PortType::DebugPort, 0)?);
-
-        Ok(DebugPortVersion::from(dpidr.version()))
-    }
-}
-
 impl<'interface> ArmCommunicationInterface<Initialized> {
     fn from_uninitialized(
         interface: ArmCommunicationInterface<Uninitialized>,
@@ -509,6 +567,22 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
             ctrl_reg.set_orun_detect(self.state.use_overrun_detect);
             self.write_dp_register(dp, ctrl_reg)?;
 
+            let dpidr: DPIDR = self.read_dp_register(dp)?;
+            let version = DebugPortVersion::from(dpidr.version());
+            tracing::debug!("Debug port version: {}", version);
+            // note(unwrap): we have inserted the state above, it must exist.
+            self.state.dps.get_mut(&dp).unwrap().debug_port_version = version;
+
+            // In ADIv6, the APs have to be found in the ROM tables of the debug port.
+            if version == DebugPortVersion::DPv3 {
+                let ap_base_addresses = self.find_adi_v6_access_ports(dp)?;
+                // The APs are numbered with a u8 in `ApAddress`.
+                if ap_base_addresses.len() > usize::from(u8::MAX) + 1 {
+                    return Err(ArmError::TooManyAccessPorts(ap_base_addresses.len()));
+                }
+                self.state.dps.get_mut(&dp).unwrap().ap_base_addresses = ap_base_addresses;
+            }
+
             /* determine the number and type of available APs */
             tracing::trace!("Searching valid APs");
 
@@ -528,6 +602,63 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
         Ok(self.state.dps.get_mut(&dp).unwrap())
     }
 
+    /// Finds the base addresses of the APs of an ADIv6 debug port, starting from its base pointer.
+    fn find_adi_v6_access_ports(&mut self, dp: DpAddress) -> Result<Vec<u64>, ArmError> {
+        let baseptr0: BASEPTR0 = self.read_dp_register(dp)?;
+        if !baseptr0.valid() {
+            tracing::warn!("The base pointer of the DPv3 is not valid, no APs can be found");
+            return Ok(Vec::new());
+        }
+
+        let baseptr1: BASEPTR1 = self.read_dp_register(dp)?;
+        let base = (u64::from(baseptr1.ptr()) << 32) | u64::from(baseptr0.ptr() << 12);
+        tracing::debug!("Searching APs from base address {:#x}", base);
+
+        adi_v6::find_access_ports(&mut |address| self.read_adi_v6_address(dp, address), base)
+    }
+
+    /// Reads a word from the address space of an ADIv6 debug port.
+    fn read_adi_v6_address(&mut self, dp: DpAddress, address: u64) -> Result<u32, ArmError> {
+        self.select_adi_v6_address(dp, address)?;
+        let value = self
+            .probe
+            .raw_read_register(PortType::AccessPort, (address & 0xC) as u8)?;
+        Ok(value)
+    }
+
+    /// Selects an address in the address space of an ADIv6 debug port, for the next AP access.
+    ///
+    /// Only the bits above the lowest four are selected, the AP access itself selects the word.
+    fn select_adi_v6_address(&mut self, dp: DpAddress, address: u64) -> Result<(), ArmError> {
+        let dp_state = self.select_dp(dp)?;
+
+        let address = address & !0xF;
+        let previous = dp_state.current_address;
+        if previous == Some(address) {
+            return Ok(());
+        }
+
+        tracing::debug!("Changing selected address to {:#x}", address);
+
+        // The cached address is only updated once both registers are written, a failed write
+        // leaves the selection unknown.
+        dp_state.current_address = None;
+
+        if previous.map(|previous| previous >> 32) != Some(address >> 32) {
+            self.write_dp_register(dp, Select1((address >> 32) as u32))?;
+        }
+
+        // note(unwrap): the state was selected above, it must exist.
+        let dp_state = self.state.dps.get_mut(&dp).unwrap();
+        let select = Select((address as u32) | u32::from(dp_state.current_dpbanksel));
+        self.write_dp_register(dp, select)?;
+
+        // note(unwrap): the state was selected above, it must exist.
+        self.state.dps.get_mut(&dp).unwrap().current_address = Some(address);
+
+        Ok(())
+    }
+
     fn select_dp_and_dp_bank(
         &mut self,
         dp: DpAddress,
@@ -537,26 +668,23 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
 
         // DP register addresses are 4 bank bits, 4 address bits. Lowest 2 address bits are
         // always 0, so this leaves only 4 possible addresses: 0x0, 0x4, 0x8, 0xC.
-        // Only address 0x4 is banked, the rest are don't care.
+        // Only address 0x4 is banked, and on a DPv3 also address 0x0. The rest are don't care.
 
         let bank = dp_register_address >> 4;
         let addr = dp_register_address & 0xF;
 
-        if addr != 4 {
+        let banked =
+            addr == 4 || (addr == 0 && dp_state.debug_port_version == DebugPortVersion::DPv3);
+        if !banked {
             return Ok(());
         }
 
         if bank != dp_state.current_dpbanksel {
             dp_state.current_dpbanksel = bank;
 
-            let mut select = Select(0);
-
             tracing::debug!("Changing DP_BANK_SEL to {}", dp_state.current_dpbanksel);
 
-            select.set_ap_sel(dp_state.current_apsel);
-            select.set_ap_bank_sel(dp_state.current_apbanksel);
-            select.set_dp_bank_sel(dp_state.current_dpbanksel);
-
+            let select = dp_state.select();
             self.write_dp_register(dp, select)?;
         }
 
@@ -570,6 +698,19 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
     ) -> Result<(), ArmError> {
         let dp_state = self.select_dp(ap.dp)?;
 
+        // ADIv6 APs are selected by the address of the register.
+        if dp_state.debug_port_version == DebugPortVersion::DPv3 {
+            let base = *dp_state
+                .ap_base_addresses
+                .get(ap.ap as usize)
+                .ok_or(ArmError::ApDoesNotExist(ap))?;
+
+            return self.select_adi_v6_address(
+                ap.dp,
+                adi_v6::ap_register_address(base, ap_register_address),
+            );
+        }
+
         let port = ap.ap;
         let ap_bank = ap_register_address >> 4;
 
@@ -586,18 +727,13 @@ impl<'interface> ArmCommunicationInterface<Initialized> {
         }
 
         if cache_changed {
-            let mut select = Select(0);
-
             tracing::debug!(
                 "Changing AP to {}, AP_BANK_SEL to {}",
                 dp_state.current_apsel,
                 dp_state.current_apbanksel
             );
 
-            select.set_ap_sel(dp_state.current_apsel);
-            select.set_ap_bank_sel(dp_state.current_apbanksel);
-            select.set_dp_bank_sel(dp_state.current_dpbanksel);
-
+            let select = dp_state.select();
             self.write_dp_register(ap.dp, select)?;
         }
 
@@ -638,7 +774,7 @@ impl FlushableArmAccess for ArmCommunicationInterface<Initialized> {
 }
 
 impl SwoAccess for ArmCommunicationInterface<Initialized> {
-    fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError> {
+    fn enable_swo(&mut self, config: &SwoConfig) -> Result<u32, ArmError> {
         match self.probe.get_swo_interface_mut() {
             Some(interface) => interface.enable_swo(config),
             None => Err(ArmError::ArchitectureRequired(&["ARMv7", "ARMv8"])),
@@ -78,6 +78,44 @@ impl<'a> Dwt<'a> {
         function.store_unit(self.component, self.interface, unit)
     }
 
+    /// Enables periodic PC sampling, which emits a PC sample every `interval` cycles.
+    ///
+    /// The interval is rounded to a multiple of 64 or 1024 cycles, of at most 16 times that.
+    ///
+    /// # Returns
+    /// The interval which is actually used.
+    pub fn enable_pc_sampling(&mut self, interval: u32) -> Result<u32, ArmError> {
+        let mut ctrl = Ctrl::load(self.component, self.interface)?;
+        if ctrl.notrcpkt() {
+            return Err(ArmError::Other(anyhow::anyhow!(
+                "The DWT does not support PC sampling"
+            )));
+        }
+
+        // The sample counter is decremented at every 64th or 1024th cycle.
+        let (cyctap, tap) = if interval <= 16 * 64 {
+            (false, 64)
+        } else {
+            (true, 1024)
+        };
+        let reload = (interval / tap).clamp(1, 16);
+
+        ctrl.set_cyctap(cyctap);
+        ctrl.set_postpreset(reload - 1);
+        ctrl.set_cyccntena(true);
+        ctrl.set_pcsamplena(true);
+        ctrl.store(self.component, self.interface)?;
+
+        Ok(reload * tap)
+    }
+
+    /// Disables periodic PC sampling.
+    pub fn disable_pc_sampling(&mut self) -> Result<(), ArmError> {
+        let mut ctrl = Ctrl::load(self.component, self.interface)?;
+        ctrl.set_pcsamplena(false);
+        ctrl.store(self.component, self.interface)
+    }
+
     /// Enable exception tracing.
     pub fn enable_exception_trace(&mut self) -> Result<(), ArmError> {
         let mut ctrl = Ctrl::load(self.component, self.interface)?;
@@ -161,6 +199,7 @@ memory_mapped_bitfield_register! {
 impl DebugComponentInterface for Mask {}
 
 memory_mapped_bitfield_register! {
+    /// The comparator function register, in the layout of ARMv6-M and ARMv7-M.
     pub struct Function(u32);
     0x28, "DWT/FUNCTION",
     impl From;
@@ -179,3 +218,24 @@ memory_mapped_bitfield_register! {
 }
 
 impl DebugComponentInterface for Function {}
+
+memory_mapped_bitfield_register! {
+    /// The comparator function register, in the layout of ARMv8-M.
+    pub struct FunctionV8(u32);
+    0x28, "DWT/FUNCTION",
+    impl From;
+    pub u8, id, _: 31, 27;
+    pub matched, _: 24;
+    /// 00 Byte.
+    /// 01 Halfword.
+    /// 10 Word.
+    pub u8, datavsize, set_datavsize: 11, 10;
+    /// 00 Trigger only.
+    /// 01 Generate a debug event.
+    /// 10 Generate a data trace match packet.
+    /// 11 Generate a data trace data value packet.
+    pub u8, action, set_action: 5, 4;
+    pub u8, match_, set_match: 3, 0;
+}
+
+impl DebugComponentInterface for FunctionV8 {}
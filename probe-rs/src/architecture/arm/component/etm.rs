@@ -0,0 +1,342 @@
+//! Module for using the ETMv4.
+//!
+//! ETM = Embedded Trace Macrocell
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::architecture::arm::{
+    component::DebugComponentInterface, etm::DecoderConfig, memory::CoresightComponent, ArmError,
+    ArmProbeInterface,
+};
+use crate::{memory_mapped_bitfield_register, Error};
+
+const REGISTER_OFFSET_TRCEVENTCTL0R: u32 = 0x020;
+const REGISTER_OFFSET_TRCEVENTCTL1R: u32 = 0x024;
+const REGISTER_OFFSET_TRCSTALLCTLR: u32 = 0x02C;
+const REGISTER_OFFSET_TRCTSCTLR: u32 = 0x030;
+const REGISTER_OFFSET_TRCSYNCPR: u32 = 0x034;
+const REGISTER_OFFSET_TRCCCCTLR: u32 = 0x038;
+const REGISTER_OFFSET_TRCBBCTLR: u32 = 0x03C;
+const REGISTER_OFFSET_TRCTRACEIDR: u32 = 0x040;
+const REGISTER_OFFSET_TRCVICTLR: u32 = 0x080;
+const REGISTER_OFFSET_TRCVIIECTLR: u32 = 0x084;
+const REGISTER_OFFSET_TRCVISSCTLR: u32 = 0x088;
+const REGISTER_OFFSET_TRCOSLAR: u32 = 0x300;
+const REGISTER_OFFSET_TRCACVR: u32 = 0x400;
+const REGISTER_OFFSET_TRCACATR: u32 = 0x480;
+const REGISTER_OFFSET_ACCESS: u32 = 0xFB0;
+
+/// The configuration of the instruction trace generated by an ETM.
+#[derive(Debug, Clone)]
+pub struct EtmConfig {
+    /// The ATB trace ID of the trace stream.
+    trace_id: u8,
+
+    /// The address ranges in which instructions are traced. Everything is traced if this is empty.
+    address_ranges: Vec<Range<u64>>,
+
+    /// The threshold of the cycle counts, if cycle counting is enabled.
+    cycle_count_threshold: Option<u16>,
+
+    /// Whether global timestamps are inserted into the trace.
+    timestamps: bool,
+
+    /// Whether the targets of direct branches are traced as well.
+    branch_broadcast: bool,
+}
+
+impl Default for EtmConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EtmConfig {
+    /// The trace ID used by default, which does not collide with the one of the ITM.
+    pub const DEFAULT_TRACE_ID: u8 = 0x10;
+
+    /// Create a new EtmConfig which traces all instructions.
+    ///
+    /// By default cycle counting, timestamps and branch broadcasting are disabled.
+    pub fn new() -> Self {
+        EtmConfig {
+            trace_id: Self::DEFAULT_TRACE_ID,
+            address_ranges: Vec::new(),
+            cycle_count_threshold: None,
+            timestamps: false,
+            branch_broadcast: false,
+        }
+    }
+
+    /// Set the ATB trace ID of the trace stream.
+    pub fn set_trace_id(mut self, trace_id: u8) -> Self {
+        self.trace_id = trace_id;
+        self
+    }
+
+    /// Only trace instructions in the given address range.
+    ///
+    /// Each range uses a pair of address comparators, so the number of ranges is limited by the ETM.
+    pub fn add_address_range(mut self, range: Range<u64>) -> Self {
+        self.address_ranges.push(range);
+        self
+    }
+
+    /// Enable cycle counting, with cycle counts being emitted after at least `threshold` cycles.
+    pub fn set_cycle_counting(mut self, threshold: Option<u16>) -> Self {
+        self.cycle_count_threshold = threshold;
+        self
+    }
+
+    /// Set whether global timestamps are inserted into the trace.
+    pub fn set_timestamps(mut self, enabled: bool) -> Self {
+        self.timestamps = enabled;
+        self
+    }
+
+    /// Set whether the targets of direct branches are traced as well.
+    ///
+    /// This is only needed if the program image is not available while decoding.
+    pub fn set_branch_broadcast(mut self, enabled: bool) -> Self {
+        self.branch_broadcast = enabled;
+        self
+    }
+
+    /// The ATB trace ID of the trace stream.
+    pub fn trace_id(&self) -> u8 {
+        self.trace_id
+    }
+
+    /// The address ranges in which instructions are traced. Everything is traced if this is empty.
+    pub fn address_ranges(&self) -> &[Range<u64>] {
+        &self.address_ranges
+    }
+
+    /// The threshold of the cycle counts, if cycle counting is enabled.
+    pub fn cycle_count_threshold(&self) -> Option<u16> {
+        self.cycle_count_threshold
+    }
+
+    /// Whether global timestamps are inserted into the trace.
+    pub fn timestamps(&self) -> bool {
+        self.timestamps
+    }
+
+    /// Whether the targets of direct branches are traced as well.
+    pub fn branch_broadcast(&self) -> bool {
+        self.branch_broadcast
+    }
+}
+
+/// An interface to control the ETM (Embedded Trace Macrocell) of a core.
+///
+/// The ETM generates a compressed trace of the executed instructions. It only traces the
+/// outcome of branches and the targets of indirect branches, so the program image is needed to
+/// reconstruct the executed instructions, see [`crate::architecture::arm::etm`].
+///
+/// Only ETMv4, as found in Cortex-M7, Cortex-M33, Cortex-M55 and ARMv8-A cores, is supported.
+pub struct Etm<'a> {
+    component: &'a CoresightComponent,
+    interface: &'a mut dyn ArmProbeInterface,
+}
+
+impl<'a> Etm<'a> {
+    /// Create a new ETM interface from a probe and a ROM table component.
+    pub fn new(
+        interface: &'a mut dyn ArmProbeInterface,
+        component: &'a CoresightComponent,
+    ) -> Self {
+        Etm {
+            interface,
+            component,
+        }
+    }
+
+    /// Unlock the ETM for programming.
+    pub fn unlock(&mut self) -> Result<(), Error> {
+        self.component
+            .write_reg(self.interface, REGISTER_OFFSET_ACCESS, 0xC5AC_CE55)?;
+        self.component
+            .write_reg(self.interface, REGISTER_OFFSET_TRCOSLAR, 0)?;
+
+        Ok(())
+    }
+
+    /// Configure the ETM for instruction trace, and enable it.
+    pub fn enable(&mut self, config: &EtmConfig) -> Result<(), Error> {
+        self.disable()?;
+
+        let idr4 = Trcidr4::load(self.component, self.interface)?;
+        if config.address_ranges.len() > idr4.numacpairs() as usize {
+            return Err(ArmError::Other(anyhow::anyhow!(
+                "The ETM only supports {} address ranges, but {} were requested",
+                idr4.numacpairs(),
+                config.address_ranges.len()
+            ))
+            .into());
+        }
+
+        let mut configr = Trcconfigr(0);
+        configr.set_bb(config.branch_broadcast);
+        configr.set_cci(config.cycle_count_threshold.is_some());
+        configr.set_ts(config.timestamps);
+        configr.store(self.component, self.interface)?;
+
+        self.write(REGISTER_OFFSET_TRCEVENTCTL0R, 0)?;
+        self.write(REGISTER_OFFSET_TRCEVENTCTL1R, 0)?;
+        self.write(REGISTER_OFFSET_TRCSTALLCTLR, 0)?;
+        // Timestamps are requested by the resource selector 1, which is always true.
+        self.write(REGISTER_OFFSET_TRCTSCTLR, u32::from(config.timestamps))?;
+        // Emit a synchronization every 256 bytes, so that a partial trace can be decoded.
+        self.write(REGISTER_OFFSET_TRCSYNCPR, 8)?;
+        self.write(
+            REGISTER_OFFSET_TRCCCCTLR,
+            u32::from(config.cycle_count_threshold.unwrap_or(0)) & 0xFFF,
+        )?;
+        self.write(REGISTER_OFFSET_TRCBBCTLR, 0)?;
+        self.write(
+            REGISTER_OFFSET_TRCTRACEIDR,
+            u32::from(config.trace_id) & 0x7F,
+        )?;
+
+        let mut include = 0;
+        for (pair, range) in config.address_ranges.iter().enumerate() {
+            let comparator = 2 * pair as u32;
+            self.write_comparator(comparator, range.start)?;
+            self.write_comparator(comparator + 1, range.end.saturating_sub(1))?;
+            include |= 1 << pair;
+        }
+        self.write(REGISTER_OFFSET_TRCVIIECTLR, include)?;
+        self.write(REGISTER_OFFSET_TRCVISSCTLR, 0)?;
+        // Trace all instructions selected by the address ranges: the event is the resource
+        // selector 1, which is always true, and the start/stop logic is started.
+        self.write(REGISTER_OFFSET_TRCVICTLR, (1 << 9) | 1)?;
+
+        let mut prgctlr = Trcprgctlr(0);
+        prgctlr.set_en(true);
+        prgctlr.store(self.component, self.interface)?;
+
+        self.wait_for_idle(false)
+    }
+
+    /// Disable the ETM, and wait until all trace is emitted.
+    pub fn disable(&mut self) -> Result<(), Error> {
+        Trcprgctlr(0).store(self.component, self.interface)?;
+
+        self.wait_for_idle(true)
+    }
+
+    /// The ATB trace ID of the trace stream.
+    pub fn trace_id(&mut self) -> Result<u8, Error> {
+        let trace_id = self
+            .component
+            .read_reg(self.interface, REGISTER_OFFSET_TRCTRACEIDR)?;
+
+        Ok(trace_id as u8 & 0x7F)
+    }
+
+    /// The configuration needed to decode the trace of this ETM.
+    pub fn decoder_config(&mut self) -> Result<DecoderConfig, Error> {
+        let idr0 = Trcidr0::load(self.component, self.interface)?;
+        let idr2 = Trcidr2::load(self.component, self.interface)?;
+
+        Ok(DecoderConfig {
+            commit_opt: idr0.commopt(),
+            context_id_size: idr2.cidsize(),
+            vmid_size: idr2.vmidsize(),
+        })
+    }
+
+    fn write(&mut self, offset: u32, value: u32) -> Result<(), Error> {
+        self.component.write_reg(self.interface, offset, value)?;
+        Ok(())
+    }
+
+    fn write_comparator(&mut self, comparator: u32, address: u64) -> Result<(), Error> {
+        let value = REGISTER_OFFSET_TRCACVR + 8 * comparator;
+        self.write(value, address as u32)?;
+        self.write(value + 4, (address >> 32) as u32)?;
+
+        // Compare instruction addresses, in all security states and exception levels.
+        let access_type = REGISTER_OFFSET_TRCACATR + 8 * comparator;
+        self.write(access_type, 0)?;
+        self.write(access_type + 4, 0)
+    }
+
+    fn wait_for_idle(&mut self, idle: bool) -> Result<(), Error> {
+        let start = Instant::now();
+        while Trcstatr::load(self.component, self.interface)?.idle() != idle {
+            if start.elapsed() > Duration::from_millis(100) {
+                return Err(ArmError::Timeout.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+memory_mapped_bitfield_register! {
+    pub struct Trcprgctlr(u32);
+    0x004, "TRCPRGCTLR",
+    impl From;
+
+    pub en, set_en: 0;
+}
+
+impl DebugComponentInterface for Trcprgctlr {}
+
+memory_mapped_bitfield_register! {
+    pub struct Trcstatr(u32);
+    0x00C, "TRCSTATR",
+    impl From;
+
+    pub pmstable, _: 1;
+    pub idle, _: 0;
+}
+
+impl DebugComponentInterface for Trcstatr {}
+
+memory_mapped_bitfield_register! {
+    pub struct Trcconfigr(u32);
+    0x010, "TRCCONFIGR",
+    impl From;
+
+    pub ts, set_ts: 11;
+    pub cci, set_cci: 4;
+    pub bb, set_bb: 3;
+}
+
+impl DebugComponentInterface for Trcconfigr {}
+
+memory_mapped_bitfield_register! {
+    pub struct Trcidr0(u32);
+    0x1E0, "TRCIDR0",
+    impl From;
+
+    pub commopt, _: 29;
+    pub u8, tssize, _: 28, 24;
+}
+
+impl DebugComponentInterface for Trcidr0 {}
+
+memory_mapped_bitfield_register! {
+    pub struct Trcidr2(u32);
+    0x1E8, "TRCIDR2",
+    impl From;
+
+    pub u8, vmidsize, _: 14, 10;
+    pub u8, cidsize, _: 9, 5;
+}
+
+impl DebugComponentInterface for Trcidr2 {}
+
+memory_mapped_bitfield_register! {
+    pub struct Trcidr4(u32);
+    0x1F0, "TRCIDR4",
+    impl From;
+
+    pub u8, numacpairs, _: 3, 0;
+}
+
+impl DebugComponentInterface for Trcidr4 {}
@@ -1,7 +1,9 @@
 //! Types and functions for interacting with CoreSight Components
 
-mod dwt;
+pub(crate) mod dwt;
+mod etm;
 mod itm;
+mod mtb;
 mod scs;
 mod swo;
 mod tmc;
@@ -9,6 +11,7 @@ mod tpiu;
 mod trace_funnel;
 
 use super::ap::{GenericAp, MemoryAp};
+use super::etm::DecoderConfig;
 use super::memory::romtable::{CoresightComponent, PeripheralType, RomTableError};
 use super::memory::Component;
 use super::ArmError;
@@ -18,10 +21,13 @@ use crate::architecture::arm::{ArmProbeInterface, SwoConfig, SwoMode};
 use crate::{Core, Error, MemoryInterface, MemoryMappedRegister};
 
 pub use self::itm::Itm;
+pub(crate) use self::itm::ITM_TRACE_ID;
 pub use dwt::Dwt;
+pub use etm::{Etm, EtmConfig};
+pub use mtb::{Mtb, MtbBranch, MtbConfig};
 pub use scs::Scs;
 pub use swo::Swo;
-pub use tmc::TraceMemoryController;
+pub use tmc::{Deformatter, TraceMemoryController};
 pub use tpiu::Tpiu;
 pub use trace_funnel::TraceFunnel;
 
@@ -181,8 +187,7 @@ fn configure_tpiu(
     let mut tpiu = Tpiu::new(interface, component);
 
     tpiu.set_port_size(1)?;
-    let prescaler = (config.tpiu_clk() / config.baud()) - 1;
-    tpiu.set_prescaler(prescaler)?;
+    tpiu.set_prescaler(config.prescaler())?;
     match config.mode() {
         SwoMode::Manchester => tpiu.set_pin_protocol(1)?,
         SwoMode::Uart => tpiu.set_pin_protocol(2)?,
@@ -233,8 +238,7 @@ pub(crate) fn setup_tracing(
                 let mut swo = Swo::new(interface, peripheral);
                 swo.unlock()?;
 
-                let prescaler = (config.tpiu_clk() / config.baud()) - 1;
-                swo.set_prescaler(prescaler)?;
+                swo.set_prescaler(config.prescaler())?;
 
                 match config.mode() {
                     SwoMode::Manchester => swo.set_pin_protocol(1)?,
@@ -286,6 +290,8 @@ pub(crate) fn setup_tracing(
 ///
 /// # Returns
 /// All data stored in trace memory, with an upper bound at the size of internal trace memory.
+/// The data is formatted into whole frames, which are split into the data of each trace source
+/// by a [`Deformatter`].
 pub(crate) fn read_trace_memory(
     interface: &mut dyn ArmProbeInterface,
     components: &[CoresightComponent],
@@ -322,28 +328,42 @@ pub(crate) fn read_trace_memory(
         }
     }
 
-    // The TMC formats data into frames, as it contains trace data from multiple data sources. We
-    // need to deserialize the frames and pull out only the data source of interest. For now, all
-    // we care about is the ITM data.
-
-    let mut id = 0.into();
-    let mut itm_trace = Vec::new();
-
-    // Process each formatted frame and extract the multiplexed trace data.
-    for frame_buffer in etf_trace.chunks_exact(16) {
-        let mut frame = tmc::Frame::new(frame_buffer, id);
-        for (id, data) in &mut frame {
-            match id.into() {
-                // ITM ATID, see Itm::tx_enable()
-                13 => itm_trace.push(data),
-                0 => (),
-                id => tracing::warn!("Unexpected trace source ATID {id}: {data}, ignoring"),
-            }
-        }
-        id = frame.id();
-    }
+    Ok(etf_trace)
+}
+
+/// Configures the ETM of the core for instruction trace, and enables it.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+///
+/// # Returns
+/// The configuration needed to decode the trace of the ETM.
+pub(crate) fn enable_etm(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+    config: &EtmConfig,
+) -> Result<DecoderConfig, Error> {
+    let mut etm = Etm::new(interface, find_component(components, PeripheralType::Etm)?);
+    etm.unlock()?;
+    etm.enable(config)?;
+    etm.decoder_config()
+}
+
+/// Reads the ATB trace ID of the ETM.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn etm_trace_id(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+) -> Result<u8, Error> {
+    Etm::new(interface, find_component(components, PeripheralType::Etm)?).trace_id()
+}
 
-    Ok(itm_trace)
+/// Finds the MTB in the ROM table.
+///
+/// Expects to be given a list of all ROM table `components` as the first argument.
+pub(crate) fn find_mtb(components: &[CoresightComponent]) -> Result<Mtb, ArmError> {
+    let component = find_component(components, PeripheralType::Mtb)?;
+    Ok(Mtb::new(component.component.id().component_address()))
 }
 
 /// Configures DWT trace unit `unit` to begin tracing `address`.
@@ -373,6 +393,29 @@ pub fn remove_swv_data_trace(
     dwt.disable_data_trace(unit)
 }
 
+/// Configures the DWT to emit a PC sample every `interval` cycles.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn enable_pc_sampling(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+    interval: u32,
+) -> Result<u32, ArmError> {
+    let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
+    dwt.enable_pc_sampling(interval)
+}
+
+/// Configures the DWT to stop emitting PC samples.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub(crate) fn disable_pc_sampling(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+) -> Result<(), ArmError> {
+    let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
+    dwt.disable_pc_sampling()
+}
+
 /// Sets TRCENA in DEMCR to begin trace generation.
 pub fn enable_tracing(core: &mut Core) -> Result<(), Error> {
     let mut demcr = Demcr(core.read_word_32(Demcr::get_mmio_address())?);
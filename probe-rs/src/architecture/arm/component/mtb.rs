@@ -0,0 +1,338 @@
+//! Module for using the MTB.
+//!
+//! MTB = Micro Trace Buffer
+
+use crate::architecture::arm::ArmError;
+use crate::{memory_mapped_bitfield_register, Error, MemoryInterface, MemoryMappedRegister};
+
+/// The configuration of the trace buffer of an MTB.
+#[derive(Debug, Clone)]
+pub struct MtbConfig {
+    /// The address of the trace buffer in the SRAM.
+    buffer_address: u64,
+
+    /// The size of the trace buffer in bytes.
+    buffer_size: u32,
+
+    /// Whether tracing is started by a match of the DWT comparator connected to TSTART.
+    start_on_dwt_match: bool,
+
+    /// Whether tracing is stopped by a match of the DWT comparator connected to TSTOP.
+    stop_on_dwt_match: bool,
+}
+
+impl MtbConfig {
+    /// Create a new MtbConfig which traces into the buffer at `buffer_address`.
+    ///
+    /// The MTB writes the trace into the SRAM which is also used by the program, so the buffer
+    /// has to be reserved, e.g. in the linker script. `buffer_size` has to be a power of two of at
+    /// least 16 bytes, and the buffer has to be aligned to its size.
+    ///
+    /// By default tracing starts immediately, and is not stopped by DWT matches.
+    pub fn new(buffer_address: u64, buffer_size: u32) -> Self {
+        MtbConfig {
+            buffer_address,
+            buffer_size,
+            start_on_dwt_match: false,
+            stop_on_dwt_match: false,
+        }
+    }
+
+    /// Only start tracing when the DWT comparator connected to the TSTART input of the MTB matches.
+    ///
+    /// Which comparator drives TSTART is implementation defined, and it has to be configured
+    /// separately.
+    pub fn set_start_on_dwt_match(mut self, enabled: bool) -> Self {
+        self.start_on_dwt_match = enabled;
+        self
+    }
+
+    /// Stop tracing when the DWT comparator connected to the TSTOP input of the MTB matches.
+    ///
+    /// Which comparator drives TSTOP is implementation defined, and it has to be configured
+    /// separately.
+    pub fn set_stop_on_dwt_match(mut self, enabled: bool) -> Self {
+        self.stop_on_dwt_match = enabled;
+        self
+    }
+
+    /// The address of the trace buffer in the SRAM.
+    pub fn buffer_address(&self) -> u64 {
+        self.buffer_address
+    }
+
+    /// The size of the trace buffer in bytes.
+    pub fn buffer_size(&self) -> u32 {
+        self.buffer_size
+    }
+
+    /// Whether tracing is started by a match of the DWT comparator connected to TSTART.
+    pub fn start_on_dwt_match(&self) -> bool {
+        self.start_on_dwt_match
+    }
+
+    /// Whether tracing is stopped by a match of the DWT comparator connected to TSTOP.
+    pub fn stop_on_dwt_match(&self) -> bool {
+        self.stop_on_dwt_match
+    }
+}
+
+/// A branch recorded by the MTB.
+///
+/// The instructions from the destination of a branch up to the source of the next one were
+/// executed sequentially.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MtbBranch {
+    /// The address of the branch instruction. For an exception entry, this is the address at
+    /// which execution continues when the exception returns.
+    pub source: u64,
+    /// The address of the instruction executed after the branch.
+    pub destination: u64,
+    /// Whether the branch was an exception entry or return, rather than a branch instruction.
+    pub exception: bool,
+    /// Whether this is the first branch after tracing was started.
+    pub trace_start: bool,
+}
+
+impl MtbBranch {
+    /// Decodes a packet of the MTB, which consists of a source and a destination word.
+    fn from_packet(source: u32, destination: u32) -> Self {
+        MtbBranch {
+            source: u64::from(source & !1),
+            destination: u64::from(destination & !1),
+            exception: source & 1 != 0,
+            trace_start: destination & 1 != 0,
+        }
+    }
+
+    /// Decodes the packets of an MTB trace buffer, given as words from the oldest to the newest one.
+    pub fn decode_trace(words: &[u32]) -> Vec<MtbBranch> {
+        words
+            .chunks_exact(2)
+            .map(|packet| MtbBranch::from_packet(packet[0], packet[1]))
+            .collect()
+    }
+}
+
+/// Decodes a circular trace buffer, in which `next` is the index of the word written next.
+fn decode_circular_buffer(buffer: &[u32], next: usize, wrapped: bool) -> Vec<MtbBranch> {
+    let (written, oldest) = buffer.split_at(next);
+
+    if wrapped {
+        let words: Vec<u32> = oldest.iter().chain(written).copied().collect();
+        MtbBranch::decode_trace(&words)
+    } else {
+        MtbBranch::decode_trace(written)
+    }
+}
+
+/// An interface to control the MTB (Micro Trace Buffer) of a Cortex-M0+ core.
+///
+/// The MTB records the source and destination of every branch into the SRAM, which is enough to
+/// reconstruct the executed instructions on cores without an ETM.
+///
+/// The registers of the MTB are part of the memory map of the core, so they are accessed through
+/// its memory interface, e.g. a [`Core`](crate::Core).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Mtb {
+    address: u64,
+}
+
+impl Mtb {
+    /// Create a new MTB interface for the MTB with the registers at `address`.
+    pub fn new(address: u64) -> Self {
+        Mtb { address }
+    }
+
+    /// The address of the registers of the MTB.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Configure the trace buffer, and enable tracing.
+    pub fn enable(
+        &self,
+        memory: &mut impl MemoryInterface,
+        config: &MtbConfig,
+    ) -> Result<(), Error> {
+        self.disable(memory)?;
+
+        let size = config.buffer_size;
+        if !size.is_power_of_two() || size < 16 {
+            return Err(ArmError::Other(anyhow::anyhow!(
+                "The MTB buffer size has to be a power of two of at least 16 bytes, not {}",
+                size
+            ))
+            .into());
+        }
+
+        let sram =
+            u64::from(memory.read_word_32(MtbBase::get_mmio_address_from_base(self.address)?)?);
+        let offset = config.buffer_address.wrapping_sub(sram);
+        if config.buffer_address < sram || offset % u64::from(size) != 0 {
+            return Err(ArmError::Other(anyhow::anyhow!(
+                "The MTB buffer at {:#010x} has to be in the SRAM at {:#010x}, aligned to its size of {} bytes",
+                config.buffer_address,
+                sram,
+                size
+            ))
+            .into());
+        }
+
+        // The mask only implements as many bits as are needed to address the SRAM of the MTB.
+        let mask = size.trailing_zeros() - 4;
+        let mut master = MtbMaster(0);
+        master.set_mask(mask);
+        self.write(memory, master)?;
+        if self.read::<MtbMaster>(memory)?.mask() != mask {
+            return Err(ArmError::Other(anyhow::anyhow!(
+                "The MTB buffer size of {} bytes is larger than its SRAM",
+                size
+            ))
+            .into());
+        }
+
+        let mut position = MtbPosition(0);
+        position.set_pointer(offset as u32 >> 3);
+        self.write(memory, position)?;
+        self.write(memory, MtbFlow(0))?;
+
+        // When tracing is started by the DWT, TSTART sets the enable bit.
+        master.set_tstarten(config.start_on_dwt_match);
+        master.set_tstopen(config.stop_on_dwt_match);
+        master.set_en(!config.start_on_dwt_match);
+        self.write(memory, master)
+    }
+
+    /// Stop tracing, keeping the recorded trace in the buffer.
+    pub fn disable(&self, memory: &mut impl MemoryInterface) -> Result<(), Error> {
+        let mut master = self.read::<MtbMaster>(memory)?;
+        master.set_en(false);
+        master.set_tstarten(false);
+        self.write(memory, master)
+    }
+
+    /// Reads the recorded branches from the trace buffer, from the oldest to the newest one.
+    ///
+    /// The MTB keeps writing to the buffer while the core is running, so it should be halted
+    /// or tracing should be disabled before.
+    pub fn read_trace(&self, memory: &mut impl MemoryInterface) -> Result<Vec<MtbBranch>, Error> {
+        let master = self.read::<MtbMaster>(memory)?;
+        let position = self.read::<MtbPosition>(memory)?;
+        let sram = memory.read_word_32(MtbBase::get_mmio_address_from_base(self.address)?)?;
+
+        let size = 1 << (master.mask() + 4);
+        let pointer = position.pointer() << 3;
+        let start = pointer & !(size - 1);
+
+        let mut buffer = vec![0; size as usize / 4];
+        memory.read_32(u64::from(sram.wrapping_add(start)), &mut buffer)?;
+
+        let next = (pointer - start) as usize / 4;
+        Ok(decode_circular_buffer(&buffer, next, position.wrap()))
+    }
+
+    fn read<R: MemoryMappedRegister<u32>>(
+        &self,
+        memory: &mut impl MemoryInterface,
+    ) -> Result<R, Error> {
+        let address = R::get_mmio_address_from_base(self.address)?;
+        Ok(R::from(memory.read_word_32(address)?))
+    }
+
+    fn write<R: MemoryMappedRegister<u32>>(
+        &self,
+        memory: &mut impl MemoryInterface,
+        register: R,
+    ) -> Result<(), Error> {
+        let address = R::get_mmio_address_from_base(self.address)?;
+        memory.write_word_32(address, register.into())?;
+        Ok(())
+    }
+}
+
+memory_mapped_bitfield_register! {
+    pub struct MtbPosition(u32);
+    0x000, "MTB_POSITION",
+    impl From;
+
+    pub pointer, set_pointer: 31, 3;
+    pub wrap, set_wrap: 2;
+}
+
+memory_mapped_bitfield_register! {
+    pub struct MtbMaster(u32);
+    0x004, "MTB_MASTER",
+    impl From;
+
+    pub en, set_en: 31;
+    pub haltreq, set_haltreq: 9;
+    pub tstopen, set_tstopen: 6;
+    pub tstarten, set_tstarten: 5;
+    pub mask, set_mask: 4, 0;
+}
+
+memory_mapped_bitfield_register! {
+    pub struct MtbFlow(u32);
+    0x008, "MTB_FLOW",
+    impl From;
+
+    pub watermark, set_watermark: 31, 3;
+    pub autohalt, set_autohalt: 1;
+    pub autostop, set_autostop: 0;
+}
+
+memory_mapped_bitfield_register! {
+    pub struct MtbBase(u32);
+    0x00C, "MTB_BASE",
+    impl From;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_packets() {
+        let words = [0x0000_1005, 0x0000_2001, 0x0000_2010, 0x0000_0040];
+
+        assert_eq!(
+            MtbBranch::decode_trace(&words),
+            vec![
+                MtbBranch {
+                    source: 0x1004,
+                    destination: 0x2000,
+                    exception: true,
+                    trace_start: true,
+                },
+                MtbBranch {
+                    source: 0x2010,
+                    destination: 0x0040,
+                    exception: false,
+                    trace_start: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn order_circular_buffer() {
+        let buffer = [0x30, 0x40, 0x10, 0x20];
+
+        let branch = |source, destination| MtbBranch {
+            source,
+            destination,
+            exception: false,
+            trace_start: false,
+        };
+
+        assert_eq!(
+            decode_circular_buffer(&buffer, 2, true),
+            vec![branch(0x10, 0x20), branch(0x30, 0x40)]
+        );
+        assert_eq!(
+            decode_circular_buffer(&buffer, 2, false),
+            vec![branch(0x30, 0x40)]
+        );
+    }
+}
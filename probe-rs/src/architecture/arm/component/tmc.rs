@@ -275,3 +275,177 @@ impl<'a> Iterator for &mut Frame<'a> {
         ret
     }
 }
+
+/// A full synchronization packet, which is inserted between frames of a continuous trace stream.
+const FULL_SYNC: [u8; 4] = [0xFF, 0xFF, 0xFF, 0x7F];
+
+/// A streaming demultiplexer of formatted trace data.
+///
+/// The TMC (ETB/ETF) and the TPIU with continuous formatting enabled (see
+/// [`SwoConfig::set_continuous_formatting`](crate::architecture::arm::SwoConfig::set_continuous_formatting))
+/// multiplex the trace data of all trace sources into 16 byte frames. The deformatter splits
+/// them into the data of each trace source, identified by its ATB trace ID, e.g. to separate the
+/// ITM and ETM data.
+///
+/// Data can be passed in chunks of any size, incomplete frames are kept until the next call.
+#[derive(Debug, Clone)]
+pub struct Deformatter {
+    /// The data of an incomplete frame.
+    buffer: Vec<u8>,
+    /// The ID of the trace source of the last data.
+    id: Id,
+    /// Whether the data is aligned to frames.
+    synchronized: bool,
+}
+
+impl Default for Deformatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deformatter {
+    /// Creates a deformatter for data which starts at a frame boundary, like the data read from
+    /// the trace memory.
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            id: 0.into(),
+            synchronized: true,
+        }
+    }
+
+    /// Creates a deformatter for a stream which may start within a frame, like SWO data.
+    ///
+    /// All data before the first full synchronization packet is discarded.
+    pub fn unsynchronized() -> Self {
+        Self {
+            synchronized: false,
+            ..Self::new()
+        }
+    }
+
+    /// Demultiplexes the formatted `data`.
+    ///
+    /// Returns the data of the trace sources in the order it was traced, as pairs of the trace ID
+    /// of the source and consecutive bytes of that source. The null, trigger and reserved IDs
+    /// are discarded.
+    pub fn decode(&mut self, data: &[u8]) -> Vec<(u8, Vec<u8>)> {
+        self.buffer.extend_from_slice(data);
+
+        let mut sources: Vec<(u8, Vec<u8>)> = Vec::new();
+        let mut offset = 0;
+        loop {
+            let remaining = &self.buffer[offset..];
+
+            if !self.synchronized {
+                match remaining
+                    .windows(FULL_SYNC.len())
+                    .position(|window| window == FULL_SYNC)
+                {
+                    Some(position) => {
+                        offset += position + FULL_SYNC.len();
+                        self.synchronized = true;
+                        continue;
+                    }
+                    None => {
+                        // Keep the bytes which may be the start of a synchronization packet.
+                        offset += remaining.len().saturating_sub(FULL_SYNC.len() - 1);
+                        break;
+                    }
+                }
+            }
+
+            if remaining.starts_with(&FULL_SYNC) {
+                offset += FULL_SYNC.len();
+                continue;
+            }
+            if remaining.len() < 16 {
+                break;
+            }
+
+            let mut frame = Frame::new(&remaining[..16], self.id);
+            for (id, data) in &mut frame {
+                let id = u8::from(id);
+                if !(0x01..=0x6F).contains(&id) {
+                    continue;
+                }
+
+                match sources.last_mut() {
+                    Some((last_id, bytes)) if *last_id == id => bytes.push(data),
+                    _ => sources.push((id, vec![data])),
+                }
+            }
+            self.id = frame.id();
+            offset += 16;
+        }
+
+        self.buffer.drain(..offset);
+        sources
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A frame which switches from source 13 to source 16 after one byte, with the data of
+    /// source 16 continuing in the next frame.
+    #[rustfmt::skip]
+    const FRAMES: [u8; 32] = [
+        // ID 13 with the data 0x01, ID 16 with the data 0x02 and 0x04 to 0x0E, and the flags.
+        0x1B, 0x01, 0x21, 0x02, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+        0x00,
+        // Data 0x10 to 0x1E of the last source.
+        0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x1B, 0x1C, 0x1D, 0x1E,
+        0x00,
+    ];
+
+    #[test]
+    fn split_sources() {
+        let mut deformatter = Deformatter::new();
+
+        let sources = deformatter.decode(&FRAMES);
+
+        assert_eq!(
+            sources,
+            vec![
+                (13, vec![0x01]),
+                (
+                    16,
+                    std::iter::once(0x02)
+                        .chain(0x04..=0x0E)
+                        .chain(0x10..=0x1E)
+                        .collect::<Vec<u8>>()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn synchronize_stream_in_chunks() {
+        let mut deformatter = Deformatter::unsynchronized();
+
+        // Garbage from the middle of a frame, a split synchronization packet and the frames.
+        let mut stream = vec![0x55, 0x12, 0xFF, 0xFF];
+        stream.extend_from_slice(&[0xFF, 0x7F]);
+        stream.extend_from_slice(&FRAMES);
+        stream.extend_from_slice(&FULL_SYNC);
+
+        let mut sources = Vec::new();
+        for chunk in stream.chunks(5) {
+            sources.extend(deformatter.decode(chunk));
+        }
+
+        let data = |id| -> Vec<u8> {
+            sources
+                .iter()
+                .filter(|(source, _)| *source == id)
+                .flat_map(|(_, data)| data.iter().copied())
+                .collect()
+        };
+        assert_eq!(data(13), vec![0x01]);
+        assert_eq!(data(16).len(), 27);
+        assert!(deformatter.buffer.is_empty());
+    }
+}
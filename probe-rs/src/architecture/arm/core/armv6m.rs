@@ -1,17 +1,21 @@
 //! Register types and the core interface for armv6-M
 
-use super::{registers::cortex_m::*, CortexMState, Dfsr};
+use super::{cortex_m::DwtVersion, registers::cortex_m::*, CortexMState, Dfsr};
 use crate::{
     architecture::arm::{
-        memory::adi_v5_memory_interface::ArmProbe, sequences::ArmDebugSequence, ArmError,
+        memory::adi_v5_memory_interface::ArmProbe,
+        sequences::{self, ArmDebugSequence},
+        ArmError,
+    },
+    core::{
+        CoreRegisters, RegisterId, RegisterValue, VectorCatchCondition, Watchpoint, WatchpointKind,
     },
-    core::{CoreRegisters, RegisterId, RegisterValue},
     error::Error,
     memory::valid_32bit_address,
     Architecture, CoreInformation, CoreInterface, CoreRegister, CoreStatus, CoreType,
-    DebugProbeError, HaltReason, InstructionSet, MemoryInterface, MemoryMappedRegister,
+    DebugProbeError, HaltReason, InstructionSet, MemoryInterface, MemoryMappedRegister, ResetType,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use bitfield::bitfield;
 use std::{
     mem::size_of,
@@ -459,6 +463,35 @@ impl<'probe> Armv6m<'probe> {
         })
     }
 
+    /// Sets or clears the DEMCR vector catch bits for `condition`.
+    fn update_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        let update: fn(&mut Demcr, bool) = match condition {
+            VectorCatchCondition::CoreReset => Demcr::set_vc_corereset,
+            VectorCatchCondition::HardFault => Demcr::set_vc_harderr,
+            VectorCatchCondition::All => |demcr, enabled| {
+                demcr.set_vc_corereset(enabled);
+                demcr.set_vc_harderr(enabled);
+            },
+            unsupported => {
+                return Err(Error::Other(anyhow!(
+                    "{:?} vector catch is not supported on ARMv6-M cores",
+                    unsupported
+                )))
+            }
+        };
+
+        let mut demcr = Demcr(self.memory.read_word_32(Demcr::get_mmio_address())?);
+        update(&mut demcr, enabled);
+        self.memory
+            .write_word_32(Demcr::get_mmio_address(), demcr.into())?;
+
+        Ok(())
+    }
+
     fn set_core_status(&mut self, new_status: CoreStatus) {
         super::update_core_status(&mut self.memory, &mut self.state.current_state, new_status);
     }
@@ -515,7 +548,11 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         if dhcsr.s_halt() {
             let dfsr = Dfsr(self.memory.read_word_32(Dfsr::get_mmio_address())?);
 
-            let reason = dfsr.halt_reason();
+            let mut reason = dfsr.halt_reason();
+
+            if reason == HaltReason::Exception {
+                reason = super::cortex_m::vector_catch_halt_reason(&mut *self.memory)?;
+            }
 
             // Clear bits from Dfsr register
             self.memory
@@ -594,17 +631,41 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         Ok(())
     }
 
-    fn reset(&mut self) -> Result<(), Error> {
-        self.sequence
-            .reset_system(&mut *self.memory, crate::CoreType::Armv6m, None)?;
+    fn prepare_restart(&mut self) -> Result<(), Error> {
+        // Step over a breakpoint at the current instruction, like `run` does.
+        self.step()?;
+        super::cortex_m::prepare_restart(&mut *self.memory)?;
+
+        self.set_core_status(CoreStatus::Running);
+
         Ok(())
     }
 
-    fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+    fn reset(&mut self, reset_type: ResetType) -> Result<(), Error> {
+        sequences::reset_with_type(
+            &*self.sequence,
+            &mut *self.memory,
+            crate::CoreType::Armv6m,
+            None,
+            reset_type,
+        )?;
+        Ok(())
+    }
+
+    fn reset_and_halt(
+        &mut self,
+        _timeout: Duration,
+        reset_type: ResetType,
+    ) -> Result<CoreInformation, Error> {
         self.reset_catch_set()?;
 
-        self.sequence
-            .reset_system(&mut *self.memory, crate::CoreType::Armv6m, None)?;
+        sequences::reset_with_type(
+            &*self.sequence,
+            &mut *self.memory,
+            crate::CoreType::Armv6m,
+            None,
+            reset_type,
+        )?;
 
         // Update core status
         let _ = self.status()?;
@@ -779,6 +840,39 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        super::cortex_m::available_watchpoint_units(&mut *self.memory)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        super::cortex_m::hw_watchpoints(&mut *self.memory, DwtVersion::Armv7m)
+    }
+
+    fn reserved_watchpoint_units(&mut self) -> Result<Vec<bool>, Error> {
+        super::cortex_m::reserved_watchpoint_units(&mut *self.memory, DwtVersion::Armv7m)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        length: u64,
+        kind: WatchpointKind,
+    ) -> Result<(), Error> {
+        super::cortex_m::set_hw_watchpoint(
+            &mut *self.memory,
+            DwtVersion::Armv7m,
+            unit_index,
+            address,
+            length,
+            kind,
+        )
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        super::cortex_m::clear_hw_watchpoint(&mut *self.memory, unit_index)
+    }
+
     fn registers(&self) -> &'static CoreRegisters {
         &CORTEX_M_CORE_REGSISTERS
     }
@@ -848,6 +942,14 @@ impl<'probe> CoreInterface for Armv6m<'probe> {
 
         Ok(())
     }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.update_vector_catch(condition, true)
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.update_vector_catch(condition, false)
+    }
 }
 
 impl<'probe> MemoryInterface for Armv6m<'probe> {
@@ -862,6 +964,11 @@ impl<'probe> MemoryInterface for Armv6m<'probe> {
         let value = self.memory.read_word_32(address)?;
         Ok(value)
     }
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        let value = self.memory.read_word_16(address)?;
+        Ok(value)
+    }
+
     fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
         let value = self.memory.read_word_8(address)?;
         Ok(value)
@@ -877,6 +984,11 @@ impl<'probe> MemoryInterface for Armv6m<'probe> {
         Ok(())
     }
 
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        self.memory.read_16(address, data)?;
+        Ok(())
+    }
+
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
         self.memory.read_8(address, data)?;
         Ok(())
@@ -892,6 +1004,11 @@ impl<'probe> MemoryInterface for Armv6m<'probe> {
         Ok(())
     }
 
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        self.memory.write_word_16(address, data)?;
+        Ok(())
+    }
+
     fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
         self.memory.write_word_8(address, data)?;
         Ok(())
@@ -907,6 +1024,11 @@ impl<'probe> MemoryInterface for Armv6m<'probe> {
         Ok(())
     }
 
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        self.memory.write_16(address, data)?;
+        Ok(())
+    }
+
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
         self.memory.write_8(address, data)?;
         Ok(())
@@ -2,8 +2,8 @@
 
 use super::{
     instructions::aarch32::{
-        build_bx, build_ldc, build_mcr, build_mov, build_mrc, build_mrs, build_stc, build_vmov,
-        build_vmrs,
+        build_bx, build_ldc, build_ldrh, build_mcr, build_mov, build_mrc, build_mrs, build_stc,
+        build_strh, build_vmov, build_vmrs,
     },
     registers::{
         aarch32::{
@@ -12,20 +12,25 @@ use super::{
         },
         cortex_m::{FP, PC, RA, SP},
     },
-    CortexAState,
+    watchpoint_kind_from_lsc, watchpoint_kind_to_lsc, CortexAState, WatchpointFields,
 };
 use crate::{
     architecture::arm::{
-        core::armv7a_debug_regs::*, memory::adi_v5_memory_interface::ArmProbe,
-        sequences::ArmDebugSequence, ArmError,
+        core::armv7a_debug_regs::*,
+        memory::adi_v5_memory_interface::ArmProbe,
+        sequences::{self, ArmDebugSequence},
+        ArmError,
+    },
+    core::{
+        CoreRegisters, MemoryMappedRegister, RegisterId, RegisterValue, VectorCatchCondition,
+        Watchpoint, WatchpointKind,
     },
-    core::{CoreRegisters, MemoryMappedRegister, RegisterId, RegisterValue},
     error::Error,
     memory::valid_32bit_address,
     Architecture, CoreInformation, CoreInterface, CoreRegister, CoreStatus, CoreType,
-    InstructionSet, MemoryInterface,
+    InstructionSet, MemoryInterface, ResetType,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::{
     mem::size_of,
     sync::Arc,
@@ -60,6 +65,8 @@ pub struct Armv7a<'probe> {
 
     num_breakpoints: Option<u32>,
 
+    num_watchpoints: Option<u32>,
+
     itr_enabled: bool,
 
     id: usize,
@@ -99,6 +106,7 @@ impl<'probe> Armv7a<'probe> {
             base_address,
             sequence,
             num_breakpoints: None,
+            num_watchpoints: None,
             itr_enabled: false,
             id,
         };
@@ -112,6 +120,44 @@ impl<'probe> Armv7a<'probe> {
         Ok(core)
     }
 
+    /// Sets or clears the DBGVCR vector catch bits for `condition`.
+    ///
+    /// HardFaults are mapped to Undefined Instruction and Abort exceptions, BusFaults to Aborts
+    /// and UsageFaults to Undefined Instruction exceptions.
+    fn update_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        let update: fn(&mut Dbgvcr, bool) = match condition {
+            VectorCatchCondition::CoreReset => Dbgvcr::set_reset,
+            VectorCatchCondition::HardFault => |dbgvcr, enabled| {
+                dbgvcr.set_undefined_instructions(enabled);
+                dbgvcr.set_aborts(enabled);
+            },
+            VectorCatchCondition::BusFault => Dbgvcr::set_aborts,
+            VectorCatchCondition::UsageFault => Dbgvcr::set_undefined_instructions,
+            VectorCatchCondition::All => |dbgvcr, enabled| {
+                dbgvcr.set_reset(enabled);
+                dbgvcr.set_undefined_instructions(enabled);
+                dbgvcr.set_aborts(enabled);
+            },
+            unsupported => {
+                return Err(Error::Other(anyhow!(
+                    "{:?} vector catch is not supported on ARMv7-A cores",
+                    unsupported
+                )))
+            }
+        };
+
+        let address = Dbgvcr::get_mmio_address_from_base(self.base_address)?;
+        let mut dbgvcr = Dbgvcr(self.memory.read_word_32(address)?);
+        update(&mut dbgvcr, enabled);
+        self.memory.write_word_32(address, dbgvcr.into())?;
+
+        Ok(())
+    }
+
     fn read_fp_reg_count(&mut self) -> Result<(), Error> {
         if self.state.fp_reg_count.is_none()
             && matches!(self.state.current_state, CoreStatus::Halted(_))
@@ -408,11 +454,20 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
         Ok(())
     }
 
-    fn reset(&mut self) -> Result<(), Error> {
-        self.sequence.reset_system(
+    fn prepare_restart(&mut self) -> Result<(), Error> {
+        self.writeback_registers()?;
+        self.set_core_status(CoreStatus::Running);
+
+        Ok(())
+    }
+
+    fn reset(&mut self, reset_type: ResetType) -> Result<(), Error> {
+        sequences::reset_with_type(
+            &*self.sequence,
             &mut *self.memory,
             crate::CoreType::Armv7a,
             Some(self.base_address),
+            reset_type,
         )?;
 
         // Reset our cached values
@@ -421,16 +476,22 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
         Ok(())
     }
 
-    fn reset_and_halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+    fn reset_and_halt(
+        &mut self,
+        timeout: Duration,
+        reset_type: ResetType,
+    ) -> Result<CoreInformation, Error> {
         self.sequence.reset_catch_set(
             &mut *self.memory,
             crate::CoreType::Armv7a,
             Some(self.base_address),
         )?;
-        self.sequence.reset_system(
+        sequences::reset_with_type(
+            &*self.sequence,
             &mut *self.memory,
             crate::CoreType::Armv7a,
             Some(self.base_address),
+            reset_type,
         )?;
 
         // Request halt
@@ -728,6 +789,95 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        if self.num_watchpoints.is_none() {
+            let address = Dbgdidr::get_mmio_address_from_base(self.base_address)?;
+            let dbgdidr = Dbgdidr(self.memory.read_word_32(address)?);
+
+            self.num_watchpoints = Some(dbgdidr.wrps() + 1);
+        }
+        Ok(self.num_watchpoints.unwrap())
+    }
+
+    /// See docs on the [`CoreInterface::hw_watchpoints`] trait
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        let mut watchpoints = vec![];
+        let num_hw_watchpoints = self.available_watchpoint_units()? as usize;
+
+        for wp_unit_index in 0..num_hw_watchpoints {
+            let wp_value_addr = Dbgwvr::get_mmio_address_from_base(self.base_address)?
+                + (wp_unit_index * size_of::<u32>()) as u64;
+            let wp_value = self.memory.read_word_32(wp_value_addr)? as u64;
+
+            let wp_control_addr = Dbgwcr::get_mmio_address_from_base(self.base_address)?
+                + (wp_unit_index * size_of::<u32>()) as u64;
+            let wp_control = Dbgwcr(self.memory.read_word_32(wp_control_addr)?);
+
+            match watchpoint_kind_from_lsc(wp_control.lsc()) {
+                Some(kind) if wp_control.e() => {
+                    let (address, length) = WatchpointFields {
+                        value: wp_value,
+                        bas: wp_control.bas(),
+                        mask: wp_control.mask(),
+                    }
+                    .range();
+
+                    watchpoints.push(Some(Watchpoint {
+                        address,
+                        length,
+                        kind,
+                    }));
+                }
+                _ => watchpoints.push(None),
+            }
+        }
+        Ok(watchpoints)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        length: u64,
+        kind: WatchpointKind,
+    ) -> Result<(), Error> {
+        let fields = WatchpointFields::new(address, length, 4)?;
+
+        let wp_value_addr = Dbgwvr::get_mmio_address_from_base(self.base_address)?
+            + (unit_index * size_of::<u32>()) as u64;
+        let wp_control_addr = Dbgwcr::get_mmio_address_from_base(self.base_address)?
+            + (unit_index * size_of::<u32>()) as u64;
+        let mut wp_control = Dbgwcr(0);
+
+        // Match on all modes
+        wp_control.set_hmc(true);
+        wp_control.set_pac(0b11);
+        wp_control.set_bas(fields.bas);
+        wp_control.set_mask(fields.mask);
+        wp_control.set_lsc(watchpoint_kind_to_lsc(kind));
+        // Enable
+        wp_control.set_e(true);
+
+        self.memory
+            .write_word_32(wp_value_addr, valid_32bit_address(fields.value)?)?;
+        self.memory
+            .write_word_32(wp_control_addr, wp_control.into())?;
+
+        Ok(())
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        let wp_value_addr = Dbgwvr::get_mmio_address_from_base(self.base_address)?
+            + (unit_index * size_of::<u32>()) as u64;
+        let wp_control_addr = Dbgwcr::get_mmio_address_from_base(self.base_address)?
+            + (unit_index * size_of::<u32>()) as u64;
+
+        self.memory.write_word_32(wp_value_addr, 0)?;
+        self.memory.write_word_32(wp_control_addr, 0)?;
+
+        Ok(())
+    }
+
     fn registers(&self) -> &'static CoreRegisters {
         match self.state.fp_reg_count {
             Some(16) => &AARCH32_WITH_FP_16_CORE_REGSISTERS,
@@ -820,6 +970,40 @@ impl<'probe> CoreInterface for Armv7a<'probe> {
 
         Ok(())
     }
+
+    fn sync_instruction_cache(&mut self, address: u64, length: u64) -> Result<(), Error> {
+        let first = valid_32bit_address(address)?;
+        let last = valid_32bit_address(address + length.max(1) - 1)?;
+
+        self.prepare_r0_for_clobber()?;
+
+        // Clean the data cache to the point of unification, so that the instruction fetches see
+        // the written instructions. They are at most two cache lines.
+        for line_address in [first, last] {
+            self.set_r0(line_address)?;
+            // DCCMVAU r0
+            self.execute_instruction(build_mcr(15, 0, 0, 7, 11, 1))?;
+        }
+
+        // DSB
+        self.execute_instruction(build_mcr(15, 0, 0, 7, 10, 4))?;
+        // ICIALLU
+        self.execute_instruction(build_mcr(15, 0, 0, 7, 5, 0))?;
+        // BPIALL
+        self.execute_instruction(build_mcr(15, 0, 0, 7, 5, 6))?;
+        // DSB
+        self.execute_instruction(build_mcr(15, 0, 0, 7, 10, 4))?;
+
+        Ok(())
+    }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.update_vector_catch(condition, true)
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.update_vector_catch(condition, false)
+    }
 }
 
 impl<'probe> MemoryInterface for Armv7a<'probe> {
@@ -850,6 +1034,34 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
         self.execute_instruction_with_result(instr)
     }
 
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        if address & 1 != 0 {
+            return Err(Error::MemoryNotAligned {
+                address,
+                alignment: 2,
+            });
+        }
+
+        let address = valid_32bit_address(address)?;
+
+        // LDRH r1, [r0], #2
+        let instr = build_ldrh(1, 0, 2);
+
+        // Save r0, r1
+        self.prepare_for_clobber(0)?;
+        self.prepare_for_clobber(1)?;
+
+        // Load r0 with the address to read from
+        self.set_r0(address)?;
+
+        // Read memory from [r0]
+        self.execute_instruction(instr)?;
+
+        // Move from r1 to transfer buffer
+        let instr = build_mcr(14, 0, 1, 0, 5, 0);
+        Ok(self.execute_instruction_with_result(instr)? as u16)
+    }
+
     fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
         // Find the word this is in and its byte offset
         let byte_offset = address % 4;
@@ -878,6 +1090,14 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
         Ok(())
     }
 
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        for (i, half_word) in data.iter_mut().enumerate() {
+            *half_word = self.read_word_16(address + ((i as u64) * 2))?;
+        }
+
+        Ok(())
+    }
+
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
         for (i, byte) in data.iter_mut().enumerate() {
             *byte = self.read_word_8(address + (i as u64))?;
@@ -910,6 +1130,35 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
         self.execute_instruction_with_input(instr, data)
     }
 
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        if address & 1 != 0 {
+            return Err(Error::MemoryNotAligned {
+                address,
+                alignment: 2,
+            });
+        }
+
+        let address = valid_32bit_address(address)?;
+
+        // STRH r1, [r0], #2
+        let instr = build_strh(1, 0, 2);
+
+        // Save r0, r1
+        self.prepare_for_clobber(0)?;
+        self.prepare_for_clobber(1)?;
+
+        // Load r0 with the address to write to
+        self.set_r0(address)?;
+
+        // Move the data from the transfer buffer to r1
+        self.execute_instruction_with_input(build_mrc(14, 0, 1, 0, 5, 0), data.into())?;
+
+        // Write r1 to [r0]
+        self.execute_instruction(instr)?;
+
+        Ok(())
+    }
+
     fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
         // Find the word this is in and its byte offset
         let byte_offset = address % 4;
@@ -939,9 +1188,17 @@ impl<'probe> MemoryInterface for Armv7a<'probe> {
         Ok(())
     }
 
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        for (i, half_word) in data.iter().enumerate() {
+            self.write_word_16(address + ((i as u64) * 2), *half_word)?;
+        }
+
+        Ok(())
+    }
+
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
         for (i, byte) in data.iter().enumerate() {
-            self.write_word_8(address + ((i as u64) * 4), *byte)?;
+            self.write_word_8(address + (i as u64), *byte)?;
         }
 
         Ok(())
@@ -1016,6 +1273,10 @@ mod test {
             todo!()
         }
 
+        fn read_16(&mut self, _address: u64, _data: &mut [u16]) -> Result<(), ArmError> {
+            todo!()
+        }
+
         fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), ArmError> {
             if self.expected_ops.is_empty() {
                 panic!(
@@ -1055,6 +1316,10 @@ mod test {
             todo!()
         }
 
+        fn write_16(&mut self, _address: u64, _data: &[u16]) -> Result<(), ArmError> {
+            todo!()
+        }
+
         fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), ArmError> {
             if self.expected_ops.is_empty() {
                 panic!(
@@ -1856,6 +2121,99 @@ mod test {
         armv7a.clear_hw_breakpoint(0).unwrap();
     }
 
+    #[test]
+    fn armv7a_set_hw_watchpoint() {
+        const WP_ADDRESS: u64 = 0x2346;
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        // Update WP value and control
+        let mut dbgwcr = Dbgwcr(0);
+        // Match on all modes
+        dbgwcr.set_hmc(true);
+        dbgwcr.set_pac(0b11);
+        // Match on the upper two bytes of the word
+        dbgwcr.set_bas(0b1100);
+        // Match on writes
+        dbgwcr.set_lsc(0b10);
+        // Enable
+        dbgwcr.set_e(true);
+
+        probe.expected_write(
+            Dbgwvr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            0x2344,
+        );
+        probe.expected_write(
+            Dbgwcr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgwcr.into(),
+        );
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+            0,
+        )
+        .unwrap();
+
+        armv7a
+            .set_hw_watchpoint(0, WP_ADDRESS, 2, WatchpointKind::Write)
+            .unwrap();
+    }
+
+    #[test]
+    fn armv7a_set_vector_catch() {
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        // Read-modify-write of DBGVCR, keeping the reset catch
+        let mut dbgvcr = Dbgvcr(0);
+        dbgvcr.set_reset(true);
+        probe.expected_read(
+            Dbgvcr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgvcr.into(),
+        );
+
+        dbgvcr.set_aborts(true);
+        probe.expected_write(
+            Dbgvcr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgvcr.into(),
+        );
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+            0,
+        )
+        .unwrap();
+
+        armv7a
+            .enable_vector_catch(VectorCatchCondition::BusFault)
+            .unwrap();
+        assert!(armv7a
+            .enable_vector_catch(VectorCatchCondition::SecureFault)
+            .is_err());
+    }
+
     #[test]
     fn armv7a_read_word_32() {
         const MEMORY_VALUE: u32 = 0xBA5EBA11;
@@ -1887,6 +2245,116 @@ mod test {
         assert_eq!(MEMORY_VALUE, armv7a.read_word_32(MEMORY_ADDRESS).unwrap());
     }
 
+    #[test]
+    fn armv7a_read_word_16() {
+        const MEMORY_VALUE: u16 = 0xBA5E;
+        const MEMORY_ADDRESS: u64 = 0x1234567A;
+
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        // Save r1
+        add_read_reg_expectations(&mut probe, 1, 0);
+
+        // Read memory
+        add_set_r0_expectation(&mut probe, MEMORY_ADDRESS as u32);
+
+        let mut dbgdscr = Dbgdscr(0);
+        dbgdscr.set_instrcoml_l(true);
+
+        probe.expected_write(
+            Dbgitr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            build_ldrh(1, 0, 2),
+        );
+        probe.expected_read(
+            Dbgdscr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgdscr.into(),
+        );
+        add_read_reg_expectations(&mut probe, 1, MEMORY_VALUE.into());
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(MEMORY_VALUE, armv7a.read_word_16(MEMORY_ADDRESS).unwrap());
+    }
+
+    #[test]
+    fn armv7a_write_word_16() {
+        const MEMORY_VALUE: u16 = 0xBA5E;
+        const MEMORY_ADDRESS: u64 = 0x1234567A;
+
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        // Save r1
+        add_read_reg_expectations(&mut probe, 1, 0);
+
+        // Write memory
+        add_set_r0_expectation(&mut probe, MEMORY_ADDRESS as u32);
+
+        let mut dbgdscr = Dbgdscr(0);
+        dbgdscr.set_instrcoml_l(true);
+        dbgdscr.set_rxfull_l(true);
+
+        probe.expected_write(
+            Dbgdtrrx::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            MEMORY_VALUE.into(),
+        );
+        probe.expected_read(
+            Dbgdscr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgdscr.into(),
+        );
+        probe.expected_write(
+            Dbgitr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            build_mrc(14, 0, 1, 0, 5, 0),
+        );
+        probe.expected_read(
+            Dbgdscr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgdscr.into(),
+        );
+        probe.expected_write(
+            Dbgitr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            build_strh(1, 0, 2),
+        );
+        probe.expected_read(
+            Dbgdscr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgdscr.into(),
+        );
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+            0,
+        )
+        .unwrap();
+
+        armv7a.write_word_16(MEMORY_ADDRESS, MEMORY_VALUE).unwrap();
+    }
+
     #[test]
     fn armv7a_read_word_8() {
         const MEMORY_VALUE: u32 = 0xBA5EBA11;
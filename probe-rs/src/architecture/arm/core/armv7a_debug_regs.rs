@@ -249,7 +249,7 @@ memory_mapped_bitfield_register! {
     impl From;
 
     /// The number of watchpoints implemented. The number of implemented watchpoints is one more than the value of this field.
-    pub wrps, _: 31, 28;
+    pub wrps, set_wrps: 31, 28;
 
     /// The number of breakpoints implemented. The number of implemented breakpoints is one more than value of this field.
     pub brps, set_brps: 27, 24;
@@ -318,6 +318,58 @@ memory_mapped_bitfield_register! {
     pub hrq, set_hrq: 0;
 }
 
+memory_mapped_bitfield_register! {
+    /// DBGVCR - Vector Catch Register
+    pub struct Dbgvcr(u32);
+    7, "DBGVCR",
+    impl From;
+
+    /// Data Abort vector catch, Non-secure state
+    pub ns_data_abort, set_ns_data_abort: 28;
+
+    /// Prefetch Abort vector catch, Non-secure state
+    pub ns_prefetch_abort, set_ns_prefetch_abort: 27;
+
+    /// Undefined Instruction vector catch, Non-secure state
+    pub ns_undefined, set_ns_undefined: 25;
+
+    /// Data Abort vector catch, Monitor mode
+    pub mon_data_abort, set_mon_data_abort: 12;
+
+    /// Prefetch Abort vector catch, Monitor mode
+    pub mon_prefetch_abort, set_mon_prefetch_abort: 11;
+
+    /// Data Abort vector catch, Secure state
+    pub data_abort, set_data_abort: 4;
+
+    /// Prefetch Abort vector catch, Secure state
+    pub prefetch_abort, set_prefetch_abort: 3;
+
+    /// Undefined Instruction vector catch, Secure state
+    pub undefined, set_undefined: 1;
+
+    /// Reset vector catch
+    pub reset, set_reset: 0;
+}
+
+impl Dbgvcr {
+    /// Enable or disable the vector catches for Prefetch Aborts and Data Aborts in all states.
+    pub fn set_aborts(&mut self, enabled: bool) {
+        self.set_ns_data_abort(enabled);
+        self.set_ns_prefetch_abort(enabled);
+        self.set_mon_data_abort(enabled);
+        self.set_mon_prefetch_abort(enabled);
+        self.set_data_abort(enabled);
+        self.set_prefetch_abort(enabled);
+    }
+
+    /// Enable or disable the vector catches for Undefined Instruction exceptions in all states.
+    pub fn set_undefined_instructions(&mut self, enabled: bool) {
+        self.set_ns_undefined(enabled);
+        self.set_undefined(enabled);
+    }
+}
+
 memory_mapped_bitfield_register! {
     /// DBGBVR - Breakpoint Value Register
     pub struct Dbgbvr(u32);
@@ -359,6 +411,50 @@ memory_mapped_bitfield_register! {
     pub e, set_e: 0;
 }
 
+memory_mapped_bitfield_register! {
+    /// DBGWVR - Watchpoint Value Register
+    pub struct Dbgwvr(u32);
+    96, "DBGWVR",
+    impl From;
+
+    /// Watchpoint address
+    pub value, set_value : 31, 0;
+}
+
+memory_mapped_bitfield_register! {
+    /// DBGWCR - Watchpoint Control Register
+    pub struct Dbgwcr(u32);
+    112, "DBGWCR",
+    impl From;
+
+    /// Address range mask. Whether masking is supported is implementation defined.
+    pub mask, set_mask : 28, 24;
+
+    /// Watchpoint type
+    pub wt, set_wt : 20;
+
+    /// Linked breakpoint number
+    pub lbn, set_lbn : 19, 16;
+
+    /// Security state control
+    pub ssc, set_ssc : 15, 14;
+
+    /// Hyp mode control bit
+    pub hmc, set_hmc: 13;
+
+    /// Byte address select
+    pub bas, set_bas: 12, 5;
+
+    /// Load/store access control
+    pub lsc, set_lsc: 4, 3;
+
+    /// Privileged access control
+    pub pac, set_pac: 2, 1;
+
+    /// Watchpoint enable
+    pub e, set_e: 0;
+}
+
 memory_mapped_bitfield_register! {
     /// DBGLAR - Lock Access Register
     pub struct Dbglar(u32);
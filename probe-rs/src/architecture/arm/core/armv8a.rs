@@ -1,27 +1,34 @@
 //! Register types and the core interface for armv8-a
 
 use super::{
+    cti,
     instructions::{
         aarch64,
-        thumb2::{build_ldr, build_mcr, build_mrc, build_str, build_vmov, build_vmrs},
+        thumb2::{
+            build_dsb, build_ldr, build_ldrh, build_mcr, build_mrc, build_str, build_strh,
+            build_vmov, build_vmrs,
+        },
     },
     registers::{aarch32::AARCH32_WITH_FP_32_CORE_REGSISTERS, aarch64::AARCH64_CORE_REGSISTERS},
-    CortexAState,
+    watchpoint_kind_from_lsc, watchpoint_kind_to_lsc, CortexAState, WatchpointFields,
 };
 use crate::{
     architecture::arm::{
-        core::armv8a_debug_regs::*, memory::adi_v5_memory_interface::ArmProbe,
-        sequences::ArmDebugSequence, ArmError,
+        core::armv8a_debug_regs::*,
+        memory::adi_v5_memory_interface::ArmProbe,
+        sequences::{self, ArmDebugSequence},
+        ArmError,
     },
     core::{
         memory_mapped_registers::MemoryMappedRegister, CoreRegisters, RegisterId, RegisterValue,
+        VectorCatchCondition, Watchpoint, WatchpointKind,
     },
     error::Error,
     memory::valid_32bit_address,
     Architecture, CoreInformation, CoreInterface, CoreRegister, CoreStatus, CoreType,
-    InstructionSet, MemoryInterface,
+    InstructionSet, MemoryInterface, ResetType,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::{
     sync::Arc,
     time::{Duration, Instant},
@@ -62,6 +69,8 @@ pub struct Armv8a<'probe> {
 
     num_breakpoints: Option<u32>,
 
+    num_watchpoints: Option<u32>,
+
     id: usize,
 }
 
@@ -104,6 +113,7 @@ impl<'probe> Armv8a<'probe> {
             cti_address,
             sequence,
             num_breakpoints: None,
+            num_watchpoints: None,
             id,
         };
 
@@ -115,6 +125,33 @@ impl<'probe> Armv8a<'probe> {
         Ok(core)
     }
 
+    /// Sets or clears the reset catch in EDECR.
+    ///
+    /// The exception catch of ARMv8-A works on exception levels instead of vectors,
+    /// so only the reset catch is supported.
+    fn update_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        match condition {
+            VectorCatchCondition::CoreReset | VectorCatchCondition::All => {
+                let address = Edecr::get_mmio_address_from_base(self.base_address)?;
+                let mut edecr = Edecr(self.memory.read_word_32(address)?);
+
+                edecr.set_rce(enabled);
+
+                self.memory.write_word_32(address, edecr.into())?;
+
+                Ok(())
+            }
+            _ => Err(Error::Other(anyhow!(
+                "{:?} vector catch is not supported on ARMv8-A cores",
+                condition
+            ))),
+        }
+    }
+
     /// Execute an instruction
     fn execute_instruction(&mut self, instruction: u32) -> Result<Edscr, Error> {
         if !self.state.current_state.is_halted() {
@@ -402,25 +439,6 @@ impl<'probe> Armv8a<'probe> {
         }
     }
 
-    fn ack_cti_halt(&mut self) -> Result<(), Error> {
-        let mut ack = CtiIntack(0);
-        ack.set_ack(0, 1);
-
-        let address = CtiIntack::get_mmio_address_from_base(self.cti_address)?;
-        self.memory.write_word_32(address, ack.into())?;
-
-        loop {
-            let address = CtiTrigoutstatus::get_mmio_address_from_base(self.cti_address)?;
-            let trig_status = CtiTrigoutstatus(self.memory.read_word_32(address)?);
-
-            if trig_status.status(0) == 0 {
-                break;
-            }
-        }
-
-        Ok(())
-    }
-
     fn read_core_reg_32(&mut self, reg_num: u16) -> Result<RegisterValue, Error> {
         // Generate instruction to extract register
         match reg_num {
@@ -648,6 +666,44 @@ impl<'probe> Armv8a<'probe> {
         self.execute_instruction_with_result_32(instruction)
     }
 
+    fn read_cpu_memory_aarch32_16(&mut self, address: u64) -> Result<u16, Error> {
+        let address = valid_32bit_address(address)?;
+
+        // Save r0, r1
+        self.prepare_for_clobber(0)?;
+        self.prepare_for_clobber(1)?;
+
+        // Load r0 with the address to read from
+        self.set_reg_value(0, address.into())?;
+
+        // Read data to r1 - LDRH r1, [r0], #2
+        let instruction = build_ldrh(1, 0, 2);
+
+        self.execute_instruction(instruction)?;
+
+        // Move from r1 to transfer buffer - MCR p14, 0, r1, c0, c5, 0
+        let instruction = build_mcr(14, 0, 1, 0, 5, 0);
+        Ok(self.execute_instruction_with_result_32(instruction)? as u16)
+    }
+
+    fn read_cpu_memory_aarch64_16(&mut self, address: u64) -> Result<u16, Error> {
+        // Save x0, x1
+        self.prepare_for_clobber(0)?;
+        self.prepare_for_clobber(1)?;
+
+        // Load x0 with the address to read from
+        self.set_reg_value(0, address)?;
+
+        // Read data to w1 - LDRH w1, [x0], #2
+        let instruction = aarch64::build_ldrh(1, 0, 2);
+
+        self.execute_instruction(instruction)?;
+
+        // MSR DBGDTRTX_EL0, X1
+        let instruction = aarch64::build_msr(2, 3, 0, 5, 0, 1);
+        Ok(self.execute_instruction_with_result_32(instruction)? as u16)
+    }
+
     fn read_cpu_memory_aarch64_64(&mut self, address: u64) -> Result<u64, Error> {
         // Save x0, x1
         self.prepare_for_clobber(0)?;
@@ -702,6 +758,42 @@ impl<'probe> Armv8a<'probe> {
         Ok(())
     }
 
+    fn write_cpu_memory_aarch32_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        let address = valid_32bit_address(address)?;
+
+        // Save r0, r1
+        self.prepare_for_clobber(0)?;
+        self.prepare_for_clobber(1)?;
+
+        // Load r0 with the address to write to
+        self.set_reg_value(0, address.into())?;
+        self.set_reg_value(1, data.into())?;
+
+        // Write data to memory - STRH r1, [r0], #2
+        let instruction = build_strh(1, 0, 2);
+
+        self.execute_instruction(instruction)?;
+
+        Ok(())
+    }
+
+    fn write_cpu_memory_aarch64_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        // Save x0, x1
+        self.prepare_for_clobber(0)?;
+        self.prepare_for_clobber(1)?;
+
+        // Load x0 with the address to write to
+        self.set_reg_value(0, address)?;
+        self.set_reg_value(1, data.into())?;
+
+        // Write data to memory - STRH w1, [x0], #2
+        let instruction = aarch64::build_strh(1, 0, 2);
+
+        self.execute_instruction(instruction)?;
+
+        Ok(())
+    }
+
     fn write_cpu_memory_aarch64_64(&mut self, address: u64, data: u64) -> Result<(), Error> {
         // Save x0, x1
         self.prepare_for_clobber(0)?;
@@ -773,19 +865,8 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
 
     fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
         if !matches!(self.state.current_state, CoreStatus::Halted(_)) {
-            // Ungate halt CTI channel
-            let mut cti_gate = CtiGate(0);
-            cti_gate.set_en(0, 1);
-
-            let address = CtiGate::get_mmio_address_from_base(self.cti_address)?;
-            self.memory.write_word_32(address, cti_gate.into())?;
-
-            // Pulse it
-            let mut pulse = CtiApppulse(0);
-            pulse.set_apppulse(0, 1);
-
-            let address = CtiApppulse::get_mmio_address_from_base(self.cti_address)?;
-            self.memory.write_word_32(address, pulse.into())?;
+            // Pulse the halt CTI channel
+            cti::broadcast_channel(&mut *self.memory, self.cti_address, cti::CTI_HALT_CHANNEL)?;
 
             // Wait for halt
             self.wait_for_core_halted(timeout)?;
@@ -797,12 +878,6 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         // Update core status
         let _ = self.status()?;
 
-        // Gate halt channel
-        let cti_gate = CtiGate(0);
-
-        let address = CtiGate::get_mmio_address_from_base(self.cti_address)?;
-        self.memory.write_word_32(address, cti_gate.into())?;
-
         // try to read the program counter
         let pc_value = self.read_core_reg(self.program_counter().into())?;
 
@@ -819,50 +894,54 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         // set writeback values
         self.writeback_registers()?;
 
-        self.ack_cti_halt()?;
-
-        // Ungate restart CTI channel
-        let mut cti_gate = CtiGate(0);
-        cti_gate.set_en(1, 1);
-
-        let address = CtiGate::get_mmio_address_from_base(self.cti_address)?;
-        self.memory.write_word_32(address, cti_gate.into())?;
-
-        // Pulse it
-        let mut pulse = CtiApppulse(0);
-        pulse.set_apppulse(1, 1);
+        cti::acknowledge_halt(
+            &mut *self.memory,
+            self.cti_address,
+            Duration::from_millis(100),
+        )?;
 
-        let address = CtiApppulse::get_mmio_address_from_base(self.cti_address)?;
-        self.memory.write_word_32(address, pulse.into())?;
+        // Pulse the restart CTI channel
+        cti::broadcast_channel(
+            &mut *self.memory,
+            self.cti_address,
+            cti::CTI_RESTART_CHANNEL,
+        )?;
 
         // Wait for ack
         let address = Edprsr::get_mmio_address_from_base(self.base_address)?;
+        let start = Instant::now();
 
         loop {
             let edprsr = Edprsr(self.memory.read_word_32(address)?);
             if edprsr.sdr() {
                 break;
             }
+            if start.elapsed() > Duration::from_millis(100) {
+                return Err(Error::Arm(ArmError::Timeout));
+            }
         }
 
         // Recompute / verify current state
         self.set_core_status(CoreStatus::Running);
         let _ = self.status()?;
 
-        // Gate restart channel
-        let cti_gate = CtiGate(0);
+        Ok(())
+    }
 
-        let address = CtiGate::get_mmio_address_from_base(self.cti_address)?;
-        self.memory.write_word_32(address, cti_gate.into())?;
+    fn prepare_restart(&mut self) -> Result<(), Error> {
+        self.writeback_registers()?;
+        self.set_core_status(CoreStatus::Running);
 
         Ok(())
     }
 
-    fn reset(&mut self) -> Result<(), Error> {
-        self.sequence.reset_system(
+    fn reset(&mut self, reset_type: ResetType) -> Result<(), Error> {
+        sequences::reset_with_type(
+            &*self.sequence,
             &mut *self.memory,
             crate::CoreType::Armv8a,
             Some(self.base_address),
+            reset_type,
         )?;
 
         // Reset our cached values
@@ -871,16 +950,22 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         Ok(())
     }
 
-    fn reset_and_halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+    fn reset_and_halt(
+        &mut self,
+        timeout: Duration,
+        reset_type: ResetType,
+    ) -> Result<CoreInformation, Error> {
         self.sequence.reset_catch_set(
             &mut *self.memory,
             crate::CoreType::Armv8a,
             Some(self.base_address),
         )?;
-        self.sequence.reset_system(
+        sequences::reset_with_type(
+            &*self.sequence,
             &mut *self.memory,
             crate::CoreType::Armv8a,
             Some(self.base_address),
+            reset_type,
         )?;
 
         // Release from reset
@@ -1053,6 +1138,99 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        if self.num_watchpoints.is_none() {
+            let address = Eddfr::get_mmio_address_from_base(self.base_address)?;
+            let eddfr = Eddfr(self.memory.read_word_32(address)?);
+
+            self.num_watchpoints = Some(eddfr.wrps() + 1);
+        }
+        Ok(self.num_watchpoints.unwrap())
+    }
+
+    /// See docs on the [`CoreInterface::hw_watchpoints`] trait
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        let mut watchpoints = vec![];
+        let num_hw_watchpoints = self.available_watchpoint_units()? as usize;
+
+        for wp_unit_index in 0..num_hw_watchpoints {
+            let wp_value_addr = Dbgwvr::get_mmio_address_from_base(self.base_address)?
+                + (wp_unit_index * 16) as u64;
+            let mut wp_value = self.memory.read_word_32(wp_value_addr)? as u64;
+            wp_value |= (self.memory.read_word_32(wp_value_addr + 4)? as u64) << 32;
+
+            let wp_control_addr = Dbgwcr::get_mmio_address_from_base(self.base_address)?
+                + (wp_unit_index * 16) as u64;
+            let wp_control = Dbgwcr(self.memory.read_word_32(wp_control_addr)?);
+
+            match watchpoint_kind_from_lsc(wp_control.lsc()) {
+                Some(kind) if wp_control.e() => {
+                    let (address, length) = WatchpointFields {
+                        value: wp_value,
+                        bas: wp_control.bas(),
+                        mask: wp_control.mask(),
+                    }
+                    .range();
+
+                    watchpoints.push(Some(Watchpoint {
+                        address,
+                        length,
+                        kind,
+                    }));
+                }
+                _ => watchpoints.push(None),
+            }
+        }
+        Ok(watchpoints)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        length: u64,
+        kind: WatchpointKind,
+    ) -> Result<(), Error> {
+        let fields = WatchpointFields::new(address, length, 8)?;
+
+        let wp_value_addr =
+            Dbgwvr::get_mmio_address_from_base(self.base_address)? + (unit_index * 16) as u64;
+        let wp_control_addr =
+            Dbgwcr::get_mmio_address_from_base(self.base_address)? + (unit_index * 16) as u64;
+        let mut wp_control = Dbgwcr(0);
+
+        // Match on all modes
+        wp_control.set_hmc(true);
+        wp_control.set_pac(0b11);
+        wp_control.set_bas(fields.bas);
+        wp_control.set_mask(fields.mask);
+        wp_control.set_lsc(watchpoint_kind_to_lsc(kind));
+        // Enable
+        wp_control.set_e(true);
+
+        self.memory
+            .write_word_32(wp_value_addr, fields.value as u32)?;
+        self.memory
+            .write_word_32(wp_value_addr + 4, (fields.value >> 32) as u32)?;
+        self.memory
+            .write_word_32(wp_control_addr, wp_control.into())?;
+
+        Ok(())
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        let wp_value_addr =
+            Dbgwvr::get_mmio_address_from_base(self.base_address)? + (unit_index * 16) as u64;
+        let wp_control_addr =
+            Dbgwcr::get_mmio_address_from_base(self.base_address)? + (unit_index * 16) as u64;
+
+        self.memory.write_word_32(wp_value_addr, 0)?;
+        self.memory.write_word_32(wp_value_addr + 4, 0)?;
+        self.memory.write_word_32(wp_control_addr, 0)?;
+
+        Ok(())
+    }
+
     fn registers(&self) -> &'static CoreRegisters {
         if self.state.is_64_bit {
             &AARCH64_CORE_REGSISTERS
@@ -1163,6 +1341,45 @@ impl<'probe> CoreInterface for Armv8a<'probe> {
 
         Ok(())
     }
+
+    fn sync_instruction_cache(&mut self, address: u64, length: u64) -> Result<(), Error> {
+        self.prepare_for_clobber(0)?;
+
+        // Clean the data cache to the point of unification, so that the instruction fetches see
+        // the written instructions. They are at most two cache lines.
+        for line_address in [address, address + length.max(1) - 1] {
+            self.set_reg_value(0, line_address)?;
+            if self.state.is_64_bit {
+                // DC CVAU, x0
+                self.execute_instruction(aarch64::build_sys(3, 7, 11, 1, 0))?;
+            } else {
+                // DCCMVAU r0
+                self.execute_instruction(build_mcr(15, 0, 0, 7, 11, 1))?;
+            }
+        }
+
+        if self.state.is_64_bit {
+            // DSB ISH, IC IALLU, DSB ISH
+            self.execute_instruction(aarch64::build_dsb(0b1011))?;
+            self.execute_instruction(aarch64::build_sys(0, 7, 5, 0, 31))?;
+            self.execute_instruction(aarch64::build_dsb(0b1011))?;
+        } else {
+            // DSB ISH, ICIALLU, DSB ISH
+            self.execute_instruction(build_dsb(0b1011))?;
+            self.execute_instruction(build_mcr(15, 0, 0, 7, 5, 0))?;
+            self.execute_instruction(build_dsb(0b1011))?;
+        }
+
+        Ok(())
+    }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.update_vector_catch(condition, true)
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.update_vector_catch(condition, false)
+    }
 }
 
 impl<'probe> MemoryInterface for Armv8a<'probe> {
@@ -1189,6 +1406,21 @@ impl<'probe> MemoryInterface for Armv8a<'probe> {
         }
     }
 
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        if address & 1 != 0 {
+            return Err(Error::MemoryNotAligned {
+                address,
+                alignment: 2,
+            });
+        }
+
+        if self.state.is_64_bit {
+            self.read_cpu_memory_aarch64_16(address)
+        } else {
+            self.read_cpu_memory_aarch32_16(address)
+        }
+    }
+
     fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
         // Find the word this is in and its byte offset
         let byte_offset = address % 4;
@@ -1217,6 +1449,14 @@ impl<'probe> MemoryInterface for Armv8a<'probe> {
         Ok(())
     }
 
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        for (i, half_word) in data.iter_mut().enumerate() {
+            *half_word = self.read_word_16(address + ((i as u64) * 2))?;
+        }
+
+        Ok(())
+    }
+
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
         for (i, byte) in data.iter_mut().enumerate() {
             *byte = self.read_word_8(address + (i as u64))?;
@@ -1245,6 +1485,21 @@ impl<'probe> MemoryInterface for Armv8a<'probe> {
         }
     }
 
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        if address & 1 != 0 {
+            return Err(Error::MemoryNotAligned {
+                address,
+                alignment: 2,
+            });
+        }
+
+        if self.state.is_64_bit {
+            self.write_cpu_memory_aarch64_16(address, data)
+        } else {
+            self.write_cpu_memory_aarch32_16(address, data)
+        }
+    }
+
     fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
         // Find the word this is in and its byte offset
         let byte_offset = address % 4;
@@ -1274,9 +1529,17 @@ impl<'probe> MemoryInterface for Armv8a<'probe> {
         Ok(())
     }
 
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        for (i, half_word) in data.iter().enumerate() {
+            self.write_word_16(address + ((i as u64) * 2), *half_word)?;
+        }
+
+        Ok(())
+    }
+
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
         for (i, byte) in data.iter().enumerate() {
-            self.write_word_8(address + ((i as u64) * 4), *byte)?;
+            self.write_word_8(address + (i as u64), *byte)?;
         }
 
         Ok(())
@@ -1303,6 +1566,7 @@ mod test {
     };
 
     use super::*;
+    use crate::architecture::arm::core::{armv8a_debug_regs::CtiInen, cti::configure_cross_halt};
 
     const TEST_BASE_ADDRESS: u64 = 0x8000_1000;
     const TEST_CTI_ADDRESS: u64 = 0x8000_2000;
@@ -1354,6 +1618,10 @@ mod test {
             todo!()
         }
 
+        fn read_16(&mut self, _address: u64, _data: &mut [u16]) -> Result<(), ArmError> {
+            todo!()
+        }
+
         fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), ArmError> {
             if self.expected_ops.is_empty() {
                 panic!(
@@ -1389,6 +1657,10 @@ mod test {
             todo!()
         }
 
+        fn write_16(&mut self, _address: u64, _data: &[u16]) -> Result<(), ArmError> {
+            todo!()
+        }
+
         fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), ArmError> {
             if self.expected_ops.is_empty() {
                 panic!(
@@ -1590,26 +1862,28 @@ mod test {
         add_read_reg_64_expectations(probe, 0, value.into());
     }
 
-    fn add_halt_expectations(probe: &mut MockProbe) {
+    fn add_broadcast_expectations(probe: &mut MockProbe, channel: usize) {
+        // The gate of the other channels is left as it is.
         let mut cti_gate = CtiGate(0);
-        cti_gate.set_en(0, 1);
+        cti_gate.set_en(2, 1);
+        probe.expected_read(
+            CtiGate::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            cti_gate.into(),
+        );
 
+        let mut open_gate = cti_gate;
+        open_gate.set_en(channel, 1);
         probe.expected_write(
             CtiGate::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
-            cti_gate.into(),
+            open_gate.into(),
         );
 
         let mut pulse = CtiApppulse(0);
-        pulse.set_apppulse(0, 1);
-
+        pulse.set_apppulse(channel, 1);
         probe.expected_write(
             CtiApppulse::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
             pulse.into(),
         );
-    }
-
-    fn add_halt_cleanup_expectations(probe: &mut MockProbe) {
-        let cti_gate = CtiGate(0);
 
         probe.expected_write(
             CtiGate::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
@@ -1617,6 +1891,10 @@ mod test {
         );
     }
 
+    fn add_halt_expectations(probe: &mut MockProbe) {
+        add_broadcast_expectations(probe, 0);
+    }
+
     fn add_resume_expectations(probe: &mut MockProbe) {
         let mut ack = CtiIntack(0);
         ack.set_ack(0, 1);
@@ -1632,19 +1910,7 @@ mod test {
             status.into(),
         );
 
-        let mut cti_gate = CtiGate(0);
-        cti_gate.set_en(1, 1);
-        probe.expected_write(
-            CtiGate::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
-            cti_gate.into(),
-        );
-
-        let mut pulse = CtiApppulse(0);
-        pulse.set_apppulse(1, 1);
-        probe.expected_write(
-            CtiApppulse::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
-            pulse.into(),
-        );
+        add_broadcast_expectations(probe, 1);
 
         let mut edprsr = Edprsr(0);
         edprsr.set_sdr(true);
@@ -1654,14 +1920,6 @@ mod test {
         );
     }
 
-    fn add_resume_cleanup_expectations(probe: &mut MockProbe) {
-        let cti_gate = CtiGate(0);
-        probe.expected_write(
-            CtiGate::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
-            cti_gate.into(),
-        );
-    }
-
     fn add_idr_expectations(probe: &mut MockProbe, bp_count: u32) {
         let mut eddfr = Eddfr(0);
         eddfr.set_brps(bp_count - 1);
@@ -2197,7 +2455,6 @@ mod test {
 
         // Read status
         add_status_expectations(&mut probe, true);
-        add_halt_cleanup_expectations(&mut probe);
 
         // Read PC
         add_read_reg_expectations(&mut probe, 0, 0);
@@ -2236,8 +2493,6 @@ mod test {
         // Read status
         add_status_expectations(&mut probe, false);
 
-        add_resume_cleanup_expectations(&mut probe);
-
         let mock_mem = Box::new(probe) as _;
 
         let mut armv8a = Armv8a::new(
@@ -2450,6 +2705,58 @@ mod test {
         armv8a.clear_hw_breakpoint(0).unwrap();
     }
 
+    #[test]
+    fn armv8a_set_hw_watchpoint() {
+        const WP_ADDRESS: u64 = 0x2340;
+        let mut probe = MockProbe::new(false);
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+
+        // Update WP value and control
+        let mut dbgwcr = Dbgwcr(0);
+        // Match on all modes
+        dbgwcr.set_hmc(true);
+        dbgwcr.set_pac(0b11);
+        // Match on all bytes of a 64 byte range
+        dbgwcr.set_bas(0b1111_1111);
+        dbgwcr.set_mask(6);
+        // Match on loads and stores
+        dbgwcr.set_lsc(0b11);
+        // Enable
+        dbgwcr.set_e(true);
+
+        probe.expected_write(
+            Dbgwvr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            WP_ADDRESS as u32,
+        );
+        probe.expected_write(
+            Dbgwvr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap() + 4,
+            0,
+        );
+        probe.expected_write(
+            Dbgwcr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgwcr.into(),
+        );
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv8a = Armv8a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            TEST_CTI_ADDRESS,
+            DefaultArmSequence::create(),
+            0,
+        )
+        .unwrap();
+
+        armv8a
+            .set_hw_watchpoint(0, WP_ADDRESS, 64, WatchpointKind::Access)
+            .unwrap();
+    }
+
     #[test]
     fn armv8a_read_word_32() {
         const MEMORY_VALUE: u32 = 0xBA5EBA11;
@@ -2514,6 +2821,110 @@ mod test {
         assert_eq!(MEMORY_VALUE, armv8a.read_word_32(MEMORY_ADDRESS).unwrap());
     }
 
+    #[test]
+    fn armv8a_read_word_16() {
+        const MEMORY_VALUE: u16 = 0xBA5E;
+        const MEMORY_ADDRESS: u64 = 0x1234567A;
+
+        let mut probe = MockProbe::new(false);
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+
+        // Read memory
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_reg_expectations(&mut probe, 1, 0);
+
+        add_set_r0_expectation(&mut probe, MEMORY_ADDRESS as u32);
+
+        let mut edscr = Edscr(0);
+        edscr.set_ite(true);
+        edscr.set_txfull(true);
+
+        probe.expected_write(
+            Editr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            prep_instr_for_itr_32(build_ldrh(1, 0, 2)),
+        );
+        probe.expected_read(
+            Edscr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            edscr.into(),
+        );
+        add_read_reg_expectations(&mut probe, 1, MEMORY_VALUE.into());
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv8a = Armv8a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            TEST_CTI_ADDRESS,
+            DefaultArmSequence::create(),
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(MEMORY_VALUE, armv8a.read_word_16(MEMORY_ADDRESS).unwrap());
+    }
+
+    #[test]
+    fn armv8a_read_word_16_aarch64() {
+        const MEMORY_VALUE: u16 = 0xBA5E;
+        const MEMORY_ADDRESS: u64 = 0x1234567A;
+
+        let mut probe = MockProbe::new(true);
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+
+        // Read memory
+        add_read_reg_64_expectations(&mut probe, 0, 0);
+        add_read_reg_64_expectations(&mut probe, 1, 0);
+
+        add_set_x0_expectation(&mut probe, MEMORY_ADDRESS);
+
+        let mut edscr = Edscr(0);
+        edscr.set_ite(true);
+        edscr.set_txfull(true);
+
+        probe.expected_write(
+            Editr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            aarch64::build_ldrh(1, 0, 2),
+        );
+        probe.expected_read(
+            Edscr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            edscr.into(),
+        );
+
+        probe.expected_write(
+            Editr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            aarch64::build_msr(2, 3, 0, 5, 0, 1),
+        );
+        probe.expected_read(
+            Edscr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            edscr.into(),
+        );
+        probe.expected_read(
+            Dbgdtrtx::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            MEMORY_VALUE.into(),
+        );
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv8a = Armv8a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            TEST_CTI_ADDRESS,
+            DefaultArmSequence::create(),
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(MEMORY_VALUE, armv8a.read_word_16(MEMORY_ADDRESS).unwrap());
+    }
+
     #[test]
     fn armv8a_read_word_8() {
         const MEMORY_VALUE: u32 = 0xBA5EBA11;
@@ -2577,4 +2988,117 @@ mod test {
 
         assert_eq!(0xBA, armv8a.read_word_8(MEMORY_ADDRESS).unwrap());
     }
+
+    #[test]
+    fn cti_configure_cross_halt() {
+        let mut probe = MockProbe::new(false);
+
+        // CTICONTROL: enable the CTI
+        probe.expected_write(TEST_CTI_ADDRESS, 1);
+        // CTIOUTEN0: channel 0 requests a halt
+        probe.expected_write(
+            CtiOuten::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            1,
+        );
+        // CTIOUTEN1: channel 1 requests a restart
+        probe.expected_write(
+            CtiOuten::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap() + 4,
+            2,
+        );
+        // CTIINEN0: the halted event is broadcast on channel 0
+        probe.expected_write(
+            CtiInen::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            1,
+        );
+        // CTIGATE: channel 0 is passed to the other cores
+        probe.expected_write(
+            CtiGate::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            1,
+        );
+
+        configure_cross_halt(&mut probe, TEST_CTI_ADDRESS, true).unwrap();
+
+        assert!(probe.expected_ops.is_empty());
+    }
+
+    #[test]
+    fn cti_disable_cross_halt() {
+        let mut probe = MockProbe::new(false);
+
+        probe.expected_write(TEST_CTI_ADDRESS, 1);
+        probe.expected_write(
+            CtiOuten::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            1,
+        );
+        probe.expected_write(
+            CtiOuten::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap() + 4,
+            2,
+        );
+        // The halted event and channel 0 are no longer passed on
+        probe.expected_write(
+            CtiInen::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            0,
+        );
+        probe.expected_write(
+            CtiGate::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            0,
+        );
+
+        configure_cross_halt(&mut probe, TEST_CTI_ADDRESS, false).unwrap();
+
+        assert!(probe.expected_ops.is_empty());
+    }
+
+    #[test]
+    fn cti_broadcast_channel_restores_gate() {
+        let mut probe = MockProbe::new(false);
+
+        add_broadcast_expectations(&mut probe, cti::CTI_RESTART_CHANNEL);
+
+        cti::broadcast_channel(&mut probe, TEST_CTI_ADDRESS, cti::CTI_RESTART_CHANNEL).unwrap();
+
+        assert!(probe.expected_ops.is_empty());
+    }
+
+    #[test]
+    fn cti_acknowledge_halt_waits_for_trigger() {
+        let mut probe = MockProbe::new(false);
+
+        probe.expected_write(
+            CtiIntack::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            1,
+        );
+        // The halt trigger output is still asserted on the first read
+        probe.expected_read(
+            CtiTrigoutstatus::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            1,
+        );
+        probe.expected_read(
+            CtiTrigoutstatus::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            0,
+        );
+
+        cti::acknowledge_halt(&mut probe, TEST_CTI_ADDRESS, Duration::from_millis(100)).unwrap();
+
+        assert!(probe.expected_ops.is_empty());
+    }
+
+    #[test]
+    fn cti_acknowledge_halt_timeout() {
+        let mut probe = MockProbe::new(false);
+
+        probe.expected_write(
+            CtiIntack::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            1,
+        );
+        probe.expected_read(
+            CtiTrigoutstatus::get_mmio_address_from_base(TEST_CTI_ADDRESS).unwrap(),
+            1,
+        );
+
+        assert!(matches!(
+            cti::acknowledge_halt(&mut probe, TEST_CTI_ADDRESS, Duration::ZERO),
+            Err(ArmError::Timeout)
+        ));
+    }
 }
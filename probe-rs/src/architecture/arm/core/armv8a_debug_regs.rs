@@ -1,6 +1,9 @@
 //! Debug register definitions for ARMv8-A
 
-use crate::{core::BreakpointCause, memory_mapped_bitfield_register, HaltReason};
+use crate::{
+    core::{BreakpointCause, VectorCatchCondition},
+    memory_mapped_bitfield_register, HaltReason,
+};
 
 memory_mapped_bitfield_register! {
     /// EDSCR - Debug Status and Control Register
@@ -130,7 +133,7 @@ impl Edscr {
             // OS Unlock catch.
             0b100011 => HaltReason::Exception,
             // Reset catch.
-            0b100111 => HaltReason::Exception,
+            0b100111 => HaltReason::VectorCatch(VectorCatchCondition::CoreReset),
             // Watchpoint
             0b101011 => HaltReason::Watchpoint,
             // HLT instruction - causes entry into Debug state.
@@ -194,6 +197,50 @@ memory_mapped_bitfield_register! {
     pub e, set_e: 0;
 }
 
+memory_mapped_bitfield_register! {
+    /// DBGWVR - Watchpoint Value Register
+    pub struct Dbgwvr(u32);
+    512, "DBGWVR",
+    impl From;
+
+    /// Watchpoint address
+    pub value, set_value : 31, 0;
+}
+
+memory_mapped_bitfield_register! {
+    /// DBGWCR - Watchpoint Control Register
+    pub struct Dbgwcr(u32);
+    514, "DBGWCR",
+    impl From;
+
+    /// Address range mask
+    pub mask, set_mask : 28, 24;
+
+    /// Watchpoint type
+    pub wt, set_wt : 20;
+
+    /// Linked breakpoint number
+    pub lbn, set_lbn : 19, 16;
+
+    /// Security state control
+    pub ssc, set_ssc : 15, 14;
+
+    /// Hyp mode control bit
+    pub hmc, set_hmc: 13;
+
+    /// Byte address select
+    pub bas, set_bas: 12, 5;
+
+    /// Load/store access control
+    pub lsc, set_lsc: 4, 3;
+
+    /// Privileged access control
+    pub pac, set_pac: 2, 1;
+
+    /// Watchpoint enable
+    pub e, set_e: 0;
+}
+
 memory_mapped_bitfield_register! {
     /// EDDFR - External Debug Feature Register
     pub struct Eddfr(u32);
@@ -204,7 +251,7 @@ memory_mapped_bitfield_register! {
     pub ctx_cmps, _: 31, 28;
 
     /// Number of watchpoints, minus 1.
-    pub wrps, _: 23, 20;
+    pub wrps, set_wrps: 23, 20;
 
     /// Number of breakpoints, minus 1
     pub brps, set_brps: 15, 12;
@@ -367,6 +414,16 @@ memory_mapped_bitfield_register! {
     pub outen, set_outen : 0, 0, 32;
 }
 
+memory_mapped_bitfield_register! {
+    /// CTIINEN<n> - CTI input trigger to output channel enable register
+    pub struct CtiInen(u32);
+    8, "CTIINEN",
+    impl From;
+
+    /// Enables or disables input trigger <n> generating an event on channel N
+    pub inen, set_inen : 0, 0, 32;
+}
+
 memory_mapped_bitfield_register! {
     /// CTIAPPPULSE - CTI application pulse register
     pub struct CtiApppulse(u32);
@@ -1,7 +1,7 @@
 //! Register types and the core interface for armv8-M
 
 use super::{
-    cortex_m::Mvfr0,
+    cortex_m::{DwtVersion, Mvfr0},
     registers::cortex_m::{
         CORTEX_M_CORE_REGSISTERS, CORTEX_M_WITH_FP_CORE_REGSISTERS, FP, PC, RA, SP,
     },
@@ -9,14 +9,19 @@ use super::{
 };
 use crate::{
     architecture::arm::{
-        core::registers::cortex_m::XPSR, memory::adi_v5_memory_interface::ArmProbe,
-        sequences::ArmDebugSequence, ArmError,
+        core::registers::cortex_m::XPSR,
+        memory::adi_v5_memory_interface::ArmProbe,
+        sequences::{self, ArmDebugSequence},
+        ArmError,
+    },
+    core::{
+        CoreRegisters, RegisterDataType, RegisterId, RegisterRole, RegisterValue,
+        VectorCatchCondition, Watchpoint, WatchpointKind,
     },
-    core::{CoreRegisters, RegisterDataType, RegisterId, RegisterRole, RegisterValue},
     error::Error,
     memory::valid_32bit_address,
     Architecture, CoreInformation, CoreInterface, CoreRegister, CoreStatus, CoreType, HaltReason,
-    InstructionSet, MemoryInterface, MemoryMappedRegister,
+    InstructionSet, MemoryInterface, MemoryMappedRegister, ResetType,
 };
 use anyhow::Result;
 use bitfield::bitfield;
@@ -132,6 +137,45 @@ impl<'probe> Armv8m<'probe> {
         })
     }
 
+    /// Sets or clears the DEMCR vector catch bits for `condition`.
+    fn update_vector_catch(
+        &mut self,
+        condition: VectorCatchCondition,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        let update: fn(&mut Demcr, bool) = match condition {
+            VectorCatchCondition::CoreReset => Demcr::set_vc_corereset,
+            VectorCatchCondition::HardFault => Demcr::set_vc_harderr,
+            VectorCatchCondition::MemManage => Demcr::set_vc_mmerr,
+            VectorCatchCondition::BusFault => Demcr::set_vc_buserr,
+            VectorCatchCondition::UsageFault => |demcr, enabled| {
+                demcr.set_vc_staterr(enabled);
+                demcr.set_vc_chkerr(enabled);
+                demcr.set_vc_nocperr(enabled);
+            },
+            VectorCatchCondition::InterruptError => Demcr::set_vc_interr,
+            VectorCatchCondition::SecureFault => Demcr::set_vc_sferr,
+            VectorCatchCondition::All => |demcr, enabled| {
+                demcr.set_vc_corereset(enabled);
+                demcr.set_vc_harderr(enabled);
+                demcr.set_vc_mmerr(enabled);
+                demcr.set_vc_buserr(enabled);
+                demcr.set_vc_staterr(enabled);
+                demcr.set_vc_chkerr(enabled);
+                demcr.set_vc_nocperr(enabled);
+                demcr.set_vc_interr(enabled);
+                demcr.set_vc_sferr(enabled);
+            },
+        };
+
+        let mut demcr = Demcr(self.memory.read_word_32(Demcr::get_mmio_address())?);
+        update(&mut demcr, enabled);
+        self.memory
+            .write_word_32(Demcr::get_mmio_address(), demcr.into())?;
+
+        Ok(())
+    }
+
     fn set_core_status(&mut self, new_status: CoreStatus) {
         super::update_core_status(&mut self.memory, &mut self.state.current_state, new_status);
     }
@@ -186,7 +230,11 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         if dhcsr.s_halt() {
             let dfsr = Dfsr(self.memory.read_word_32(Dfsr::get_mmio_address())?);
 
-            let reason = dfsr.halt_reason();
+            let mut reason = dfsr.halt_reason();
+
+            if reason == HaltReason::Exception {
+                reason = super::cortex_m::vector_catch_halt_reason(&mut *self.memory)?;
+            }
 
             // Clear bits from Dfsr register
             self.memory
@@ -266,19 +314,43 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         Ok(())
     }
 
-    fn reset(&mut self) -> Result<(), Error> {
-        self.sequence
-            .reset_system(&mut *self.memory, crate::CoreType::Armv8m, None)?;
+    fn prepare_restart(&mut self) -> Result<(), Error> {
+        // Step over a breakpoint at the current instruction, like `run` does.
+        self.step()?;
+        super::cortex_m::prepare_restart(&mut *self.memory)?;
+
+        self.set_core_status(CoreStatus::Running);
+
         Ok(())
     }
 
-    fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+    fn reset(&mut self, reset_type: ResetType) -> Result<(), Error> {
+        sequences::reset_with_type(
+            &*self.sequence,
+            &mut *self.memory,
+            crate::CoreType::Armv8m,
+            None,
+            reset_type,
+        )?;
+        Ok(())
+    }
+
+    fn reset_and_halt(
+        &mut self,
+        _timeout: Duration,
+        reset_type: ResetType,
+    ) -> Result<CoreInformation, Error> {
         // Set the vc_corereset bit in the DEMCR register.
         // This will halt the core after reset.
         self.reset_catch_set()?;
 
-        self.sequence
-            .reset_system(&mut *self.memory, crate::CoreType::Armv8m, None)?;
+        sequences::reset_with_type(
+            &*self.sequence,
+            &mut *self.memory,
+            crate::CoreType::Armv8m,
+            None,
+            reset_type,
+        )?;
 
         // Update core status
         let _ = self.status()?;
@@ -442,6 +514,39 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        super::cortex_m::available_watchpoint_units(&mut *self.memory)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        super::cortex_m::hw_watchpoints(&mut *self.memory, DwtVersion::Armv8m)
+    }
+
+    fn reserved_watchpoint_units(&mut self) -> Result<Vec<bool>, Error> {
+        super::cortex_m::reserved_watchpoint_units(&mut *self.memory, DwtVersion::Armv8m)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        length: u64,
+        kind: WatchpointKind,
+    ) -> Result<(), Error> {
+        super::cortex_m::set_hw_watchpoint(
+            &mut *self.memory,
+            DwtVersion::Armv8m,
+            unit_index,
+            address,
+            length,
+            kind,
+        )
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        super::cortex_m::clear_hw_watchpoint(&mut *self.memory, unit_index)
+    }
+
     fn registers(&self) -> &'static CoreRegisters {
         if self.state.fp_present {
             &CORTEX_V8_M_CORE_REGISTERS
@@ -513,6 +618,18 @@ impl<'probe> CoreInterface for Armv8m<'probe> {
 
         Ok(())
     }
+
+    fn sync_instruction_cache(&mut self, address: u64, length: u64) -> Result<(), Error> {
+        super::cortex_m::sync_instruction_cache(&mut *self.memory, address, length)
+    }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.update_vector_catch(condition, true)
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.update_vector_catch(condition, false)
+    }
 }
 
 impl<'probe> MemoryInterface for Armv8m<'probe> {
@@ -524,6 +641,12 @@ impl<'probe> MemoryInterface for Armv8m<'probe> {
             .read_word_32(address)
             .map_err(From::<ArmError>::from)
     }
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        self.memory
+            .read_word_16(address)
+            .map_err(From::<ArmError>::from)
+    }
+
     fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
         self.memory
             .read_word_8(address)
@@ -542,6 +665,12 @@ impl<'probe> MemoryInterface for Armv8m<'probe> {
             .map_err(From::<ArmError>::from)
     }
 
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        self.memory
+            .read_16(address, data)
+            .map_err(From::<ArmError>::from)
+    }
+
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
         self.memory
             .read_8(address, data)
@@ -566,6 +695,12 @@ impl<'probe> MemoryInterface for Armv8m<'probe> {
             .map_err(From::<ArmError>::from)
     }
 
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        self.memory
+            .write_word_16(address, data)
+            .map_err(From::<ArmError>::from)
+    }
+
     fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
         self.memory
             .write_word_8(address, data)
@@ -584,6 +719,12 @@ impl<'probe> MemoryInterface for Armv8m<'probe> {
             .map_err(From::<ArmError>::from)
     }
 
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        self.memory
+            .write_16(address, data)
+            .map_err(From::<ArmError>::from)
+    }
+
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
         self.memory
             .write_8(address, data)
@@ -1,10 +1,13 @@
 //! Common functions and data types for Cortex-M core variants
 
+use super::{armv7m::Demcr, registers::cortex_m::XPSR};
 use crate::{
-    architecture::arm::{memory::adi_v5_memory_interface::ArmProbe, ArmError},
-    core::RegisterId,
+    architecture::arm::{component::dwt, memory::adi_v5_memory_interface::ArmProbe, ArmError},
+    core::{HaltReason, RegisterId, VectorCatchCondition, Watchpoint, WatchpointKind},
+    memory::valid_32bit_address,
     memory_mapped_bitfield_register, Error, MemoryMappedRegister,
 };
+use anyhow::anyhow;
 use std::time::{Duration, Instant};
 
 memory_mapped_bitfield_register! {
@@ -80,6 +83,31 @@ impl Mvfr0 {
     }
 }
 
+memory_mapped_bitfield_register! {
+    /// Configuration and Control Register
+    pub struct Ccr(u32);
+    0xE000_ED14, "CCR",
+    impl From;
+    /// Instruction cache enable. RAZ if the core has no instruction cache.
+    pub ic, _: 17;
+    /// Data cache enable. RAZ if the core has no data cache.
+    pub dc, _: 16;
+}
+
+memory_mapped_bitfield_register! {
+    /// Instruction cache invalidate by address to the point of unification
+    pub struct Icimvau(u32);
+    0xE000_EF58, "ICIMVAU",
+    impl From;
+}
+
+memory_mapped_bitfield_register! {
+    /// Data cache clean by address to the point of unification
+    pub struct Dccmvau(u32);
+    0xE000_EF64, "DCCMVAU",
+    impl From;
+}
+
 pub(crate) fn read_core_reg(memory: &mut dyn ArmProbe, addr: RegisterId) -> Result<u32, Error> {
     // Write the DCRSR value to select the register we want to read.
     let mut dcrsr_val = Dcrsr(0);
@@ -114,6 +142,80 @@ pub(crate) fn write_core_reg(
     Ok(())
 }
 
+/// Makes instructions written to memory visible to the instruction fetches of a core with caches,
+/// like the Cortex-M7.
+///
+/// The data cache lines are cleaned and the instruction cache lines are invalidated. Caches which
+/// are not enabled in CCR, or not implemented, are left alone.
+pub(crate) fn sync_instruction_cache(
+    memory: &mut dyn ArmProbe,
+    address: u64,
+    length: u64,
+) -> Result<(), Error> {
+    let ccr = Ccr(memory.read_word_32(Ccr::get_mmio_address())?);
+
+    if !ccr.ic() && !ccr.dc() {
+        return Ok(());
+    }
+
+    let first = valid_32bit_address(address)?;
+    let last = valid_32bit_address(address + length.max(1) - 1)?;
+
+    // An instruction spans at most two cache lines.
+    for line_address in [first, last] {
+        if ccr.dc() {
+            memory.write_word_32(Dccmvau::get_mmio_address(), line_address)?;
+        }
+        if ccr.ic() {
+            memory.write_word_32(Icimvau::get_mmio_address(), line_address)?;
+        }
+    }
+
+    memory.flush()?;
+
+    Ok(())
+}
+
+/// Keeps the core halted, but clears the single step and interrupt masking bits left behind
+/// by a step, so that the core runs normally once it is restarted by its CTI.
+pub(crate) fn prepare_restart(memory: &mut dyn ArmProbe) -> Result<(), Error> {
+    let mut value = Dhcsr(0);
+    value.set_c_halt(true);
+    value.set_c_debugen(true);
+    value.enable_write();
+
+    memory.write_word_32(Dhcsr::get_mmio_address(), value.into())?;
+    memory.flush()?;
+
+    Ok(())
+}
+
+/// Determines which vector catch halted the core, based on the exception number in IPSR.
+///
+/// Returns [`HaltReason::Exception`] if the vector catch cannot be identified.
+pub(crate) fn vector_catch_halt_reason(memory: &mut dyn ArmProbe) -> Result<HaltReason, Error> {
+    let xpsr = read_core_reg(memory, XPSR.id)?;
+
+    Ok(match vector_catch_condition(xpsr & 0x1ff) {
+        Some(condition) => HaltReason::VectorCatch(condition),
+        None => HaltReason::Exception,
+    })
+}
+
+/// Maps the exception number of the handler the core halted in to the vector catch which fired.
+fn vector_catch_condition(exception_number: u32) -> Option<VectorCatchCondition> {
+    match exception_number {
+        // The reset vector catch halts before the first instruction, in thread mode.
+        0 => Some(VectorCatchCondition::CoreReset),
+        3 => Some(VectorCatchCondition::HardFault),
+        4 => Some(VectorCatchCondition::MemManage),
+        5 => Some(VectorCatchCondition::BusFault),
+        6 => Some(VectorCatchCondition::UsageFault),
+        7 => Some(VectorCatchCondition::SecureFault),
+        _ => None,
+    }
+}
+
 fn wait_for_core_register_transfer(
     memory: &mut dyn ArmProbe,
     timeout: Duration,
@@ -131,3 +233,298 @@ fn wait_for_core_register_transfer(
     }
     Err(ArmError::Timeout)
 }
+
+/// The layout of the DWT comparator registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DwtVersion {
+    /// The DWT of ARMv6-M and ARMv7-M cores, which uses `DWT_MASKn` to watch address ranges.
+    Armv7m,
+    /// The DWT of ARMv8-M cores, which uses `DWT_FUNCTIONn.DATAVSIZE` to watch address ranges.
+    Armv8m,
+}
+
+/// The base address of the DWT, which is fixed on all Cortex-M cores.
+const DWT_BASE_ADDRESS: u64 = 0xE000_1000;
+
+/// Offset between the register sets of two consecutive DWT comparators.
+const DWT_COMPARATOR_STRIDE: u64 = 16;
+
+fn dwt_register_address<R: MemoryMappedRegister<u32>>(unit_index: usize) -> u64 {
+    DWT_BASE_ADDRESS + R::ADDRESS_OFFSET + unit_index as u64 * DWT_COMPARATOR_STRIDE
+}
+
+/// Returns the number of DWT comparators, which can be used as data watchpoints.
+pub(crate) fn available_watchpoint_units(memory: &mut dyn ArmProbe) -> Result<u32, Error> {
+    let ctrl = dwt::Ctrl(memory.read_word_32(DWT_BASE_ADDRESS + dwt::Ctrl::ADDRESS_OFFSET)?);
+
+    Ok(ctrl.numcomp().into())
+}
+
+/// The use of a DWT comparator, as configured in its function register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DwtComparatorUse {
+    /// The comparator is disabled.
+    Free,
+    /// The comparator halts the core on data accesses.
+    Watchpoint(WatchpointKind),
+    /// The comparator is used for something else, e.g. data tracing with the ITM, or as a
+    /// trigger of the ETM.
+    Other,
+}
+
+/// Decodes the use of a DWT comparator from the value of its function register.
+fn dwt_comparator_use(function: u32, version: DwtVersion) -> DwtComparatorUse {
+    match version {
+        DwtVersion::Armv7m => {
+            let function = dwt::Function(function);
+
+            let kind = match function.function() {
+                0b0000 => return DwtComparatorUse::Free,
+                0b0101 => WatchpointKind::Read,
+                0b0110 => WatchpointKind::Write,
+                0b0111 => WatchpointKind::Access,
+                _ => return DwtComparatorUse::Other,
+            };
+
+            // Data value and cycle count matches are not address watchpoints.
+            if function.datavmatch() || function.cycmatch() {
+                DwtComparatorUse::Other
+            } else {
+                DwtComparatorUse::Watchpoint(kind)
+            }
+        }
+        DwtVersion::Armv8m => {
+            let function = dwt::FunctionV8(function);
+
+            // Only comparators which generate a debug event are watchpoints.
+            match (function.action(), function.match_()) {
+                (_, 0b0000) => DwtComparatorUse::Free,
+                (0b01, 0b0100) => DwtComparatorUse::Watchpoint(WatchpointKind::Access),
+                (0b01, 0b0101) => DwtComparatorUse::Watchpoint(WatchpointKind::Write),
+                (0b01, 0b0110) => DwtComparatorUse::Watchpoint(WatchpointKind::Read),
+                _ => DwtComparatorUse::Other,
+            }
+        }
+    }
+}
+
+/// Reads the use of all DWT comparators.
+fn dwt_comparator_uses(
+    memory: &mut dyn ArmProbe,
+    version: DwtVersion,
+) -> Result<Vec<DwtComparatorUse>, Error> {
+    let num_units = available_watchpoint_units(memory)? as usize;
+
+    let mut uses = Vec::with_capacity(num_units);
+    for unit_index in 0..num_units {
+        let function = memory.read_word_32(dwt_register_address::<dwt::Function>(unit_index))?;
+        uses.push(dwt_comparator_use(function, version));
+    }
+
+    Ok(uses)
+}
+
+/// Reads the data watchpoints configured in the DWT comparators.
+pub(crate) fn hw_watchpoints(
+    memory: &mut dyn ArmProbe,
+    version: DwtVersion,
+) -> Result<Vec<Option<Watchpoint>>, Error> {
+    let uses = dwt_comparator_uses(memory, version)?;
+
+    let mut watchpoints = Vec::with_capacity(uses.len());
+    for (unit_index, comparator_use) in uses.into_iter().enumerate() {
+        let kind = match comparator_use {
+            DwtComparatorUse::Watchpoint(kind) => kind,
+            DwtComparatorUse::Free | DwtComparatorUse::Other => {
+                watchpoints.push(None);
+                continue;
+            }
+        };
+
+        let length = match version {
+            DwtVersion::Armv7m => {
+                let mask =
+                    dwt::Mask(memory.read_word_32(dwt_register_address::<dwt::Mask>(unit_index))?);
+                1u64 << mask.mask()
+            }
+            DwtVersion::Armv8m => {
+                let function =
+                    dwt::FunctionV8(
+                        memory.read_word_32(dwt_register_address::<dwt::Function>(unit_index))?,
+                    );
+                1u64 << function.datavsize()
+            }
+        };
+
+        let address = memory.read_word_32(dwt_register_address::<dwt::Comp>(unit_index))?;
+
+        watchpoints.push(Some(Watchpoint {
+            address: address as u64,
+            length,
+            kind,
+        }));
+    }
+
+    Ok(watchpoints)
+}
+
+/// Returns which DWT comparators are used for something else than watchpoints, e.g. by an
+/// active data trace, and must not be used for new watchpoints.
+pub(crate) fn reserved_watchpoint_units(
+    memory: &mut dyn ArmProbe,
+    version: DwtVersion,
+) -> Result<Vec<bool>, Error> {
+    Ok(dwt_comparator_uses(memory, version)?
+        .into_iter()
+        .map(|comparator_use| comparator_use == DwtComparatorUse::Other)
+        .collect())
+}
+
+/// Configures DWT comparator `unit_index` to halt the core on accesses to the given memory range.
+pub(crate) fn set_hw_watchpoint(
+    memory: &mut dyn ArmProbe,
+    version: DwtVersion,
+    unit_index: usize,
+    address: u64,
+    length: u64,
+    kind: WatchpointKind,
+) -> Result<(), Error> {
+    let address = valid_32bit_address(address)?;
+
+    if !length.is_power_of_two() || address as u64 & (length - 1) != 0 {
+        return Err(Error::Other(anyhow!(
+            "Unable to watch {} bytes at address {:#010x}. The length must be a power of two, and the address must be aligned to it.",
+            length,
+            address
+        )));
+    }
+
+    // The DWT only works if it is globally enabled in DEMCR.
+    let mut demcr = Demcr(memory.read_word_32(Demcr::get_mmio_address())?);
+    if !demcr.trcena() {
+        demcr.set_trcena(true);
+        memory.write_word_32(Demcr::get_mmio_address(), demcr.into())?;
+    }
+
+    let function: u32 = match version {
+        DwtVersion::Armv7m => {
+            let mask = length.trailing_zeros();
+
+            // DWT_MASKn is implementation defined in width, so read back the value to check
+            // if the requested range is supported.
+            let mask_address = dwt_register_address::<dwt::Mask>(unit_index);
+            memory.write_word_32(mask_address, mask)?;
+            let written_mask = dwt::Mask(memory.read_word_32(mask_address)?).mask();
+            if written_mask != mask {
+                return Err(Error::Other(anyhow!(
+                    "Unable to watch {} bytes, the DWT supports watching at most {} bytes.",
+                    length,
+                    1u64 << written_mask
+                )));
+            }
+
+            let mut function = dwt::Function(0);
+            function.set_function(match kind {
+                WatchpointKind::Read => 0b0101,
+                WatchpointKind::Write => 0b0110,
+                WatchpointKind::Access => 0b0111,
+            });
+            function.into()
+        }
+        DwtVersion::Armv8m => {
+            if length > 4 {
+                return Err(Error::Other(anyhow!(
+                    "Unable to watch {} bytes, the DWT supports watching at most 4 bytes.",
+                    length
+                )));
+            }
+
+            let mut function = dwt::FunctionV8(0);
+            // Generate a debug event, i.e. halt the core.
+            function.set_action(0b01);
+            function.set_match(match kind {
+                WatchpointKind::Access => 0b0100,
+                WatchpointKind::Write => 0b0101,
+                WatchpointKind::Read => 0b0110,
+            });
+            function.set_datavsize(length.trailing_zeros() as u8);
+            function.into()
+        }
+    };
+
+    memory.write_word_32(dwt_register_address::<dwt::Comp>(unit_index), address)?;
+    memory.write_word_32(dwt_register_address::<dwt::Function>(unit_index), function)?;
+
+    Ok(())
+}
+
+/// Disables DWT comparator `unit_index`.
+pub(crate) fn clear_hw_watchpoint(
+    memory: &mut dyn ArmProbe,
+    unit_index: usize,
+) -> Result<(), Error> {
+    memory.write_word_32(dwt_register_address::<dwt::Function>(unit_index), 0)?;
+    memory.write_word_32(dwt_register_address::<dwt::Comp>(unit_index), 0)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{dwt_comparator_use, vector_catch_condition, DwtComparatorUse, DwtVersion};
+    use crate::core::{VectorCatchCondition, WatchpointKind};
+
+    #[test]
+    fn dwt_comparators_used_for_tracing_are_reserved() {
+        // ARMv7-M: disabled, write watchpoint, data address trace and PC trace.
+        assert_eq!(
+            dwt_comparator_use(0x0, DwtVersion::Armv7m),
+            DwtComparatorUse::Free
+        );
+        assert_eq!(
+            dwt_comparator_use(0x6, DwtVersion::Armv7m),
+            DwtComparatorUse::Watchpoint(WatchpointKind::Write)
+        );
+        assert_eq!(
+            dwt_comparator_use(0x1, DwtVersion::Armv7m),
+            DwtComparatorUse::Other
+        );
+        assert_eq!(
+            dwt_comparator_use(0x3, DwtVersion::Armv7m),
+            DwtComparatorUse::Other
+        );
+
+        // ARMv8-M: disabled, read watchpoint, and data address trace with a data value packet.
+        assert_eq!(
+            dwt_comparator_use(0x00, DwtVersion::Armv8m),
+            DwtComparatorUse::Free
+        );
+        assert_eq!(
+            dwt_comparator_use(0x16, DwtVersion::Armv8m),
+            DwtComparatorUse::Watchpoint(WatchpointKind::Read)
+        );
+        assert_eq!(
+            dwt_comparator_use(0x34, DwtVersion::Armv8m),
+            DwtComparatorUse::Other
+        );
+    }
+
+    #[test]
+    fn vector_catch_condition_from_exception_number() {
+        assert_eq!(
+            vector_catch_condition(0),
+            Some(VectorCatchCondition::CoreReset)
+        );
+        assert_eq!(
+            vector_catch_condition(3),
+            Some(VectorCatchCondition::HardFault)
+        );
+        assert_eq!(
+            vector_catch_condition(7),
+            Some(VectorCatchCondition::SecureFault)
+        );
+        // NMI and external interrupts are never caught.
+        assert_eq!(vector_catch_condition(2), None);
+        assert_eq!(vector_catch_condition(16), None);
+    }
+}
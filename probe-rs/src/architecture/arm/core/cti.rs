@@ -0,0 +1,149 @@
+//! Cross-core halt and restart through the Cross Trigger Interface (CTI).
+//!
+//! Every core with a CTI is connected to the Cross Trigger Matrix (CTM) of the system.
+//! Trigger output 0 of a core's CTI requests the core to halt, and trigger output 1 requests
+//! it to restart. Trigger input 0 signals that the core entered debug state.
+//!
+//! Channel 0 of the CTM is used to distribute halt requests, and channel 1 to distribute
+//! restart requests, in the same way as the ARMv8-A debug core start sequence configures them.
+//! Cortex-M cores with a CTI, like the Cortex-M7 and Cortex-M33, use the same trigger
+//! assignment.
+
+use super::armv8a_debug_regs::{
+    CtiApppulse, CtiControl, CtiGate, CtiInen, CtiIntack, CtiOuten, CtiTrigoutstatus,
+};
+use crate::{
+    architecture::arm::{memory::adi_v5_memory_interface::ArmProbe, ArmError},
+    MemoryMappedRegister,
+};
+use std::time::{Duration, Instant};
+
+/// The CTM channel used to distribute halt requests.
+pub(crate) const CTI_HALT_CHANNEL: usize = 0;
+/// The CTM channel used to distribute restart requests.
+pub(crate) const CTI_RESTART_CHANNEL: usize = 1;
+
+/// The CTI trigger input signalling that the core has halted.
+const TRIGGER_IN_HALTED: u64 = 0;
+/// The CTI trigger output requesting the core to halt.
+const TRIGGER_OUT_HALT: u64 = 0;
+/// The CTI trigger output requesting the core to restart.
+const TRIGGER_OUT_RESTART: u64 = 1;
+
+/// Configures the CTI of a core to take part in cross-core halting.
+///
+/// With `cross_halt` enabled, the core broadcasts a halt request on the halt channel whenever it halts,
+/// e.g. because of a breakpoint, and halt requests broadcast by other cores are passed on to it.
+/// Restart requests are never passed through the CTM, so restarting a core never restarts the other cores.
+pub(crate) fn configure_cross_halt(
+    memory: &mut dyn ArmProbe,
+    cti_base: u64,
+    cross_halt: bool,
+) -> Result<(), ArmError> {
+    let mut control = CtiControl(0);
+    control.set_glben(true);
+    memory.write_word_32(
+        CtiControl::get_mmio_address_from_base(cti_base)?,
+        control.into(),
+    )?;
+
+    let mut halt_outen = CtiOuten(0);
+    halt_outen.set_outen(CTI_HALT_CHANNEL, 1);
+    memory.write_word_32(
+        CtiOuten::get_mmio_address_from_base(cti_base)? + 4 * TRIGGER_OUT_HALT,
+        halt_outen.into(),
+    )?;
+
+    let mut restart_outen = CtiOuten(0);
+    restart_outen.set_outen(CTI_RESTART_CHANNEL, 1);
+    memory.write_word_32(
+        CtiOuten::get_mmio_address_from_base(cti_base)? + 4 * TRIGGER_OUT_RESTART,
+        restart_outen.into(),
+    )?;
+
+    let mut halted_inen = CtiInen(0);
+    halted_inen.set_inen(CTI_HALT_CHANNEL, u32::from(cross_halt));
+    memory.write_word_32(
+        CtiInen::get_mmio_address_from_base(cti_base)? + 4 * TRIGGER_IN_HALTED,
+        halted_inen.into(),
+    )?;
+
+    let mut gate = CtiGate(0);
+    gate.set_en(CTI_HALT_CHANNEL, u32::from(cross_halt));
+    memory.write_word_32(CtiGate::get_mmio_address_from_base(cti_base)?, gate.into())?;
+
+    Ok(())
+}
+
+/// Generates an event on the given channel of a CTI.
+///
+/// The event is only passed to the other CTIs if the gate of the channel is open.
+pub(crate) fn pulse_channel(
+    memory: &mut dyn ArmProbe,
+    cti_base: u64,
+    channel: usize,
+) -> Result<(), ArmError> {
+    let mut pulse = CtiApppulse(0);
+    pulse.set_apppulse(channel, 1);
+
+    memory.write_word_32(
+        CtiApppulse::get_mmio_address_from_base(cti_base)?,
+        pulse.into(),
+    )?;
+
+    Ok(())
+}
+
+/// Generates an event on the given channel of a CTI, and passes it to all other CTIs
+/// connected to the CTM.
+///
+/// The gate of the channel is only opened for the event, the other channels are left as they are.
+pub(crate) fn broadcast_channel(
+    memory: &mut dyn ArmProbe,
+    cti_base: u64,
+    channel: usize,
+) -> Result<(), ArmError> {
+    let address = CtiGate::get_mmio_address_from_base(cti_base)?;
+    let gate = memory.read_word_32(address)?;
+
+    let mut open_gate = CtiGate(gate);
+    open_gate.set_en(channel, 1);
+    memory.write_word_32(address, open_gate.into())?;
+
+    pulse_channel(memory, cti_base, channel)?;
+
+    memory.write_word_32(address, gate)?;
+
+    Ok(())
+}
+
+/// Acknowledges the halt request of a CTI, so that the core can be restarted.
+///
+/// The halt trigger output stays asserted until it is acknowledged.
+pub(crate) fn acknowledge_halt(
+    memory: &mut dyn ArmProbe,
+    cti_base: u64,
+    timeout: Duration,
+) -> Result<(), ArmError> {
+    let mut ack = CtiIntack(0);
+    ack.set_ack(TRIGGER_OUT_HALT as usize, 1);
+
+    memory.write_word_32(CtiIntack::get_mmio_address_from_base(cti_base)?, ack.into())?;
+
+    let address = CtiTrigoutstatus::get_mmio_address_from_base(cti_base)?;
+    let start = Instant::now();
+
+    loop {
+        let status = CtiTrigoutstatus(memory.read_word_32(address)?);
+
+        if status.status(TRIGGER_OUT_HALT as usize) == 0 {
+            return Ok(());
+        }
+
+        if start.elapsed() > timeout {
+            return Err(ArmError::Timeout);
+        }
+
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
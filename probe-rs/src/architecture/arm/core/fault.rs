@@ -0,0 +1,818 @@
+//! Decoding of Cortex-M fault state.
+//!
+//! When a Cortex-M core takes a fault, the reason is recorded in the fault status registers
+//! of the System Control Block, and the state of the interrupted code is pushed to the stack.
+//! [`read_fault_report`] collects both into a [`FaultReport`].
+
+use super::registers::cortex_m::{PC, RA, XPSR};
+use crate::{
+    core::RegisterId, memory_mapped_bitfield_register, Core, CoreType, Error, MemoryInterface,
+    MemoryMappedRegister,
+};
+use anyhow::anyhow;
+use std::fmt;
+
+memory_mapped_bitfield_register! {
+    /// Configurable Fault Status Register, containing the MMFSR, BFSR and UFSR.
+    pub struct Cfsr(u32);
+    0xE000_ED28, "CFSR",
+    impl From;
+    pub divbyzero, _: 25;
+    pub unaligned, _: 24;
+    pub stkof, _: 20;
+    pub nocp, _: 19;
+    pub invpc, _: 18;
+    pub invstate, _: 17;
+    pub undefinstr, _: 16;
+    pub bfarvalid, _: 15;
+    pub lsperr, _: 13;
+    pub stkerr, _: 12;
+    pub unstkerr, _: 11;
+    pub impreciserr, _: 10;
+    pub preciserr, _: 9;
+    pub ibuserr, _: 8;
+    pub mmarvalid, _: 7;
+    pub mlsperr, _: 5;
+    pub mstkerr, _: 4;
+    pub munstkerr, _: 3;
+    pub daccviol, _: 1;
+    pub iaccviol, _: 0;
+}
+
+memory_mapped_bitfield_register! {
+    /// HardFault Status Register
+    pub struct Hfsr(u32);
+    0xE000_ED2C, "HFSR",
+    impl From;
+    pub debugevt, _: 31;
+    pub forced, _: 30;
+    pub vecttbl, _: 1;
+}
+
+memory_mapped_bitfield_register! {
+    /// MemManage Fault Address Register
+    pub struct Mmfar(u32);
+    0xE000_ED34, "MMFAR",
+    impl From;
+}
+
+memory_mapped_bitfield_register! {
+    /// BusFault Address Register
+    pub struct Bfar(u32);
+    0xE000_ED38, "BFAR",
+    impl From;
+}
+
+memory_mapped_bitfield_register! {
+    /// SecureFault Status Register (ARMv8-M with the Security Extension only)
+    pub struct Sfsr(u32);
+    0xE000_EDE4, "SFSR",
+    impl From;
+    pub lserr, _: 7;
+    pub sfarvalid, _: 6;
+    pub lsperr, _: 5;
+    pub invtran, _: 4;
+    pub auviol, _: 3;
+    pub inver, _: 2;
+    pub invis, _: 1;
+    pub invep, _: 0;
+}
+
+memory_mapped_bitfield_register! {
+    /// SecureFault Address Register (ARMv8-M with the Security Extension only)
+    pub struct Sfar(u32);
+    0xE000_EDE8, "SFAR",
+    impl From;
+}
+
+memory_mapped_bitfield_register! {
+    /// Vector Table Offset Register
+    pub struct Vtor(u32);
+    0xE000_ED08, "VTOR",
+    impl From;
+}
+
+memory_mapped_bitfield_register! {
+    /// Processor Feature Register 1
+    pub struct IdPfr1(u32);
+    0xE000_ED44, "ID_PFR1",
+    impl From;
+    pub security, _: 7, 4;
+}
+
+/// The offset of the Non-secure alias of the System Control Space.
+const SCS_NON_SECURE_ALIAS_OFFSET: u64 = 0x2_0000;
+
+/// The main stack pointer of the current security state, as selected through DCRSR.
+const MSP: RegisterId = RegisterId(0b10001);
+/// The process stack pointer of the current security state, as selected through DCRSR.
+const PSP: RegisterId = RegisterId(0b10010);
+/// The Non-secure main stack pointer, as selected through DCRSR.
+const MSP_NS: RegisterId = RegisterId(0b11000);
+/// The Non-secure process stack pointer, as selected through DCRSR.
+const PSP_NS: RegisterId = RegisterId(0b11001);
+/// The Secure main stack pointer, as selected through DCRSR.
+const MSP_S: RegisterId = RegisterId(0b11010);
+/// The Secure process stack pointer, as selected through DCRSR.
+const PSP_S: RegisterId = RegisterId(0b11011);
+
+/// The size of the additional state context, which is stacked before the exception frame
+/// when Secure code is interrupted by a Non-secure exception: the integrity signature, a
+/// reserved word, and R4 to R11.
+const ADDITIONAL_STATE_CONTEXT_SIZE: u64 = 10 * 4;
+
+/// A single cause of a fault, as reported by one of the fault status registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultCause {
+    /// HFSR.VECTTBL: A bus fault occurred while reading the vector table.
+    VectorTableRead,
+    /// HFSR.FORCED: A configurable fault was escalated to a HardFault.
+    Forced,
+    /// HFSR.DEBUGEVT: A debug event occurred while halting debug was disabled.
+    DebugEvent,
+    /// MMFSR.IACCVIOL: Instruction fetch from a location that does not permit execution.
+    InstructionAccessViolation,
+    /// MMFSR.DACCVIOL: Load or store at a location that does not permit the operation.
+    DataAccessViolation,
+    /// MMFSR.MUNSTKERR: MemManage fault on unstacking for a return from exception.
+    MemManageUnstacking,
+    /// MMFSR.MSTKERR: MemManage fault on stacking for exception entry.
+    MemManageStacking,
+    /// MMFSR.MLSPERR: MemManage fault during lazy floating-point state preservation.
+    MemManageLazyFpStacking,
+    /// BFSR.IBUSERR: Bus error on an instruction prefetch.
+    InstructionBusError,
+    /// BFSR.PRECISERR: Precise data bus error.
+    PreciseDataBusError,
+    /// BFSR.IMPRECISERR: Imprecise data bus error.
+    ImpreciseDataBusError,
+    /// BFSR.UNSTKERR: Bus fault on unstacking for a return from exception.
+    BusFaultUnstacking,
+    /// BFSR.STKERR: Bus fault on stacking for exception entry.
+    BusFaultStacking,
+    /// BFSR.LSPERR: Bus fault during lazy floating-point state preservation.
+    BusFaultLazyFpStacking,
+    /// UFSR.UNDEFINSTR: Attempt to execute an undefined instruction.
+    UndefinedInstruction,
+    /// UFSR.INVSTATE: Attempt to execute an instruction with an invalid EPSR value, e.g. in ARM state.
+    InvalidState,
+    /// UFSR.INVPC: Integrity check failure on EXC_RETURN.
+    InvalidExceptionReturn,
+    /// UFSR.NOCP: Attempt to access a coprocessor which is disabled or not present.
+    NoCoprocessor,
+    /// UFSR.STKOF: Stack overflow detected by a stack limit check (ARMv8-M only).
+    StackOverflow,
+    /// UFSR.UNALIGNED: Unaligned memory access.
+    UnalignedAccess,
+    /// UFSR.DIVBYZERO: Integer division by zero.
+    DivideByZero,
+    /// SFSR.INVEP: Invalid Secure state entry point.
+    InvalidEntryPoint,
+    /// SFSR.INVIS: Invalid integrity signature on exception return.
+    InvalidIntegritySignature,
+    /// SFSR.INVER: Invalid exception return.
+    InvalidExceptionReturnSecure,
+    /// SFSR.AUVIOL: Attribution unit violation.
+    AttributionUnitViolation,
+    /// SFSR.INVTRAN: Invalid transition from Secure to Non-secure state.
+    InvalidTransition,
+    /// SFSR.LSPERR: SecureFault during lazy floating-point state preservation.
+    SecureLazyFpStacking,
+    /// SFSR.LSERR: Lazy state activation or deactivation error.
+    LazyStateError,
+}
+
+impl FaultCause {
+    /// Decodes the causes flagged in the raw values of the fault status registers.
+    ///
+    /// Registers which are not implemented by the core should be passed as `None`.
+    pub fn decode(hfsr: Option<u32>, cfsr: Option<u32>, sfsr: Option<u32>) -> Vec<FaultCause> {
+        let mut causes = Vec::new();
+
+        if let Some(hfsr) = hfsr.map(Hfsr) {
+            let flags = [
+                (hfsr.vecttbl(), FaultCause::VectorTableRead),
+                (hfsr.forced(), FaultCause::Forced),
+                (hfsr.debugevt(), FaultCause::DebugEvent),
+            ];
+            causes.extend(
+                flags
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|(_, cause)| *cause),
+            );
+        }
+
+        if let Some(cfsr) = cfsr.map(Cfsr) {
+            let flags = [
+                (cfsr.iaccviol(), FaultCause::InstructionAccessViolation),
+                (cfsr.daccviol(), FaultCause::DataAccessViolation),
+                (cfsr.munstkerr(), FaultCause::MemManageUnstacking),
+                (cfsr.mstkerr(), FaultCause::MemManageStacking),
+                (cfsr.mlsperr(), FaultCause::MemManageLazyFpStacking),
+                (cfsr.ibuserr(), FaultCause::InstructionBusError),
+                (cfsr.preciserr(), FaultCause::PreciseDataBusError),
+                (cfsr.impreciserr(), FaultCause::ImpreciseDataBusError),
+                (cfsr.unstkerr(), FaultCause::BusFaultUnstacking),
+                (cfsr.stkerr(), FaultCause::BusFaultStacking),
+                (cfsr.lsperr(), FaultCause::BusFaultLazyFpStacking),
+                (cfsr.undefinstr(), FaultCause::UndefinedInstruction),
+                (cfsr.invstate(), FaultCause::InvalidState),
+                (cfsr.invpc(), FaultCause::InvalidExceptionReturn),
+                (cfsr.nocp(), FaultCause::NoCoprocessor),
+                (cfsr.stkof(), FaultCause::StackOverflow),
+                (cfsr.unaligned(), FaultCause::UnalignedAccess),
+                (cfsr.divbyzero(), FaultCause::DivideByZero),
+            ];
+            causes.extend(
+                flags
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|(_, cause)| *cause),
+            );
+        }
+
+        if let Some(sfsr) = sfsr.map(Sfsr) {
+            let flags = [
+                (sfsr.invep(), FaultCause::InvalidEntryPoint),
+                (sfsr.invis(), FaultCause::InvalidIntegritySignature),
+                (sfsr.inver(), FaultCause::InvalidExceptionReturnSecure),
+                (sfsr.auviol(), FaultCause::AttributionUnitViolation),
+                (sfsr.invtran(), FaultCause::InvalidTransition),
+                (sfsr.lsperr(), FaultCause::SecureLazyFpStacking),
+                (sfsr.lserr(), FaultCause::LazyStateError),
+            ];
+            causes.extend(
+                flags
+                    .iter()
+                    .filter(|(set, _)| *set)
+                    .map(|(_, cause)| *cause),
+            );
+        }
+
+        causes
+    }
+}
+
+impl fmt::Display for FaultCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (bit, description) = match self {
+            FaultCause::VectorTableRead => ("VECTTBL", "bus fault on vector table read"),
+            FaultCause::Forced => ("FORCED", "escalated from a configurable fault"),
+            FaultCause::DebugEvent => ("DEBUGEVT", "debug event"),
+            FaultCause::InstructionAccessViolation => ("IACCVIOL", "instruction access violation"),
+            FaultCause::DataAccessViolation => ("DACCVIOL", "data access violation"),
+            FaultCause::MemManageUnstacking => (
+                "MUNSTKERR",
+                "MemManage fault on exception return unstacking",
+            ),
+            FaultCause::MemManageStacking => {
+                ("MSTKERR", "MemManage fault on exception entry stacking")
+            }
+            FaultCause::MemManageLazyFpStacking => (
+                "MLSPERR",
+                "MemManage fault during lazy FP state preservation",
+            ),
+            FaultCause::InstructionBusError => ("IBUSERR", "bus error on instruction prefetch"),
+            FaultCause::PreciseDataBusError => ("PRECISERR", "precise data bus error"),
+            FaultCause::ImpreciseDataBusError => ("IMPRECISERR", "imprecise data bus error"),
+            FaultCause::BusFaultUnstacking => {
+                ("UNSTKERR", "bus fault on exception return unstacking")
+            }
+            FaultCause::BusFaultStacking => ("STKERR", "bus fault on exception entry stacking"),
+            FaultCause::BusFaultLazyFpStacking => {
+                ("LSPERR", "bus fault during lazy FP state preservation")
+            }
+            FaultCause::UndefinedInstruction => ("UNDEFINSTR", "undefined instruction"),
+            FaultCause::InvalidState => ("INVSTATE", "invalid execution state"),
+            FaultCause::InvalidExceptionReturn => ("INVPC", "invalid EXC_RETURN value"),
+            FaultCause::NoCoprocessor => ("NOCP", "coprocessor disabled or not present"),
+            FaultCause::StackOverflow => ("STKOF", "stack overflow"),
+            FaultCause::UnalignedAccess => ("UNALIGNED", "unaligned memory access"),
+            FaultCause::DivideByZero => ("DIVBYZERO", "division by zero"),
+            FaultCause::InvalidEntryPoint => ("INVEP", "invalid Secure state entry point"),
+            FaultCause::InvalidIntegritySignature => {
+                ("INVIS", "invalid integrity signature on exception return")
+            }
+            FaultCause::InvalidExceptionReturnSecure => ("INVER", "invalid exception return"),
+            FaultCause::AttributionUnitViolation => ("AUVIOL", "attribution unit violation"),
+            FaultCause::InvalidTransition => (
+                "INVTRAN",
+                "invalid transition from Secure to Non-secure state",
+            ),
+            FaultCause::SecureLazyFpStacking => {
+                ("LSPERR", "SecureFault during lazy FP state preservation")
+            }
+            FaultCause::LazyStateError => ("LSERR", "lazy state activation or deactivation error"),
+        };
+
+        write!(f, "{bit} ({description})")
+    }
+}
+
+/// The registers pushed to the stack by the core on exception entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceptionFrame {
+    /// Stacked R0
+    pub r0: u32,
+    /// Stacked R1
+    pub r1: u32,
+    /// Stacked R2
+    pub r2: u32,
+    /// Stacked R3
+    pub r3: u32,
+    /// Stacked R12
+    pub r12: u32,
+    /// Stacked link register of the interrupted code.
+    pub lr: u32,
+    /// Stacked program counter, pointing at or after the faulting instruction.
+    pub pc: u32,
+    /// Stacked program status register.
+    pub xpsr: u32,
+}
+
+impl From<[u32; 8]> for ExceptionFrame {
+    fn from(words: [u32; 8]) -> Self {
+        let [r0, r1, r2, r3, r12, lr, pc, xpsr] = words;
+        ExceptionFrame {
+            r0,
+            r1,
+            r2,
+            r3,
+            r12,
+            lr,
+            pc,
+            xpsr,
+        }
+    }
+}
+
+/// The fault state of a Cortex-M core, as returned by [`read_fault_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultReport {
+    /// The number of the exception the core is currently handling, taken from IPSR.
+    ///
+    /// This is `None` if the core registers could not be read, e.g. because the core is not halted.
+    pub exception_number: Option<u32>,
+    /// The raw value of the HardFault Status Register, if implemented by the core.
+    pub hfsr: Option<u32>,
+    /// The raw value of the Configurable Fault Status Register, if implemented by the core.
+    pub cfsr: Option<u32>,
+    /// The raw value of the SecureFault Status Register, if implemented by the core.
+    pub sfsr: Option<u32>,
+    /// The decoded causes of the fault.
+    pub causes: Vec<FaultCause>,
+    /// The faulting address of a MemManage fault, if MMFAR holds a valid value.
+    pub mem_manage_address: Option<u64>,
+    /// The faulting address of a BusFault, if BFAR holds a valid value.
+    pub bus_fault_address: Option<u64>,
+    /// The faulting address of a SecureFault, if SFAR holds a valid value.
+    pub secure_fault_address: Option<u64>,
+    /// The registers stacked on exception entry, if the core is halted in an exception handler.
+    ///
+    /// The frame can only be located reliably if LR still holds the EXC_RETURN value, and if
+    /// the handler has not pushed anything on the stack of the frame yet. This is the case at
+    /// the first instruction of the handler, e.g. when the core was halted by a vector catch.
+    pub exception_frame: Option<ExceptionFrame>,
+}
+
+impl FaultReport {
+    /// The program counter of the faulting instruction, taken from the stacked exception frame.
+    pub fn pc(&self) -> Option<u32> {
+        self.exception_frame.map(|frame| frame.pc)
+    }
+
+    /// The link register at the time of the fault, taken from the stacked exception frame.
+    pub fn lr(&self) -> Option<u32> {
+        self.exception_frame.map(|frame| frame.lr)
+    }
+
+    /// The valid fault address registers, with the names of the registers.
+    pub fn fault_addresses(&self) -> Vec<(&'static str, u64)> {
+        [
+            ("MMFAR", self.mem_manage_address),
+            ("BFAR", self.bus_fault_address),
+            ("SFAR", self.secure_fault_address),
+        ]
+        .into_iter()
+        .filter_map(|(name, address)| Some((name, address?)))
+        .collect()
+    }
+
+    /// A single line summary of the fault, suitable for status messages.
+    pub fn summary(&self) -> String {
+        let mut summary = match self.exception_number {
+            Some(number) => exception_name(number),
+            None => "Fault".to_string(),
+        };
+
+        if !self.causes.is_empty() {
+            let causes = self
+                .causes
+                .iter()
+                .map(|cause| cause.to_string())
+                .collect::<Vec<_>>();
+            summary.push_str(&format!(": {}", causes.join(", ")));
+        }
+        let addresses = self
+            .fault_addresses()
+            .into_iter()
+            .map(|(name, address)| format!("{address:#010x} ({name})"))
+            .collect::<Vec<_>>();
+        if !addresses.is_empty() {
+            summary.push_str(&format!(" accessing {}", addresses.join(", ")));
+        }
+        if let Some(pc) = self.pc() {
+            summary.push_str(&format!(" at PC {pc:#010x}"));
+        }
+
+        summary
+    }
+}
+
+impl fmt::Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.exception_number {
+            Some(number) => writeln!(f, "{} (exception {number})", exception_name(number))?,
+            None => writeln!(f, "Fault")?,
+        }
+
+        for (name, value) in [
+            ("HFSR", self.hfsr),
+            ("CFSR", self.cfsr),
+            ("SFSR", self.sfsr),
+        ] {
+            if let Some(value) = value {
+                writeln!(f, "  {name}: {value:#010x}")?;
+            }
+        }
+
+        if self.causes.is_empty() {
+            writeln!(f, "  No fault status bits set")?;
+        } else {
+            writeln!(f, "  Causes:")?;
+            for cause in &self.causes {
+                writeln!(f, "    {cause}")?;
+            }
+        }
+
+        for (name, address) in self.fault_addresses() {
+            writeln!(f, "  Fault address ({name}): {address:#010x}")?;
+        }
+
+        match &self.exception_frame {
+            Some(frame) => {
+                writeln!(f, "  PC: {:#010x}", frame.pc)?;
+                writeln!(f, "  LR: {:#010x}", frame.lr)?;
+                write!(
+                    f,
+                    "  R0: {:#010x} R1: {:#010x} R2: {:#010x} R3: {:#010x} R12: {:#010x} xPSR: {:#010x}",
+                    frame.r0, frame.r1, frame.r2, frame.r3, frame.r12, frame.xpsr
+                )
+            }
+            None => write!(f, "  No exception frame available"),
+        }
+    }
+}
+
+/// Returns the name of a Cortex-M exception number.
+fn exception_name(number: u32) -> String {
+    match number {
+        0 => "Thread mode".to_string(),
+        1 => "Reset".to_string(),
+        2 => "NMI".to_string(),
+        3 => "HardFault".to_string(),
+        4 => "MemManage".to_string(),
+        5 => "BusFault".to_string(),
+        6 => "UsageFault".to_string(),
+        7 => "SecureFault".to_string(),
+        11 => "SVCall".to_string(),
+        12 => "DebugMonitor".to_string(),
+        14 => "PendSV".to_string(),
+        15 => "SysTick".to_string(),
+        n if n >= 16 => format!("External interrupt {}", n - 16),
+        n => format!("Reserved exception {n}"),
+    }
+}
+
+/// Reads a fault address register, if `valid` is set.
+fn read_fault_address(core: &mut Core, valid: bool, address: u64) -> Result<Option<u64>, Error> {
+    if valid {
+        Ok(Some(core.read_word_32(address)? as u64))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Where the exception frame of an exception was pushed, as described by its EXC_RETURN value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameLocation {
+    /// The stack pointer which points to the frame.
+    stack_pointer: RegisterId,
+    /// The offset of the frame from the stack pointer.
+    offset: u64,
+    /// Whether the frame is on the stack which is used by the exception handler.
+    handler_stack: bool,
+}
+
+impl FrameLocation {
+    /// Decodes the location of the exception frame from an EXC_RETURN value.
+    ///
+    /// Returns `None` if `lr` does not hold an EXC_RETURN value.
+    fn from_exc_return(lr: u32) -> Option<Self> {
+        if lr & 0xFF00_0000 != 0xFF00_0000 {
+            return None;
+        }
+
+        // ES: the exception was taken to the Secure state.
+        let secure_exception = lr & (1 << 0) != 0;
+        // SPSEL: the frame was pushed to the process stack.
+        let process_stack = lr & (1 << 2) != 0;
+        // DCRS: the default stacking rules were used, without the additional state context.
+        let default_stacking = lr & (1 << 5) != 0;
+        // S: the frame was pushed to the Secure stack.
+        let secure_stack = lr & (1 << 6) != 0;
+
+        // Without the Security Extension, and on ARMv6-M and ARMv7-M, the frame is always on a
+        // stack of the current security state.
+        let stack_pointer = match (
+            secure_stack == secure_exception,
+            process_stack,
+            secure_stack,
+        ) {
+            (true, false, _) => MSP,
+            (true, true, _) => PSP,
+            (false, false, false) => MSP_NS,
+            (false, true, false) => PSP_NS,
+            (false, false, true) => MSP_S,
+            (false, true, true) => PSP_S,
+        };
+
+        Some(FrameLocation {
+            stack_pointer,
+            offset: if default_stacking {
+                0
+            } else {
+                ADDITIONAL_STATE_CONTEXT_SIZE
+            },
+            handler_stack: stack_pointer == MSP,
+        })
+    }
+}
+
+/// Returns the address of the handler of an exception, read from the vector table.
+fn handler_address(
+    core: &mut Core,
+    exception_number: u32,
+    secure_exception: bool,
+) -> Result<u32, Error> {
+    let mut vtor_address = Vtor::get_mmio_address();
+
+    // The vector table of the Non-secure state is only visible through the Non-secure alias.
+    if core.core_type() == CoreType::Armv8m && !secure_exception {
+        let pfr1 = IdPfr1(core.read_word_32(IdPfr1::get_mmio_address())?);
+        if pfr1.security() != 0 {
+            vtor_address += SCS_NON_SECURE_ALIAS_OFFSET;
+        }
+    }
+
+    let vtor = core.read_word_32(vtor_address)?;
+    let handler = core.read_word_32(vtor as u64 + 4 * exception_number as u64)?;
+
+    Ok(handler & !1)
+}
+
+/// Reads the exception frame stacked on entry to the current exception handler.
+///
+/// The stack the frame was pushed to is determined from the EXC_RETURN value in LR, which
+/// also tells if the Secure or Non-secure stack was used. Returns `None` if the frame cannot
+/// be located reliably, see [`FaultReport::exception_frame`].
+fn read_exception_frame(
+    core: &mut Core,
+    exception_number: u32,
+) -> Result<Option<ExceptionFrame>, Error> {
+    let lr: u32 = core.read_core_reg(RA.id)?;
+
+    let location = match FrameLocation::from_exc_return(lr) {
+        Some(location) => location,
+        None => {
+            tracing::debug!(
+                "LR {:#010x} is not an EXC_RETURN value, the exception frame cannot be located",
+                lr
+            );
+            return Ok(None);
+        }
+    };
+
+    if location.handler_stack {
+        // The handler may have pushed to the stack after the frame, which is only at the stack
+        // pointer before the first instruction of the handler.
+        let pc: u32 = core.read_core_reg(PC.id)?;
+        let handler = handler_address(core, exception_number, lr & 1 != 0)?;
+        if pc & !1 != handler {
+            tracing::debug!(
+                "The core is not halted at the entry of the handler at {:#010x}, the exception frame cannot be located",
+                handler
+            );
+            return Ok(None);
+        }
+    }
+
+    let stack_pointer: u32 = core.read_core_reg(location.stack_pointer)?;
+
+    let mut words = [0u32; 8];
+    core.read_32(stack_pointer as u64 + location.offset, &mut words)?;
+
+    Ok(Some(ExceptionFrame::from(words)))
+}
+
+/// Reads the fault status registers and the stacked exception frame of a Cortex-M core.
+///
+/// ARMv6-M cores do not implement the fault status registers, so only the exception
+/// frame is reported for them. The exception frame can only be read if the core is halted.
+pub fn read_fault_report(core: &mut Core) -> Result<FaultReport, Error> {
+    let core_type = core.core_type();
+    if !core_type.is_cortex_m() {
+        return Err(Error::Other(anyhow!(
+            "Fault reports are only supported on Cortex-M cores, not on {:?}",
+            core_type
+        )));
+    }
+
+    let (hfsr, cfsr) = if core_type == CoreType::Armv6m {
+        (None, None)
+    } else {
+        (
+            Some(core.read_word_32(Hfsr::get_mmio_address())?),
+            Some(core.read_word_32(Cfsr::get_mmio_address())?),
+        )
+    };
+
+    // The SFSR reads as zero on ARMv8-M cores without the Security Extension.
+    let sfsr = if core_type == CoreType::Armv8m {
+        Some(core.read_word_32(Sfsr::get_mmio_address())?)
+    } else {
+        None
+    };
+
+    let mem_manage_address = read_fault_address(
+        core,
+        cfsr.map(Cfsr).map(|cfsr| cfsr.mmarvalid()).unwrap_or(false),
+        Mmfar::get_mmio_address(),
+    )?;
+    let bus_fault_address = read_fault_address(
+        core,
+        cfsr.map(Cfsr).map(|cfsr| cfsr.bfarvalid()).unwrap_or(false),
+        Bfar::get_mmio_address(),
+    )?;
+    let secure_fault_address = read_fault_address(
+        core,
+        sfsr.map(Sfsr).map(|sfsr| sfsr.sfarvalid()).unwrap_or(false),
+        Sfar::get_mmio_address(),
+    )?;
+
+    let (exception_number, exception_frame) = if core.core_halted()? {
+        let xpsr: u32 = core.read_core_reg(XPSR.id)?;
+        let exception_number = xpsr & 0x1ff;
+
+        // In thread mode, nothing has been stacked.
+        let exception_frame = if exception_number != 0 {
+            read_exception_frame(core, exception_number)?
+        } else {
+            None
+        };
+
+        (Some(exception_number), exception_frame)
+    } else {
+        (None, None)
+    };
+
+    Ok(FaultReport {
+        exception_number,
+        hfsr,
+        cfsr,
+        sfsr,
+        causes: FaultCause::decode(hfsr, cfsr, sfsr),
+        mem_manage_address,
+        bus_fault_address,
+        secure_fault_address,
+        exception_frame,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        ExceptionFrame, FaultCause, FaultReport, FrameLocation, ADDITIONAL_STATE_CONTEXT_SIZE, MSP,
+        PSP, PSP_NS, PSP_S,
+    };
+
+    #[test]
+    fn decode_forced_imprecise_bus_fault() {
+        let causes = FaultCause::decode(Some(0x4000_0000), Some(0x0000_0400), None);
+
+        assert_eq!(
+            causes,
+            vec![FaultCause::Forced, FaultCause::ImpreciseDataBusError]
+        );
+    }
+
+    #[test]
+    fn decode_usage_and_secure_faults() {
+        let causes = FaultCause::decode(None, Some(0x0201_0000), Some(0x0000_0048));
+
+        assert_eq!(
+            causes,
+            vec![
+                FaultCause::UndefinedInstruction,
+                FaultCause::DivideByZero,
+                FaultCause::AttributionUnitViolation,
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_ignores_address_valid_bits() {
+        // MMARVALID and BFARVALID are not causes on their own.
+        assert!(FaultCause::decode(None, Some(0x0000_8080), None).is_empty());
+    }
+
+    #[test]
+    fn summary_contains_cause_address_and_pc() {
+        let report = FaultReport {
+            exception_number: Some(3),
+            hfsr: Some(0x4000_0000),
+            cfsr: Some(0x0000_8200),
+            sfsr: None,
+            causes: FaultCause::decode(Some(0x4000_0000), Some(0x0000_8200), None),
+            mem_manage_address: None,
+            bus_fault_address: Some(0x2004_0000),
+            secure_fault_address: None,
+            exception_frame: Some(ExceptionFrame::from([
+                0,
+                1,
+                2,
+                3,
+                12,
+                0x0800_0101,
+                0x0800_0200,
+                0x0100_0000,
+            ])),
+        };
+
+        assert_eq!(
+            report.summary(),
+            "HardFault: FORCED (escalated from a configurable fault), PRECISERR (precise data bus error) accessing 0x20040000 (BFAR) at PC 0x08000200"
+        );
+        assert_eq!(report.lr(), Some(0x0800_0101));
+    }
+
+    #[test]
+    fn summary_contains_all_fault_addresses() {
+        let report = FaultReport {
+            exception_number: Some(3),
+            hfsr: Some(0x4000_0000),
+            cfsr: Some(0x0000_8282),
+            sfsr: None,
+            causes: vec![],
+            mem_manage_address: Some(0x2000_0000),
+            bus_fault_address: Some(0x4000_0000),
+            secure_fault_address: None,
+            exception_frame: None,
+        };
+
+        assert_eq!(
+            report.summary(),
+            "HardFault accessing 0x20000000 (MMFAR), 0x40000000 (BFAR)"
+        );
+    }
+
+    #[test]
+    fn frame_location_from_exc_return() {
+        let location = |lr| FrameLocation::from_exc_return(lr).unwrap();
+
+        // ARMv7-M: return to handler mode, and to thread mode with the main or process stack.
+        assert_eq!(location(0xFFFF_FFF1).stack_pointer, MSP);
+        assert!(location(0xFFFF_FFF9).handler_stack);
+        assert_eq!(location(0xFFFF_FFFD).stack_pointer, PSP);
+        assert!(!location(0xFFFF_FFFD).handler_stack);
+        assert_eq!(location(0xFFFF_FFED).offset, 0);
+
+        // ARMv8-M without the Security Extension, with the process stack.
+        assert_eq!(location(0xFFFF_FFBC).stack_pointer, PSP);
+
+        // ARMv8-M: Secure thread code on the process stack, interrupted by a Non-secure
+        // exception, which stacks the additional state context.
+        let secure_thread = location(0xFFFF_FF5C);
+        assert_eq!(secure_thread.stack_pointer, PSP_S);
+        assert_eq!(secure_thread.offset, ADDITIONAL_STATE_CONTEXT_SIZE);
+        assert!(!secure_thread.handler_stack);
+
+        // ARMv8-M: Non-secure thread code interrupted by a Secure exception.
+        assert_eq!(location(0xFFFF_FFBD).stack_pointer, PSP_NS);
+
+        // Not an EXC_RETURN value, e.g. a return address in flash, or FNC_RETURN.
+        assert_eq!(FrameLocation::from_exc_return(0x0800_0101), None);
+        assert_eq!(FrameLocation::from_exc_return(0xFEFF_FFFF), None);
+    }
+}
@@ -81,6 +81,30 @@ pub(crate) mod aarch32 {
         ret
     }
 
+    /// Build a post-indexed LDRH instruction
+    pub(crate) fn build_ldrh(reg_target: u16, reg_source: u16, imm: u8) -> u32 {
+        let mut ret = 0b1110_0000_1101_0000_0000_0000_1011_0000;
+
+        ret |= (reg_source as u32) << 16;
+        ret |= (reg_target as u32) << 12;
+        ret |= ((imm as u32) & 0xf0) << 4;
+        ret |= (imm as u32) & 0xf;
+
+        ret
+    }
+
+    /// Build a post-indexed STRH instruction
+    pub(crate) fn build_strh(reg_target: u16, reg_source: u16, imm: u8) -> u32 {
+        let mut ret = 0b1110_0000_1100_0000_0000_0000_1011_0000;
+
+        ret |= (reg_source as u32) << 16;
+        ret |= (reg_target as u32) << 12;
+        ret |= ((imm as u32) & 0xf0) << 4;
+        ret |= (imm as u32) & 0xf;
+
+        ret
+    }
+
     pub(crate) fn build_mrs(reg: u16) -> u32 {
         let mut ret = 0b1110_0001_0000_1111_0000_0000_0000_0000;
 
@@ -161,6 +185,22 @@ pub(crate) mod aarch32 {
             assert_eq!(0xECA25E01, instr);
         }
 
+        #[test]
+        fn gen_ldrh_instruction() {
+            let instr = build_ldrh(1, 0, 2);
+
+            // LDRH r1, [r0], #2
+            assert_eq!(0xE0D010B2, instr);
+        }
+
+        #[test]
+        fn gen_strh_instruction() {
+            let instr = build_strh(1, 0, 2);
+
+            // STRH r1, [r0], #2
+            assert_eq!(0xE0C010B2, instr);
+        }
+
         #[test]
         fn gen_mrs_instruction() {
             let instr = build_mrs(2);
@@ -211,6 +251,37 @@ pub(crate) mod thumb2 {
         ret
     }
 
+    /// Build a post-indexed LDRH instruction
+    pub(crate) fn build_ldrh(reg_target: u16, reg_source: u16, imm: u8) -> u32 {
+        let mut ret = 0b1111_1000_0011_0000_0000_1011_0000_0000;
+
+        ret |= (reg_source as u32) << 16;
+        ret |= (reg_target as u32) << 12;
+        ret |= imm as u32;
+
+        ret
+    }
+
+    /// Build a post-indexed STRH instruction
+    pub(crate) fn build_strh(reg_target: u16, reg_source: u16, imm: u8) -> u32 {
+        let mut ret = 0b1111_1000_0010_0000_0000_1011_0000_0000;
+
+        ret |= (reg_source as u32) << 16;
+        ret |= (reg_target as u32) << 12;
+        ret |= imm as u32;
+
+        ret
+    }
+
+    /// Build a DSB instruction
+    pub(crate) fn build_dsb(option: u8) -> u32 {
+        let mut ret = 0b1111_0011_1011_1111_1000_1111_0100_0000;
+
+        ret |= option as u32;
+
+        ret
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -230,6 +301,30 @@ pub(crate) mod thumb2 {
             // STR r2, [r3], #4
             assert_eq!(0xF8432B04, instr);
         }
+
+        #[test]
+        fn gen_ldrh_instruction() {
+            let instr = build_ldrh(2, 3, 2);
+
+            // LDRH r2, [r3], #2
+            assert_eq!(0xF8332B02, instr);
+        }
+
+        #[test]
+        fn gen_strh_instruction() {
+            let instr = build_strh(2, 3, 2);
+
+            // STRH r2, [r3], #2
+            assert_eq!(0xF8232B02, instr);
+        }
+
+        #[test]
+        fn gen_dsb_instruction() {
+            let instr = build_dsb(0b1011);
+
+            // DSB ISH
+            assert_eq!(0xF3BF8F4B, instr);
+        }
     }
 }
 
@@ -300,6 +395,28 @@ pub(crate) mod aarch64 {
         ret
     }
 
+    /// Build a post-indexed LDRH instruction
+    pub(crate) fn build_ldrh(reg_target: u16, reg_source: u16, imm: u16) -> u32 {
+        let mut ret = 0b0111_1000_0100_0000_0000_0100_0000_0000;
+
+        ret |= (imm as u32) << 12;
+        ret |= (reg_source as u32) << 5;
+        ret |= reg_target as u32;
+
+        ret
+    }
+
+    /// Build a post-indexed STRH instruction
+    pub(crate) fn build_strh(reg_target: u16, reg_source: u16, imm: u16) -> u32 {
+        let mut ret = 0b0111_1000_0000_0000_0000_0100_0000_0000;
+
+        ret |= (imm as u32) << 12;
+        ret |= (reg_source as u32) << 5;
+        ret |= reg_target as u32;
+
+        ret
+    }
+
     pub(crate) fn build_ins_fp_to_gp(reg_target: u16, reg_source: u16, index: u16) -> u32 {
         let mut ret = 0b0100_1110_0000_1000_0011_1100_0000_0000;
 
@@ -320,6 +437,28 @@ pub(crate) mod aarch64 {
         ret
     }
 
+    /// Build a SYS instruction, which is used for cache maintenance
+    pub(crate) fn build_sys(op1: u8, crn: u8, crm: u8, op2: u8, reg: u16) -> u32 {
+        let mut ret = 0b1101_0101_0000_1000_0000_0000_0000_0000;
+
+        ret |= (op1 as u32) << 16;
+        ret |= (crn as u32) << 12;
+        ret |= (crm as u32) << 8;
+        ret |= (op2 as u32) << 5;
+        ret |= reg as u32;
+
+        ret
+    }
+
+    /// Build a DSB instruction
+    pub(crate) fn build_dsb(option: u8) -> u32 {
+        let mut ret = 0b1101_0101_0000_0011_0011_0000_1001_1111;
+
+        ret |= (option as u32) << 8;
+
+        ret
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -339,6 +478,22 @@ pub(crate) mod aarch64 {
             assert_eq!(0xB8404462, instr);
         }
 
+        #[test]
+        fn gen_ldrh_instruction() {
+            let instr = build_ldrh(1, 0, 2);
+
+            // LDRH w1, [x0], #2
+            assert_eq!(0x78402401, instr);
+        }
+
+        #[test]
+        fn gen_strh_instruction() {
+            let instr = build_strh(1, 0, 2);
+
+            // STRH w1, [x0], #2
+            assert_eq!(0x78002401, instr);
+        }
+
         #[test]
         fn gen_msr_instruction() {
             let instr = build_msr(2, 3, 4, 1, 2, 3);
@@ -355,6 +510,22 @@ pub(crate) mod aarch64 {
             assert_eq!(0xD5334143, instr);
         }
 
+        #[test]
+        fn gen_sys_instruction() {
+            let instr = build_sys(3, 7, 11, 1, 2);
+
+            // DC CVAU, x2
+            assert_eq!(0xD50B7B22, instr);
+        }
+
+        #[test]
+        fn gen_dsb_instruction() {
+            let instr = build_dsb(0b1011);
+
+            // DSB ISH
+            assert_eq!(0xD5033B9F, instr);
+        }
+
         #[test]
         fn gen_str_instruction() {
             let instr = build_str(2, 3, 4);
@@ -1,17 +1,20 @@
 use crate::{
-    core::{BreakpointCause, RegisterValue},
-    memory_mapped_bitfield_register, CoreStatus, HaltReason,
+    core::{BreakpointCause, RegisterValue, WatchpointKind},
+    memory_mapped_bitfield_register, CoreStatus, Error, HaltReason,
 };
+use anyhow::anyhow;
 
 pub mod armv6m;
 pub mod armv7a;
 pub mod armv7m;
 pub mod armv8a;
 pub mod armv8m;
+pub mod fault;
 
 pub(crate) mod armv7a_debug_regs;
 pub(crate) mod armv8a_debug_regs;
 pub(crate) mod cortex_m;
+pub(crate) mod cti;
 pub(crate) mod instructions;
 pub(crate) mod registers;
 
@@ -168,6 +171,80 @@ impl CortexAState {
     }
 }
 
+/// The fields of an A-profile watchpoint, as written to the `DBGWVR` and `DBGWCR` registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WatchpointFields {
+    /// The value of `DBGWVR`.
+    value: u64,
+    /// Byte address select
+    bas: u32,
+    /// Address range mask
+    mask: u32,
+}
+
+impl WatchpointFields {
+    /// Calculate the register fields which watch `length` bytes starting at `address`.
+    ///
+    /// `granule` is the number of bytes a single `DBGWVR` value can select from using the byte address select field,
+    /// which is 4 bytes for ARMv7-A and 8 bytes for ARMv8-A. Larger ranges have to be a power of two in size,
+    /// and aligned to their size.
+    fn new(address: u64, length: u64, granule: u64) -> Result<Self, Error> {
+        let offset = address % granule;
+
+        if length > 0 && offset + length <= granule {
+            Ok(Self {
+                value: address - offset,
+                bas: ((1 << length) - 1) << offset,
+                mask: 0,
+            })
+        } else if length.is_power_of_two() && address & (length - 1) == 0 {
+            Ok(Self {
+                value: address,
+                bas: (1 << granule) - 1,
+                mask: length.trailing_zeros(),
+            })
+        } else {
+            Err(Error::Other(anyhow!(
+                "Unable to watch {} bytes at address {:#010x}. Ranges larger than {} bytes must be a power of two in size, and aligned to it.",
+                length,
+                address,
+                granule
+            )))
+        }
+    }
+
+    /// The watched address and length.
+    fn range(&self) -> (u64, u64) {
+        if self.mask != 0 {
+            (self.value, 1 << self.mask)
+        } else {
+            (
+                self.value + self.bas.trailing_zeros() as u64,
+                self.bas.count_ones() as u64,
+            )
+        }
+    }
+}
+
+/// Converts the load/store control field of `DBGWCR` into a [`WatchpointKind`].
+fn watchpoint_kind_from_lsc(lsc: u32) -> Option<WatchpointKind> {
+    match lsc {
+        0b01 => Some(WatchpointKind::Read),
+        0b10 => Some(WatchpointKind::Write),
+        0b11 => Some(WatchpointKind::Access),
+        _ => None,
+    }
+}
+
+/// Converts a [`WatchpointKind`] into the load/store control field of `DBGWCR`.
+fn watchpoint_kind_to_lsc(kind: WatchpointKind) -> u32 {
+    match kind {
+        WatchpointKind::Read => 0b01,
+        WatchpointKind::Write => 0b10,
+        WatchpointKind::Access => 0b11,
+    }
+}
+
 /// Core implementations should call this function when they
 /// wish to update the [`CoreStatus`] of their core.
 ///
@@ -0,0 +1,189 @@
+//! Discovery of the access ports of ADIv6 debug ports.
+//!
+//! In ADIv6, access ports are not numbered, but mapped into the address space of the debug
+//! port at 4 KiB aligned addresses. They are found through the ROM tables below the base
+//! pointer of the debug port.
+
+use crate::architecture::arm::ArmError;
+
+/// The offset of the ADIv5 compatible registers in the 4 KiB block of an ADIv6 access port.
+///
+/// The ADIv5 register at address `A` is found at `0xD00 + A`, e.g. the IDR at `0xDFC`.
+const AP_REGISTER_OFFSET: u64 = 0xD00;
+
+/// DEVARCH.ARCHITECT of components whose architecture is defined by ARM.
+const ARCHITECT_ARM: u32 = 0x23B;
+/// DEVARCH.ARCHID of a Class 0x9 ROM table.
+const ARCHID_ROM_TABLE: u16 = 0x0AF7;
+/// DEVARCH.ARCHID of a MEM-AP.
+const ARCHID_MEM_AP: u16 = 0x0A17;
+/// DEVARCH.ARCHID of a JTAG-AP.
+const ARCHID_JTAG_AP: u16 = 0x0A27;
+/// DEVARCH.ARCHID of an access port which is neither a MEM-AP nor a JTAG-AP.
+const ARCHID_UNKNOWN_AP: u16 = 0x0A47;
+
+/// ROM tables nested deeper than this are not searched, which guards against loops in broken tables.
+const MAX_DEPTH: usize = 8;
+
+/// Returns the address of an ADIv5 AP register in the ADIv6 access port at `base`.
+pub(crate) fn ap_register_address(base: u64, register: u8) -> u64 {
+    base + AP_REGISTER_OFFSET + u64::from(register)
+}
+
+/// Finds the base addresses of all access ports in and below the component at `base`,
+/// in the order of the ROM table entries.
+///
+/// `read` reads a word from the address space of the debug port.
+pub(crate) fn find_access_ports(
+    read: &mut dyn FnMut(u64) -> Result<u32, ArmError>,
+    base: u64,
+) -> Result<Vec<u64>, ArmError> {
+    let mut access_ports = Vec::new();
+    visit_component(read, base, 0, &mut access_ports)?;
+    Ok(access_ports)
+}
+
+fn visit_component(
+    read: &mut dyn FnMut(u64) -> Result<u32, ArmError>,
+    address: u64,
+    depth: usize,
+    access_ports: &mut Vec<u64>,
+) -> Result<(), ArmError> {
+    if depth > MAX_DEPTH {
+        tracing::warn!(
+            "ROM tables are nested too deep at {:#x}, ignoring them",
+            address
+        );
+        return Ok(());
+    }
+
+    let class = (read(address + 0xFF4)? >> 4) & 0xF;
+    match class {
+        0x1 => visit_rom_table(read, address, RomTable::Class1, depth, access_ports),
+        // CoreSight components are identified by their architecture.
+        0x9 => {
+            let devarch = read(address + 0xFBC)?;
+            if devarch & (1 << 20) == 0 || devarch >> 21 != ARCHITECT_ARM {
+                return Ok(());
+            }
+
+            match devarch as u16 {
+                ARCHID_ROM_TABLE => {
+                    let wide = read(address + 0xFC8)? & 0xF == 1;
+                    visit_rom_table(
+                        read,
+                        address,
+                        RomTable::Class9 { wide },
+                        depth,
+                        access_ports,
+                    )
+                }
+                ARCHID_MEM_AP | ARCHID_JTAG_AP | ARCHID_UNKNOWN_AP => {
+                    tracing::debug!("Found access port at {:#x}", address);
+                    access_ports.push(address);
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The kind of a ROM table, which determines the format of its entries.
+#[derive(Clone, Copy)]
+enum RomTable {
+    /// A Class 0x1 ROM table, which always has 32-bit entries.
+    Class1,
+    /// A Class 0x9 ROM table, with 64-bit entries if it is `wide`.
+    Class9 { wide: bool },
+}
+
+fn visit_rom_table(
+    read: &mut dyn FnMut(u64) -> Result<u32, ArmError>,
+    address: u64,
+    table: RomTable,
+    depth: usize,
+    access_ports: &mut Vec<u64>,
+) -> Result<(), ArmError> {
+    let entries = match table {
+        RomTable::Class1 => 960,
+        RomTable::Class9 { wide: true } => 256,
+        RomTable::Class9 { wide: false } => 512,
+    };
+
+    for index in 0..entries {
+        let (entry, offset) = if let RomTable::Class9 { wide: true } = table {
+            let entry_address = address + index * 8;
+            let entry =
+                u64::from(read(entry_address)?) | (u64::from(read(entry_address + 4)?) << 32);
+            (entry, entry & !0xFFF)
+        } else {
+            let entry = read(address + index * 4)?;
+            // The offset is signed, so it is sign extended to 64 bits.
+            (u64::from(entry), (entry & !0xFFF) as i32 as i64 as u64)
+        };
+
+        let present = match table {
+            // Only an entry of all zeros ends a Class 0x1 ROM table, other entries
+            // are skipped if the present bit is not set.
+            RomTable::Class1 if entry == 0 => break,
+            RomTable::Class1 => entry & 0b1 != 0,
+            // 0b00 ends a Class 0x9 ROM table, 0b10 is an entry which is not present.
+            RomTable::Class9 { .. } if entry & 0b11 == 0b00 => break,
+            RomTable::Class9 { .. } => entry & 0b11 == 0b11,
+        };
+
+        if present {
+            visit_component(read, address.wrapping_add(offset), depth + 1, access_ports)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// Adds the CIDR1 and DEVARCH of a component to a memory map.
+    fn component(memory: &mut HashMap<u64, u32>, address: u64, class: u32, archid: u32) {
+        memory.insert(address + 0xFF4, class << 4);
+        memory.insert(address + 0xFBC, (ARCHITECT_ARM << 21) | (1 << 20) | archid);
+    }
+
+    #[test]
+    fn find_access_ports_in_rom_tables() {
+        let mut memory = HashMap::new();
+
+        // The root ROM table, with a MEM-AP, an entry which is not present, and a nested ROM table.
+        component(&mut memory, 0x8000, 0x9, ARCHID_ROM_TABLE as u32);
+        memory.insert(0x8000, 0x0000_1003);
+        memory.insert(0x8004, 0x0000_3002);
+        memory.insert(0x8008, 0x0000_2003);
+        component(&mut memory, 0x9000, 0x9, ARCHID_MEM_AP as u32);
+
+        // The nested Class 0x1 ROM table points below itself, to a JTAG-AP and a component
+        // which is not an access port. The entry in between is not present, but does not
+        // end the table.
+        component(&mut memory, 0xA000, 0x1, 0);
+        memory.insert(0xA000, 0xFFFF_D003);
+        memory.insert(0xA004, 0xFFFF_B000);
+        memory.insert(0xA008, 0xFFFF_C003);
+        component(&mut memory, 0x7000, 0x9, ARCHID_JTAG_AP as u32);
+        component(&mut memory, 0x6000, 0x9, 0x1A14);
+
+        let mut read = |address| Ok(memory.get(&address).copied().unwrap_or(0));
+        let access_ports = find_access_ports(&mut read, 0x8000).unwrap();
+
+        assert_eq!(access_ports, vec![0x9000, 0x7000]);
+    }
+
+    #[test]
+    fn register_address() {
+        // The IDR of an ADIv5 AP is at 0xFC.
+        assert_eq!(ap_register_address(0x8000_2000, 0xFC), 0x8000_2DFC);
+    }
+}
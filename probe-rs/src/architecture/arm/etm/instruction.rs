@@ -0,0 +1,346 @@
+//! Classification of the instructions which are traced by the ETM.
+//!
+//! The ETM only traces branches, so only those have to be recognized, together with the size of
+//! the instructions in between.
+
+use super::Isa;
+
+/// A branch instruction, for which the ETM emits an atom.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Branch {
+    /// The target of the branch is encoded in the instruction.
+    Direct {
+        /// The address of the target.
+        target: u64,
+        /// The instruction set at the target.
+        isa: Isa,
+    },
+    /// The target of the branch is only known at runtime, and is traced by the ETM.
+    Indirect,
+}
+
+/// An instruction, as far as it is relevant for the trace.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Instruction {
+    /// The size of the instruction in bytes.
+    pub size: u64,
+    /// The branch performed by the instruction, if it is one.
+    pub branch: Option<Branch>,
+}
+
+/// Decodes the instruction at `address`.
+///
+/// `read` reads instruction memory, and returns `None` if it is not part of the program.
+pub(crate) fn decode(
+    read: impl Fn(u64, &mut [u8]) -> Option<()>,
+    address: u64,
+    isa: Isa,
+) -> Option<Instruction> {
+    match isa {
+        Isa::T32 => {
+            let mut halfword = [0; 2];
+            read(address, &mut halfword)?;
+            let first = u16::from_le_bytes(halfword);
+
+            if first >> 11 < 0b11101 {
+                Some(decode_t16(address, first))
+            } else {
+                read(address + 2, &mut halfword)?;
+                Some(decode_t32(address, first, u16::from_le_bytes(halfword)))
+            }
+        }
+        Isa::A32 | Isa::A64 => {
+            let mut word = [0; 4];
+            read(address, &mut word)?;
+            let word = u32::from_le_bytes(word);
+
+            let branch = if isa == Isa::A32 {
+                decode_a32(address, word)
+            } else {
+                decode_a64(address, word)
+            };
+            Some(Instruction { size: 4, branch })
+        }
+    }
+}
+
+/// Sign extends the lowest `bits` bits of `value`.
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    i64::from(((value << (32 - bits)) as i32) >> (32 - bits))
+}
+
+fn direct(address: u64, offset: i64, isa: Isa) -> Option<Branch> {
+    Some(Branch::Direct {
+        target: address.wrapping_add(offset as u64),
+        isa,
+    })
+}
+
+fn decode_t16(address: u64, instruction: u16) -> Instruction {
+    let word = u32::from(instruction);
+    // The PC reads as the address of the instruction plus 4.
+    let pc = address + 4;
+
+    let branch = if instruction & 0xF000 == 0xD000 && instruction & 0x0E00 != 0x0E00 {
+        // B<c>, except for the UDF and SVC encodings.
+        direct(pc, sign_extend(word & 0xFF, 8) << 1, Isa::T32)
+    } else if instruction & 0xF800 == 0xE000 {
+        // B
+        direct(pc, sign_extend(word & 0x7FF, 11) << 1, Isa::T32)
+    } else if instruction & 0xF500 == 0xB100 {
+        // CBZ, CBNZ
+        let offset = ((word >> 2) & 0x3E) | ((word >> 3) & 0x40);
+        direct(pc, i64::from(offset), Isa::T32)
+    } else if instruction & 0xFF00 == 0x4700
+        || instruction & 0xFE00 == 0xBC00 && instruction & 0x0100 != 0
+        || instruction & 0xFC87 == 0x4487 && instruction & 0x0300 != 0x0100
+    {
+        // BX, BLX, POP with the PC, and ADD or MOV to the PC.
+        Some(Branch::Indirect)
+    } else {
+        None
+    };
+
+    Instruction { size: 2, branch }
+}
+
+fn decode_t32(address: u64, first: u16, second: u16) -> Instruction {
+    let (first_word, second_word) = (u32::from(first), u32::from(second));
+    let pc = address + 4;
+
+    let branch = if first & 0xF800 == 0xF000 && second & 0x8000 != 0 {
+        let s = (first_word >> 10) & 1;
+        let j1 = (second_word >> 13) & 1;
+        let j2 = (second_word >> 11) & 1;
+        let imm11 = second_word & 0x7FF;
+
+        match (second >> 12) & 0x5 {
+            // B<c>.W, unless it is a miscellaneous control instruction.
+            0b000 if (first >> 7) & 0x7 != 0x7 => {
+                let imm6 = first_word & 0x3F;
+                let imm = (s << 20) | (j2 << 19) | (j1 << 18) | (imm6 << 12) | (imm11 << 1);
+                direct(pc, sign_extend(imm, 21), Isa::T32)
+            }
+            0b000 => None,
+            // B.W, BL and BLX.
+            exchange => {
+                let i1 = !(j1 ^ s) & 1;
+                let i2 = !(j2 ^ s) & 1;
+                let imm10 = first_word & 0x3FF;
+                let imm = (s << 24) | (i1 << 23) | (i2 << 22) | (imm10 << 12) | (imm11 << 1);
+                let offset = sign_extend(imm, 25);
+
+                if exchange == 0b100 {
+                    // BLX to A32 code, whose target is word aligned.
+                    direct(pc & !0x3, offset & !0x3, Isa::A32)
+                } else {
+                    direct(pc, offset, Isa::T32)
+                }
+            }
+        }
+    } else if first & 0xFFF0 == 0xE8D0 && second & 0xFFE0 == 0xF000 {
+        // TBB, TBH
+        Some(Branch::Indirect)
+    } else if (first & 0xFFD0 == 0xE890 || first & 0xFFD0 == 0xE910) && second & 0x8000 != 0 {
+        // LDM with the PC, including POP.W
+        Some(Branch::Indirect)
+    } else if first & 0xFF70 == 0xF850 && second >> 12 == 0xF {
+        // LDR to the PC
+        Some(Branch::Indirect)
+    } else {
+        None
+    };
+
+    Instruction { size: 4, branch }
+}
+
+fn decode_a32(address: u64, instruction: u32) -> Option<Branch> {
+    // The PC reads as the address of the instruction plus 8.
+    let pc = address + 8;
+    let condition = instruction >> 28;
+
+    if instruction & 0x0E00_0000 == 0x0A00_0000 {
+        let offset = sign_extend(instruction & 0xFF_FFFF, 24) << 2;
+        if condition == 0xF {
+            // BLX to T32 code, with the H bit selecting the halfword.
+            let halfword = i64::from((instruction >> 23) & 0x2);
+            direct(pc, offset + halfword, Isa::T32)
+        } else {
+            // B, BL
+            direct(pc, offset, Isa::A32)
+        }
+    } else if condition == 0xF {
+        None
+    } else if instruction & 0x0FFF_FFD0 == 0x012F_FF10
+        // LDR to the PC
+        || instruction & 0x0C50_F000 == 0x0410_F000 && instruction & 0x0200_0010 != 0x0200_0010
+        // LDM with the PC
+        || instruction & 0x0E10_8000 == 0x0810_8000
+        // Data processing to the PC, except for comparisons and multiplications.
+        || instruction & 0x0C00_F000 == 0x0000_F000
+            && instruction & 0x0180_0000 != 0x0100_0000
+            && instruction & 0x0200_0090 != 0x0000_0090
+    {
+        // BX, BLX and the other writes to the PC above.
+        Some(Branch::Indirect)
+    } else {
+        None
+    }
+}
+
+fn decode_a64(address: u64, instruction: u32) -> Option<Branch> {
+    if instruction & 0x7C00_0000 == 0x1400_0000 {
+        // B, BL
+        direct(
+            address,
+            sign_extend(instruction & 0x3FF_FFFF, 26) << 2,
+            Isa::A64,
+        )
+    } else if instruction & 0xFF00_0010 == 0x5400_0000 || instruction & 0x7E00_0000 == 0x3400_0000 {
+        // B.cond, CBZ, CBNZ
+        direct(
+            address,
+            sign_extend((instruction >> 5) & 0x7_FFFF, 19) << 2,
+            Isa::A64,
+        )
+    } else if instruction & 0x7E00_0000 == 0x3600_0000 {
+        // TBZ, TBNZ
+        direct(
+            address,
+            sign_extend((instruction >> 5) & 0x3FFF, 14) << 2,
+            Isa::A64,
+        )
+    } else if instruction & 0xFE00_0000 == 0xD600_0000 {
+        // BR, BLR, RET, ERET and their variants with pointer authentication.
+        Some(Branch::Indirect)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode_bytes(bytes: &[u8], address: u64, isa: Isa) -> Option<Instruction> {
+        decode(
+            |read_address, data| {
+                let offset = (read_address - address) as usize;
+                data.copy_from_slice(bytes.get(offset..offset + data.len())?);
+                Some(())
+            },
+            address,
+            isa,
+        )
+    }
+
+    fn branch(bytes: &[u8], address: u64, isa: Isa) -> Option<Branch> {
+        decode_bytes(bytes, address, isa).unwrap().branch
+    }
+
+    #[test]
+    fn thumb_branches() {
+        // b.n 0x1000 at 0x1010
+        assert_eq!(
+            branch(&[0xF6, 0xE7], 0x1010, Isa::T32),
+            Some(Branch::Direct {
+                target: 0x1000,
+                isa: Isa::T32
+            })
+        );
+        // bne.n 0x1020 at 0x1010
+        assert_eq!(
+            branch(&[0x06, 0xD1], 0x1010, Isa::T32),
+            Some(Branch::Direct {
+                target: 0x1020,
+                isa: Isa::T32
+            })
+        );
+        // cbz r0, 0x1050 at 0x1010
+        assert_eq!(
+            branch(&[0xF0, 0xB1], 0x1010, Isa::T32),
+            Some(Branch::Direct {
+                target: 0x1050,
+                isa: Isa::T32
+            })
+        );
+        // bl 0x2000 at 0x1000
+        assert_eq!(
+            branch(&[0x00, 0xF0, 0xFE, 0xFF], 0x1000, Isa::T32),
+            Some(Branch::Direct {
+                target: 0x2000,
+                isa: Isa::T32
+            })
+        );
+        // bl 0x0800 at 0x1000
+        assert_eq!(
+            branch(&[0xFF, 0xF7, 0xFE, 0xFB], 0x1000, Isa::T32),
+            Some(Branch::Direct {
+                target: 0x0800,
+                isa: Isa::T32
+            })
+        );
+        // bx lr, pop {r4, pc} and ldr.w pc, [sp], #4
+        assert_eq!(branch(&[0x70, 0x47], 0, Isa::T32), Some(Branch::Indirect));
+        assert_eq!(branch(&[0x10, 0xBD], 0, Isa::T32), Some(Branch::Indirect));
+        assert_eq!(
+            branch(&[0x5D, 0xF8, 0x04, 0xFB], 0, Isa::T32),
+            Some(Branch::Indirect)
+        );
+    }
+
+    #[test]
+    fn thumb_instruction_sizes() {
+        // movs r0, #1
+        assert_eq!(
+            decode_bytes(&[0x01, 0x20], 0, Isa::T32),
+            Some(Instruction {
+                size: 2,
+                branch: None
+            })
+        );
+        // ldr.w r0, [r1, #4]
+        assert_eq!(
+            decode_bytes(&[0xD1, 0xF8, 0x04, 0x00], 0, Isa::T32),
+            Some(Instruction {
+                size: 4,
+                branch: None
+            })
+        );
+        // The second halfword is missing.
+        assert_eq!(decode_bytes(&[0xD1, 0xF8], 0, Isa::T32), None);
+    }
+
+    #[test]
+    fn arm_branches() {
+        // bl 0x8000 at 0x8010
+        assert_eq!(
+            branch(&[0xFA, 0xFF, 0xFF, 0xEB], 0x8010, Isa::A32),
+            Some(Branch::Direct {
+                target: 0x8000,
+                isa: Isa::A32
+            })
+        );
+        // bx lr
+        assert_eq!(
+            branch(&[0x1E, 0xFF, 0x2F, 0xE1], 0, Isa::A32),
+            Some(Branch::Indirect)
+        );
+        // mov r0, r1
+        assert_eq!(branch(&[0x01, 0x00, 0xA0, 0xE1], 0, Isa::A32), None);
+
+        // b 0x4000 at 0x4010
+        assert_eq!(
+            branch(&[0xFC, 0xFF, 0xFF, 0x17], 0x4010, Isa::A64),
+            Some(Branch::Direct {
+                target: 0x4000,
+                isa: Isa::A64
+            })
+        );
+        // ret
+        assert_eq!(
+            branch(&[0xC0, 0x03, 0x5F, 0xD6], 0, Isa::A64),
+            Some(Branch::Indirect)
+        );
+    }
+}
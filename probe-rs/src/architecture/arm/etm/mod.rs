@@ -0,0 +1,354 @@
+//! Decoding of the ETMv4 instruction trace.
+//!
+//! The ETM only traces the outcome of branches and the targets of indirect branches. Combined with
+//! the program image, this is enough to reconstruct the executed instructions, e.g. to see how the
+//! program got to the location of a crash.
+//!
+//! The trace is collected by configuring the ETM with
+//! [`Session::enable_etm`](crate::Session::enable_etm) and reading it from the trace memory, or from
+//! SWO with continuous formatting, with
+//! [`Session::read_etm_trace_data`](crate::Session::read_etm_trace_data).
+
+mod instruction;
+mod packet;
+
+use object::{
+    elf::{FileHeader32, FileHeader64, PT_LOAD},
+    read::elf::{FileHeader, ProgramHeader},
+    Endianness, FileKind,
+};
+
+use self::instruction::Branch;
+pub use packet::{Atom, Isa, Packet, PacketDecoder};
+
+/// An error when decoding ETM trace occurred.
+#[derive(thiserror::Error, Debug)]
+pub enum EtmError {
+    /// The trace contains a packet which is not supported, e.g. one of the data trace.
+    #[error("Unsupported ETMv4 packet with header {header:#04x} at offset {offset}")]
+    UnsupportedPacket {
+        /// The header of the packet.
+        header: u8,
+        /// The offset of the packet in the trace.
+        offset: usize,
+    },
+    /// The program image could not be read.
+    #[error("Failed to read the program image")]
+    Object(#[from] object::read::Error),
+}
+
+/// The properties of the ETM which are needed to decode its trace.
+///
+/// They are read from the ETM by [`Etm::decoder_config`](crate::architecture::arm::component::Etm::decoder_config).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DecoderConfig {
+    /// Whether commit elements are implied by atoms, so that cycle count packets have no commit field.
+    pub commit_opt: bool,
+    /// The size of a context ID in bytes.
+    pub context_id_size: u8,
+    /// The size of a virtual machine ID in bytes.
+    pub vmid_size: u8,
+}
+
+/// The code of the traced program, which is needed to follow the branches in the trace.
+#[derive(Debug, Clone, Default)]
+pub struct ProgramImage {
+    segments: Vec<(u64, Vec<u8>)>,
+}
+
+impl ProgramImage {
+    /// Creates a program image from a list of start addresses and contents.
+    pub fn new(segments: Vec<(u64, Vec<u8>)>) -> Self {
+        Self { segments }
+    }
+
+    /// Creates a program image from the loadable segments of an ELF file.
+    pub fn from_elf(data: &[u8]) -> Result<Self, EtmError> {
+        match FileKind::parse(data)? {
+            FileKind::Elf64 => Self::from_elf_file::<FileHeader64<Endianness>>(data),
+            _ => Self::from_elf_file::<FileHeader32<Endianness>>(data),
+        }
+    }
+
+    fn from_elf_file<Elf: FileHeader<Endian = Endianness>>(data: &[u8]) -> Result<Self, EtmError> {
+        let header = Elf::parse(data)?;
+        let endian = header.endian()?;
+
+        let mut segments = Vec::new();
+        for segment in header.program_headers(endian, data)? {
+            if segment.p_type(endian) != PT_LOAD {
+                continue;
+            }
+
+            // Code is executed at the virtual address, even if it is loaded from another one.
+            let address = segment.p_vaddr(endian).into();
+            if let Ok(contents) = segment.data(endian, data) {
+                if !contents.is_empty() {
+                    segments.push((address, contents.to_vec()));
+                }
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Reads from the program image, returning `None` if the range is not part of it.
+    fn read(&self, address: u64, data: &mut [u8]) -> Option<()> {
+        let end = address + data.len() as u64;
+        let (start, contents) = self
+            .segments
+            .iter()
+            .find(|(start, contents)| *start <= address && end <= *start + contents.len() as u64)?;
+
+        let offset = (address - start) as usize;
+        data.copy_from_slice(&contents[offset..][..data.len()]);
+        Some(())
+    }
+}
+
+/// An element of the reconstructed program execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceElement {
+    /// A range of consecutive instructions was executed.
+    Instructions {
+        /// The address of the first instruction.
+        start: u64,
+        /// The address after the last instruction.
+        end: u64,
+        /// The instruction set of the instructions.
+        isa: Isa,
+    },
+    /// An exception was taken.
+    Exception {
+        /// The number of the exception.
+        number: u16,
+        /// The address at which execution continues when the exception returns.
+        return_address: u64,
+    },
+    /// An exception returned.
+    ExceptionReturn,
+    /// The trace continues after a gap, so the following instructions are not a continuation of
+    /// the previous ones.
+    TraceOn,
+    /// Trace was lost, because the ETM could not output it fast enough.
+    Overflow,
+    /// A global timestamp.
+    Timestamp(u64),
+    /// The number of cycles since the last cycle count.
+    CycleCount(u32),
+}
+
+/// The maximum number of instructions which are searched for the next branch, which stops
+/// decoding garbage when the trace does not match the program image.
+const MAX_INSTRUCTIONS_WITHOUT_BRANCH: usize = 0x10000;
+
+/// Reconstructs the executed instructions from ETMv4 trace packets and the program image.
+#[derive(Debug)]
+pub struct InstructionDecoder<'a> {
+    image: &'a ProgramImage,
+    /// The address and instruction set of the next executed instruction, if it is known.
+    next: Option<(u64, Isa)>,
+    /// The number of an exception whose return address is given by the next address packet.
+    pending_exception: Option<u16>,
+}
+
+impl<'a> InstructionDecoder<'a> {
+    /// Creates a decoder which follows the branches in the given program image.
+    pub fn new(image: &'a ProgramImage) -> Self {
+        Self {
+            image,
+            next: None,
+            pending_exception: None,
+        }
+    }
+
+    /// Decodes a single trace packet, appending the resulting elements to `elements`.
+    pub fn decode_packet(&mut self, packet: &Packet, elements: &mut Vec<TraceElement>) {
+        match packet {
+            Packet::Async | Packet::TraceInfo { .. } | Packet::Discard => {
+                self.next = None;
+                self.pending_exception = None;
+            }
+            Packet::TraceOn => {
+                self.next = None;
+                elements.push(TraceElement::TraceOn);
+            }
+            Packet::Overflow => {
+                self.next = None;
+                elements.push(TraceElement::Overflow);
+            }
+            Packet::Timestamp {
+                timestamp,
+                cycle_count,
+            } => {
+                elements.push(TraceElement::Timestamp(*timestamp));
+                if let Some(count) = cycle_count {
+                    elements.push(TraceElement::CycleCount(*count));
+                }
+            }
+            Packet::CycleCount { count: Some(count) } => {
+                elements.push(TraceElement::CycleCount(*count))
+            }
+            Packet::Exception { number } => self.pending_exception = Some(*number),
+            Packet::ExceptionReturn => elements.push(TraceElement::ExceptionReturn),
+            Packet::Address { address, isa } => match self.pending_exception.take() {
+                Some(number) => {
+                    // The instructions before the return address were executed before the
+                    // exception was taken. The target of the exception is traced separately.
+                    if let Some((start, isa)) = self.next.take() {
+                        if start < *address {
+                            elements.push(TraceElement::Instructions {
+                                start,
+                                end: *address,
+                                isa,
+                            });
+                        }
+                    }
+                    elements.push(TraceElement::Exception {
+                        number,
+                        return_address: *address,
+                    });
+                }
+                None => self.next = Some((*address, *isa)),
+            },
+            Packet::Atoms(atoms) => {
+                for atom in atoms {
+                    self.decode_atom(*atom, elements);
+                }
+            }
+            Packet::CycleCount { count: None } | Packet::Context | Packet::Ignored { .. } => {}
+        }
+    }
+
+    /// Follows the program to the next branch, whose outcome is given by `atom`.
+    fn decode_atom(&mut self, atom: Atom, elements: &mut Vec<TraceElement>) {
+        let (start, isa) = match self.next {
+            Some(next) => next,
+            None => return,
+        };
+
+        let read = |address, data: &mut [u8]| self.image.read(address, data);
+        let mut address = start;
+        for _ in 0..MAX_INSTRUCTIONS_WITHOUT_BRANCH {
+            let instruction = match instruction::decode(read, address, isa) {
+                Some(instruction) => instruction,
+                None => {
+                    tracing::warn!(
+                        "The instruction at {:#x} is not part of the program image, skipping trace until the next address",
+                        address
+                    );
+                    self.next = None;
+                    return;
+                }
+            };
+            address += instruction.size;
+
+            if let Some(branch) = instruction.branch {
+                elements.push(TraceElement::Instructions {
+                    start,
+                    end: address,
+                    isa,
+                });
+
+                self.next = match (atom, branch) {
+                    (Atom::N, _) => Some((address, isa)),
+                    (Atom::E, Branch::Direct { target, isa }) => Some((target, isa)),
+                    // The target is given by the next address packet.
+                    (Atom::E, Branch::Indirect) => None,
+                };
+                return;
+            }
+        }
+
+        tracing::warn!(
+            "No branch found after {:#x}, skipping trace until the next address",
+            start
+        );
+        self.next = None;
+    }
+}
+
+/// Decodes raw ETMv4 trace into the executed instructions.
+///
+/// This combines a [`PacketDecoder`] and an [`InstructionDecoder`].
+pub fn decode_trace(
+    config: DecoderConfig,
+    image: &ProgramImage,
+    data: &[u8],
+) -> Result<Vec<TraceElement>, EtmError> {
+    let packets = PacketDecoder::new(config).decode(data)?;
+
+    let mut decoder = InstructionDecoder::new(image);
+    let mut elements = Vec::new();
+    for packet in &packets {
+        decoder.decode_packet(packet, &mut elements);
+    }
+
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reconstruct_instructions() {
+        #[rustfmt::skip]
+        let code = vec![
+            // 0x1000: movs r0, #10
+            0x0A, 0x20,
+            // 0x1002: bl 0x100C
+            0x00, 0xF0, 0x03, 0xF8,
+            // 0x1006: subs r0, #1
+            0x01, 0x38,
+            // 0x1008: bne.n 0x1002
+            0xFB, 0xD1,
+            // 0x100A: b.n 0x100A
+            0xFE, 0xE7,
+            // 0x100C: nop
+            0x00, 0xBF,
+            // 0x100E: bx lr
+            0x70, 0x47,
+        ];
+        let image = ProgramImage::new(vec![(0x1000, code)]);
+
+        #[rustfmt::skip]
+        let trace = [
+            // A-Sync
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80,
+            // Trace info, and the start address 0x1000.
+            0x01, 0x00, 0x9B, 0x00, 0x10, 0x00, 0x00,
+            // BL taken, BX LR taken with the return address 0x1006, and BNE not taken.
+            0xDB, 0x96, 0x03, 0xF6,
+            // Exception 3 with the return address 0x100A.
+            0x06, 0x06, 0x96, 0x05,
+        ];
+
+        let elements = decode_trace(DecoderConfig::default(), &image, &trace).unwrap();
+
+        assert_eq!(
+            elements,
+            vec![
+                TraceElement::Instructions {
+                    start: 0x1000,
+                    end: 0x1006,
+                    isa: Isa::T32
+                },
+                TraceElement::Instructions {
+                    start: 0x100C,
+                    end: 0x1010,
+                    isa: Isa::T32
+                },
+                TraceElement::Instructions {
+                    start: 0x1006,
+                    end: 0x100A,
+                    isa: Isa::T32
+                },
+                TraceElement::Exception {
+                    number: 3,
+                    return_address: 0x100A
+                },
+            ]
+        );
+    }
+}
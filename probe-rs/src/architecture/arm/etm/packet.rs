@@ -0,0 +1,597 @@
+//! Parsing of the packets of the ETMv4 instruction trace stream.
+
+use super::{DecoderConfig, EtmError};
+
+/// The instruction set an address is executed in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Isa {
+    /// The A32 (ARM) instruction set of AArch32.
+    A32,
+    /// The T32 (Thumb) instruction set of AArch32, which is the only one of M-profile cores.
+    T32,
+    /// The A64 instruction set of AArch64.
+    A64,
+}
+
+/// The outcome of a branch, as traced by the ETM.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Atom {
+    /// The branch was taken.
+    E,
+    /// The branch was not taken.
+    N,
+}
+
+/// A packet of the ETMv4 instruction trace stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet {
+    /// An alignment synchronization, after which the trace can be decoded.
+    Async,
+    /// The trace unit was reset, and the state of the decoder has to be reset as well.
+    TraceInfo {
+        /// Whether cycle counting is enabled.
+        cycle_counting: bool,
+    },
+    /// Tracing was started again after a gap, e.g. because the program left the traced range.
+    TraceOn,
+    /// Trace was lost because the trace unit could not output it fast enough.
+    Overflow,
+    /// Tracing was disabled while some elements were not yet resolved.
+    Discard,
+    /// A global timestamp.
+    Timestamp {
+        /// The value of the timestamp.
+        timestamp: u64,
+        /// The number of cycles since the last cycle count, if cycle counting is enabled.
+        cycle_count: Option<u32>,
+    },
+    /// An exception was taken. The following [`Packet::Address`] is the preferred return address.
+    Exception {
+        /// The number of the exception.
+        number: u16,
+    },
+    /// An exception return instruction was executed.
+    ExceptionReturn,
+    /// The number of cycles since the last cycle count.
+    CycleCount {
+        /// The number of cycles, if it is known.
+        count: Option<u32>,
+    },
+    /// The context of the execution changed.
+    Context,
+    /// The address of the next instruction, if it can not be deduced from the program.
+    Address {
+        /// The address of the instruction.
+        address: u64,
+        /// The instruction set of the instruction.
+        isa: Isa,
+    },
+    /// The outcomes of consecutive branches, the oldest first.
+    Atoms(Vec<Atom>),
+    /// A packet which does not affect the instruction trace, such as events or commit and
+    /// cancel packets of speculative execution.
+    Ignored {
+        /// The header of the packet.
+        header: u8,
+    },
+}
+
+/// The number of bytes of an A-Sync packet, which is 11 zeros followed by `0x80`.
+const ASYNC_LENGTH: usize = 12;
+
+/// A parser for the packets of a raw ETMv4 instruction trace stream.
+///
+/// Addresses are compressed relative to previous addresses in the trace, so the parser keeps the
+/// address history between packets.
+#[derive(Debug)]
+pub struct PacketDecoder {
+    config: DecoderConfig,
+    /// The last three addresses, the most recent first.
+    address_history: [(u64, Isa); 3],
+    timestamp: u64,
+    cycle_count_threshold: u32,
+    aarch64: bool,
+}
+
+impl PacketDecoder {
+    /// Create a new packet parser for the trace of an ETM with the given configuration.
+    pub fn new(config: DecoderConfig) -> Self {
+        Self {
+            config,
+            address_history: [(0, Isa::T32); 3],
+            timestamp: 0,
+            cycle_count_threshold: 0,
+            aarch64: false,
+        }
+    }
+
+    /// Parses all packets in `data`.
+    ///
+    /// Data before the first A-Sync packet is skipped, as the trace can not be decoded without
+    /// synchronization. An incomplete packet at the end of the data is ignored.
+    pub fn decode(&mut self, data: &[u8]) -> Result<Vec<Packet>, EtmError> {
+        let mut packets = Vec::new();
+
+        let mut offset = match find_async(data) {
+            Some(offset) => offset,
+            None => return Ok(packets),
+        };
+
+        while offset < data.len() {
+            let mut reader = Reader {
+                data,
+                offset: offset + 1,
+            };
+
+            let packet = match self.parse_packet(data[offset], &mut reader) {
+                Ok(packet) => packet,
+                Err(Truncated) => break,
+            };
+            packets.push(packet.map_err(|header| EtmError::UnsupportedPacket { header, offset })?);
+
+            offset = reader.offset;
+        }
+
+        Ok(packets)
+    }
+
+    fn parse_packet(
+        &mut self,
+        header: u8,
+        reader: &mut Reader,
+    ) -> Result<Result<Packet, u8>, Truncated> {
+        let packet = match header {
+            0x00 => match reader.byte()? {
+                0x00 => {
+                    for _ in 2..ASYNC_LENGTH - 1 {
+                        if reader.byte()? != 0x00 {
+                            return Ok(Err(header));
+                        }
+                    }
+                    if reader.byte()? != 0x80 {
+                        return Ok(Err(header));
+                    }
+                    Packet::Async
+                }
+                0x03 => Packet::Discard,
+                0x05 => Packet::Overflow,
+                _ => return Ok(Err(header)),
+            },
+            0x01 => {
+                let control = reader.continued(4)?;
+                let info = if control & 0x1 != 0 {
+                    reader.continued(5)?
+                } else {
+                    0
+                };
+                // The key and speculation depth are only needed for data trace.
+                if control & 0x2 != 0 {
+                    reader.continued(5)?;
+                }
+                if control & 0x4 != 0 {
+                    reader.continued(5)?;
+                }
+                self.cycle_count_threshold = if control & 0x8 != 0 {
+                    reader.continued(2)? as u32
+                } else {
+                    0
+                };
+
+                self.address_history = [(0, Isa::T32); 3];
+                Packet::TraceInfo {
+                    cycle_counting: info & 0x1 != 0,
+                }
+            }
+            0x02 | 0x03 => {
+                let mut value = 0;
+                let mut bits = 0;
+                loop {
+                    let byte = reader.byte()?;
+                    if bits == 56 {
+                        value |= u64::from(byte) << 56;
+                        bits = 64;
+                        break;
+                    }
+                    value |= u64::from(byte & 0x7F) << bits;
+                    bits += 7;
+                    if byte & 0x80 == 0 {
+                        break;
+                    }
+                }
+                self.timestamp = merge(self.timestamp, value, bits);
+
+                let cycle_count = if header & 0x1 != 0 {
+                    Some(reader.continued(3)? as u32)
+                } else {
+                    None
+                };
+                Packet::Timestamp {
+                    timestamp: self.timestamp,
+                    cycle_count,
+                }
+            }
+            0x04 => Packet::TraceOn,
+            // Function returns of ARMv8-M cores are only needed for data trace.
+            0x05 => Packet::Ignored { header },
+            0x06 => {
+                let first = reader.byte()?;
+                let second = if first & 0x80 != 0 { reader.byte()? } else { 0 };
+                Packet::Exception {
+                    number: u16::from((first >> 1) & 0x1F) | (u16::from(second & 0x1F) << 5),
+                }
+            }
+            0x07 => Packet::ExceptionReturn,
+            0x0C | 0x0D => {
+                let count = reader.byte()? >> 4;
+                Packet::CycleCount {
+                    count: Some(u32::from(count) + self.cycle_count_threshold),
+                }
+            }
+            0x0E | 0x0F => {
+                if !self.config.commit_opt {
+                    reader.continued(5)?;
+                }
+                let count = if header & 0x1 == 0 {
+                    Some(reader.continued(3)? as u32)
+                } else {
+                    None
+                };
+                Packet::CycleCount { count }
+            }
+            0x10..=0x1F => Packet::CycleCount {
+                count: Some(u32::from(header & 0x3) + self.cycle_count_threshold),
+            },
+            0x2D..=0x2F => {
+                // Commit and cancel format 1 packets have a count as payload.
+                reader.continued(5)?;
+                Packet::Ignored { header }
+            }
+            0x20..=0x2C | 0x30..=0x3F | 0x70..=0x7F => Packet::Ignored { header },
+            0x80 => Packet::Context,
+            0x81 => {
+                self.context(reader)?;
+                Packet::Context
+            }
+            0x82 | 0x83 | 0x85 | 0x86 => {
+                let packet = match header {
+                    0x82 => self.long_address(reader, Isa::A32, false)?,
+                    0x83 => self.long_address(reader, Isa::T32, false)?,
+                    0x85 => self.long_address(reader, Isa::A64, true)?,
+                    _ => self.long_address(reader, Isa::T32, true)?,
+                };
+                self.context(reader)?;
+                packet
+            }
+            0x90..=0x92 => {
+                let (address, isa) = self.address_history[usize::from(header & 0x3)];
+                self.push_address(address, isa)
+            }
+            0x95 | 0x96 => {
+                let first = reader.byte()?;
+                let (value, bits) = if header == 0x95 {
+                    if first & 0x80 != 0 {
+                        (
+                            (u64::from(first & 0x7F) << 2) | (u64::from(reader.byte()?) << 9),
+                            17,
+                        )
+                    } else {
+                        (u64::from(first & 0x7F) << 2, 9)
+                    }
+                } else if first & 0x80 != 0 {
+                    (
+                        (u64::from(first & 0x7F) << 1) | (u64::from(reader.byte()?) << 8),
+                        16,
+                    )
+                } else {
+                    (u64::from(first & 0x7F) << 1, 8)
+                };
+
+                let isa = if header == 0x95 {
+                    self.instruction_set_0()
+                } else {
+                    Isa::T32
+                };
+                let address = merge(self.address_history[0].0, value, bits);
+                self.push_address(address, isa)
+            }
+            0x9A => self.long_address(reader, Isa::A32, false)?,
+            0x9B => self.long_address(reader, Isa::T32, false)?,
+            0x9D => self.long_address(reader, Isa::A64, true)?,
+            0x9E => self.long_address(reader, Isa::T32, true)?,
+            // Format 6 atoms: a number of E atoms, followed by an E or N atom.
+            0xC0..=0xD4 | 0xE0..=0xF4 => {
+                let count = usize::from(header & 0x1F) + 3;
+                let mut atoms = vec![Atom::E; count];
+                if header & 0x20 != 0 {
+                    atoms[count - 1] = Atom::N;
+                }
+                Packet::Atoms(atoms)
+            }
+            // Format 5 atoms: five atoms in one of four patterns.
+            0xD5..=0xD7 | 0xF5 => {
+                let pattern = match ((header >> 3) & 0x4) | (header & 0x3) {
+                    5 => 0b11110,
+                    1 => 0b00000,
+                    2 => 0b01010,
+                    _ => 0b10101,
+                };
+                atoms(pattern, 5)
+            }
+            // Format 2 atoms: two atoms.
+            0xD8..=0xDB => atoms(header, 2),
+            // Format 4 atoms: four atoms in one of four patterns.
+            0xDC..=0xDF => {
+                let pattern = [0b1110, 0b0000, 0b1010, 0b0101][usize::from(header & 0x3)];
+                atoms(pattern, 4)
+            }
+            // Format 1 atoms: a single atom.
+            0xF6 | 0xF7 => atoms(header, 1),
+            // Format 3 atoms: three atoms.
+            0xF8..=0xFF => atoms(header, 3),
+            // Conditional instruction, Q and ETE packets are not supported.
+            _ => return Ok(Err(header)),
+        };
+
+        Ok(Ok(packet))
+    }
+
+    /// The instruction set of addresses with IS = 0, which depends on the execution state.
+    fn instruction_set_0(&self) -> Isa {
+        if self.aarch64 {
+            Isa::A64
+        } else {
+            Isa::A32
+        }
+    }
+
+    fn long_address(
+        &mut self,
+        reader: &mut Reader,
+        isa: Isa,
+        wide: bool,
+    ) -> Result<Packet, Truncated> {
+        let isa = match isa {
+            Isa::T32 => Isa::T32,
+            _ if wide => {
+                self.aarch64 = true;
+                Isa::A64
+            }
+            _ => self.instruction_set_0(),
+        };
+
+        let mut value = if isa == Isa::T32 {
+            (u64::from(reader.byte()? & 0x7F) << 1) | (u64::from(reader.byte()?) << 8)
+        } else {
+            (u64::from(reader.byte()? & 0x7F) << 2) | (u64::from(reader.byte()? & 0x7F) << 9)
+        };
+        value |= u64::from(reader.byte()?) << 16;
+        value |= u64::from(reader.byte()?) << 24;
+
+        let address = if wide {
+            for shift in [32, 40, 48, 56] {
+                value |= u64::from(reader.byte()?) << shift;
+            }
+            value
+        } else {
+            merge(self.address_history[0].0, value, 32)
+        };
+
+        Ok(self.push_address(address, isa))
+    }
+
+    fn context(&mut self, reader: &mut Reader) -> Result<(), Truncated> {
+        let info = reader.byte()?;
+        self.aarch64 = info & 0x10 != 0;
+
+        if info & 0x40 != 0 {
+            reader.skip(usize::from(self.config.vmid_size))?;
+        }
+        if info & 0x80 != 0 {
+            reader.skip(usize::from(self.config.context_id_size))?;
+        }
+
+        Ok(())
+    }
+
+    fn push_address(&mut self, address: u64, isa: Isa) -> Packet {
+        self.address_history.rotate_right(1);
+        self.address_history[0] = (address, isa);
+
+        Packet::Address { address, isa }
+    }
+}
+
+/// The packet is not complete, because the end of the data was reached.
+struct Truncated;
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, Truncated> {
+        let byte = *self.data.get(self.offset).ok_or(Truncated)?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), Truncated> {
+        for _ in 0..count {
+            self.byte()?;
+        }
+        Ok(())
+    }
+
+    /// Reads a field of up to `max_bytes` bytes, each of which has 7 bits of the value and a
+    /// continuation bit.
+    fn continued(&mut self, max_bytes: usize) -> Result<u64, Truncated> {
+        let mut value = 0;
+        for index in 0..max_bytes {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7F) << (7 * index);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// Finds the offset of the first A-Sync packet.
+fn find_async(data: &[u8]) -> Option<usize> {
+    let mut zeros = 0;
+    for (offset, byte) in data.iter().enumerate() {
+        match byte {
+            0x00 => zeros += 1,
+            0x80 if zeros >= ASYNC_LENGTH - 1 => return Some(offset + 1 - ASYNC_LENGTH),
+            _ => zeros = 0,
+        }
+    }
+
+    None
+}
+
+/// Replaces the lower `bits` bits of `previous` with `value`.
+fn merge(previous: u64, value: u64, bits: u32) -> u64 {
+    if bits >= 64 {
+        value
+    } else {
+        (previous & !((1 << bits) - 1)) | value
+    }
+}
+
+/// Unpacks `count` atoms, the oldest in the least significant bit.
+fn atoms(bits: u8, count: usize) -> Packet {
+    Packet::Atoms(
+        (0..count)
+            .map(|index| {
+                if bits & (1 << index) != 0 {
+                    Atom::E
+                } else {
+                    Atom::N
+                }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ASYNC: [u8; 12] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80];
+
+    fn decode(data: &[u8]) -> Vec<Packet> {
+        let mut trace = ASYNC.to_vec();
+        trace.extend_from_slice(data);
+        PacketDecoder::new(DecoderConfig::default())
+            .decode(&trace)
+            .unwrap()
+    }
+
+    #[test]
+    fn skip_data_before_async() {
+        let mut decoder = PacketDecoder::new(DecoderConfig::default());
+        let mut trace = vec![0xF7, 0x00, 0x12];
+        trace.extend_from_slice(&ASYNC);
+        trace.push(0x04);
+
+        assert_eq!(
+            decoder.decode(&trace).unwrap(),
+            vec![Packet::Async, Packet::TraceOn]
+        );
+    }
+
+    #[test]
+    fn compressed_addresses() {
+        let packets = decode(&[
+            // Trace info, with cycle counting enabled.
+            0x01, 0x01, 0x01, // Long 32-bit T32 address 0x0800_1234.
+            0x9B, 0x1A, 0x12, 0x00, 0x08,
+            // Short T32 address, replacing the lowest 8 bits.
+            0x96, 0x40, // Short T32 address, replacing the lowest 16 bits.
+            0x96, 0x80, 0x56, // Exact match of the first address.
+            0x92,
+        ]);
+
+        assert_eq!(
+            packets,
+            vec![
+                Packet::Async,
+                Packet::TraceInfo {
+                    cycle_counting: true
+                },
+                Packet::Address {
+                    address: 0x0800_1234,
+                    isa: Isa::T32
+                },
+                Packet::Address {
+                    address: 0x0800_1280,
+                    isa: Isa::T32
+                },
+                Packet::Address {
+                    address: 0x0800_5600,
+                    isa: Isa::T32
+                },
+                Packet::Address {
+                    address: 0x0800_1234,
+                    isa: Isa::T32
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn atom_formats() {
+        use Atom::{E, N};
+
+        let packets = decode(&[0xF7, 0xF6, 0xD9, 0xFB, 0xDC, 0xF5, 0xE1]);
+
+        assert_eq!(
+            packets[1..],
+            [
+                Packet::Atoms(vec![E]),
+                Packet::Atoms(vec![N]),
+                Packet::Atoms(vec![E, N]),
+                Packet::Atoms(vec![E, E, N]),
+                Packet::Atoms(vec![N, E, E, E]),
+                Packet::Atoms(vec![N, E, E, E, E]),
+                Packet::Atoms(vec![E, E, E, N]),
+            ]
+        );
+    }
+
+    #[test]
+    fn exception_and_timestamp() {
+        let packets = decode(&[
+            // Exception 3 (HardFault), followed by the return address.
+            0x06, 0x06, 0x9B, 0x08, 0x02, 0x00, 0x08,
+            // Timestamp 0x81 with a cycle count of 5.
+            0x03, 0x81, 0x01, 0x05,
+        ]);
+
+        assert_eq!(
+            packets[1..],
+            [
+                Packet::Exception { number: 3 },
+                Packet::Address {
+                    address: 0x0800_0210,
+                    isa: Isa::T32
+                },
+                Packet::Timestamp {
+                    timestamp: 0x81,
+                    cycle_count: Some(5)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_packet_is_ignored() {
+        assert_eq!(
+            decode(&[0x04, 0x9B, 0x10]),
+            vec![Packet::Async, Packet::TraceOn]
+        );
+    }
+}
@@ -698,6 +698,7 @@ impl PeripheralID {
             ("ARM Ltd", 0x471, 0x00, 0x0000) => Some(PartInfo::new("Cortex-M0  ROM", PeripheralType::Rom)),
             ("ARM Ltd", 0x4C0, 0x00, 0x0000) => Some(PartInfo::new("Cortex-M0+ ROM", PeripheralType::Rom)),
             ("ARM Ltd", 0x4C4, 0x00, 0x0000) => Some(PartInfo::new("Cortex-M4 ROM", PeripheralType::Rom)),
+            ("ARM Ltd", 0x906, 0x14, 0x0000) => Some(PartInfo::new("CoreSight CTI", PeripheralType::Cti)),
             ("ARM Ltd", 0x907, 0x21, 0x0000) => Some(PartInfo::new("CoreSight ETB", PeripheralType::Etb)),
             ("ARM Ltd", 0x908, 0x12, 0x0000) => Some(PartInfo::new("CoreSight TraceFunnel", PeripheralType::TraceFunnel)),
             ("ARM Ltd", 0x910, 0x00, 0x0000) => Some(PartInfo::new("CoreSight ETM9", PeripheralType::Etm)),
@@ -726,7 +727,13 @@ impl PeripheralID {
             ("ARM Ltd", 0xD21, 0x13, 0x4A13) => Some(PartInfo::new("Cortex-M33 ETM", PeripheralType::Etm)),
             ("ARM Ltd", 0xD21, 0x11, 0x0000) => Some(PartInfo::new("Cortex-M33 TPIU", PeripheralType::Tpiu)),
             ("ARM Ltd", 0xD21, 0x14, 0x1A14) => Some(PartInfo::new("Cortex-M33 CTI", PeripheralType::Cti)),
+            ("ARM Ltd", 0xD22, 0x13, 0x4A13) => Some(PartInfo::new("Cortex-M55 ETM", PeripheralType::Etm)),
             ("ARM Ltd", 0x9A3, 0x13, 0x0000) => Some(PartInfo::new("Cortex-M0 MTB", PeripheralType::Mtb)),
+            ("ARM Ltd", 0x932, 0x31, _) => Some(PartInfo::new("Cortex-M0+ MTB", PeripheralType::Mtb)),
+            // Other ETMv4 implementations, e.g. the ones of Cortex-A cores, are identified by their architecture.
+            ("ARM Ltd", _, 0x13, 0x4A13) => Some(PartInfo::new("CoreSight ETMv4", PeripheralType::Etm)),
+            // The same goes for the CTIs of newer cores.
+            ("ARM Ltd", _, 0x14, 0x1A14) => Some(PartInfo::new("CoreSight CTI", PeripheralType::Cti)),
             _ => None,
         }
     }
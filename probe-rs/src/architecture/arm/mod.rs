@@ -5,11 +5,13 @@ pub(crate) mod communication_interface;
 pub mod component;
 pub(crate) mod core;
 pub mod dp;
+pub mod etm;
 pub mod memory;
 pub mod sequences;
 pub mod swo;
 mod traits;
 
+pub use self::core::fault::{read_fault_report, ExceptionFrame, FaultCause, FaultReport};
 pub use self::core::{armv6m, armv7a, armv7m, armv8a, armv8m, Dump};
 use self::{
     ap::{AccessPort, AccessPortError},
@@ -96,6 +98,14 @@ pub enum ArmError {
     #[error("The AP with address {0:?} does not exist.")]
     ApDoesNotExist(ApAddress),
 
+    /// No AP was found at the specified base address of an ADIv6 debug port.
+    #[error("No AP was found at address {0:#x}.")]
+    NoApAtAddress(u64),
+
+    /// An ADIv6 debug port has more APs than can be numbered with [`ApAddress::ap`].
+    #[error("The debug port has {0} APs, but only 256 APs are supported.")]
+    TooManyAccessPorts(usize),
+
     /// The AP has the wrong type for the operation.
     WrongApType,
 
@@ -19,13 +19,14 @@ use std::{
     time::{Duration, Instant},
 };
 
-use probe_rs_target::CoreType;
+use probe_rs_target::{CoreType, ResetType};
 
 use crate::{architecture::arm::ArmProbeInterface, DebugProbeError, MemoryMappedRegister};
 
 use super::{
     ap::{AccessPortError, MemoryAp},
     armv6m::Demcr,
+    armv7m::Aircr,
     communication_interface::{DapProbe, Initialized},
     component::{TraceFunnel, TraceSink},
     core::cortex_m::Dhcsr,
@@ -366,12 +367,28 @@ fn cortex_m_reset_catch_set(core: &mut dyn ArmProbe) -> Result<(), ArmError> {
 
 /// ResetSystem for Cortex-M devices
 fn cortex_m_reset_system(interface: &mut dyn ArmProbe) -> Result<(), ArmError> {
-    use crate::architecture::arm::core::armv7m::{Aircr, Dhcsr};
-
     let mut aircr = Aircr(0);
     aircr.vectkey();
     aircr.set_sysresetreq(true);
 
+    cortex_m_request_reset(interface, aircr)
+}
+
+/// Core-only reset for ARMv7-M devices, using `AIRCR.VECTRESET`.
+///
+/// The peripherals of the chip are not reset.
+fn cortex_m_vect_reset(interface: &mut dyn ArmProbe) -> Result<(), ArmError> {
+    let mut aircr = Aircr(0);
+    aircr.vectkey();
+    aircr.set_vectreset(true);
+
+    cortex_m_request_reset(interface, aircr)
+}
+
+/// Requests a reset through the given AIRCR value, and waits until the reset has happened.
+fn cortex_m_request_reset(interface: &mut dyn ArmProbe, aircr: Aircr) -> Result<(), ArmError> {
+    use crate::architecture::arm::core::armv7m::Dhcsr;
+
     interface.write_word_32(Aircr::get_mmio_address(), aircr.into())?;
 
     let start = Instant::now();
@@ -399,6 +416,40 @@ fn cortex_m_reset_system(interface: &mut dyn ArmProbe) -> Result<(), ArmError> {
     Err(ArmError::Timeout)
 }
 
+/// Resets a core with the given [`ResetType`].
+///
+/// [`ResetType::Default`] uses [`ArmDebugSequence::reset_system`], so that chip specific
+/// reset sequences are used. All other reset types bypass the debug sequence.
+pub(crate) fn reset_with_type(
+    sequence: &dyn ArmDebugSequence,
+    interface: &mut dyn ArmProbe,
+    core_type: CoreType,
+    debug_base: Option<u64>,
+    reset_type: ResetType,
+) -> Result<(), ArmError> {
+    match (reset_type, core_type) {
+        (ResetType::Default, _) => sequence.reset_system(interface, core_type, debug_base),
+        (ResetType::System, _) if core_type.is_cortex_m() => cortex_m_reset_system(interface),
+        (ResetType::VectReset | ResetType::Core, CoreType::Armv7m | CoreType::Armv7em) => {
+            cortex_m_vect_reset(interface)
+        }
+        (ResetType::Core, CoreType::Armv7a) => armv7a_reset_system(interface, debug_base),
+        (ResetType::Core, CoreType::Armv8a) => armv8a_reset_system(interface, debug_base),
+        (ResetType::Hardware, _) => {
+            let mut n_reset = Pins(0);
+            n_reset.set_nreset(true);
+
+            interface.swj_pins(0, n_reset.0 as u32, 0)?;
+            thread::sleep(Duration::from_millis(10));
+
+            sequence.reset_hardware_deassert(interface)
+        }
+        (reset_type, core_type) => Err(ArmError::Other(anyhow::anyhow!(
+            "Reset type {reset_type:?} is not supported for {core_type:?} cores"
+        ))),
+    }
+}
+
 /// A interface to operate debug sequences for ARM targets.
 ///
 /// Should be implemented on a custom handle for chips that require special sequence code.
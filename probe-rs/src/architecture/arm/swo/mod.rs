@@ -5,7 +5,7 @@ use crate::architecture::arm::communication_interface::ArmProbeInterface;
 use super::ArmError;
 
 /// The protocol the SWO pin should use for data transmission.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum SwoMode {
     /// UART
     Uart,
@@ -14,7 +14,7 @@ pub enum SwoMode {
 }
 
 /// The config for the SWO pin.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct SwoConfig {
     /// SWO mode: either UART or Manchester.
     mode: SwoMode,
@@ -103,12 +103,24 @@ impl SwoConfig {
     pub fn tpiu_continuous_formatting(&self) -> bool {
         self.tpiu_continuous_formatting
     }
+
+    /// The prescaler for the TPIU clock which results in the baud rate closest to the configured one.
+    pub(crate) fn prescaler(&self) -> u32 {
+        ((self.tpiu_clk + self.baud / 2) / self.baud).saturating_sub(1)
+    }
+
+    /// The baud rate the target actually generates, which is the TPIU clock divided by a whole number.
+    pub(crate) fn achievable_baud(&self) -> u32 {
+        self.tpiu_clk / (self.prescaler() + 1)
+    }
 }
 
 /// An interface to operate SWO to be implemented on drivers that support SWO.
 pub trait SwoAccess {
     /// Configure a SwoAccess interface for reading SWO data.
-    fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError>;
+    ///
+    /// Returns the baud rate the probe receives at, which may differ from the requested one.
+    fn enable_swo(&mut self, config: &SwoConfig) -> Result<u32, ArmError>;
 
     /// Disable SWO reading on this SwoAccess interface.
     fn disable_swo(&mut self) -> Result<(), ArmError>;
@@ -210,3 +222,24 @@ impl<'a> std::io::Read for SwoReader<'a> {
         Ok(swo.len())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::SwoConfig;
+
+    #[test]
+    fn prescaler_rounds_to_closest_baud() {
+        let config = SwoConfig::new(64_000_000).set_baud(2_000_000);
+        assert_eq!(config.prescaler(), 31);
+        assert_eq!(config.achievable_baud(), 2_000_000);
+
+        // 72 MHz / 2.2 MBd = 32.7, so dividing by 33 is closer than dividing by 32.
+        let config = SwoConfig::new(72_000_000).set_baud(2_200_000);
+        assert_eq!(config.prescaler(), 32);
+
+        // 16 MHz / 3 MBd = 5.33, so the target ends up at 16 MHz / 5.
+        let config = SwoConfig::new(16_000_000).set_baud(3_000_000);
+        assert_eq!(config.prescaler(), 4);
+        assert_eq!(config.achievable_baud(), 3_200_000);
+    }
+}
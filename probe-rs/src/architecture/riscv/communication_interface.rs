@@ -13,7 +13,7 @@ use crate::{
     core::RegisterId,
     memory::valid_32bit_address,
     memory_mapped_bitfield_register,
-    probe::{CommandResult, DeferredResultIndex, JTAGAccess},
+    probe::{CommandResult, DeferredResultIndex, JTAGAccess, UartAccess},
     DebugProbeError, Error as ProbeRsError, MemoryInterface, MemoryMappedRegister, Probe,
 };
 use std::{
@@ -294,6 +294,11 @@ impl RiscvCommunicationInterface {
         Ok(s)
     }
 
+    /// Assert the target reset.
+    pub fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+        self.dtm.target_reset_assert()
+    }
+
     /// Deassert the target reset.
     pub fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
         self.dtm.target_reset_deassert()
@@ -1287,6 +1292,11 @@ impl RiscvCommunicationInterface {
         Ok(())
     }
 
+    /// Returns the interface to the UART of the target, if the probe bridges it.
+    pub fn uart_interface(&mut self) -> Option<&mut dyn UartAccess> {
+        self.dtm.probe.get_uart_interface_mut()
+    }
+
     /// Destruct the interface and return the stored probe driver.
     pub fn close(self) -> Probe {
         Probe::from_attached_probe(self.dtm.probe.into_probe())
@@ -1734,6 +1744,12 @@ impl MemoryInterface for RiscvCommunicationInterface {
         self.read_word(address)
     }
 
+    fn read_word_16(&mut self, address: u64) -> Result<u16, crate::Error> {
+        let address = valid_32bit_address(address)?;
+        tracing::debug!("read_word_16 from {:#08x}", address);
+        self.read_word(address)
+    }
+
     fn read_word_8(&mut self, address: u64) -> Result<u8, crate::Error> {
         let address = valid_32bit_address(address)?;
         tracing::debug!("read_word_8 from {:#08x}", address);
@@ -1757,6 +1773,13 @@ impl MemoryInterface for RiscvCommunicationInterface {
         self.read_multiple(address, data)
     }
 
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+        tracing::debug!("read_16 from {:#08x}", address);
+
+        self.read_multiple(address, data)
+    }
+
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), crate::Error> {
         let address = valid_32bit_address(address)?;
         tracing::debug!("read_8 from {:#08x}", address);
@@ -1783,6 +1806,11 @@ impl MemoryInterface for RiscvCommunicationInterface {
         self.write_word(address, data)
     }
 
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+        self.write_word(address, data)
+    }
+
     fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), crate::Error> {
         let address = valid_32bit_address(address)?;
         self.write_word(address, data)
@@ -1806,6 +1834,13 @@ impl MemoryInterface for RiscvCommunicationInterface {
         self.write_multiple(address, data)
     }
 
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+        tracing::debug!("write_16 to {:#08x}", address);
+
+        self.write_multiple(address, data)
+    }
+
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), crate::Error> {
         let address = valid_32bit_address(address)?;
         tracing::debug!("write_8 to {:#08x}", address);
@@ -67,6 +67,10 @@ impl Dtm {
         })
     }
 
+    pub fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+        self.probe.target_reset_assert()
+    }
+
     pub fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
         self.probe.target_reset_deassert()
     }
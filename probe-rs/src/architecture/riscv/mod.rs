@@ -6,10 +6,11 @@ use self::registers::*;
 use crate::{
     core::{
         Architecture, BreakpointCause, CoreInformation, CoreRegisters, RegisterId, RegisterValue,
+        VectorCatchCondition, Watchpoint, WatchpointKind,
     },
     memory::valid_32bit_address,
     memory_mapped_bitfield_register, CoreInterface, CoreRegister, CoreStatus, CoreType, Error,
-    HaltReason, InstructionSet, MemoryInterface,
+    HaltReason, InstructionSet, MemoryInterface, ResetType,
 };
 use anyhow::{anyhow, Result};
 use bitfield::bitfield;
@@ -101,6 +102,27 @@ impl<'probe> Riscv32<'probe> {
         Ok(())
     }
 
+    /// Returns the size of the `ebreak` or `c.ebreak` instruction at `address`,
+    /// or `None` if there is a different instruction at `address`.
+    fn ebreak_size_at(&mut self, address: u64) -> Result<Option<usize>, crate::Error> {
+        let mut instruction = [0u8; 4];
+
+        self.read_8(address, &mut instruction[..2])?;
+        if instruction[..2] == [0x02, 0x90] {
+            return Ok(Some(2));
+        }
+
+        // Only read the second half of the instruction if it is not a compressed instruction.
+        if instruction[0] & 0b11 == 0b11 {
+            self.read_8(address + 2, &mut instruction[2..])?;
+            if u32::from_le_bytes(instruction) == 0x0010_0073 {
+                return Ok(Some(4));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Check if the connected device supports halt after reset.
     ///
     /// Returns a cached value if available, otherwise queries the
@@ -116,6 +138,73 @@ impl<'probe> Riscv32<'probe> {
             Ok(dmstatus.hasresethaltreq())
         }
     }
+
+    /// Resets the hart using the `hartreset` bit, while requesting a halt.
+    ///
+    /// Returns `false` if the debug module does not support `hartreset`.
+    fn hart_reset(&mut self) -> Result<bool, RiscvError> {
+        tracing::debug!("Resetting core, setting hartreset bit");
+
+        let mut dmcontrol = Dmcontrol(0);
+        dmcontrol.set_dmactive(true);
+        dmcontrol.set_hartreset(true);
+        dmcontrol.set_haltreq(true);
+
+        self.interface.write_dm_register(dmcontrol)?;
+
+        // Read back register to verify reset is supported
+        let readback: Dmcontrol = self.interface.read_dm_register()?;
+
+        if !readback.hartreset() {
+            return Ok(false);
+        }
+
+        tracing::debug!("Clearing hartreset bit");
+        // Reset is performed by setting the bit high, and then low again
+        let mut dmcontrol = readback;
+        dmcontrol.set_dmactive(true);
+        dmcontrol.set_hartreset(false);
+
+        self.interface.write_dm_register(dmcontrol)?;
+
+        Ok(true)
+    }
+
+    /// Resets the whole system using the `ndmreset` bit, while requesting a halt.
+    fn ndm_reset(&mut self) -> Result<(), RiscvError> {
+        tracing::debug!("Resetting system, setting ndmreset bit");
+        let mut dmcontrol = Dmcontrol(0);
+        dmcontrol.set_dmactive(true);
+        dmcontrol.set_ndmreset(true);
+        dmcontrol.set_haltreq(true);
+
+        self.interface.write_dm_register(dmcontrol)?;
+
+        tracing::debug!("Clearing ndmreset bit");
+        let mut dmcontrol = Dmcontrol(0);
+        dmcontrol.set_dmactive(true);
+        dmcontrol.set_ndmreset(false);
+        dmcontrol.set_haltreq(true);
+
+        self.interface.write_dm_register(dmcontrol)?;
+
+        Ok(())
+    }
+
+    /// Resets the target using the reset line of the probe, while requesting a halt.
+    fn hardware_reset(&mut self) -> Result<(), RiscvError> {
+        let mut dmcontrol = Dmcontrol(0);
+        dmcontrol.set_dmactive(true);
+        dmcontrol.set_haltreq(true);
+
+        self.interface.write_dm_register(dmcontrol)?;
+
+        self.interface.target_reset_assert()?;
+        std::thread::sleep(Duration::from_millis(10));
+        self.interface.target_reset_deassert()?;
+
+        Ok(())
+    }
 }
 
 impl<'probe> CoreInterface for Riscv32<'probe> {
@@ -216,8 +305,8 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         Ok(())
     }
 
-    fn reset(&mut self) -> Result<(), crate::Error> {
-        match self.reset_and_halt(Duration::from_millis(500)) {
+    fn reset(&mut self, reset_type: ResetType) -> Result<(), crate::Error> {
+        match self.reset_and_halt(Duration::from_millis(500), reset_type) {
             Ok(_) => self.resume_core()?,
             Err(error) => {
                 return Err(RiscvError::DebugProbe(crate::DebugProbeError::Other(
@@ -232,48 +321,35 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
     fn reset_and_halt(
         &mut self,
         _timeout: Duration,
+        reset_type: ResetType,
     ) -> Result<crate::core::CoreInformation, crate::Error> {
-        tracing::debug!("Resetting core, setting hartreset bit");
+        if reset_type == ResetType::VectReset {
+            return Err(Error::Other(anyhow!(
+                "Reset type {reset_type:?} is not supported for RISC-V cores"
+            )));
+        }
 
         self.reset_catch_set()?;
 
-        let mut dmcontrol = Dmcontrol(0);
-        dmcontrol.set_dmactive(true);
-        dmcontrol.set_hartreset(true);
-        dmcontrol.set_haltreq(true);
-
-        self.interface.write_dm_register(dmcontrol)?;
-
-        // Read back register to verify reset is supported
-        let readback: Dmcontrol = self.interface.read_dm_register()?;
-
-        if readback.hartreset() {
-            tracing::debug!("Clearing hartreset bit");
-            // Reset is performed by setting the bit high, and then low again
-            let mut dmcontrol = readback;
-            dmcontrol.set_dmactive(true);
-            dmcontrol.set_hartreset(false);
-
-            self.interface.write_dm_register(dmcontrol)?;
-        } else {
-            // Hartreset is not supported, whole core needs to be reset
-            //
-            // TODO: Cache this
-            tracing::debug!("Hartreset bit not supported, using ndmreset");
-            let mut dmcontrol = Dmcontrol(0);
-            dmcontrol.set_dmactive(true);
-            dmcontrol.set_ndmreset(true);
-            dmcontrol.set_haltreq(true);
-
-            self.interface.write_dm_register(dmcontrol)?;
-
-            tracing::debug!("Clearing ndmreset bit");
-            let mut dmcontrol = Dmcontrol(0);
-            dmcontrol.set_dmactive(true);
-            dmcontrol.set_ndmreset(false);
-            dmcontrol.set_haltreq(true);
-
-            self.interface.write_dm_register(dmcontrol)?;
+        match reset_type {
+            ResetType::Core => {
+                if !self.hart_reset()? {
+                    return Err(Error::Other(anyhow!(
+                        "The debug module does not support resetting a single hart"
+                    )));
+                }
+            }
+            ResetType::System => self.ndm_reset()?,
+            ResetType::Hardware => self.hardware_reset()?,
+            _ => {
+                if !self.hart_reset()? {
+                    // Hartreset is not supported, whole core needs to be reset
+                    //
+                    // TODO: Cache this
+                    tracing::debug!("Hartreset bit not supported, using ndmreset");
+                    self.ndm_reset()?;
+                }
+            }
         }
 
         // check that cores have reset
@@ -304,19 +380,20 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Software))
         ) && self.state.hw_breakpoints_enabled
         {
-            // If we are halted on a software breakpoint AND we have passed the flashing operation, we can skip the single step and manually advance the dpc.
             let mut debug_pc = self.read_core_reg(RegisterId(0x7b1))?;
-            // Advance the dpc by the size of the EBREAK (ebreak or c.ebreak) instruction.
-            if matches!(self.instruction_set()?, InstructionSet::RV32C) {
-                debug_pc.increment_address(2)?;
-            } else {
-                debug_pc.increment_address(4)?;
-            }
 
-            self.write_core_reg(RegisterId(0x7b1), debug_pc)?;
-            return Ok(CoreInformation {
-                pc: debug_pc.try_into()?,
-            });
+            // If we are halted on a software breakpoint AND we have passed the flashing operation, we can skip the single step and manually advance the dpc.
+            // The breakpoint instruction might have been replaced by the original instruction in the meantime,
+            // e.g. when stepping over a software breakpoint set by the debugger, which then has to be executed.
+            if let Some(ebreak_size) = self.ebreak_size_at(debug_pc.try_into()?)? {
+                // Advance the dpc by the size of the EBREAK (ebreak or c.ebreak) instruction.
+                debug_pc.increment_address(ebreak_size)?;
+
+                self.write_core_reg(RegisterId(0x7b1), debug_pc)?;
+                return Ok(CoreInformation {
+                    pc: debug_pc.try_into()?,
+                });
+            }
         } else if matches!(
             halt_reason,
             CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Hardware))
@@ -466,15 +543,13 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
             // The trigger must be active in at least a single mode
             let trigger_any_mode_active = tdata_value.m() || tdata_value.s() || tdata_value.u();
 
-            let trigger_any_action_enabled =
-                tdata_value.execute() || tdata_value.store() || tdata_value.load();
-
             // Only return if the trigger if it is for an execution debug action in all modes.
+            // Triggers on loads and stores are watchpoints, see `hw_watchpoints`.
             if tdata_value.type_() == 0b10
                 && tdata_value.action() == 1
                 && tdata_value.match_() == 0
                 && trigger_any_mode_active
-                && trigger_any_action_enabled
+                && tdata_value.execute()
             {
                 let breakpoint = self.read_csr(tdata2)?;
                 breakpoints.push(Some(breakpoint as u64));
@@ -587,6 +662,161 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         Ok(())
     }
 
+    fn available_watchpoint_units(&mut self) -> Result<u32, crate::Error> {
+        // Watchpoints use the same triggers as breakpoints.
+        self.available_breakpoint_units()
+    }
+
+    /// See docs on the [`CoreInterface::hw_watchpoints`] trait
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+
+        let mut watchpoints = vec![];
+        let num_hw_watchpoints = self.available_watchpoint_units()? as usize;
+        for wp_unit_index in 0..num_hw_watchpoints {
+            // Select the trigger.
+            self.write_csr(tselect, wp_unit_index as u32)?;
+
+            // Read the trigger "configuration" data.
+            let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+            // The trigger must be active in at least a single mode
+            let trigger_any_mode_active = tdata_value.m() || tdata_value.s() || tdata_value.u();
+
+            let kind = match (tdata_value.load(), tdata_value.store()) {
+                (true, true) => Some(WatchpointKind::Access),
+                (true, false) => Some(WatchpointKind::Read),
+                (false, true) => Some(WatchpointKind::Write),
+                (false, false) => None,
+            };
+
+            // Only return the trigger if it is a data access debug action on an address.
+            match kind {
+                Some(kind)
+                    if tdata_value.type_() == 0b10
+                        && tdata_value.action() == 1
+                        && !tdata_value.execute()
+                        && !tdata_value.select()
+                        && trigger_any_mode_active =>
+                {
+                    let value = self.read_csr(tdata2)? as u64;
+
+                    let (address, length) = match tdata_value.match_() {
+                        // The address matches exactly.
+                        0 => (value, 1),
+                        // The address matches in the upper bits, the size of the range is encoded
+                        // in the number of trailing ones.
+                        1 => {
+                            let length = 1 << (value.trailing_ones() + 1);
+                            (value & !(length - 1), length)
+                        }
+                        _ => {
+                            watchpoints.push(None);
+                            continue;
+                        }
+                    };
+
+                    watchpoints.push(Some(Watchpoint {
+                        address,
+                        length,
+                        kind,
+                    }));
+                }
+                _ => watchpoints.push(None),
+            }
+        }
+
+        Ok(watchpoints)
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        length: u64,
+        kind: WatchpointKind,
+    ) -> Result<(), crate::Error> {
+        let address = valid_32bit_address(address)?;
+
+        if !length.is_power_of_two() || address as u64 & (length - 1) != 0 {
+            return Err(Error::Other(anyhow!(
+                "Unable to watch {} bytes at address {:#010x}. The length must be a power of two, and the address must be aligned to it.",
+                length,
+                address
+            )));
+        }
+
+        // select requested trigger
+        let tselect = 0x7a0;
+        let tdata1 = 0x7a1;
+        let tdata2 = 0x7a2;
+
+        tracing::debug!("Setting watchpoint {}", unit_index);
+
+        self.write_csr(tselect, unit_index as u32)?;
+
+        // verify the trigger has the correct type
+        let tdata_value = Mcontrol(self.read_csr(tdata1)?);
+
+        let trigger_type = tdata_value.type_();
+        if trigger_type != 0b10 {
+            return Err(RiscvError::UnexpectedTriggerType(trigger_type).into());
+        }
+
+        // Ranges are matched using the "napot" encoding, where the upper bits of tdata2 are compared,
+        // and the number of trailing ones determines the size of the range.
+        let (match_mode, value) = if length == 1 {
+            (0, address)
+        } else if length.trailing_zeros() <= tdata_value.maskmax() {
+            (1, address | ((length as u32 / 2) - 1))
+        } else {
+            return Err(Error::Other(anyhow!(
+                "Unable to watch {} bytes, the trigger supports watching at most {} bytes.",
+                length,
+                1u64 << tdata_value.maskmax()
+            )));
+        };
+
+        // Setup the trigger
+        let mut data_watchpoint = Mcontrol(0);
+
+        // Enter debug mode
+        data_watchpoint.set_action(1);
+
+        data_watchpoint.set_match(match_mode);
+
+        data_watchpoint.set_m(true);
+
+        data_watchpoint.set_u(true);
+
+        // Trigger on data accesses
+        data_watchpoint.set_load(matches!(
+            kind,
+            WatchpointKind::Read | WatchpointKind::Access
+        ));
+        data_watchpoint.set_store(matches!(
+            kind,
+            WatchpointKind::Write | WatchpointKind::Access
+        ));
+
+        data_watchpoint.set_dmode(true);
+
+        // Match address
+        data_watchpoint.set_select(false);
+
+        self.write_csr(tdata1, data_watchpoint.0)?;
+        self.write_csr(tdata2, value)?;
+
+        Ok(())
+    }
+
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), crate::Error> {
+        // Watchpoints use the same triggers as breakpoints.
+        self.clear_hw_breakpoint(unit_index)
+    }
+
     fn registers(&self) -> &'static CoreRegisters {
         &RISCV_CORE_REGSISTERS
     }
@@ -682,6 +912,27 @@ impl<'probe> CoreInterface for Riscv32<'probe> {
         self.debug_on_sw_breakpoint(false)?;
         Ok(())
     }
+
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        // RISC-V has no vector catch, but the debug module can halt the hart after a reset.
+        match condition {
+            VectorCatchCondition::CoreReset | VectorCatchCondition::All => self.reset_catch_set(),
+            _ => Err(Error::Other(anyhow!(
+                "{:?} vector catch is not supported on RISC-V cores",
+                condition
+            ))),
+        }
+    }
+
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        match condition {
+            VectorCatchCondition::CoreReset | VectorCatchCondition::All => self.reset_catch_clear(),
+            _ => Err(Error::Other(anyhow!(
+                "{:?} vector catch is not supported on RISC-V cores",
+                condition
+            ))),
+        }
+    }
 }
 
 impl<'probe> MemoryInterface for Riscv32<'probe> {
@@ -697,6 +948,10 @@ impl<'probe> MemoryInterface for Riscv32<'probe> {
         self.interface.read_word_32(address)
     }
 
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        self.interface.read_word_16(address)
+    }
+
     fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
         self.interface.read_word_8(address)
     }
@@ -709,6 +964,10 @@ impl<'probe> MemoryInterface for Riscv32<'probe> {
         self.interface.read_32(address, data)
     }
 
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        self.interface.read_16(address, data)
+    }
+
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
         self.interface.read_8(address, data)
     }
@@ -721,6 +980,10 @@ impl<'probe> MemoryInterface for Riscv32<'probe> {
         self.interface.write_word_32(address, data)
     }
 
+    fn write_word_16(&mut self, address: u64, data: u16) -> Result<(), Error> {
+        self.interface.write_word_16(address, data)
+    }
+
     fn write_word_8(&mut self, address: u64, data: u8) -> Result<(), Error> {
         self.interface.write_word_8(address, data)
     }
@@ -733,6 +996,10 @@ impl<'probe> MemoryInterface for Riscv32<'probe> {
         self.interface.write_32(address, data)
     }
 
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        self.interface.write_16(address, data)
+    }
+
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
         self.interface.write_8(address, data)
     }
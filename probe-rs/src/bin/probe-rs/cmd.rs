@@ -1,16 +1,21 @@
 pub mod benchmark;
+pub mod bscan;
 pub mod cargo_embed;
 pub mod cargo_flash;
 pub mod chip;
+pub mod coredump;
 pub mod dap_server;
 pub mod debug;
 pub mod download;
 pub mod dump;
 pub mod erase;
+pub mod etm;
 pub mod gdb;
 pub mod info;
 pub mod itm;
 pub mod list;
+pub mod profile;
 pub mod reset;
 pub mod run;
+pub mod serve;
 pub mod trace;
@@ -0,0 +1,140 @@
+//! Reads and drives the pins of a device using its boundary register.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context};
+use probe_rs::{
+    bscan::{BoundaryScan, Bsdl},
+    WireProtocol,
+};
+
+use crate::util::common_options::ProbeOptions;
+
+#[derive(clap::Parser)]
+pub struct Cmd {
+    #[clap(flatten)]
+    common: ProbeOptions,
+
+    /// The BSDL file describing the boundary register of the device.
+    #[clap(long, value_parser)]
+    bsdl: PathBuf,
+
+    #[clap(subcommand)]
+    operation: Operation,
+}
+
+#[derive(clap::Subcommand)]
+enum Operation {
+    /// List the pins of the device which are connected to the boundary register
+    Pins,
+    /// Capture the state of the pins, without affecting the device
+    Sample {
+        /// The pins to show. If left open, all pins which can be read are shown.
+        pins: Vec<String>,
+    },
+    /// Disconnect the pins from the device, and drive or read them from the boundary register.
+    ///
+    /// All pins which are not set are in their safe state. The pins are handed back to the device
+    /// when the command exits, so reading them allows testing the connections between pins.
+    Extest {
+        /// Drive a pin, given as PIN=VALUE where VALUE is 0, 1 or z to disable the output.
+        #[clap(long = "set", value_parser = parse_pin_value)]
+        set: Vec<(String, Option<bool>)>,
+
+        /// Read a pin after all pins have been set.
+        #[clap(long = "read")]
+        read: Vec<String>,
+    },
+}
+
+fn parse_pin_value(value: &str) -> Result<(String, Option<bool>), String> {
+    let (pin, value) = value
+        .split_once('=')
+        .ok_or_else(|| format!("'{value}' is not of the form PIN=VALUE"))?;
+
+    let value = match value {
+        "0" => Some(false),
+        "1" => Some(true),
+        "z" | "Z" => None,
+        _ => return Err(format!("'{value}' is not 0, 1 or z")),
+    };
+
+    Ok((pin.to_string(), value))
+}
+
+impl Cmd {
+    pub fn run(self) -> anyhow::Result<()> {
+        let source = std::fs::read_to_string(&self.bsdl)
+            .with_context(|| format!("Failed to read {}", self.bsdl.display()))?;
+        let bsdl = Bsdl::parse(&source)
+            .with_context(|| format!("Failed to parse {}", self.bsdl.display()))?;
+
+        if let Operation::Pins = self.operation {
+            for cell in &bsdl.boundary_register {
+                if let Some(port) = &cell.port {
+                    println!("{:>5}  {:<16} {:?}", cell.number, port, cell.function);
+                }
+            }
+            return Ok(());
+        }
+
+        let mut probe = self.common.attach_probe()?;
+        probe.select_protocol(WireProtocol::Jtag)?;
+        probe.attach_to_unspecified()?;
+
+        let mut scan = probe
+            .try_into_boundary_scan(bsdl)
+            .map_err(|(_, error)| error)?;
+
+        if let Some(idcode) = scan.idcode() {
+            println!("{} with IDCODE {:#010x}", scan.bsdl().entity, idcode);
+        }
+
+        let result = run_operation(&mut scan, self.operation);
+        // Hand the pins back to the device, even if the operation failed.
+        scan.close()?;
+
+        result
+    }
+}
+
+fn run_operation(scan: &mut BoundaryScan, operation: Operation) -> anyhow::Result<()> {
+    match operation {
+        Operation::Pins => unreachable!("listing the pins does not need a probe"),
+        Operation::Sample { pins } => {
+            let states = scan.sample()?;
+
+            if pins.is_empty() {
+                for state in states {
+                    println!("{:<16} {}", state.name, u8::from(state.value));
+                }
+            } else {
+                for pin in pins {
+                    let state = states
+                        .iter()
+                        .find(|state| state.name.eq_ignore_ascii_case(&pin))
+                        .ok_or_else(|| anyhow!("The pin {pin} cannot be read"))?;
+                    println!("{:<16} {}", state.name, u8::from(state.value));
+                }
+            }
+        }
+        Operation::Extest { set, read } => {
+            if set.is_empty() && read.is_empty() {
+                bail!("Nothing to do, use --set or --read");
+            }
+
+            // Preload the values, so that the pins change at the same time.
+            for (pin, value) in &set {
+                scan.write_pin(pin, *value)?;
+            }
+            scan.enter_extest()?;
+
+            for pin in read {
+                let value = scan.read_pin(&pin)?;
+                println!("{:<16} {}", pin, u8::from(value));
+            }
+        }
+    }
+
+    Ok(())
+}
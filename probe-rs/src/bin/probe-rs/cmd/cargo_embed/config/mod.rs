@@ -4,7 +4,7 @@ use figment::{
     Figment,
 };
 use probe_rs::rtt::ChannelMode;
-use probe_rs::WireProtocol;
+use probe_rs::{ResetType, WireProtocol};
 use serde::{Deserialize, Serialize};
 use std::{path::PathBuf, time::Duration};
 
@@ -25,6 +25,7 @@ pub struct Config {
     pub reset: Reset,
     pub probe: Probe,
     pub rtt: Rtt,
+    pub uart: Uart,
     pub gdb: Gdb,
 }
 
@@ -35,6 +36,7 @@ pub struct Probe {
     pub usb_vid: Option<String>,
     pub usb_pid: Option<String>,
     pub serial: Option<String>,
+    pub ftdi_layout: Option<String>,
     pub protocol: WireProtocol,
     pub speed: Option<u32>,
 }
@@ -60,6 +62,8 @@ pub struct Flashing {
 pub struct Reset {
     pub enabled: bool,
     pub halt_afterwards: bool,
+    /// The reset type to use. If not set, the default reset type of the target is used.
+    pub reset_type: Option<ResetType>,
 }
 
 /// The general config struct holding all the possible general options.
@@ -94,6 +98,16 @@ pub struct Rtt {
     pub log_path: PathBuf,
 }
 
+/// The uart config struct holding all the possible options for the UART of the target.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Uart {
+    /// Whether to show the UART of the target in the RTTUI, if the probe bridges it.
+    pub enabled: bool,
+    /// The baud rate of the UART.
+    pub baud_rate: u32,
+}
+
 mod duration_ms {
     use std::time::Duration;
 
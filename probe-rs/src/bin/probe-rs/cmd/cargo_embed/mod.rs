@@ -11,7 +11,7 @@ use probe_rs::rtt::{Rtt, ScanRegion};
 use probe_rs::{
     config::TargetSelector,
     flashing::{download_file_with_options, DownloadOptions, FlashProgress, Format, ProgressEvent},
-    DebugProbeSelector, Permissions, Probe, Session,
+    DebugProbeSelector, Permissions, Probe, Session, UartConfig,
 };
 use std::ffi::OsString;
 use std::{
@@ -180,6 +180,8 @@ fn main_try(mut args: Vec<OsString>, offset: UtcOffset) -> Result<()> {
                     vendor_id: u16::from_str_radix(vid, 16)?,
                     product_id: u16::from_str_radix(pid, 16)?,
                     serial_number: config.probe.serial.clone(),
+                    network_address: None,
+                    ftdi_layout: config.probe.ftdi_layout.clone(),
                 };
                 // if two probes with the same VID:PID pair exist we just choose one
                 Probe::open(selector)?
@@ -265,8 +267,19 @@ fn main_try(mut args: Vec<OsString>, offset: UtcOffset) -> Result<()> {
         flash(&config, &mut session, path, opt.disable_progressbars)?;
     }
 
+    // Start receiving before the reset, so that no output of the target is lost.
+    if config.uart.enabled {
+        session
+            .uart_interface()
+            .ok_or_else(|| anyhow!("The probe does not bridge the UART of the target"))?
+            .enable_uart(&UartConfig::new(config.uart.baud_rate))?;
+    }
+
     if config.reset.enabled {
         let mut core = session.core(0)?;
+        if let Some(reset_type) = config.reset.reset_type {
+            core.set_reset_type(reset_type);
+        }
         let halt_timeout = Duration::from_millis(500);
         #[allow(deprecated)] // Remove in 0.10
         if config.flashing.halt_afterwards {
@@ -312,7 +325,7 @@ fn main_try(mut args: Vec<OsString>, offset: UtcOffset) -> Result<()> {
         }));
     }
 
-    if config.rtt.enabled {
+    if config.rtt.enabled || config.uart.enabled {
         let defmt_enable = config
             .rtt
             .channels
@@ -328,23 +341,28 @@ fn main_try(mut args: Vec<OsString>, offset: UtcOffset) -> Result<()> {
             None
         };
 
-        let rtt_header_address = if let Ok(mut file) = File::open(path) {
-            if let Some(address) = rttui::app::App::get_rtt_symbol(&mut file) {
-                ScanRegion::Exact(address as u32)
+        let rtt = if config.rtt.enabled {
+            let rtt_header_address = if let Ok(mut file) = File::open(path) {
+                if let Some(address) = rttui::app::App::get_rtt_symbol(&mut file) {
+                    ScanRegion::Exact(address as u32)
+                } else {
+                    ScanRegion::Ram
+                }
             } else {
                 ScanRegion::Ram
-            }
-        } else {
-            ScanRegion::Ram
-        };
+            };
 
-        let mut rtt = rtt_attach(session.clone(), config.rtt.timeout, &rtt_header_address)
-            .context("Failed to attach to RTT")?;
+            let mut rtt = rtt_attach(session.clone(), config.rtt.timeout, &rtt_header_address)
+                .context("Failed to attach to RTT")?;
 
-        // Configure rtt channels according to configuration
-        rtt_config(session.clone(), &config, &mut rtt)?;
+            // Configure rtt channels according to configuration
+            rtt_config(session.clone(), &config, &mut rtt)?;
 
-        log::info!("RTT initialized.");
+            log::info!("RTT initialized.");
+            Some(rtt)
+        } else {
+            None
+        };
 
         // Check if the terminal supports x
 
@@ -376,15 +394,23 @@ fn main_try(mut args: Vec<OsString>, offset: UtcOffset) -> Result<()> {
         loop {
             {
                 let mut session_handle = session.lock().unwrap();
-                let mut core = session_handle.core(0)?;
+                {
+                    let mut core = session_handle.core(0)?;
 
-                app.poll_rtt(&mut core, offset)?;
+                    app.poll_rtt(&mut core, offset)?;
 
-                app.render(defmt_state.as_ref());
-                if app.handle_event(&mut core) {
-                    logging::println("Shutting down.");
-                    return Ok(());
-                };
+                    app.render(defmt_state.as_ref());
+                    if app.handle_event(&mut core) {
+                        logging::println("Shutting down.");
+                        return Ok(());
+                    };
+                }
+
+                if config.uart.enabled {
+                    if let Some(uart) = session_handle.uart_interface() {
+                        app.poll_uart(uart, offset)?;
+                    }
+                }
             }
 
             std::thread::sleep(Duration::from_millis(10));
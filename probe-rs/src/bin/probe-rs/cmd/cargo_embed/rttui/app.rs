@@ -5,7 +5,7 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use probe_rs::rtt::RttChannel;
-use probe_rs::Core;
+use probe_rs::{Core, UartAccess};
 use std::{fmt::write, path::PathBuf, sync::mpsc::RecvTimeoutError};
 use std::{
     io::{Read, Seek, Write},
@@ -51,48 +51,16 @@ fn pull_channel<C: RttChannel>(channels: &mut Vec<C>, n: usize) -> Option<C> {
 
 impl App {
     pub fn new(
-        mut rtt: probe_rs::rtt::Rtt,
+        rtt: Option<probe_rs::rtt::Rtt>,
         config: &config::Config,
         logname: String,
     ) -> Result<Self> {
         let mut tabs = Vec::new();
-        if !config.rtt.channels.is_empty() {
-            let mut up_channels = rtt.up_channels().drain().collect::<Vec<_>>();
-            let mut down_channels = rtt.down_channels().drain().collect::<Vec<_>>();
-            for channel in &config.rtt.channels {
-                tabs.push(ChannelState::new(
-                    channel.up.and_then(|up| pull_channel(&mut up_channels, up)),
-                    channel
-                        .down
-                        .and_then(|down| pull_channel(&mut down_channels, down)),
-                    channel.name.clone(),
-                    config.rtt.show_timestamps,
-                    channel.format,
-                ))
-            }
-        } else {
-            let up_channels = rtt.up_channels().drain();
-            let mut down_channels = rtt.down_channels().drain().collect::<Vec<_>>();
-            for channel in up_channels {
-                let number = channel.number();
-                tabs.push(ChannelState::new(
-                    Some(channel),
-                    pull_channel(&mut down_channels, number),
-                    None,
-                    config.rtt.show_timestamps,
-                    DataFormat::String,
-                ));
-            }
-
-            for channel in down_channels {
-                tabs.push(ChannelState::new(
-                    None,
-                    Some(channel),
-                    None,
-                    config.rtt.show_timestamps,
-                    DataFormat::String,
-                ));
-            }
+        if let Some(rtt) = rtt {
+            Self::add_rtt_tabs(&mut tabs, rtt, config);
+        }
+        if config.uart.enabled {
+            tabs.push(ChannelState::new_uart(config.rtt.show_timestamps));
         }
 
         // Code farther down relies on tabs being configured and might panic
@@ -138,6 +106,51 @@ impl App {
         })
     }
 
+    fn add_rtt_tabs(
+        tabs: &mut Vec<ChannelState>,
+        mut rtt: probe_rs::rtt::Rtt,
+        config: &config::Config,
+    ) {
+        if !config.rtt.channels.is_empty() {
+            let mut up_channels = rtt.up_channels().drain().collect::<Vec<_>>();
+            let mut down_channels = rtt.down_channels().drain().collect::<Vec<_>>();
+            for channel in &config.rtt.channels {
+                tabs.push(ChannelState::new(
+                    channel.up.and_then(|up| pull_channel(&mut up_channels, up)),
+                    channel
+                        .down
+                        .and_then(|down| pull_channel(&mut down_channels, down)),
+                    channel.name.clone(),
+                    config.rtt.show_timestamps,
+                    channel.format,
+                ))
+            }
+        } else {
+            let up_channels = rtt.up_channels().drain();
+            let mut down_channels = rtt.down_channels().drain().collect::<Vec<_>>();
+            for channel in up_channels {
+                let number = channel.number();
+                tabs.push(ChannelState::new(
+                    Some(channel),
+                    pull_channel(&mut down_channels, number),
+                    None,
+                    config.rtt.show_timestamps,
+                    DataFormat::String,
+                ));
+            }
+
+            for channel in down_channels {
+                tabs.push(ChannelState::new(
+                    None,
+                    Some(channel),
+                    None,
+                    config.rtt.show_timestamps,
+                    DataFormat::String,
+                ));
+            }
+        }
+    }
+
     pub fn get_rtt_symbol<T: Read + Seek>(file: &mut T) -> Option<u64> {
         let mut buffer = Vec::new();
         if file.read_to_end(&mut buffer).is_ok() {
@@ -492,6 +505,15 @@ impl App {
         Ok(())
     }
 
+    /// Polls the UART of the target, if it is shown in a tab.
+    pub fn poll_uart(&mut self, uart: &mut dyn UartAccess, offset: time::UtcOffset) -> Result<()> {
+        for channel in self.tabs.iter_mut().filter(|channel| channel.is_uart()) {
+            channel.poll_uart(uart, offset)?;
+        }
+
+        Ok(())
+    }
+
     pub fn push_rtt(&mut self, core: &mut Core) {
         self.tabs[self.current_tab].push_rtt(core);
     }
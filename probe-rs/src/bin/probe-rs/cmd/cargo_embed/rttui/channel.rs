@@ -1,7 +1,7 @@
 use std::fmt;
 
 use probe_rs::rtt::{ChannelMode, DownChannel, UpChannel};
-use probe_rs::Core;
+use probe_rs::{Core, UartAccess};
 use time::UtcOffset;
 use time::{macros::format_description, OffsetDateTime};
 
@@ -37,6 +37,10 @@ pub struct ChannelState {
     scroll_offset: usize,
     rtt_buffer: RttBuffer,
     show_timestamps: bool,
+    /// Whether this channel shows the UART of the target instead of RTT channels.
+    uart: bool,
+    /// Data which is sent to the UART with the next poll.
+    uart_output: Vec<u8>,
 }
 
 impl ChannelState {
@@ -68,11 +72,31 @@ impl ChannelState {
             rtt_buffer: RttBuffer([0u8; 1024]),
             show_timestamps,
             data: Vec::new(),
+            uart: false,
+            uart_output: Vec::new(),
+        }
+    }
+
+    /// Creates a channel which shows the UART of the target.
+    pub fn new_uart(show_timestamps: bool) -> Self {
+        Self {
+            uart: true,
+            ..Self::new(
+                None,
+                None,
+                Some("UART".to_owned()),
+                show_timestamps,
+                DataFormat::String,
+            )
         }
     }
 
     pub fn has_down_channel(&self) -> bool {
-        self.down_channel.is_some()
+        self.down_channel.is_some() || self.uart
+    }
+
+    pub fn is_uart(&self) -> bool {
+        self.uart
     }
 
     pub fn messages(&self) -> &Vec<String> {
@@ -147,12 +171,39 @@ impl ChannelState {
             return Ok(());
         }
 
+        let data = self.rtt_buffer.0[..count].to_vec();
+        self.push_data(&data, offset)
+    }
+
+    /// Sends the pending input to the UART, and receives new data from it.
+    ///
+    /// Processes all the new data and adds it to the linebuffer of the channel.
+    pub fn poll_uart(
+        &mut self,
+        uart: &mut dyn UartAccess,
+        offset: UtcOffset,
+    ) -> anyhow::Result<()> {
+        if !self.uart_output.is_empty() {
+            uart.write_uart(&self.uart_output)?;
+            self.uart_output.clear();
+        }
+
+        let data = uart.read_uart()?;
+        if !data.is_empty() {
+            self.push_data(&data, offset)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds received data to the linebuffer of the channel.
+    fn push_data(&mut self, data: &[u8], offset: UtcOffset) -> Result<(), time::Error> {
         match self.format {
             DataFormat::String => {
                 let now = OffsetDateTime::now_utc().to_offset(offset);
 
                 // First, convert the incoming bytes to UTF8.
-                let mut incoming = String::from_utf8_lossy(&self.rtt_buffer.0[..count]).to_string();
+                let mut incoming = String::from_utf8_lossy(data).to_string();
 
                 // Then pop the last stored line from our line buffer if possible and append our new line.
                 let last_line_done = self.last_line_done;
@@ -185,7 +236,7 @@ impl ChannelState {
             }
             // defmt output is later formatted into strings in [App::render].
             DataFormat::BinaryLE | DataFormat::Defmt => {
-                self.data.extend_from_slice(&self.rtt_buffer.0[..count]);
+                self.data.extend_from_slice(data);
             }
         };
 
@@ -193,7 +244,11 @@ impl ChannelState {
     }
 
     pub fn push_rtt(&mut self, core: &mut Core) {
-        if let Some(down_channel) = self.down_channel.as_mut() {
+        if self.uart {
+            self.input += "\n";
+            self.uart_output.extend_from_slice(self.input.as_bytes());
+            self.input.clear();
+        } else if let Some(down_channel) = self.down_channel.as_mut() {
             self.input += "\n";
             down_channel.write(core, self.input.as_bytes()).unwrap();
             self.input.clear();
@@ -163,6 +163,14 @@ pub(crate) fn render_diagnostics(error: OperationError) {
                 format!("Try specifying a speed lower than {speed} kHz")
             ],
         ),
+        OperationError::FailedToConfigureJtagChain(_e) => (
+            error.to_string(),
+            vec![
+                "Check the `--jtag-chain` and `--jtag-tap` arguments. See `--help` for how to use them.".into(),
+                "The IR lengths given with `--jtag-chain` have to match the TAPs of the scan chain, starting with the TAP closest to TDO.".into(),
+                "If the IR lengths are not given, they are detected, which does not work for all TAPs. Try specifying them with `--jtag-chain`.".into(),
+            ],
+        ),
         OperationError::AttachingFailed { source, connect_under_reset } => match source {
             ProbeRsError::ChipNotFound(RegistryError::ChipAutodetectFailed) => (
                 error.to_string(),
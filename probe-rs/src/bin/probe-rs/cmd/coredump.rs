@@ -0,0 +1,70 @@
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::util::{common_options::ProbeOptions, parse_u64};
+
+#[derive(clap::Parser)]
+pub struct Cmd {
+    #[clap(flatten)]
+    common: ProbeOptions,
+
+    /// The path of the ELF core file to create.
+    path: PathBuf,
+
+    /// Additional memory range to include in the core dump, in addition to all RAM regions of the target.
+    /// Can be given multiple times.
+    #[clap(long = "range", value_name = "START..END", value_parser = parse_range)]
+    ranges: Vec<Range<u64>>,
+}
+
+impl Cmd {
+    pub fn run(self) -> anyhow::Result<()> {
+        let mut session = self.common.simple_attach()?;
+
+        let mut running_cores = Vec::new();
+        for (index, _) in session.list_cores() {
+            if !session.core(index)?.core_halted()? {
+                running_cores.push(index);
+            }
+        }
+
+        session.halt_all(Duration::from_millis(100))?;
+
+        let dump = session.core_dump(&self.ranges)?;
+        dump.store(&self.path)?;
+
+        // Leave the target in the state it was found in.
+        for index in running_cores {
+            session.core(index)?.run()?;
+        }
+
+        let memory_size: usize = dump.memory.iter().map(|(_, data)| data.len()).sum();
+        println!(
+            "Stored the registers of {} cores and {} bytes of memory in {}",
+            dump.cores.len(),
+            memory_size,
+            self.path.display()
+        );
+
+        Ok(())
+    }
+}
+
+/// Parses a memory range given as `START..END`.
+fn parse_range(input: &str) -> Result<Range<u64>, String> {
+    let (start, end) = input
+        .split_once("..")
+        .ok_or_else(|| format!("'{input}' is not a range of the form START..END"))?;
+
+    let start = parse_u64(start).map_err(|e| format!("Invalid start address '{start}': {e}"))?;
+    let end = parse_u64(end).map_err(|e| format!("Invalid end address '{end}': {e}"))?;
+
+    if end < start {
+        return Err(format!(
+            "The range {start:#x}..{end:#x} ends before it starts"
+        ));
+    }
+
+    Ok(start..end)
+}
@@ -23,7 +23,10 @@ use dap_types::*;
 use num_traits::Zero;
 use parse_int::parse;
 use probe_rs::{
-    architecture::{arm::ArmError, riscv::communication_interface::RiscvError},
+    architecture::{
+        arm::{component::MtbConfig, ArmError},
+        riscv::communication_interface::RiscvError,
+    },
     debug::{
         ColumnType, DebugRegisters, SourceLocation, SteppingMode, VariableName, VariableNodeType,
         VerifiedBreakpoint,
@@ -1694,6 +1697,85 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
         Ok(())
     }
 
+    /// Custom `mtbEnable` request, which starts recording the branch history of a Cortex-M0+ core in its MTB (Micro Trace Buffer).
+    pub(crate) fn mtb_enable(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: &Request,
+    ) -> Result<()> {
+        let arguments: MtbEnableArguments = get_arguments(self, request)?;
+
+        let mtb = match target_core.core_data.mtb {
+            Some(mtb) => mtb,
+            None => {
+                return self.send_response::<()>(
+                    request,
+                    Err(DebuggerError::Other(anyhow!("The target has no MTB"))),
+                )
+            }
+        };
+
+        let config = MtbConfig::new(arguments.buffer_address, arguments.buffer_size)
+            .set_start_on_dwt_match(arguments.start_on_dwt_match)
+            .set_stop_on_dwt_match(arguments.stop_on_dwt_match);
+
+        match mtb.enable(&mut target_core.core, &config) {
+            Ok(()) => self.send_response::<()>(request, Ok(None)),
+            Err(error) => self.send_response::<()>(request, Err(DebuggerError::ProbeRs(error))),
+        }
+    }
+
+    /// Custom `mtbTrace` request, which returns the branches recorded by the MTB, with their source locations.
+    pub(crate) fn mtb_trace(
+        &mut self,
+        target_core: &mut CoreHandle,
+        request: &Request,
+    ) -> Result<()> {
+        let mtb = match target_core.core_data.mtb {
+            Some(mtb) => mtb,
+            None => {
+                return self.send_response::<()>(
+                    request,
+                    Err(DebuggerError::Other(anyhow!("The target has no MTB"))),
+                )
+            }
+        };
+
+        let branches = match mtb.read_trace(&mut target_core.core) {
+            Ok(branches) => branches,
+            Err(error) => {
+                return self.send_response::<()>(request, Err(DebuggerError::ProbeRs(error)))
+            }
+        };
+
+        let debug_info = &target_core.core_data.debug_info;
+        let location = |address: u64| {
+            let source_location = debug_info.get_source_location(address);
+
+            MtbBranchLocation {
+                address: format!("{address:#010x}"),
+                function: debug_info.function_name(address, true).ok().flatten(),
+                source: source_location.as_ref().and_then(get_dap_source),
+                line: source_location
+                    .and_then(|source_location| source_location.line)
+                    .map(|line| line as i64),
+            }
+        };
+
+        let body = MtbTraceResponseBody {
+            branches: branches
+                .iter()
+                .map(|branch| MtbBranchInfo {
+                    source: location(branch.source),
+                    destination: location(branch.destination),
+                    exception: branch.exception,
+                    trace_start: branch.trace_start,
+                })
+                .collect(),
+        };
+        self.send_response(request, Ok(Some(body)))
+    }
+
     /// Returns one of the standard DAP Requests if all goes well, or a "error" request, which should indicate that the calling function should return.
     /// When preparing to return an "error" request, we will send a Response containing the DebuggerError encountered.
     pub fn listen_for_request(&mut self) -> anyhow::Result<Option<Request>> {
@@ -1881,9 +1963,9 @@ pub fn get_arguments<T: DeserializeOwned, P: ProtocolAdapter>(
     let Some(raw_arguments) = &req.arguments else {
         debug_adapter.send_response::<()>(req, Err(DebuggerError::InvalidRequest))?;
         return Err(DebuggerError::Other(anyhow!(
-            "Failed to get {} arguments", req.command
+            "Failed to get {} arguments",
+            req.command
         )));
-
     };
 
     match serde_json::from_value(raw_arguments.to_owned()) {
@@ -30,6 +30,10 @@ impl DapStatus for CoreStatus {
                     "exception",
                     "Core halted due to an exception, e.g. interupt handler".to_string(),
                 ),
+                HaltReason::VectorCatch(condition) => (
+                    "exception",
+                    format!("Core halted due to a {condition:?} vector catch"),
+                ),
                 HaltReason::Watchpoint => (
                     "data breakpoint",
                     "Core halted due to a watchpoint or data breakpoint".to_string(),
@@ -76,6 +76,62 @@ pub struct RttWindowOpenedArguments {
     pub window_is_open: bool,
 }
 
+/// Arguments for the custom `mtbEnable` request, which starts recording the branch history in the
+/// MTB (Micro Trace Buffer) of a Cortex-M0+ core.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MtbEnableArguments {
+    /// The address of the trace buffer, which has to be reserved in the SRAM.
+    pub buffer_address: u64,
+    /// The size of the trace buffer in bytes, which has to be a power of two.
+    pub buffer_size: u32,
+    /// Only start recording when the DWT comparator connected to the TSTART input of the MTB matches.
+    #[serde(default)]
+    pub start_on_dwt_match: bool,
+    /// Stop recording when the DWT comparator connected to the TSTOP input of the MTB matches.
+    #[serde(default)]
+    pub stop_on_dwt_match: bool,
+}
+
+/// Body of the response to the custom `mtbTrace` request.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MtbTraceResponseBody {
+    /// The branches recorded by the MTB, from the oldest to the newest one.
+    pub branches: Vec<MtbBranchInfo>,
+}
+
+/// A branch recorded by the MTB.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MtbBranchInfo {
+    /// The branch instruction, or the return address of an exception.
+    pub source: MtbBranchLocation,
+    /// The instruction executed after the branch.
+    pub destination: MtbBranchLocation,
+    /// Whether the branch was an exception entry or return.
+    pub exception: bool,
+    /// Whether this is the first branch after recording was started.
+    pub trace_start: bool,
+}
+
+/// The location of one end of a branch recorded by the MTB.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MtbBranchLocation {
+    /// The address of the instruction, formatted as hexadecimal number.
+    pub address: String,
+    /// The function containing the instruction, if it is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    /// The source file of the instruction, if it is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+    /// The line in the source file, if it is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<i64>,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RttChannelEventBody {
@@ -288,6 +288,17 @@ pub(crate) static REPL_COMMANDS: &[ReplCommand<ReplHandler>] = &[
             get_local_variable(evaluate_arguments, target_core, variable_name, gdb_nuf)
         },
     },
+    ReplCommand {
+        command: "call",
+        help_text: "Call a function on the target and show its return value.",
+        sub_commands: None,
+        args: Some(&[
+            ReplCommandArgs::Optional("-t <timeout ms>"),
+            ReplCommandArgs::Required("address (hex)"),
+            ReplCommandArgs::Optional("arguments..."),
+        ]),
+        handler: |target_core, command_arguments, _| call_function(target_core, command_arguments),
+    },
     ReplCommand {
         command: "x",
         help_text: "Examine Memory, using format specifications, at the specified address.",
@@ -1,4 +1,6 @@
-use probe_rs::{debug::VariableName, MemoryInterface};
+use parse_int::parse;
+use probe_rs::{debug::VariableName, MemoryInterface, RegisterValue};
+use std::time::Duration;
 
 use crate::cmd::dap_server::{server::core_data::CoreHandle, DebuggerError};
 
@@ -163,6 +165,55 @@ pub(crate) fn memory_read(
 
 /// Get a list of command matches, based on the given command piece.
 /// The `command_piece` is a valid [`ReplCommand`], which can be either a command or a sub_command.
+/// Call the function at the address given in the `command_arguments`, and report its return value.
+/// - The arguments are `[-t <timeout ms>] <address> [arguments...]`, the timeout defaults to one second.
+pub(crate) fn call_function(
+    target_core: &mut CoreHandle,
+    command_arguments: &str,
+) -> Result<Response, DebuggerError> {
+    let input_arguments = command_arguments.split_whitespace().collect::<Vec<_>>();
+
+    let parse_argument = |index: usize, argument_name: &str| {
+        let argument =
+            input_arguments
+                .get(index)
+                .ok_or_else(|| DebuggerError::MissingArgument {
+                    argument_name: argument_name.to_string(),
+                })?;
+
+        parse::<u64>(argument).map_err(|error| DebuggerError::ArgumentParseError {
+            argument_index: index,
+            argument: argument.to_string(),
+            source: error.into(),
+        })
+    };
+
+    let (timeout, address_index) = if input_arguments.first() == Some(&"-t") {
+        (Duration::from_millis(parse_argument(1, "timeout")?), 2)
+    } else {
+        (Duration::from_secs(1), 0)
+    };
+
+    let address = parse_argument(address_index, "address")?;
+    let arguments = (address_index + 1..input_arguments.len())
+        .map(|index| parse_argument(index, "argument").map(RegisterValue::from))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let result = target_core
+        .core
+        .call_function(address, &arguments, timeout)?;
+
+    Ok(Response {
+        command: "call".to_string(),
+        success: true,
+        message: Some(format!("Function at {address:#010x} returned {result}")),
+        type_: "response".to_string(),
+        request_seq: 0,
+        seq: 0,
+        body: None,
+    })
+}
+
 pub(crate) fn find_commands<'a>(
     repl_commands: &[&'a ReplCommand<ReplHandler>],
     command_piece: &'a str,
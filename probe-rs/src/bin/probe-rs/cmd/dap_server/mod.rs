@@ -10,7 +10,7 @@ use probe_rs::{
     architecture::arm::ap::AccessPortError, flashing::FileDownloadError, DebugProbeError, Error,
 };
 use server::startup::debug;
-use std::{env::var, fs::File, io::stderr};
+use std::{env::var, fs::File, io::stderr, path::PathBuf};
 use time::{OffsetDateTime, UtcOffset};
 use tracing::metadata::LevelFilter;
 use tracing_subscriber::{
@@ -67,12 +67,23 @@ pub struct Cmd {
     /// The debug adapter processed was launched by VSCode, and should terminate itself at the end of every debug session (when receiving `Disconnect` or `Terminate` Request from VSCode). The "false"(default) state of this option implies that the process was launched (and will be managed) by the user.
     #[clap(long, hide = true)]
     vscode: bool,
+
+    /// Inspect the given core dump, created with `probe-rs coredump`, instead of a target connected to a debug probe.
+    /// The `chip` of the debug configuration has to be the target the core dump was created from.
+    #[clap(long)]
+    core_dump: Option<PathBuf>,
 }
 
 pub fn run(cmd: Cmd, time_offset: UtcOffset) -> Result<()> {
     let log_info_message = setup_logging(time_offset)?;
 
-    debug(cmd.port, cmd.vscode, &log_info_message, time_offset)
+    debug(
+        cmd.port,
+        cmd.vscode,
+        cmd.core_dump,
+        &log_info_message,
+        time_offset,
+    )
 }
 
 /// Setup logging, according to the following rules.
@@ -1,7 +1,7 @@
 use crate::cmd::dap_server::DebuggerError;
 use crate::util::rtt;
 use anyhow::{anyhow, Result};
-use probe_rs::{DebugProbeSelector, WireProtocol};
+use probe_rs::{DebugProbeSelector, ResetType, WireProtocol};
 use serde::Deserialize;
 use std::{env::current_dir, path::PathBuf};
 
@@ -37,10 +37,16 @@ pub struct SessionConfig {
     #[serde(default)]
     pub(crate) allow_erase_all: bool,
 
+    /// The reset type used whenever the target is reset. If not set, the default reset type of the target is used.
+    pub(crate) reset_type: Option<ResetType>,
+
     /// Flashing configuration
     #[serde(default)]
     pub(crate) flashing_config: FlashingConfig,
 
+    /// Inspect this core dump, created with `probe-rs coredump`, instead of a target connected to a debug probe.
+    pub(crate) core_dump: Option<PathBuf>,
+
     /// Every core on the target has certain configuration.
     ///
     /// NOTE: Although we allow specifying multiple core configurations, this is a work in progress, and probe-rs-debugger currently only supports debugging a single core.
@@ -53,6 +59,18 @@ impl SessionConfig {
         // Update the `cwd`.
         self.cwd = self.resolve_cwd()?;
 
+        // Update the `core_dump` and validate that the file exists.
+        if let Some(core_dump) = self.core_dump.take() {
+            let core_dump = get_absolute_path(self.cwd.clone(), Some(&core_dump))?;
+            if !core_dump.is_file() {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Core dump file {:?} not found.",
+                    core_dump
+                )));
+            }
+            self.core_dump = Some(core_dump);
+        }
+
         for target_core_config in &mut self.core_configs {
             // Update the `program_binary` and validate that the file exists.
             target_core_config.program_binary = match get_absolute_path(
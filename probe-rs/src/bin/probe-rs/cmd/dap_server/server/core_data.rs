@@ -17,9 +17,10 @@ use crate::cmd::dap_server::{
 use crate::util::rtt::{self, ChannelMode, DataFormat, RttActiveTarget};
 use anyhow::{anyhow, Result};
 use probe_rs::{
+    architecture::arm::{component::Mtb, read_fault_report},
     debug::{debug_info::DebugInfo, ColumnType, VerifiedBreakpoint},
     rtt::{Rtt, ScanRegion},
-    Core, CoreStatus, Error, HaltReason,
+    Core, CoreStatus, Error, HaltReason, VectorCatchCondition,
 };
 use time::UtcOffset;
 
@@ -41,6 +42,8 @@ pub struct CoreData {
     pub(crate) stack_frames: Vec<probe_rs::debug::stack_frame::StackFrame>,
     pub(crate) breakpoints: Vec<session_data::ActiveBreakpoint>,
     pub(crate) rtt_connection: Option<debug_rtt::RttConnection>,
+    /// The MTB (Micro Trace Buffer) of the target, which records the branch history of Cortex-M0+ cores.
+    pub(crate) mtb: Option<Mtb>,
 }
 
 /// [CoreHandle] provides handles to various data structures required to debug a single instance of a core. The actual state is stored in [session_data::SessionData].
@@ -103,7 +106,7 @@ impl<'p> CoreHandle<'p> {
                                             .0
                                             .to_owned(),
                                         description: Some(
-                                            status.short_long_status(program_counter).1,
+                                            self.status_description(status, program_counter),
                                         ),
                                         thread_id: Some(self.core.id() as i64),
                                         preserve_focus_hint: Some(false),
@@ -119,13 +122,10 @@ impl<'p> CoreHandle<'p> {
                                 }
                             }
                             CoreStatus::LockedUp => {
-                                debug_adapter.show_message(
-                                    MessageSeverity::Error,
-                                    status.short_long_status(None).1,
-                                );
-                                return Err(Error::Other(anyhow!(
-                                    status.short_long_status(None).1
-                                )));
+                                let description = self.status_description(status, None);
+                                debug_adapter
+                                    .show_message(MessageSeverity::Error, description.clone());
+                                return Err(Error::Other(anyhow!(description)));
                             }
                             CoreStatus::Unknown => {
                                 debug_adapter.show_error_message(&DebuggerError::Other(
@@ -156,6 +156,31 @@ impl<'p> CoreHandle<'p> {
         }
     }
 
+    /// The long description of the core status, with the fault report appended if a Cortex-M core halted or locked up because of a fault.
+    fn status_description(&mut self, status: CoreStatus, program_counter: Option<u64>) -> String {
+        let description = status.short_long_status(program_counter).1;
+
+        let is_fault = match status {
+            CoreStatus::LockedUp | CoreStatus::Halted(HaltReason::Exception) => true,
+            CoreStatus::Halted(HaltReason::VectorCatch(condition)) => !matches!(
+                condition,
+                VectorCatchCondition::CoreReset | VectorCatchCondition::All
+            ),
+            _ => false,
+        };
+        if !is_fault || !self.core.core_type().is_cortex_m() {
+            return description;
+        }
+
+        match read_fault_report(&mut self.core) {
+            Ok(report) => format!("{description} - {}", report.summary()),
+            Err(error) => {
+                tracing::debug!("Failed to read the fault report: {:?}", error);
+                description
+            }
+        }
+    }
+
     /// Search available [`probe_rs::debug::StackFrame`]'s for the given `id`
     pub(crate) fn get_stackframe(
         &'p self,
@@ -249,9 +274,9 @@ impl<'p> CoreHandle<'p> {
         }
 
         self.core
-            .set_hw_breakpoint(address)
+            .set_breakpoint(address)
             .map_err(DebuggerError::ProbeRs)?;
-        // Wait until the set of the hw breakpoint succeeded, before we cache it here ...
+        // Wait until the set of the breakpoint succeeded, before we cache it here ...
         self.core_data
             .breakpoints
             .push(session_data::ActiveBreakpoint {
@@ -264,7 +289,7 @@ impl<'p> CoreHandle<'p> {
     /// Clear a single breakpoint from target configuration.
     pub(crate) fn clear_breakpoint(&mut self, address: u64) -> Result<()> {
         self.core
-            .clear_hw_breakpoint(address)
+            .clear_breakpoint(address)
             .map_err(DebuggerError::ProbeRs)?;
         if let Some((breakpoint_position, _)) = self.find_breakpoint_in_cache(address) {
             self.core_data.breakpoints.remove(breakpoint_position);
@@ -1,5 +1,5 @@
 use super::{
-    configuration::{self, ConsoleLog},
+    configuration::{self, ConsoleLog, FlashingConfig},
     session_data::SessionData,
     startup::{get_file_timestamp, TargetSessionType},
 };
@@ -27,7 +27,7 @@ use std::{
     cell::RefCell,
     fs,
     ops::Mul,
-    path::Path,
+    path::{Path, PathBuf},
     rc::Rc,
     thread,
     time::{Duration, UNIX_EPOCH},
@@ -59,15 +59,20 @@ pub struct Debugger {
     // TODO: Store somewhere else
     // Timestamp of the flashed binary
     binary_timestamp: Option<Duration>,
+
+    /// Core dump to inspect instead of a target connected to a debug probe,
+    /// as given on the command line.
+    core_dump: Option<PathBuf>,
 }
 
 impl Debugger {
     /// Create a new debugger instance
-    pub fn new(timestamp_offset: UtcOffset) -> Self {
+    pub fn new(timestamp_offset: UtcOffset, core_dump: Option<PathBuf>) -> Self {
         Self {
             config: configuration::SessionConfig::default(),
             timestamp_offset,
             binary_timestamp: None,
+            core_dump,
         }
     }
 
@@ -132,17 +137,18 @@ impl Debugger {
                 // Attach to the core. so that we have the handle available for processing the request.
 
                 let Some(target_core_config) = self.config.core_configs.get_mut(core_id) else {
-                        return Err(DebuggerError::Other(anyhow!(
-                            "No core configuration found for core id {}",
-                            core_id
-                        )));
-                    };
+                    return Err(DebuggerError::Other(anyhow!(
+                        "No core configuration found for core id {}",
+                        core_id
+                    )));
+                };
 
-                let Ok(mut target_core) = session_data.attach_core(target_core_config.core_index) else {
-                        return Err(DebuggerError::Other(anyhow!(
-                            "Unable to connect to target core"
-                        )));
-                    };
+                let Ok(mut target_core) = session_data.attach_core(target_core_config.core_index)
+                else {
+                    return Err(DebuggerError::Other(anyhow!(
+                        "Unable to connect to target core"
+                    )));
+                };
 
                 // For some operations, we need to make sure the core isn't sleeping, by calling `Core::halt()`.
                 // When we do this, we need to flag it (`unhalt_me = true`), and later call `Core::run()` again.
@@ -260,6 +266,8 @@ impl Debugger {
                     "continue" => debug_adapter.r#continue(&mut target_core, &request),
                     "evaluate" => debug_adapter.evaluate(&mut target_core, &request),
                     "completions" => debug_adapter.completions(&mut target_core, &request),
+                    "mtbEnable" => debug_adapter.mtb_enable(&mut target_core, &request),
+                    "mtbTrace" => debug_adapter.mtb_trace(&mut target_core, &request),
                     other_command => {
                         // Unimplemented command.
                         debug_adapter.send_response::<()>(
@@ -400,6 +408,18 @@ impl Debugger {
 
         self.config = configuration::SessionConfig { ..arguments };
 
+        if let Some(core_dump) = &self.core_dump {
+            self.config.core_dump = Some(core_dump.clone());
+        }
+
+        // A core dump can only be inspected, so there is nothing to flash or reset, and the request is handled like an `attach` request.
+        let requested_target_session_type = if self.config.core_dump.is_some() {
+            self.config.flashing_config = FlashingConfig::default();
+            TargetSessionType::AttachRequest
+        } else {
+            requested_target_session_type
+        };
+
         if requested_target_session_type == TargetSessionType::AttachRequest {
             // Since VSCode doesn't do field validation checks for relationships in launch.json request types, check it here.
             if self.config.flashing_config.flashing_enabled
@@ -443,11 +463,11 @@ impl Debugger {
                 ))
             })?;
             let Some(path_to_elf) = target_core_config.program_binary.clone() else {
-                    let err =  DebuggerError::Other(anyhow!("Please specify use the `program-binary` option in `launch.json` to specify an executable"));
+                let err =  DebuggerError::Other(anyhow!("Please specify use the `program-binary` option in `launch.json` to specify an executable"));
 
-                    debug_adapter.show_error_message(&err)?;
-                    return Err(err);
-                };
+                debug_adapter.show_error_message(&err)?;
+                return Err(err);
+            };
 
             // Store timestamp of flashed binary
             self.binary_timestamp = get_file_timestamp(&path_to_elf);
@@ -529,11 +549,11 @@ impl Debugger {
                 ))
             })?;
             let Some(path_to_elf) = target_core_config.program_binary.clone() else {
-                    let err =  DebuggerError::Other(anyhow!("Please specify use the `program-binary` option in `launch.json` to specify an executable"));
+                let err =  DebuggerError::Other(anyhow!("Please specify use the `program-binary` option in `launch.json` to specify an executable"));
 
-                    debug_adapter.show_error_message(&err)?;
-                    return Err(err);
-                };
+                debug_adapter.show_error_message(&err)?;
+                return Err(err);
+            };
 
             if is_file_newer(&mut self.binary_timestamp, &path_to_elf) {
                 // If there is a new binary as part of a restart, there are some key things that
@@ -12,9 +12,9 @@ use anyhow::{anyhow, Result};
 use probe_rs::{
     config::TargetSelector,
     debug::{debug_info::DebugInfo, SourceLocation},
-    CoreStatus, DebugProbeError, Permissions, Probe, ProbeCreationError, Session,
+    CoreDump, CoreStatus, DebugProbeError, Permissions, Probe, ProbeCreationError, Session,
 };
-use std::env::set_current_dir;
+use std::{env::set_current_dir, path::Path, time::Duration};
 use time::UtcOffset;
 
 /// The supported breakpoint types
@@ -65,72 +65,9 @@ impl SessionData {
         timestamp_offset: UtcOffset,
     ) -> Result<Self, DebuggerError> {
         // `SessionConfig` Probe/Session level configurations initialization.
-        let mut target_probe = match config.probe_selector.clone() {
-            Some(selector) => Probe::open(selector.clone()).map_err(|e| match e {
-                DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound) => {
-                    DebuggerError::Other(anyhow!(
-                        "Could not find the probe_selector specified as {:04x}:{:04x}:{:?}",
-                        selector.vendor_id,
-                        selector.product_id,
-                        selector.serial_number
-                    ))
-                }
-                other_error => DebuggerError::DebugProbe(other_error),
-            }),
-            None => {
-                // Only automatically select a probe if there is only a single probe detected.
-                let list = Probe::list_all();
-                if list.len() > 1 {
-                    return Err(DebuggerError::Other(anyhow!(
-                        "Found multiple ({}) probes",
-                        list.len()
-                    )));
-                }
-
-                if let Some(info) = list.first() {
-                    Probe::open(info).map_err(DebuggerError::DebugProbe)
-                } else {
-                    return Err(DebuggerError::Other(anyhow!(
-                        "No probes found. Please check your USB connections."
-                    )));
-                }
-            }
-        }?;
-
-        let target_selector = match &config.chip {
-            Some(identifier) => identifier.into(),
-            None => TargetSelector::Auto,
-        };
-
-        // Set the protocol, if the user explicitly selected a protocol. Otherwise, use the default protocol of the probe.
-        if let Some(wire_protocol) = config.wire_protocol {
-            target_probe.select_protocol(wire_protocol)?;
-        }
-
-        // Set the speed.
-        if let Some(speed) = config.speed {
-            let actual_speed = target_probe.set_speed(speed)?;
-            if actual_speed != speed {
-                tracing::warn!(
-                    "Protocol speed {} kHz not supported, actual speed is {} kHz",
-                    speed,
-                    actual_speed
-                );
-            }
-        }
-
-        let mut permissions = Permissions::new();
-        if config.allow_erase_all {
-            permissions = permissions.allow_erase_all();
-        }
-
-        // Attach to the probe.
-        let target_session = if config.connect_under_reset {
-            target_probe.attach_under_reset(target_selector, permissions)?
-        } else {
-            target_probe
-                .attach(target_selector, permissions)
-                .map_err(|err| anyhow!("Error attaching to the probe: {:?}.", err))?
+        let mut target_session = match &config.core_dump {
+            Some(core_dump) => open_core_dump(core_dump, config.chip.as_deref())?,
+            None => attach_to_probe(config)?,
         };
 
         // Change the current working directory if `config.cwd` is `Some(T)`.
@@ -165,9 +102,18 @@ impl SessionData {
             .cloned()
             .collect::<Vec<CoreConfig>>();
 
+        // Only Cortex-M0+ cores commonly have an MTB, so it is fine if none is found.
+        let mtb = target_session.mtb().ok();
+
         let mut core_data_vec = vec![];
 
         for core_configuration in &valid_core_configs {
+            if let Some(reset_type) = config.reset_type {
+                target_session
+                    .core(core_configuration.core_index)?
+                    .set_reset_type(reset_type);
+            }
+
             core_data_vec.push(CoreData {
                 core_index: core_configuration.core_index,
                 last_known_status: CoreStatus::Unknown,
@@ -181,6 +127,7 @@ impl SessionData {
                 stack_frames: Vec::<probe_rs::debug::stack_frame::StackFrame>::new(),
                 breakpoints: Vec::<ActiveBreakpoint>::new(),
                 rtt_connection: None,
+                mtb,
             })
         }
 
@@ -257,6 +204,9 @@ impl SessionData {
 
         let timestamp_offset = self.timestamp_offset;
 
+        // Set if one of the cores halted since the last poll, e.g. because of a breakpoint.
+        let mut newly_halted_core = false;
+
         // Always set `all_cores_halted` to true, until one core is found to be running.
         debug_adapter.all_cores_halted = true;
         for core_config in session_config.core_configs.iter() {
@@ -268,6 +218,8 @@ impl SessionData {
                 continue;
             };
 
+            let was_halted = target_core.core_data.last_known_status.is_halted();
+
             // We need to poll the core to determine its status.
             let current_core_status = target_core.poll_core(debug_adapter).map_err(|error| {
                 let error = DebuggerError::ProbeRs(error);
@@ -311,13 +263,112 @@ impl SessionData {
             // By setting it here, we ensure that RTT will be checked at least once after the core has halted.
             if !current_core_status.is_halted() {
                 debug_adapter.all_cores_halted = false;
+            } else if !was_halted {
+                newly_halted_core = true;
             }
             status_of_cores.push(current_core_status);
         }
+
+        // When one core halts, stop the others as well, so that the state of all cores can be inspected.
+        // The new state of the other cores is reported on the next poll.
+        if newly_halted_core && !debug_adapter.all_cores_halted {
+            self.session
+                .halt_all(Duration::from_millis(100))
+                .map_err(DebuggerError::ProbeRs)?;
+        }
+
         Ok((status_of_cores, suggest_delay_required))
     }
 }
 
+/// Opens the debug probe and attaches to the target, as selected in the [`SessionConfig`].
+fn attach_to_probe(config: &SessionConfig) -> Result<Session, DebuggerError> {
+    let mut target_probe = match config.probe_selector.clone() {
+        Some(selector) => Probe::open(selector.clone()).map_err(|e| match e {
+            DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound) => {
+                DebuggerError::Other(anyhow!(
+                    "Could not find the probe_selector specified as {:04x}:{:04x}:{:?}",
+                    selector.vendor_id,
+                    selector.product_id,
+                    selector.serial_number
+                ))
+            }
+            other_error => DebuggerError::DebugProbe(other_error),
+        }),
+        None => {
+            // Only automatically select a probe if there is only a single probe detected.
+            let list = Probe::list_all();
+            if list.len() > 1 {
+                return Err(DebuggerError::Other(anyhow!(
+                    "Found multiple ({}) probes",
+                    list.len()
+                )));
+            }
+
+            if let Some(info) = list.first() {
+                Probe::open(info).map_err(DebuggerError::DebugProbe)
+            } else {
+                return Err(DebuggerError::Other(anyhow!(
+                    "No probes found. Please check your USB connections."
+                )));
+            }
+        }
+    }?;
+
+    let target_selector = match &config.chip {
+        Some(identifier) => identifier.into(),
+        None => TargetSelector::Auto,
+    };
+
+    // Set the protocol, if the user explicitly selected a protocol. Otherwise, use the default protocol of the probe.
+    if let Some(wire_protocol) = config.wire_protocol {
+        target_probe.select_protocol(wire_protocol)?;
+    }
+
+    // Set the speed.
+    if let Some(speed) = config.speed {
+        let actual_speed = target_probe.set_speed(speed)?;
+        if actual_speed != speed {
+            tracing::warn!(
+                "Protocol speed {} kHz not supported, actual speed is {} kHz",
+                speed,
+                actual_speed
+            );
+        }
+    }
+
+    let mut permissions = Permissions::new();
+    if config.allow_erase_all {
+        permissions = permissions.allow_erase_all();
+    }
+
+    // Attach to the probe.
+    let target_session = if config.connect_under_reset {
+        target_probe.attach_under_reset(target_selector, permissions)?
+    } else {
+        target_probe
+            .attach(target_selector, permissions)
+            .map_err(|err| anyhow!("Error attaching to the probe: {:?}.", err))?
+    };
+
+    Ok(target_session)
+}
+
+/// Opens a core dump, to inspect the target state it captured instead of a target connected to a debug probe.
+fn open_core_dump(core_dump: &Path, chip: Option<&str>) -> Result<Session, DebuggerError> {
+    let chip = chip.ok_or_else(|| {
+        DebuggerError::UserMessage(
+            "Please use the `chip` option to specify the target the core dump was created from."
+                .to_string(),
+        )
+    })?;
+
+    let dump = CoreDump::load(core_dump)
+        .map_err(|error| anyhow!("Failed to load the core dump {:?}: {:?}", core_dump, error))?;
+
+    Ok(Session::from_core_dump(dump, chip)?)
+}
+
 pub(crate) fn debug_info_from_binary(
     core_configuration: &CoreConfig,
 ) -> Result<DebugInfo, DebuggerError> {
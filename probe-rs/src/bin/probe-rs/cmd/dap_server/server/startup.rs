@@ -5,7 +5,7 @@ use serde::Deserialize;
 use std::{
     fs,
     net::{Ipv4Addr, TcpListener},
-    path::Path,
+    path::{Path, PathBuf},
     time::{Duration, UNIX_EPOCH},
 };
 use time::UtcOffset;
@@ -33,10 +33,11 @@ impl std::str::FromStr for TargetSessionType {
 pub fn debug(
     port: u16,
     vscode: bool,
+    core_dump: Option<PathBuf>,
     log_info_message: &str,
     timestamp_offset: UtcOffset,
 ) -> Result<()> {
-    let mut debugger = Debugger::new(timestamp_offset);
+    let mut debugger = Debugger::new(timestamp_offset, core_dump);
 
     log_to_console_and_tracing("Starting as a DAP Protocol server");
 
@@ -12,11 +12,15 @@ use probe_rs::architecture::arm::ap::AccessPortError;
 use probe_rs::flashing::FileDownloadError;
 use probe_rs::DebugProbeError;
 use probe_rs::{
-    architecture::arm::Dump,
+    architecture::arm::{
+        component::{Mtb, MtbConfig},
+        read_fault_report, Dump,
+    },
     debug::{
         debug_info::DebugInfo, registers::DebugRegisters, stack_frame::StackFrame, VariableName,
     },
     Core, CoreRegister, CoreType, InstructionSet, MemoryInterface, RegisterId, RegisterValue,
+    VectorCatchCondition, WatchpointKind,
 };
 use rustyline::DefaultEditor;
 
@@ -46,9 +50,12 @@ impl Cmd {
 
         let cli = DebugCli::new();
 
+        // Only Cortex-M0+ cores commonly have an MTB, so it is fine if none is found.
+        let mtb = session.mtb().ok();
+
         let core = session.core(self.shared.core)?;
 
-        let mut cli_data = CliData::new(core, di)?;
+        let mut cli_data = CliData::new(core, di, mtb)?;
 
         let mut rl = DefaultEditor::new()?;
 
@@ -129,6 +136,30 @@ where
     })
 }
 
+/// Parse the vector catch condition at the given index.
+fn get_vector_catch_argument(
+    args: &[&str],
+    index: usize,
+) -> Result<VectorCatchCondition, CliError> {
+    let arg_str = args.get(index).ok_or(CliError::MissingArgument)?;
+
+    match *arg_str {
+        "reset" => Ok(VectorCatchCondition::CoreReset),
+        "hardfault" => Ok(VectorCatchCondition::HardFault),
+        "memmanage" => Ok(VectorCatchCondition::MemManage),
+        "busfault" => Ok(VectorCatchCondition::BusFault),
+        "usagefault" => Ok(VectorCatchCondition::UsageFault),
+        "interrupt" => Ok(VectorCatchCondition::InterruptError),
+        "securefault" => Ok(VectorCatchCondition::SecureFault),
+        "all" => Ok(VectorCatchCondition::All),
+        other => Err(CliError::ArgumentParseError {
+            argument_index: index,
+            argument: other.to_string(),
+            source: anyhow!("Expected one of 'reset', 'hardfault', 'memmanage', 'busfault', 'usagefault', 'interrupt', 'securefault' or 'all'."),
+        }),
+    }
+}
+
 impl DebugCli {
     fn new() -> DebugCli {
         let mut cli = DebugCli {
@@ -233,84 +264,54 @@ impl DebugCli {
 
                 if status.is_halted() {
                     let pc_desc = cli_data.core.program_counter();
-                    let pc: u64 = cli_data
-                        .core
-                        .read_core_reg(pc_desc)?;
-                    println!("Core halted at address {:#0width$x}", pc, width = pc_desc.format_hex_width());
+                    let pc: u64 = cli_data.core.read_core_reg(pc_desc)?;
+                    println!(
+                        "Core halted at address {:#0width$x}",
+                        pc,
+                        width = pc_desc.format_hex_width()
+                    );
 
                     // determine if the target is handling an interupt
 
                     if cli_data.core.architecture() == probe_rs::Architecture::Arm {
                         match cli_data.core.core_type() {
-                            CoreType::Armv6m | CoreType::Armv7em | CoreType::Armv7m | CoreType::Armv8m | CoreType::Armv7a | CoreType::Armv8a => {
+                            CoreType::Armv6m
+                            | CoreType::Armv7em
+                            | CoreType::Armv7m
+                            | CoreType::Armv8m
+                            | CoreType::Armv7a
+                            | CoreType::Armv8a => {
                                 // Unwrap is safe here because ARM always defines this register
                                 let psr_desc = cli_data.core.registers().psr().unwrap();
 
-                                let xpsr: u32 = cli_data.core.read_core_reg(
-                                    psr_desc,
-                                )?;
+                                let xpsr: u32 = cli_data.core.read_core_reg(psr_desc)?;
 
-                                println!("XPSR: {:#0width$x}", xpsr, width = psr_desc.format_hex_width());
+                                println!(
+                                    "XPSR: {:#0width$x}",
+                                    xpsr,
+                                    width = psr_desc.format_hex_width()
+                                );
 
                                 // This is Cortex-M specific interpretation
                                 // It's hard to generally model these concepts for any possible CoreType,
                                 // but it may be worth considering moving this into the CoreInterface somehow
                                 // in the future
                                 if cli_data.core.core_type().is_cortex_m() {
-
                                     let exception_number = xpsr & 0xff;
 
                                     if exception_number != 0 {
                                         println!("Currently handling exception {exception_number}");
 
-                                        if exception_number == 3 {
-                                            println!("Hard Fault!");
-
-
-                                            let return_address: u64 = cli_data.core.read_core_reg(cli_data.core.return_address())?;
-
-                                            println!("Return address (LR): {return_address:#010x}");
-
-                                            // Get reason for hard fault
-                                            let hfsr = cli_data.core.read_word_32(0xE000_ED2C)?;
-
-                                            if hfsr & (1 << 30) == (1 << 30) {
-                                                println!("-> configurable priority exception has been escalated to hard fault!");
-
-
-                                                // read cfsr 
-                                                let cfsr = cli_data.core.read_word_32(0xE000_ED28)?;
-
-                                                let ufsr = (cfsr >> 16) & 0xffff;
-                                                let bfsr = (cfsr >> 8) & 0xff;
-                                                let mmfsr = cfsr & 0xff;
-
-
-                                                if ufsr != 0 {
-                                                    println!("\tUsage Fault     - UFSR: {ufsr:#06x}");
-                                                }
-
-                                                if bfsr != 0 {
-                                                    println!("\tBus Fault       - BFSR: {bfsr:#04x}");
-
-                                                    if bfsr & (1 << 7) == (1 << 7) {
-                                                        // Read address from BFAR
-                                                        let bfar = cli_data.core.read_word_32(0xE000_ED38)?;
-                                                        println!("\t Location       - BFAR: {bfar:#010x}");
-                                                    }
-                                                }
-
-                                                if mmfsr != 0 {
-                                                    println!("\tMemManage Fault - BFSR: {bfsr:04x}");
-                                                }
-
-                                            }
+                                        // Exceptions 3 to 7 are the fault handlers.
+                                        if (3..=7).contains(&exception_number) {
+                                            let report = read_fault_report(&mut cli_data.core)?;
+                                            println!("{report}");
                                         }
                                     }
                                 }
-                            },
+                            }
                             // Nothing extra to log
-                            _ => {},
+                            _ => {}
                         }
                     }
                 }
@@ -319,6 +320,19 @@ impl DebugCli {
             },
         });
 
+        cli.add_command(Command {
+            name: "fault",
+            help_text: "Show the fault status registers and the stacked exception frame of a Cortex-M core",
+
+            function: |cli_data, _args| {
+                let report = read_fault_report(&mut cli_data.core)?;
+
+                println!("{report}");
+
+                Ok(CliState::Continue)
+            },
+        });
+
         cli.add_command(Command {
             name: "run",
             help_text: "Resume execution of the CPU",
@@ -395,6 +409,35 @@ impl DebugCli {
             },
         });
 
+        cli.add_command(Command {
+            name: "read_16",
+            help_text: "Read 16bit value from memory",
+
+            function: |cli_data, args| {
+                let address = get_int_argument(args, 0)?;
+
+                let num_words = if args.len() > 1 {
+                    get_int_argument(args, 1)?
+                } else {
+                    1
+                };
+
+                let mut buff = vec![0u16; num_words];
+
+                if num_words > 1 {
+                    cli_data.core.read_16(address, &mut buff)?;
+                } else {
+                    buff[0] = cli_data.core.read_word_16(address)?;
+                }
+
+                for (offset, word) in buff.iter().enumerate() {
+                    println!("0x{:08x} = 0x{:04x}", address + (offset * 2) as u64, word);
+                }
+
+                Ok(CliState::Continue)
+            },
+        });
+
         cli.add_command(Command {
             name: "read_64",
             help_text: "Read 64bit value from memory",
@@ -452,6 +495,20 @@ impl DebugCli {
             },
         });
 
+        cli.add_command(Command {
+            name: "write_16",
+            help_text: "Write a 16bit value to memory",
+
+            function: |cli_data, args| {
+                let address = get_int_argument(args, 0)?;
+                let data: u16 = get_int_argument(args, 1)?;
+
+                cli_data.core.write_word_16(address, data)?;
+
+                Ok(CliState::Continue)
+            },
+        });
+
         cli.add_command(Command {
             name: "write_64",
             help_text: "Write a 64bit value to memory",
@@ -494,6 +551,219 @@ impl DebugCli {
             },
         });
 
+        cli.add_command(Command {
+            name: "sbreak",
+            help_text: "Set a software breakpoint at a specific address in RAM",
+
+            function: |cli_data, args| {
+                let address = get_int_argument(args, 0)?;
+
+                cli_data.core.set_sw_breakpoint(address)?;
+
+                println!("Set new software breakpoint at address {address:#08x}");
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "clear_sbreak",
+            help_text: "Clear a software breakpoint",
+
+            function: |cli_data, args| {
+                let address = get_int_argument(args, 0)?;
+
+                cli_data.core.clear_sw_breakpoint(address)?;
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "watch",
+            help_text: "Set a watchpoint on a memory range. Usage: watch <address> <length> [read|write|access]",
+
+            function: |cli_data, args| {
+                let address = get_int_argument(args, 0)?;
+                let length = get_int_argument(args, 1)?;
+
+                let kind = match args.get(2).copied() {
+                    None | Some("access") => WatchpointKind::Access,
+                    Some("read") => WatchpointKind::Read,
+                    Some("write") => WatchpointKind::Write,
+                    Some(other) => {
+                        return Err(CliError::ArgumentParseError {
+                            argument_index: 2,
+                            argument: other.to_string(),
+                            source: anyhow!("Expected one of 'read', 'write' or 'access'."),
+                        })
+                    }
+                };
+
+                cli_data.core.set_hw_watchpoint(address, length, kind)?;
+
+                println!("Set new {kind:?} watchpoint on {length} bytes at address {address:#08x}");
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "clear_watch",
+            help_text: "Clear a watchpoint",
+
+            function: |cli_data, args| {
+                let address = get_int_argument(args, 0)?;
+
+                cli_data.core.clear_hw_watchpoint(address)?;
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "catch",
+            help_text: "Halt the core when an exception occurs. Usage: catch <reset|hardfault|memmanage|busfault|usagefault|interrupt|securefault|all>",
+
+            function: |cli_data, args| {
+                let condition = get_vector_catch_argument(args, 0)?;
+
+                cli_data.core.set_vector_catch(condition)?;
+
+                println!("Enabled {condition:?} vector catch");
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "clear_catch",
+            help_text: "Clear a vector catch",
+
+            function: |cli_data, args| {
+                let condition = get_vector_catch_argument(args, 0)?;
+
+                cli_data.core.clear_vector_catch(condition)?;
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "mtb",
+            help_text: "Record the branch history in the MTB of a Cortex-M0+ core. Usage: mtb <buffer address> <buffer size> [dwt_start] [dwt_stop]",
+
+            function: |cli_data, args| {
+                let mtb = cli_data.mtb.ok_or_else(|| anyhow!("The target has no MTB"))?;
+
+                let address = get_int_argument(args, 0)?;
+                let size = get_int_argument(args, 1)?;
+
+                let mut config = MtbConfig::new(address, size);
+                for (index, argument) in args.iter().enumerate().skip(2) {
+                    config = match *argument {
+                        "dwt_start" => config.set_start_on_dwt_match(true),
+                        "dwt_stop" => config.set_stop_on_dwt_match(true),
+                        other => {
+                            return Err(CliError::ArgumentParseError {
+                                argument_index: index,
+                                argument: other.to_string(),
+                                source: anyhow!("Expected one of 'dwt_start' or 'dwt_stop'."),
+                            })
+                        }
+                    };
+                }
+
+                mtb.enable(&mut cli_data.core, &config)?;
+
+                println!("Recording branches into {size} bytes at address {address:#010x}");
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "mtb_trace",
+            help_text: "Show the last branches recorded by the MTB. Usage: mtb_trace [count]",
+
+            function: |cli_data, args| {
+                let mtb = cli_data
+                    .mtb
+                    .ok_or_else(|| anyhow!("The target has no MTB"))?;
+
+                let count = if args.is_empty() {
+                    32
+                } else {
+                    get_int_argument(args, 0)?
+                };
+
+                let branches = mtb.read_trace(&mut cli_data.core)?;
+                for branch in &branches[branches.len().saturating_sub(count)..] {
+                    if branch.trace_start {
+                        println!("Trace start");
+                    }
+
+                    let kind = if branch.exception {
+                        "exception"
+                    } else {
+                        "branch"
+                    };
+                    println!(
+                        "{kind} {:#010x} {} -> {:#010x} {}",
+                        branch.source,
+                        cli_data.describe_address(branch.source),
+                        branch.destination,
+                        cli_data.describe_address(branch.destination)
+                    );
+                }
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "clear_mtb",
+            help_text: "Stop recording the branch history in the MTB",
+
+            function: |cli_data, _args| {
+                let mtb = cli_data
+                    .mtb
+                    .ok_or_else(|| anyhow!("The target has no MTB"))?;
+
+                mtb.disable(&mut cli_data.core)?;
+
+                Ok(CliState::Continue)
+            },
+        });
+
+        cli.add_command(Command {
+            name: "call",
+            help_text: "Call a function on the target and show its return value. Usage: call [-t <timeout ms>] <address> [arguments...]",
+
+            function: |cli_data, args| {
+                // The function is halted if it does not return within the timeout, one second by default.
+                let (timeout, args) = if args.first() == Some(&"-t") {
+                    (Duration::from_millis(get_int_argument(args, 1)?), &args[2..])
+                } else {
+                    (Duration::from_secs(1), args)
+                };
+
+                let address = get_int_argument(args, 0)?;
+
+                let arguments = (1..args.len())
+                    .map(|index| get_int_argument::<u64>(args, index).map(RegisterValue::from))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let result = cli_data
+                    .core
+                    .call_function(address, &arguments, timeout)?;
+
+                println!("Function at {address:#010x} returned {result}");
+
+                Ok(CliState::Continue)
+            },
+        });
+
         cli.add_command(Command {
             name: "bt",
             help_text: "Show backtrace",
@@ -879,15 +1149,21 @@ impl DebugCli {
 pub struct CliData<'p> {
     pub core: Core<'p>,
     pub debug_info: Option<DebugInfo>,
+    pub mtb: Option<Mtb>,
 
     state: DebugState,
 }
 
 impl<'p> CliData<'p> {
-    fn new(core: Core<'p>, debug_info: Option<DebugInfo>) -> Result<CliData, CliError> {
+    fn new(
+        core: Core<'p>,
+        debug_info: Option<DebugInfo>,
+        mtb: Option<Mtb>,
+    ) -> Result<CliData, CliError> {
         let mut cli_data = CliData {
             core,
             debug_info,
+            mtb,
             state: DebugState::default(),
         };
 
@@ -921,6 +1197,26 @@ impl<'p> CliData<'p> {
         Ok(())
     }
 
+    /// Describe an address by its function and source location, as far as they are known.
+    fn describe_address(&self, address: u64) -> String {
+        let debug_info = match &self.debug_info {
+            Some(debug_info) => debug_info,
+            None => return String::new(),
+        };
+
+        let function = debug_info
+            .function_name(address, true)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "<unknown>".to_string());
+        let location = debug_info
+            .get_source_location(address)
+            .and_then(|location| Some(format!("{}:{}", location.file?, location.line?)))
+            .unwrap_or_default();
+
+        format!("{function} {location}")
+    }
+
     fn print_state(&mut self) -> Result<(), CliError> {
         match self.state {
             DebugState::Running => println!("Core is running."),
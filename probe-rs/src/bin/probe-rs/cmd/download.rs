@@ -4,10 +4,12 @@ use std::path::Path;
 use anyhow::Context;
 use probe_rs::flashing::FileDownloadError;
 use probe_rs::flashing::Format;
+use probe_rs::flashing::MemoryAccessWidth;
 
 use crate::util::common_options::ProbeOptions;
 use crate::util::common_options::{CargoOptions, FlashOptions};
 use crate::util::flash::run_flash_download;
+use crate::util::parse_access_width;
 use crate::util::parse_u32;
 use crate::util::parse_u64;
 use crate::FormatOptions;
@@ -39,6 +41,10 @@ pub struct Cmd {
     #[clap(long = "disable-double-buffering")]
     disable_double_buffering: bool,
 
+    /// The access width in bits (8, 16 or 32) used to write data into RAM regions.
+    #[clap(long, value_parser = parse_access_width, default_value = "8")]
+    width: MemoryAccessWidth,
+
     #[clap(flatten)]
     format_options: FormatOptions,
 }
@@ -71,6 +77,7 @@ impl Cmd {
                 reset_halt: false,
                 log: None,
                 restore_unwritten: false,
+                ram_access_width: self.width,
                 flash_layout_output_path: None,
                 elf: None,
                 work_dir: None,
@@ -1,8 +1,9 @@
 use std::time::Instant;
 
+use probe_rs::flashing::MemoryAccessWidth;
 use probe_rs::MemoryInterface;
 
-use crate::util::{common_options::ProbeOptions, parse_u32, parse_u64};
+use crate::util::{common_options::ProbeOptions, parse_access_width, parse_u32, parse_u64};
 use crate::CoreOptions;
 
 #[derive(clap::Parser)]
@@ -17,35 +18,53 @@ pub struct Cmd {
     #[clap(value_parser = parse_u64)]
     loc: u64,
 
-    /// The amount of memory (in words) to dump.
+    /// The amount of memory (in words of the selected width) to dump.
     #[clap(value_parser = parse_u32)]
     words: u32,
+
+    /// The access width in bits (8, 16 or 32) used to read the memory.
+    #[clap(long, value_parser = parse_access_width, default_value = "32")]
+    width: MemoryAccessWidth,
 }
 
 impl Cmd {
     pub fn run(self) -> anyhow::Result<()> {
         let mut session = self.common.simple_attach()?;
 
-        let mut data = vec![0_u32; self.words as usize];
-
         // Start timer.
         let instant = Instant::now();
 
-        // let loc = 220 * 1024;
-
         let mut core = session.core(self.shared.core)?;
 
-        core.read_32(self.loc, data.as_mut_slice())?;
+        let words = self.words as usize;
+        let data = match self.width {
+            MemoryAccessWidth::Bits8 => {
+                let mut data = vec![0_u8; words];
+                core.read_8(self.loc, data.as_mut_slice())?;
+                data.into_iter().map(u32::from).collect::<Vec<_>>()
+            }
+            MemoryAccessWidth::Bits16 => {
+                let mut data = vec![0_u16; words];
+                core.read_16(self.loc, data.as_mut_slice())?;
+                data.into_iter().map(u32::from).collect::<Vec<_>>()
+            }
+            MemoryAccessWidth::Bits32 => {
+                let mut data = vec![0_u32; words];
+                core.read_32(self.loc, data.as_mut_slice())?;
+                data
+            }
+        };
         // Stop timer.
         let elapsed = instant.elapsed();
 
         // Print read values.
-        let words = self.words;
-        for word in 0..words {
+        let byte_width = self.width.byte_width();
+        for (word, value) in data.iter().enumerate() {
             println!(
-                "Addr 0x{:08x?}: 0x{:08x}",
-                self.loc + 4 * word as u64,
-                data[word as usize]
+                "Addr 0x{:08x?}: 0x{:0width$x}",
+                self.loc + (byte_width * word) as u64,
+                value,
+                width = byte_width * 2
             );
         }
         // Print stats.
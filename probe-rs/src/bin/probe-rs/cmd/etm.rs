@@ -0,0 +1,123 @@
+//! Provides ETM instruction tracing capabilities.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use probe_rs::architecture::arm::{
+    component::{EtmConfig, TraceSink},
+    etm::{decode_trace, ProgramImage, TraceElement},
+};
+use probe_rs::debug::DebugInfo;
+
+use crate::util::{common_options::ProbeOptions, parse_u64};
+use crate::CoreOptions;
+
+#[derive(clap::Parser)]
+pub struct Cmd {
+    #[clap(flatten)]
+    shared: CoreOptions,
+
+    #[clap(flatten)]
+    common: ProbeOptions,
+
+    /// The ELF file of the program running on the target.
+    elf: PathBuf,
+
+    /// How long to trace for. Tracing stops earlier when the core halts, e.g. at a breakpoint.
+    #[clap(value_parser = parse_u64)]
+    duration_ms: u64,
+
+    /// The number of instruction ranges before the end of the trace to show.
+    #[clap(long, default_value = "32")]
+    last: usize,
+
+    /// Include cycle counts in the trace.
+    #[clap(long)]
+    cycle_counting: bool,
+}
+
+impl Cmd {
+    pub fn run(self) -> anyhow::Result<()> {
+        let elf = std::fs::read(&self.elf)?;
+        let image = ProgramImage::from_elf(&elf)?;
+        let debug_info = DebugInfo::from_raw(&elf).ok();
+
+        let mut session = self.common.simple_attach()?;
+
+        let config = EtmConfig::new().set_cycle_counting(self.cycle_counting.then_some(0));
+        session.setup_tracing(self.shared.core, TraceSink::TraceMemory)?;
+        let decoder_config = session.enable_etm(self.shared.core, &config)?;
+
+        {
+            let mut core = session.core(self.shared.core)?;
+            if core.core_halted()? {
+                core.run()?;
+            }
+        }
+
+        // The trace memory stalls when it is full, so it is drained while the core is running.
+        let mut trace = Vec::new();
+        let start = Instant::now();
+        loop {
+            trace.extend(session.read_etm_trace_data()?);
+
+            let halted = session.core(self.shared.core)?.core_halted()?;
+            if halted || start.elapsed() > Duration::from_millis(self.duration_ms) {
+                break;
+            }
+        }
+
+        session
+            .core(self.shared.core)?
+            .halt(Duration::from_millis(100))?;
+        trace.extend(session.read_etm_trace_data()?);
+
+        let elements = decode_trace(decoder_config, &image, &trace)?;
+        let ranges = elements
+            .iter()
+            .filter(|element| matches!(element, TraceElement::Instructions { .. }))
+            .count();
+
+        // Skip to the element of the first instruction range which is shown.
+        let mut skipped_ranges = 0;
+        let first = elements
+            .iter()
+            .position(|element| {
+                if matches!(element, TraceElement::Instructions { .. }) {
+                    skipped_ranges += 1;
+                }
+                skipped_ranges > ranges.saturating_sub(self.last)
+            })
+            .unwrap_or(elements.len());
+
+        for element in &elements[first..] {
+            match element {
+                TraceElement::Instructions { start, end, .. } => {
+                    let function = debug_info
+                        .as_ref()
+                        .and_then(|debug_info| debug_info.function_name(*start, true).ok())
+                        .flatten()
+                        .unwrap_or_else(|| "<unknown>".to_string());
+                    let location = debug_info
+                        .as_ref()
+                        .and_then(|debug_info| debug_info.get_source_location(*start))
+                        .and_then(|location| Some(format!("{}:{}", location.file?, location.line?)))
+                        .unwrap_or_default();
+
+                    println!("{start:#010x}..{end:#010x} {function} {location}");
+                }
+                TraceElement::Exception {
+                    number,
+                    return_address,
+                } => println!("Exception {number}, returning to {return_address:#010x}"),
+                TraceElement::ExceptionReturn => println!("Exception return"),
+                TraceElement::TraceOn => println!("Trace on"),
+                TraceElement::Overflow => println!("Trace overflow, trace was lost"),
+                TraceElement::Timestamp(timestamp) => println!("Timestamp {timestamp}"),
+                TraceElement::CycleCount(count) => println!("{count} cycles"),
+            }
+        }
+
+        Ok(())
+    }
+}
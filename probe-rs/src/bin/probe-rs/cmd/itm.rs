@@ -19,10 +19,38 @@ pub(crate) enum ItmSource {
         clk: u32,
 
         /// The desired baud rate of the SWO output.
+        ///
+        /// The closest baud rate supported by both the target and the probe is used.
         baud: u32,
+
+        /// Use Manchester encoding instead of UART, if the probe supports it.
+        #[clap(long)]
+        manchester: bool,
     },
 }
 
+impl ItmSource {
+    /// The trace sink to which the ITM data is directed.
+    pub(crate) fn sink(self) -> TraceSink {
+        match self {
+            ItmSource::TraceMemory => TraceSink::TraceMemory,
+            ItmSource::Swo {
+                clk,
+                baud,
+                manchester,
+            } => {
+                let config = SwoConfig::new(clk).set_baud(baud);
+                let config = if manchester {
+                    config.set_mode_manchester()
+                } else {
+                    config
+                };
+                TraceSink::Swo(config)
+            }
+        }
+    }
+}
+
 #[derive(clap::Parser)]
 pub struct Cmd {
     #[clap(flatten)]
@@ -40,14 +68,10 @@ pub struct Cmd {
 
 impl Cmd {
     pub fn run(self) -> anyhow::Result<()> {
-        let sink = match self.source {
-            ItmSource::TraceMemory => TraceSink::TraceMemory,
-            ItmSource::Swo { clk, baud } => TraceSink::Swo(SwoConfig::new(clk).set_baud(baud)),
-        };
         itm_trace(
             &self.shared,
             &self.common,
-            sink,
+            self.source.sink(),
             std::time::Duration::from_millis(self.duration_ms),
         )
     }
@@ -0,0 +1,140 @@
+//! Provides statistical profiling by sampling the program counter.
+
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use probe_rs::debug::DebugInfo;
+use probe_rs::profiling::{read_pcsr, PcSample, PcSampleDecoder, Profile};
+
+use crate::cmd::itm::ItmSource;
+use crate::util::{common_options::ProbeOptions, parse_u64};
+use crate::CoreOptions;
+
+#[derive(clap::Subcommand)]
+enum Method {
+    /// Read the sampled PC from the DWT_PCSR register, without halting the core.
+    #[clap(name = "pcsr")]
+    Pcsr,
+
+    /// Halt the core to read the PC. This works on every core, but disturbs the timing of the
+    /// program.
+    #[clap(name = "halt")]
+    Halt,
+
+    /// Let the DWT emit periodic PC samples over the ITM.
+    #[clap(name = "itm")]
+    Itm {
+        /// The number of cycles between two samples. The closest interval supported by the DWT
+        /// is used.
+        #[clap(long, default_value = "4096")]
+        interval: u32,
+
+        #[clap(subcommand)]
+        source: ItmSource,
+    },
+}
+
+#[derive(clap::Parser)]
+pub struct Cmd {
+    #[clap(flatten)]
+    shared: CoreOptions,
+
+    #[clap(flatten)]
+    common: ProbeOptions,
+
+    /// The ELF file of the program running on the target.
+    elf: PathBuf,
+
+    /// How long to sample for.
+    #[clap(value_parser = parse_u64)]
+    duration_ms: u64,
+
+    /// Write the samples as folded stacks to this file, e.g. to generate a flamegraph with
+    /// `inferno-flamegraph`.
+    #[clap(long)]
+    folded: Option<PathBuf>,
+
+    /// Add the source line of every sample as the innermost frame.
+    #[clap(long)]
+    lines: bool,
+
+    /// The number of the most sampled stacks to show.
+    #[clap(long, default_value = "20")]
+    top: usize,
+
+    #[clap(subcommand)]
+    method: Method,
+}
+
+impl Cmd {
+    pub fn run(self) -> anyhow::Result<()> {
+        let debug_info = DebugInfo::from_file(&self.elf)?;
+        let mut profile = Profile::new(&debug_info).set_source_lines(self.lines);
+
+        let mut session = self.common.simple_attach()?;
+        let duration = Duration::from_millis(self.duration_ms);
+        let start = Instant::now();
+
+        match self.method {
+            Method::Pcsr => {
+                let mut core = session.core(self.shared.core)?;
+                while start.elapsed() < duration {
+                    if let Some(pc) = read_pcsr(&mut core)? {
+                        profile.add_sample(PcSample::Pc(pc));
+                    }
+                }
+            }
+            Method::Halt => {
+                let mut core = session.core(self.shared.core)?;
+                let was_halted = core.core_halted()?;
+                while start.elapsed() < duration {
+                    core.halt(Duration::from_millis(100))?;
+                    let pc: u64 = core.read_core_reg(core.program_counter())?;
+                    core.run()?;
+                    profile.add_sample(PcSample::Pc(pc));
+                }
+                if was_halted {
+                    core.halt(Duration::from_millis(100))?;
+                }
+            }
+            Method::Itm { interval, source } => {
+                session.setup_tracing(self.shared.core, source.sink())?;
+                let interval = session.enable_pc_sampling(interval)?;
+                println!("Sampling the PC every {interval} cycles");
+
+                let mut decoder = PcSampleDecoder::new();
+                while start.elapsed() < duration {
+                    for sample in decoder.decode(&session.read_trace_data()?) {
+                        profile.add_sample(sample);
+                    }
+                }
+
+                session.disable_pc_sampling()?;
+            }
+        }
+
+        if profile.samples() == 0 {
+            anyhow::bail!(
+                "No PC samples were collected. The sampling method might not be supported by the core, try another one."
+            );
+        }
+
+        if let Some(path) = &self.folded {
+            profile.write_folded(&mut BufWriter::new(File::create(path)?))?;
+        }
+
+        let mut stacks: Vec<_> = profile.folded_stacks().collect();
+        stacks.sort_by_key(|s| Reverse(s.1));
+
+        println!("{} samples", profile.samples());
+        for (stack, count) in stacks.iter().take(self.top) {
+            let percentage = *count as f64 * 100.0 / profile.samples() as f64;
+            println!("{percentage:6.2}% {count:>8} {stack}");
+        }
+
+        Ok(())
+    }
+}
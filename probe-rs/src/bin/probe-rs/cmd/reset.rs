@@ -1,3 +1,5 @@
+use probe_rs::ResetType;
+
 use crate::{util::common_options::ProbeOptions, CoreOptions};
 
 #[derive(clap::Parser)]
@@ -10,13 +12,22 @@ pub struct Cmd {
 
     /// Whether the reset pin should be asserted or deasserted. If left open, just pulse it
     assert: Option<bool>,
+
+    /// The reset type to use (default, system, vect_reset, core or hardware).
+    /// If left open, the default reset type of the target is used.
+    #[clap(long = "type")]
+    reset_type: Option<ResetType>,
 }
 
 impl Cmd {
     pub fn run(self) -> anyhow::Result<()> {
         let mut session = self.common.simple_attach()?;
 
-        session.core(self.shared.core)?.reset()?;
+        let mut core = session.core(self.shared.core)?;
+        if let Some(reset_type) = self.reset_type {
+            core.set_reset_type(reset_type);
+        }
+        core.reset()?;
 
         Ok(())
     }
@@ -3,8 +3,10 @@ use std::io::Write;
 use std::path::Path;
 use std::time::Duration;
 
-use anyhow::Context;
-use probe_rs::flashing::{FileDownloadError, Format};
+use anyhow::{anyhow, Context};
+use probe_rs::architecture::arm::read_fault_report;
+use probe_rs::flashing::{FileDownloadError, Format, MemoryAccessWidth};
+use probe_rs::{Core, CoreStatus, HaltReason, UartConfig, VectorCatchCondition};
 use time::UtcOffset;
 
 use crate::util::common_options::{CargoOptions, FlashOptions, ProbeOptions};
@@ -28,6 +30,15 @@ pub struct Cmd {
     #[clap(long = "disable-double-buffering")]
     disable_double_buffering: bool,
 
+    /// Do not halt the core and print a fault report when a Cortex-M core hits a HardFault.
+    #[clap(long = "no-catch-hardfault")]
+    no_catch_hardfault: bool,
+
+    /// Show the serial console of the target, bridged by the probe, at the given baud rate.
+    /// This is supported by CMSIS-DAP v2.1 probes with a UART bridge.
+    #[clap(long = "uart", value_name = "BAUD")]
+    uart_baud: Option<u32>,
+
     #[clap(flatten)]
     format_options: FormatOptions,
 }
@@ -60,6 +71,7 @@ impl Cmd {
                 reset_halt: false,
                 log: None,
                 restore_unwritten: false,
+                ram_access_width: MemoryAccessWidth::default(),
                 flash_layout_output_path: None,
                 elf: None,
                 work_dir: None,
@@ -74,7 +86,21 @@ impl Cmd {
 
         let memory_map = session.target().memory_map.clone();
 
+        // Start receiving before the reset, so that no output of the target is lost.
+        if let Some(baud) = self.uart_baud {
+            session
+                .uart_interface()
+                .ok_or_else(|| anyhow!("The probe does not bridge the UART of the target"))?
+                .enable_uart(&UartConfig::new(baud))?;
+        }
+
         let mut core = session.core(0)?;
+
+        let catch_hardfault = !self.no_catch_hardfault && core.core_type().is_cortex_m();
+        if catch_hardfault {
+            core.set_vector_catch(VectorCatchCondition::HardFault)?;
+        }
+
         core.reset()?;
 
         let mut rtta = match rtt::attach_to_rtt(
@@ -90,12 +116,30 @@ impl Cmd {
                 None
             }
         };
+        drop(core);
 
-        if let Some(rtta) = &mut rtta {
+        if rtta.is_some() || catch_hardfault || self.uart_baud.is_some() {
             let mut stdout = std::io::stdout();
             loop {
-                for (_ch, data) in rtta.poll_rtt_fallible(&mut core)? {
-                    stdout.write_all(data.as_bytes())?;
+                {
+                    let mut core = session.core(0)?;
+
+                    if let Some(rtta) = &mut rtta {
+                        for (_ch, data) in rtta.poll_rtt_fallible(&mut core)? {
+                            stdout.write_all(data.as_bytes())?;
+                        }
+                    }
+
+                    if catch_hardfault {
+                        check_for_fault(&mut core)?;
+                    }
+                }
+
+                if self.uart_baud.is_some() {
+                    if let Some(uart) = session.uart_interface() {
+                        stdout.write_all(&uart.read_uart()?)?;
+                        stdout.flush()?;
+                    }
                 }
 
                 // Poll RTT with a frequency of 10 Hz
@@ -109,3 +153,19 @@ impl Cmd {
         Ok(())
     }
 }
+
+/// Returns an error containing the fault report if the core has faulted.
+fn check_for_fault(core: &mut Core) -> anyhow::Result<()> {
+    match core.status()? {
+        CoreStatus::Halted(HaltReason::VectorCatch(VectorCatchCondition::HardFault)) => {}
+        CoreStatus::LockedUp => {
+            // The core has to be halted to read the stacked exception frame.
+            core.halt(Duration::from_millis(100))?;
+        }
+        _ => return Ok(()),
+    }
+
+    let report = read_fault_report(core)?;
+
+    Err(anyhow!("The core faulted:\n{report}"))
+}
@@ -0,0 +1,24 @@
+use probe_rs::ProbeServer;
+
+#[derive(clap::Parser)]
+pub struct Cmd {
+    /// The address to listen on for clients.
+    ///
+    /// Connections are not authenticated. Use e.g. '0.0.0.0:3500' to accept clients
+    /// from other machines only on trusted networks.
+    #[clap(long, default_value = "127.0.0.1:3500")]
+    address: String,
+}
+
+impl Cmd {
+    pub fn run(self) -> anyhow::Result<()> {
+        let server = ProbeServer::bind(&self.address)?;
+
+        println!("Serving probes on {}", server.local_addr()?);
+        println!("Use '--probe tcp://<host>:<port>/<selector>' on the client to select a probe.");
+
+        server.run()?;
+
+        Ok(())
+    }
+}
@@ -51,6 +51,8 @@ enum Subcommand {
     Dump(cmd::dump::Cmd),
     /// Download memory to attached target
     Download(cmd::download::Cmd),
+    /// Halt the target and store its registers and memory in an ELF core file
+    Coredump(cmd::coredump::Cmd),
     /// Erase all nonvolatile memory of attached target
     Erase(cmd::erase::Cmd),
     /// Flash and run an ELF program
@@ -62,8 +64,18 @@ enum Subcommand {
     /// Configure and monitor ITM trace packets from the target.
     #[clap(name = "itm")]
     Itm(cmd::itm::Cmd),
+    /// Trace the executed instructions using the ETM, and show the last ones.
+    #[clap(name = "etm")]
+    Etm(cmd::etm::Cmd),
+    /// Profile the program by sampling the program counter, and write the samples as folded stacks.
+    #[clap(name = "profile")]
+    Profile(cmd::profile::Cmd),
     Chip(cmd::chip::Cmd),
     Benchmark(cmd::benchmark::Cmd),
+    /// Read and drive the pins of a device using JTAG boundary scan
+    Bscan(cmd::bscan::Cmd),
+    /// Serve the locally attached debug probes to other machines over TCP
+    Serve(cmd::serve::Cmd),
 }
 
 /// Shared options for core selection, shared between commands
@@ -229,12 +241,17 @@ fn main() -> Result<()> {
         Subcommand::Debug(cmd) => cmd.run(),
         Subcommand::Dump(cmd) => cmd.run(),
         Subcommand::Download(cmd) => cmd.run(),
+        Subcommand::Coredump(cmd) => cmd.run(),
         Subcommand::Run(cmd) => cmd.run(utc_offset),
         Subcommand::Erase(cmd) => cmd.run(),
         Subcommand::Trace(cmd) => cmd.run(),
         Subcommand::Itm(cmd) => cmd.run(),
+        Subcommand::Etm(cmd) => cmd.run(),
+        Subcommand::Profile(cmd) => cmd.run(),
         Subcommand::Chip(cmd) => cmd.run(),
         Subcommand::Benchmark(cmd) => cmd.run(),
+        Subcommand::Bscan(cmd) => cmd.run(),
+        Subcommand::Serve(cmd) => cmd.run(),
     };
 
     tracing::info!("Wrote log to {:?}", log_path);
@@ -38,9 +38,9 @@ use std::{fs::File, path::Path, path::PathBuf};
 use clap;
 use probe_rs::{
     config::{RegistryError, TargetSelector},
-    flashing::{FileDownloadError, FlashError, FlashLoader},
-    DebugProbeError, DebugProbeSelector, FakeProbe, Permissions, Probe, Session, Target,
-    WireProtocol,
+    flashing::{FileDownloadError, FlashError, FlashLoader, MemoryAccessWidth},
+    DebugProbeError, DebugProbeSelector, FakeProbe, JtagChainConfig, JtagTapSelector, Permissions,
+    Probe, Session, Target, WireProtocol,
 };
 
 /// Common options when flashing a target device.
@@ -73,6 +73,9 @@ pub struct FlashOptions {
         help = "Enable this flag to restore all bytes erased in the sector erase but not overwritten by any page."
     )]
     pub restore_unwritten: bool,
+    /// The access width used to write data into RAM regions, set with `probe-rs download --width`.
+    #[clap(skip)]
+    pub ram_access_width: MemoryAccessWidth,
     #[clap(
         name = "filename",
         long = "flash-layout",
@@ -114,6 +117,10 @@ pub struct ProbeOptions {
     /// Use this flag to select a specific probe in the list.
     ///
     /// Use '--probe VID:PID' or '--probe VID:PID:Serial' if you have more than one probe with the same VID:PID.",
+    ///
+    /// Use '--probe remote-bitbang:host:port' to connect to an OpenOCD remote_bitbang server.
+    ///
+    /// Use '--probe tcp://host:port/VID:PID' to use a probe served by 'probe-rs serve' on another machine.
     #[structopt(long = "probe", help_heading = "PROBE CONFIGURATION")]
     pub probe_selector: Option<DebugProbeSelector>,
     #[clap(
@@ -122,6 +129,20 @@ pub struct ProbeOptions {
         help_heading = "PROBE CONFIGURATION"
     )]
     pub speed: Option<u32>,
+    /// The IR lengths of the TAPs in the JTAG chain, starting with the TAP closest to TDO.
+    ///
+    /// The IR lengths are detected if they are not given, which does not work for all TAPs.
+    #[structopt(
+        long = "jtag-chain",
+        value_delimiter = ',',
+        help_heading = "PROBE CONFIGURATION"
+    )]
+    pub jtag_chain: Option<Vec<u8>>,
+    /// The TAP used for debugging, given by its index in the JTAG chain or by its IDCODE, e.g. '0x4ba00477'.
+    ///
+    /// A known debug TAP is used if it is not given.
+    #[structopt(long = "jtag-tap", help_heading = "PROBE CONFIGURATION")]
+    pub jtag_tap: Option<JtagTapSelector>,
     #[structopt(
         long = "connect-under-reset",
         help = "Use this flag to assert the nreset & ntrst pins during attaching the probe to the chip."
@@ -129,6 +150,20 @@ pub struct ProbeOptions {
     pub connect_under_reset: bool,
     #[structopt(long = "dry-run")]
     pub dry_run: bool,
+    /// Record all transactions with the probe to the given file.
+    ///
+    /// The recording can be replayed without the probe with '--replay-probe', to reproduce problems with a target.
+    #[structopt(long = "record-probe", help_heading = "PROBE CONFIGURATION")]
+    pub record_probe: Option<PathBuf>,
+    /// Answer all transactions with the probe from a file written with '--record-probe', instead of using a probe.
+    ///
+    /// The command has to be run with the same options as the recorded one.
+    #[structopt(
+        long = "replay-probe",
+        conflicts_with = "record_probe",
+        help_heading = "PROBE CONFIGURATION"
+    )]
+    pub replay_probe: Option<PathBuf>,
     #[structopt(
         long = "allow-erase-all",
         help = "Use this flag to allow all memory, including security keys and 3rd party firmware, to be erased \
@@ -181,29 +216,39 @@ impl ProbeOptions {
                 Probe::from_specific_probe(Box::new(FakeProbe::new()));
             }
 
-            // If we got a probe selector as an argument, open the probe
-            // matching the selector if possible.
-            match &self.probe_selector {
-                Some(selector) => {
-                    Probe::open(selector.clone()).map_err(OperationError::FailedToOpenProbe)
-                }
-                None => {
-                    // Only automatically select a probe if there is
-                    // only a single probe detected.
-                    let list = Probe::list_all();
-                    if list.len() > 1 {
-                        return Err(OperationError::MultipleProbesFound { number: list.len() });
+            if let Some(path) = &self.replay_probe {
+                Probe::replay(path).map_err(OperationError::FailedToOpenProbe)
+            } else {
+                // If we got a probe selector as an argument, open the probe
+                // matching the selector if possible.
+                match &self.probe_selector {
+                    Some(selector) => {
+                        Probe::open(selector.clone()).map_err(OperationError::FailedToOpenProbe)
                     }
-
-                    if let Some(info) = list.first() {
-                        Probe::open(info).map_err(OperationError::FailedToOpenProbe)
-                    } else {
-                        Err(OperationError::NoProbesFound)
+                    None => {
+                        // Only automatically select a probe if there is
+                        // only a single probe detected.
+                        let list = Probe::list_all();
+                        if list.len() > 1 {
+                            return Err(OperationError::MultipleProbesFound { number: list.len() });
+                        }
+
+                        if let Some(info) = list.first() {
+                            Probe::open(info).map_err(OperationError::FailedToOpenProbe)
+                        } else {
+                            Err(OperationError::NoProbesFound)
+                        }
                     }
                 }
             }
         }?;
 
+        if let Some(path) = &self.record_probe {
+            probe = probe
+                .record(path)
+                .map_err(OperationError::FailedToOpenProbe)?;
+        }
+
         if let Some(protocol) = self.protocol {
             // Select protocol and speed
             probe.select_protocol(protocol).map_err(|error| {
@@ -214,6 +259,15 @@ impl ProbeOptions {
             })?;
         }
 
+        if self.jtag_chain.is_some() || self.jtag_tap.is_some() {
+            probe
+                .set_jtag_chain(JtagChainConfig {
+                    ir_lengths: self.jtag_chain.clone(),
+                    tap: self.jtag_tap,
+                })
+                .map_err(OperationError::FailedToConfigureJtagChain)?;
+        }
+
         if let Some(speed) = self.speed {
             let _actual_speed = probe.set_speed(speed).map_err(|error| {
                 OperationError::FailedToSelectProtocolSpeed {
@@ -465,6 +519,8 @@ pub enum OperationError {
         source: DebugProbeError,
         speed: u32,
     },
+    #[error("The JTAG scan chain could not be configured.")]
+    FailedToConfigureJtagChain(#[source] DebugProbeError),
     #[error("Connecting to the chip was unsuccessful.")]
     AttachingFailed {
         #[source]
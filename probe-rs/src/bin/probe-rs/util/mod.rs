@@ -6,6 +6,7 @@ pub mod rtt;
 use anyhow::Result;
 
 use cargo_metadata::Message;
+use probe_rs::flashing::MemoryAccessWidth;
 
 use std::process::{Command, Stdio};
 
@@ -21,6 +22,17 @@ pub fn parse_u64(input: &str) -> Result<u64, ParseIntError> {
     parse_int::parse(input)
 }
 
+pub fn parse_access_width(input: &str) -> Result<MemoryAccessWidth, String> {
+    match input {
+        "8" => Ok(MemoryAccessWidth::Bits8),
+        "16" => Ok(MemoryAccessWidth::Bits16),
+        "32" => Ok(MemoryAccessWidth::Bits32),
+        _ => Err(format!(
+            "'{input}' is not a supported access width, expected one of 8, 16 or 32"
+        )),
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ArtifactError {
     #[error("Failed to canonicalize path '{work_dir}'.")]
@@ -0,0 +1,541 @@
+//! Parser for Boundary Scan Description Language (BSDL) files.
+//!
+//! BSDL is a subset of VHDL, describing the TAP of a device as an entity with a number of
+//! attributes. Only the attributes needed to access the boundary register are parsed, everything
+//! else is ignored.
+
+use std::collections::HashMap;
+
+/// Errors while parsing a BSDL file.
+#[derive(Debug, thiserror::Error)]
+pub enum BsdlError {
+    /// The file does not describe an entity.
+    #[error("The BSDL file does not contain an entity")]
+    NoEntity,
+    /// A required attribute is missing.
+    #[error("The attribute {0} is missing")]
+    MissingAttribute(&'static str),
+    /// An attribute has an invalid value.
+    #[error("The value '{value}' of the attribute {attribute} is invalid")]
+    InvalidAttribute {
+        /// The name of the attribute.
+        attribute: &'static str,
+        /// The invalid value.
+        value: String,
+    },
+    /// A cell of the boundary register is invalid.
+    #[error("The boundary register cell '{0}' is invalid")]
+    InvalidCell(String),
+    /// A string literal is not terminated.
+    #[error("Unterminated string in line {0}")]
+    UnterminatedString(usize),
+}
+
+/// The function of a boundary register cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellFunction {
+    /// Captures the value of an input pin.
+    Input,
+    /// Captures the value of a clock input pin.
+    Clock,
+    /// Drives an output pin which cannot be disabled.
+    Output2,
+    /// Drives an output pin which can be disabled by a control cell.
+    Output3,
+    /// Drives and captures a bidirectional pin, which is disabled by a control cell.
+    Bidir,
+    /// Controls whether output cells drive their pins.
+    Control,
+    /// Like [`CellFunction::Control`], but reset when the TAP is reset.
+    ControlR,
+    /// Captures an internal signal.
+    Internal,
+    /// Captures the value of a pin which is not driven by the boundary register.
+    ObserveOnly,
+}
+
+impl CellFunction {
+    /// Returns true if the cell captures the value of its pin.
+    pub fn is_input(self) -> bool {
+        matches!(
+            self,
+            CellFunction::Input
+                | CellFunction::Clock
+                | CellFunction::Bidir
+                | CellFunction::ObserveOnly
+        )
+    }
+
+    /// Returns true if the cell drives its pin in EXTEST.
+    pub fn is_output(self) -> bool {
+        matches!(
+            self,
+            CellFunction::Output2 | CellFunction::Output3 | CellFunction::Bidir
+        )
+    }
+
+    fn parse(function: &str) -> Option<Self> {
+        let function = match function.to_ascii_uppercase().as_str() {
+            "INPUT" => CellFunction::Input,
+            "CLOCK" => CellFunction::Clock,
+            "OUTPUT2" => CellFunction::Output2,
+            "OUTPUT3" => CellFunction::Output3,
+            "BIDIR" => CellFunction::Bidir,
+            "CONTROL" => CellFunction::Control,
+            "CONTROLR" => CellFunction::ControlR,
+            "INTERNAL" => CellFunction::Internal,
+            "OBSERVE_ONLY" => CellFunction::ObserveOnly,
+            _ => return None,
+        };
+
+        Some(function)
+    }
+}
+
+/// The control cell which enables an output cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlCell {
+    /// The number of the control cell.
+    pub cell: usize,
+    /// The value of the control cell which disables the output.
+    pub disable_value: bool,
+}
+
+/// A cell of the boundary register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundaryCell {
+    /// The position in the boundary register, cell 0 is closest to TDO.
+    pub number: usize,
+    /// The name of the port, `None` for cells which are not connected to a port.
+    pub port: Option<String>,
+    /// The function of the cell.
+    pub function: CellFunction,
+    /// The value which keeps the device safe, if it matters.
+    pub safe: Option<bool>,
+    /// The cell which enables the output, for output cells which can be disabled.
+    pub control: Option<ControlCell>,
+}
+
+/// The expected IDCODE of a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdCode {
+    /// The value of the IDCODE.
+    pub value: u32,
+    /// The bits of the IDCODE which are checked, the others may differ between devices.
+    pub mask: u32,
+}
+
+impl IdCode {
+    /// Returns true if the IDCODE read from a device matches.
+    pub fn matches(&self, idcode: u32) -> bool {
+        idcode & self.mask == self.value & self.mask
+    }
+}
+
+/// The description of the TAP of a device, parsed from a BSDL file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bsdl {
+    /// The name of the entity, usually the name of the device.
+    pub entity: String,
+    /// The length of the instruction register.
+    pub instruction_length: usize,
+    /// The opcodes of the instructions, by their upper case name.
+    pub instructions: HashMap<String, u32>,
+    /// The expected IDCODE, if the device has an IDCODE register.
+    pub idcode: Option<IdCode>,
+    /// The cells of the boundary register, ordered by their number.
+    pub boundary_register: Vec<BoundaryCell>,
+}
+
+impl Bsdl {
+    /// Parses the contents of a BSDL file.
+    pub fn parse(source: &str) -> Result<Self, BsdlError> {
+        let tokens = tokenize(source)?;
+
+        let mut entity = None;
+        let mut attributes = HashMap::new();
+
+        for statement in tokens.split(|token| *token == Token::Punct(';')) {
+            match statement {
+                [Token::Word(keyword), Token::Word(name), ..]
+                    if keyword.eq_ignore_ascii_case("entity") && entity.is_none() =>
+                {
+                    entity = Some(name.clone());
+                }
+                [Token::Word(keyword), Token::Word(name), Token::Word(of), _, Token::Punct(':'), Token::Word(class), Token::Word(is), value @ ..]
+                    if keyword.eq_ignore_ascii_case("attribute")
+                        && of.eq_ignore_ascii_case("of")
+                        && class.eq_ignore_ascii_case("entity")
+                        && is.eq_ignore_ascii_case("is") =>
+                {
+                    attributes.insert(name.to_ascii_uppercase(), attribute_value(value));
+                }
+                _ => {}
+            }
+        }
+
+        let entity = entity.ok_or(BsdlError::NoEntity)?;
+        let attribute = |name: &'static str| {
+            attributes
+                .get(name)
+                .map(String::as_str)
+                .ok_or(BsdlError::MissingAttribute(name))
+        };
+        let invalid = |attribute: &'static str, value: &str| BsdlError::InvalidAttribute {
+            attribute,
+            value: value.to_string(),
+        };
+
+        let value = attribute("INSTRUCTION_LENGTH")?;
+        let instruction_length: usize = value
+            .parse()
+            .map_err(|_| invalid("INSTRUCTION_LENGTH", value))?;
+
+        let value = attribute("INSTRUCTION_OPCODE")?;
+        let mut instructions = HashMap::new();
+        for (name, opcodes) in
+            parse_list(value).ok_or_else(|| invalid("INSTRUCTION_OPCODE", value))?
+        {
+            // Some instructions have more than one opcode, any of them can be used.
+            let opcode = opcodes
+                .first()
+                .filter(|opcode| opcode.len() == instruction_length)
+                .and_then(|opcode| u32::from_str_radix(opcode, 2).ok())
+                .ok_or_else(|| invalid("INSTRUCTION_OPCODE", value))?;
+            instructions.insert(name.to_ascii_uppercase(), opcode);
+        }
+
+        let idcode = match attributes.get("IDCODE_REGISTER") {
+            Some(value) => {
+                Some(parse_idcode(value).ok_or_else(|| invalid("IDCODE_REGISTER", value))?)
+            }
+            None => None,
+        };
+
+        let value = attribute("BOUNDARY_LENGTH")?;
+        let boundary_length: usize = value
+            .parse()
+            .map_err(|_| invalid("BOUNDARY_LENGTH", value))?;
+
+        let value = attribute("BOUNDARY_REGISTER")?;
+        let mut boundary_register = parse_list(value)
+            .ok_or_else(|| invalid("BOUNDARY_REGISTER", value))?
+            .into_iter()
+            .map(|(number, fields)| parse_cell(&number, &fields))
+            .collect::<Result<Vec<_>, _>>()?;
+        boundary_register.sort_by_key(|cell| cell.number);
+
+        let numbers_valid = boundary_register.len() == boundary_length
+            && boundary_register
+                .iter()
+                .enumerate()
+                .all(|(i, cell)| cell.number == i);
+        let controls_valid = boundary_register
+            .iter()
+            .filter_map(|cell| cell.control)
+            .all(|control| control.cell < boundary_length);
+        if !numbers_valid || !controls_valid {
+            return Err(invalid("BOUNDARY_REGISTER", value));
+        }
+
+        Ok(Bsdl {
+            entity,
+            instruction_length,
+            instructions,
+            idcode,
+            boundary_register,
+        })
+    }
+
+    /// Returns the opcode of the instruction with the given name.
+    pub fn instruction(&self, name: &str) -> Option<u32> {
+        self.instructions.get(&name.to_ascii_uppercase()).copied()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Punct(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, BsdlError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '-' if chars.peek() == Some(&'-') => {
+                // Comments run until the end of the line.
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => string.push(c),
+                        None => return Err(BsdlError::UnterminatedString(line)),
+                    }
+                }
+                tokens.push(Token::Str(string));
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut word = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            c => tokens.push(Token::Punct(c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Returns the concatenated strings of an attribute value, or its words for other values.
+fn attribute_value(tokens: &[Token]) -> String {
+    let strings = tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Str(string) => Some(string.as_str()),
+            _ => None,
+        })
+        .collect::<String>();
+
+    if !strings.is_empty() {
+        return strings;
+    }
+
+    tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Word(word) => Some(word.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses a list of `NAME (FIELD, FIELD, ...)` entries, separated by commas.
+fn parse_list(value: &str) -> Option<Vec<(String, Vec<String>)>> {
+    let mut entries = Vec::new();
+    let mut rest = value;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            return Some(entries);
+        }
+
+        let open = rest.find('(')?;
+        let close = open + rest[open..].find(')')?;
+
+        let name = rest[..open].trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let fields = rest[open + 1..close]
+            .split(',')
+            .map(|field| field.trim().to_string())
+            .collect();
+        entries.push((name.to_string(), fields));
+
+        rest = &rest[close + 1..];
+    }
+}
+
+/// Parses the 32 bits of an IDCODE, given MSB first, where `X` marks bits which are not checked.
+fn parse_idcode(value: &str) -> Option<IdCode> {
+    let bits = value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    if bits.len() != 32 {
+        return None;
+    }
+
+    let mut idcode = IdCode { value: 0, mask: 0 };
+    for bit in bits {
+        idcode.value <<= 1;
+        idcode.mask <<= 1;
+        match bit {
+            '0' => idcode.mask |= 1,
+            '1' => {
+                idcode.value |= 1;
+                idcode.mask |= 1;
+            }
+            'x' | 'X' => {}
+            _ => return None,
+        }
+    }
+
+    Some(idcode)
+}
+
+/// Parses a value which is `0`, `1` or `X` for values which do not matter.
+fn parse_bit(value: &str) -> Option<Option<bool>> {
+    match value {
+        "0" => Some(Some(false)),
+        "1" => Some(Some(true)),
+        "x" | "X" => Some(None),
+        _ => None,
+    }
+}
+
+/// Parses a cell given as `NUMBER (CELL, PORT, FUNCTION, SAFE [, CCELL, DISVAL, RSLT])`.
+fn parse_cell(number: &str, fields: &[String]) -> Result<BoundaryCell, BsdlError> {
+    let invalid = || BsdlError::InvalidCell(format!("{number} ({})", fields.join(", ")));
+
+    let (port, function, safe, control) = match fields {
+        [_cell, port, function, safe] => (port, function, safe, None),
+        [_cell, port, function, safe, ccell, disval, _rslt] => {
+            let control = ControlCell {
+                cell: ccell.parse().map_err(|_| invalid())?,
+                disable_value: parse_bit(disval).flatten().ok_or_else(invalid)?,
+            };
+            (port, function, safe, Some(control))
+        }
+        _ => return Err(invalid()),
+    };
+
+    Ok(BoundaryCell {
+        number: number.parse().map_err(|_| invalid())?,
+        port: (port != "*").then(|| port.clone()),
+        function: CellFunction::parse(function).ok_or_else(invalid)?,
+        safe: parse_bit(safe).ok_or_else(invalid)?,
+        control,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// A made up device with a bidirectional pin, a tristate output and an input.
+    pub(crate) const TEST_BSDL: &str = r#"
+-- Test device
+entity TESTCHIP is
+  generic (PHYSICAL_PIN_MAP : string := "QFN16");
+
+  port (
+    PA0     : inout bit;
+    PA1     : inout bit;
+    RESET_N : in bit;
+    LED     : out bit;
+    TCK, TMS, TDI : in bit;
+    TDO     : out bit
+  );
+
+  use STD_1149_1_2001.all;
+
+  attribute COMPONENT_CONFORMANCE of TESTCHIP : entity is "STD_1149_1_2001";
+  attribute PIN_MAP of TESTCHIP : entity is PHYSICAL_PIN_MAP;
+  constant QFN16 : PIN_MAP_STRING := "PA0:1, PA1:2, RESET_N:3, LED:4, " &
+    "TCK:5, TMS:6, TDI:7, TDO:8";
+
+  attribute TAP_SCAN_IN of TDI : signal is true;
+  attribute TAP_SCAN_OUT of TDO : signal is true;
+
+  attribute INSTRUCTION_LENGTH of TESTCHIP : entity is 4;
+  attribute INSTRUCTION_OPCODE of TESTCHIP : entity is
+    "BYPASS (1111, 1110)," &
+    "EXTEST (0000)," &
+    "SAMPLE (0010)," &
+    "PRELOAD (0010)," &
+    "IDCODE (0001)";
+  attribute INSTRUCTION_CAPTURE of TESTCHIP : entity is "0001";
+
+  attribute IDCODE_REGISTER of TESTCHIP : entity is
+    "XXXX" &               -- version
+    "0110010000010001" &   -- part number
+    "00000100000" &        -- manufacturer
+    "1";                   -- required by the standard
+
+  attribute BOUNDARY_LENGTH of TESTCHIP : entity is 7;
+  attribute BOUNDARY_REGISTER of TESTCHIP : entity is
+    -- num cell  port     function safe ccell disval rslt
+    "6 (BC_1, LED,     OUTPUT2, X)," &
+    "5 (BC_4, RESET_N, INPUT,   X)," &
+    "4 (BC_1, *,       CONTROL, 1)," &
+    "3 (BC_7, PA1,     BIDIR,   X, 4, 1, Z)," &
+    "2 (BC_1, *,       CONTROL, 1)," &
+    "1 (BC_1, PA0,     OUTPUT3, X, 2, 1, Z)," &
+    "0 (BC_4, PA0,     INPUT,   X)";
+
+end TESTCHIP;
+"#;
+
+    #[test]
+    fn parse_test_device() {
+        let bsdl = Bsdl::parse(TEST_BSDL).unwrap();
+
+        assert_eq!(bsdl.entity, "TESTCHIP");
+        assert_eq!(bsdl.instruction_length, 4);
+        assert_eq!(bsdl.instruction("bypass"), Some(0b1111));
+        assert_eq!(bsdl.instruction("EXTEST"), Some(0b0000));
+        assert_eq!(bsdl.instruction("SAMPLE"), Some(0b0010));
+        assert_eq!(bsdl.instruction("IDCODE"), Some(0b0001));
+        assert_eq!(bsdl.instruction("INTEST"), None);
+
+        let idcode = bsdl.idcode.unwrap();
+        assert_eq!(idcode.mask, 0x0fff_ffff);
+        assert!(idcode.matches(0x3641_1041));
+        assert!(!idcode.matches(0x3641_1043));
+
+        assert_eq!(bsdl.boundary_register.len(), 7);
+        assert_eq!(
+            bsdl.boundary_register[3],
+            BoundaryCell {
+                number: 3,
+                port: Some("PA1".to_string()),
+                function: CellFunction::Bidir,
+                safe: None,
+                control: Some(ControlCell {
+                    cell: 4,
+                    disable_value: true,
+                }),
+            }
+        );
+        assert_eq!(bsdl.boundary_register[4].port, None);
+        assert_eq!(bsdl.boundary_register[4].safe, Some(true));
+    }
+
+    #[test]
+    fn missing_boundary_register() {
+        let source = TEST_BSDL.replace("BOUNDARY_REGISTER", "OTHER_REGISTER");
+
+        assert!(matches!(
+            Bsdl::parse(&source),
+            Err(BsdlError::MissingAttribute("BOUNDARY_REGISTER"))
+        ));
+    }
+
+    #[test]
+    fn boundary_length_mismatch() {
+        let source = TEST_BSDL.replace("entity is 7;", "entity is 8;");
+
+        assert!(matches!(
+            Bsdl::parse(&source),
+            Err(BsdlError::InvalidAttribute {
+                attribute: "BOUNDARY_REGISTER",
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,466 @@
+//! Boundary scan of a device over JTAG.
+//!
+//! The boundary register of a device connects a cell to each of its pins. While the SAMPLE
+//! instruction is loaded, the state of the pins can be captured without affecting the device. The
+//! EXTEST instruction disconnects the pins from the device logic, and drives them from the boundary
+//! register instead. This allows testing the connections of a board without any firmware, for
+//! example during bring-up or manufacturing test.
+//!
+//! The boundary register of a device is described by its BSDL file, which is parsed by [`Bsdl`].
+//! [`Probe::try_into_boundary_scan`] puts the TAP selected by the JTAG chain configuration into
+//! boundary scan mode.
+
+mod bsdl;
+
+pub use bsdl::{BoundaryCell, Bsdl, BsdlError, CellFunction, ControlCell, IdCode};
+
+use crate::{DebugProbeError, Probe};
+use bitvec::{order::Lsb0, slice::BitSlice, vec::BitVec};
+
+/// Errors during a boundary scan.
+#[derive(Debug, thiserror::Error)]
+pub enum BoundaryScanError {
+    /// An error with the probe occurred.
+    #[error("An error with the probe occurred")]
+    Probe(#[from] DebugProbeError),
+    /// The BSDL file is invalid.
+    #[error("The BSDL file is invalid")]
+    Bsdl(#[from] BsdlError),
+    /// The probe does not support raw JTAG access.
+    #[error("The probe does not support raw JTAG access")]
+    NoJtagAccess,
+    /// An instruction needed for the boundary scan is not described by the BSDL file.
+    #[error("The BSDL file does not describe the {0} instruction")]
+    MissingInstruction(&'static str),
+    /// The IDCODE of the device does not match the BSDL file.
+    #[error("The IDCODE {found:#010x} of the device does not match the expected IDCODE {expected:#010x} (mask {mask:#010x})")]
+    IdcodeMismatch {
+        /// The expected IDCODE.
+        expected: u32,
+        /// The bits of the IDCODE which are checked.
+        mask: u32,
+        /// The IDCODE read from the device.
+        found: u32,
+    },
+    /// The device has no pin with the given name.
+    #[error("The device has no pin named {0}")]
+    UnknownPin(String),
+    /// The pin cannot be read.
+    #[error("The pin {0} cannot be read")]
+    NotAnInput(String),
+    /// The pin cannot be driven.
+    #[error("The pin {0} cannot be driven")]
+    NotAnOutput(String),
+    /// The output of the pin cannot be disabled.
+    #[error("The output of the pin {0} cannot be disabled")]
+    CannotDisable(String),
+}
+
+/// The state of a pin, captured by a boundary scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinState {
+    /// The name of the pin.
+    pub name: String,
+    /// The captured level of the pin.
+    pub value: bool,
+}
+
+/// A device in boundary scan mode.
+///
+/// The probe is returned by [`BoundaryScan::close`], which also hands the pins back to the device.
+#[derive(Debug)]
+pub struct BoundaryScan {
+    probe: Probe,
+    bsdl: Bsdl,
+    idcode: Option<u32>,
+    /// The values which are shifted into the boundary register.
+    register: BitVec<u8, Lsb0>,
+    /// Whether EXTEST is loaded, in which case the pins are driven by the boundary register.
+    extest: bool,
+}
+
+impl BoundaryScan {
+    pub(crate) fn new(mut probe: Probe, bsdl: Bsdl) -> Result<Self, (Probe, BoundaryScanError)> {
+        let idcode = match Self::verify_idcode(&mut probe, &bsdl) {
+            Ok(idcode) => idcode,
+            Err(e) => return Err((probe, e)),
+        };
+
+        // Until a pin is written, the register holds values which keep the device safe. This
+        // disables all outputs which can be disabled.
+        let register = bsdl
+            .boundary_register
+            .iter()
+            .map(|cell| cell.safe.unwrap_or(false))
+            .collect();
+
+        Ok(BoundaryScan {
+            probe,
+            bsdl,
+            idcode,
+            register,
+            extest: false,
+        })
+    }
+
+    fn verify_idcode(probe: &mut Probe, bsdl: &Bsdl) -> Result<Option<u32>, BoundaryScanError> {
+        let jtag = probe
+            .try_as_jtag_probe()
+            .ok_or(BoundaryScanError::NoJtagAccess)?;
+        jtag.set_ir_len(bsdl.instruction_length as u32);
+
+        let (expected, instruction) = match (bsdl.idcode, bsdl.instruction("IDCODE")) {
+            (Some(expected), Some(instruction)) => (expected, instruction),
+            _ => return Ok(None),
+        };
+
+        let response = jtag.read_register(instruction, 32)?;
+        let mut bytes = [0; 4];
+        let len = response.len().min(4);
+        bytes[..len].copy_from_slice(&response[..len]);
+        let found = u32::from_le_bytes(bytes);
+
+        if !expected.matches(found) {
+            return Err(BoundaryScanError::IdcodeMismatch {
+                expected: expected.value,
+                mask: expected.mask,
+                found,
+            });
+        }
+
+        tracing::debug!("Found {} with IDCODE {:#010x}", bsdl.entity, found);
+
+        Ok(Some(found))
+    }
+
+    /// Returns the description of the device.
+    pub fn bsdl(&self) -> &Bsdl {
+        &self.bsdl
+    }
+
+    /// Returns the IDCODE read from the device, if the device has an IDCODE register.
+    pub fn idcode(&self) -> Option<u32> {
+        self.idcode
+    }
+
+    /// Returns the names of all pins connected to the boundary register.
+    pub fn pins(&self) -> Vec<&str> {
+        let mut pins: Vec<&str> = Vec::new();
+        for port in self
+            .bsdl
+            .boundary_register
+            .iter()
+            .filter_map(|cell| cell.port.as_deref())
+        {
+            if !pins.contains(&port) {
+                pins.push(port);
+            }
+        }
+
+        pins
+    }
+
+    /// Returns true if EXTEST is loaded, and the pins are driven by the boundary register.
+    pub fn is_extest(&self) -> bool {
+        self.extest
+    }
+
+    /// Loads the given instruction, and shifts the boundary register.
+    ///
+    /// Returns the values captured by the boundary register.
+    fn shift(&mut self, instruction: &'static str) -> Result<BitVec<u8, Lsb0>, BoundaryScanError> {
+        let opcode = self
+            .bsdl
+            .instruction(instruction)
+            .ok_or(BoundaryScanError::MissingInstruction(instruction))?;
+        let len = self.register.len();
+
+        let jtag = self
+            .probe
+            .try_as_jtag_probe()
+            .ok_or(BoundaryScanError::NoJtagAccess)?;
+        let response = jtag.write_register(opcode, self.register.as_raw_slice(), len as u32)?;
+
+        let captured = BitSlice::<u8, Lsb0>::from_slice(&response);
+        if captured.len() < len {
+            return Err(DebugProbeError::Other(anyhow::anyhow!(
+                "The probe returned {} bits for the {} bit boundary register",
+                captured.len(),
+                len
+            ))
+            .into());
+        }
+
+        Ok(captured[..len].to_bitvec())
+    }
+
+    /// Disconnects the pins from the device, and drives them from the boundary register.
+    ///
+    /// The pins written before are preloaded, all others are in their safe state.
+    pub fn enter_extest(&mut self) -> Result<(), BoundaryScanError> {
+        if self.extest {
+            return Ok(());
+        }
+
+        let preload = if self.bsdl.instruction("PRELOAD").is_some() {
+            "PRELOAD"
+        } else {
+            "SAMPLE"
+        };
+        self.shift(preload)?;
+        self.shift("EXTEST")?;
+        self.extest = true;
+
+        Ok(())
+    }
+
+    /// Captures the state of all pins which can be read.
+    pub fn sample(&mut self) -> Result<Vec<PinState>, BoundaryScanError> {
+        // Loading SAMPLE would hand the pins back to the device.
+        let instruction = if self.extest { "EXTEST" } else { "SAMPLE" };
+        let captured = self.shift(instruction)?;
+
+        let mut pins: Vec<PinState> = Vec::new();
+        for cell in &self.bsdl.boundary_register {
+            if let Some(port) = &cell.port {
+                if cell.function.is_input() && !pins.iter().any(|pin| pin.name == *port) {
+                    pins.push(PinState {
+                        name: port.clone(),
+                        value: captured[cell.number],
+                    });
+                }
+            }
+        }
+
+        Ok(pins)
+    }
+
+    /// Captures the state of a single pin.
+    pub fn read_pin(&mut self, name: &str) -> Result<bool, BoundaryScanError> {
+        let cell = self.cell(name, CellFunction::is_input, BoundaryScanError::NotAnInput)?;
+        let number = cell.number;
+
+        let instruction = if self.extest { "EXTEST" } else { "SAMPLE" };
+        let captured = self.shift(instruction)?;
+
+        Ok(captured[number])
+    }
+
+    /// Drives a pin high or low, or disables its output if the value is `None`.
+    ///
+    /// While EXTEST is loaded, the pin changes immediately. Otherwise the value is preloaded, and
+    /// applied by [`BoundaryScan::enter_extest`].
+    pub fn write_pin(&mut self, name: &str, value: Option<bool>) -> Result<(), BoundaryScanError> {
+        let cell = self.cell(
+            name,
+            CellFunction::is_output,
+            BoundaryScanError::NotAnOutput,
+        )?;
+        let number = cell.number;
+        let control = cell.control;
+
+        match (value, control) {
+            (Some(value), control) => {
+                self.register.set(number, value);
+                if let Some(control) = control {
+                    self.register.set(control.cell, !control.disable_value);
+                }
+            }
+            (None, Some(control)) => self.register.set(control.cell, control.disable_value),
+            (None, None) => return Err(BoundaryScanError::CannotDisable(name.to_string())),
+        }
+
+        if self.extest {
+            self.shift("EXTEST")?;
+        }
+
+        Ok(())
+    }
+
+    /// Finds the cell of a pin with the given capability.
+    fn cell(
+        &self,
+        name: &str,
+        capability: fn(CellFunction) -> bool,
+        error: fn(String) -> BoundaryScanError,
+    ) -> Result<&BoundaryCell, BoundaryScanError> {
+        let mut cells = self
+            .bsdl
+            .boundary_register
+            .iter()
+            .filter(|cell| {
+                cell.port
+                    .as_deref()
+                    .map(|port| port.eq_ignore_ascii_case(name))
+                    .unwrap_or(false)
+            })
+            .peekable();
+
+        if cells.peek().is_none() {
+            return Err(BoundaryScanError::UnknownPin(name.to_string()));
+        }
+
+        cells
+            .find(|cell| capability(cell.function))
+            .ok_or_else(|| error(name.to_string()))
+    }
+
+    /// Loads the BYPASS instruction, which hands the pins back to the device, and returns the probe.
+    pub fn close(mut self) -> Result<Probe, BoundaryScanError> {
+        let opcode = self
+            .bsdl
+            .instruction("BYPASS")
+            .ok_or(BoundaryScanError::MissingInstruction("BYPASS"))?;
+
+        let jtag = self
+            .probe
+            .try_as_jtag_probe()
+            .ok_or(BoundaryScanError::NoJtagAccess)?;
+        jtag.write_register(opcode, &[0], 1)?;
+
+        Ok(self.probe)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::bsdl::test::TEST_BSDL;
+    use super::*;
+    use crate::{DebugProbeError, FakeProbe};
+    use std::sync::{Arc, Mutex};
+
+    /// The state of the pins of the simulated device.
+    #[derive(Debug, Default)]
+    struct Pins {
+        /// The levels which the board applies to the inputs.
+        inputs: [bool; 7],
+        /// The values in the update register of the boundary register.
+        update: [bool; 7],
+        extest: bool,
+    }
+
+    /// Simulates the TAP of the device described by [`TEST_BSDL`].
+    fn simulated_tap(pins: Arc<Mutex<Pins>>) -> Probe {
+        let mut probe = FakeProbe::new();
+        probe.set_jtag_register_handler(Box::new(move |address, data, len| {
+            let mut pins = pins.lock().unwrap();
+            match (address, len) {
+                // IDCODE
+                (0b0001, 32) => Ok(0x1641_1041u32.to_le_bytes().to_vec()),
+                // EXTEST or SAMPLE/PRELOAD
+                (0b0000 | 0b0010, 7) => {
+                    let mut captured = 0;
+                    for i in 0..7 {
+                        if pins.inputs[i] {
+                            captured |= 1 << i;
+                        }
+                        pins.update[i] = data[0] & (1 << i) != 0;
+                    }
+                    pins.extest = address == 0;
+                    Ok(vec![captured])
+                }
+                // BYPASS
+                (0b1111, 1) => {
+                    pins.extest = false;
+                    Ok(vec![0])
+                }
+                _ => Err(DebugProbeError::Other(anyhow::anyhow!(
+                    "Unexpected access to register {address:#x} with {len} bits"
+                ))),
+            }
+        }));
+
+        let mut probe = probe.into_probe();
+        probe.attach_to_unspecified().unwrap();
+        probe
+    }
+
+    fn boundary_scan(pins: &Arc<Mutex<Pins>>) -> BoundaryScan {
+        simulated_tap(pins.clone())
+            .try_into_boundary_scan(Bsdl::parse(TEST_BSDL).unwrap())
+            .map_err(|(_, e)| e)
+            .unwrap()
+    }
+
+    #[test]
+    fn sample_pins() {
+        let pins = Arc::new(Mutex::new(Pins::default()));
+        pins.lock().unwrap().inputs[5] = true;
+
+        let mut scan = boundary_scan(&pins);
+        assert_eq!(scan.idcode(), Some(0x1641_1041));
+        assert_eq!(scan.pins(), ["PA0", "PA1", "RESET_N", "LED"]);
+
+        let states = scan.sample().unwrap();
+        let state = |name: &str| states.iter().find(|pin| pin.name == name).unwrap().value;
+        assert!(state("RESET_N"));
+        assert!(!state("PA0"));
+        assert!(!states.iter().any(|pin| pin.name == "LED"));
+
+        assert!(!pins.lock().unwrap().extest);
+        assert!(matches!(
+            scan.read_pin("LED"),
+            Err(BoundaryScanError::NotAnInput(_))
+        ));
+        assert!(matches!(
+            scan.read_pin("PB0"),
+            Err(BoundaryScanError::UnknownPin(_))
+        ));
+    }
+
+    #[test]
+    fn drive_pins() {
+        let pins = Arc::new(Mutex::new(Pins::default()));
+        let mut scan = boundary_scan(&pins);
+
+        // Preloaded before entering EXTEST.
+        scan.write_pin("LED", Some(true)).unwrap();
+        scan.enter_extest().unwrap();
+        {
+            let pins = pins.lock().unwrap();
+            assert!(pins.extest);
+            assert!(pins.update[6]);
+            // The outputs which can be disabled are in their safe state.
+            assert!(pins.update[2] && pins.update[4]);
+        }
+
+        scan.write_pin("PA0", Some(false)).unwrap();
+        {
+            let pins = pins.lock().unwrap();
+            assert!(!pins.update[1]);
+            assert!(!pins.update[2]);
+        }
+
+        scan.write_pin("PA0", None).unwrap();
+        assert!(pins.lock().unwrap().update[2]);
+
+        assert!(matches!(
+            scan.write_pin("LED", None),
+            Err(BoundaryScanError::CannotDisable(_))
+        ));
+        assert!(matches!(
+            scan.write_pin("RESET_N", Some(true)),
+            Err(BoundaryScanError::NotAnOutput(_))
+        ));
+
+        pins.lock().unwrap().inputs[0] = true;
+        assert!(scan.read_pin("PA0").unwrap());
+        // Reading a pin keeps the device in EXTEST.
+        assert!(pins.lock().unwrap().extest);
+
+        scan.close().unwrap();
+        assert!(!pins.lock().unwrap().extest);
+    }
+
+    #[test]
+    fn idcode_mismatch() {
+        let pins = Arc::new(Mutex::new(Pins::default()));
+        let bsdl = Bsdl::parse(&TEST_BSDL.replace("\"1\";", "\"0\";")).unwrap();
+
+        let result = simulated_tap(pins).try_into_boundary_scan(bsdl);
+        assert!(matches!(
+            result,
+            Err((_, BoundaryScanError::IdcodeMismatch { .. }))
+        ));
+    }
+}
@@ -27,9 +27,9 @@ mod registry;
 mod target;
 
 pub use probe_rs_target::{
-    Chip, ChipFamily, Core, CoreType, FlashProperties, GenericRegion, InstructionSet, MemoryRange,
-    MemoryRegion, NvmRegion, PageInfo, RamRegion, RawFlashAlgorithm, SectorDescription, SectorInfo,
-    TargetDescriptionSource,
+    Chip, ChipFamily, Core, CoreType, FlashProperties, GenericRegion, InstructionSet, Jtag,
+    MemoryRange, MemoryRegion, NvmRegion, PageInfo, RamRegion, RawFlashAlgorithm, ResetType,
+    ScanChainElement, SectorDescription, SectorInfo, TargetDescriptionSource,
 };
 
 pub use registry::{
@@ -40,4 +40,3 @@ pub use target::{DebugSequence, Target, TargetParseError, TargetSelector};
 // Crate-internal API
 pub(crate) use chip_info::ChipInfo;
 pub(crate) use registry::get_target_by_chip_info;
-pub(crate) use target::CoreExt;
@@ -3,7 +3,7 @@
 use super::{Chip, ChipFamily, ChipInfo, Core, Target, TargetDescriptionSource};
 use crate::config::CoreType;
 use once_cell::sync::Lazy;
-use probe_rs_target::{CoreAccessOptions, RiscvCoreAccessOptions};
+use probe_rs_target::{CoreAccessOptions, ResetType, RiscvCoreAccessOptions};
 use std::io::Read;
 use std::sync::{Arc, Mutex};
 
@@ -105,6 +105,8 @@ fn add_generic_targets(vec: &mut Vec<ChipFamily>) {
                 }],
                 memory_map: vec![],
                 flash_algorithms: vec![],
+                default_reset_type: ResetType::default(),
+                jtag: None,
             }],
             flash_algorithms: vec![],
             source: TargetDescriptionSource::Generic,
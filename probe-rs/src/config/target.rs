@@ -1,22 +1,18 @@
-use probe_rs_target::{Architecture, ChipFamily};
+use probe_rs_target::{Architecture, ChipFamily, Jtag, ResetType};
 
 use super::{Core, MemoryRegion, RawFlashAlgorithm, RegistryError, TargetDescriptionSource};
-use crate::architecture::arm::{
-    ap::MemoryAp,
-    sequences::{
-        atsame5x::AtSAME5x,
-        efm32xg2::EFM32xG2,
-        infineon::XMC4000,
-        nrf52::Nrf52,
-        nrf53::Nrf5340,
-        nrf91::Nrf9160,
-        nxp::{LPC55Sxx, MIMXRT10xx, MIMXRT11xx, MIMXRT6xx},
-        stm32_armv6::{Stm32Armv6, Stm32Armv6Family},
-        stm32_armv7::Stm32Armv7,
-        stm32h7::Stm32h7,
-        ArmDebugSequence,
-    },
-    ApAddress, DpAddress,
+use crate::architecture::arm::sequences::{
+    atsame5x::AtSAME5x,
+    efm32xg2::EFM32xG2,
+    infineon::XMC4000,
+    nrf52::Nrf52,
+    nrf53::Nrf5340,
+    nrf91::Nrf9160,
+    nxp::{LPC55Sxx, MIMXRT10xx, MIMXRT11xx, MIMXRT6xx},
+    stm32_armv6::{Stm32Armv6, Stm32Armv6Family},
+    stm32_armv7::Stm32Armv7,
+    stm32h7::Stm32h7,
+    ArmDebugSequence,
 };
 use crate::architecture::riscv::sequences::{esp32c3::ESP32C3, esp32c6::ESP32C6};
 use crate::architecture::riscv::sequences::{DefaultRiscvSequence, RiscvDebugSequence};
@@ -40,6 +36,10 @@ pub struct Target {
     pub(crate) source: TargetDescriptionSource,
     /// Debug sequences for the given target.
     pub debug_sequence: DebugSequence,
+    /// The reset used for the target, unless a different reset is selected for a core.
+    pub default_reset_type: ResetType,
+    /// The JTAG scan chain of the target.
+    pub jtag: Option<Jtag>,
 }
 
 impl std::fmt::Debug for Target {
@@ -181,6 +181,8 @@ impl Target {
             source: family.source.clone(),
             memory_map: chip.memory_map.clone(),
             debug_sequence,
+            default_reset_type: chip.default_reset_type,
+            jtag: chip.jtag.clone(),
         })
     }
 
@@ -297,24 +299,3 @@ pub enum DebugSequence {
     /// A RISC-V debug sequence.
     Riscv(Arc<dyn RiscvDebugSequence>),
 }
-
-pub(crate) trait CoreExt {
-    // Retrieve the Coresight MemoryAP which should be used to
-    // access the core, if available.
-    fn memory_ap(&self) -> Option<MemoryAp>;
-}
-
-impl CoreExt for Core {
-    fn memory_ap(&self) -> Option<MemoryAp> {
-        match &self.core_access_options {
-            probe_rs_target::CoreAccessOptions::Arm(options) => Some(MemoryAp::new(ApAddress {
-                dp: match options.psel {
-                    0 => DpAddress::Default,
-                    x => DpAddress::Multidrop(x),
-                },
-                ap: options.ap,
-            })),
-            probe_rs_target::CoreAccessOptions::Riscv(_) => None,
-        }
-    }
-}
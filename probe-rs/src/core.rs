@@ -1,14 +1,15 @@
 use crate::{
     architecture::arm::sequences::ArmDebugSequence, error, CoreType, Error, InstructionSet,
-    MemoryInterface, Target,
+    MemoryInterface, ResetType, Target,
 };
 use anyhow::{anyhow, Result};
 pub use probe_rs_target::{Architecture, CoreAccessOptions};
-use probe_rs_target::{ArmCoreAccessOptions, RiscvCoreAccessOptions};
+use probe_rs_target::{ArmCoreAccessOptions, MemoryRegion, RiscvCoreAccessOptions};
 use std::{sync::Arc, time::Duration};
 
 pub mod core_state;
 pub mod core_status;
+pub mod dump;
 pub mod memory_mapped_registers;
 pub mod registers;
 
@@ -24,6 +25,28 @@ pub struct CoreInformation {
     pub pc: u64,
 }
 
+/// The type of memory access which triggers a data watchpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchpointKind {
+    /// Halt when the watched memory is read.
+    Read,
+    /// Halt when the watched memory is written.
+    Write,
+    /// Halt when the watched memory is either read or written.
+    Access,
+}
+
+/// A hardware data watchpoint, as configured in one of the comparator units of a core.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    /// The start address of the watched memory range.
+    pub address: u64,
+    /// The length of the watched memory range, in bytes.
+    pub length: u64,
+    /// The type of access which triggers the watchpoint.
+    pub kind: WatchpointKind,
+}
+
 /// A generic interface to control a MCU core.
 pub trait CoreInterface: MemoryInterface {
     /// Numerical ID of the core. Can be used as an argument to `Session::core()`.
@@ -47,17 +70,30 @@ pub trait CoreInterface: MemoryInterface {
     /// Continue to execute instructions.
     fn run(&mut self) -> Result<(), error::Error>;
 
-    /// Reset the core, and then continue to execute instructions. If the core
-    /// should be halted after reset, use the [`reset_and_halt`] function.
+    /// Prepare the halted core to be restarted by a restart request of its Cross Trigger
+    /// Interface, instead of by [`CoreInterface::run`].
+    ///
+    /// Everything `run` does before it restarts the core, like writing back cached register
+    /// values, is done here. Afterwards, the core is considered to be running.
+    fn prepare_restart(&mut self) -> Result<(), error::Error> {
+        Ok(())
+    }
+
+    /// Reset the core using the given [`ResetType`], and then continue to execute instructions.
+    /// If the core should be halted after reset, use the [`reset_and_halt`] function.
     ///
     /// [`reset_and_halt`]: Core::reset_and_halt
-    fn reset(&mut self) -> Result<(), error::Error>;
+    fn reset(&mut self, reset_type: ResetType) -> Result<(), error::Error>;
 
-    /// Reset the core, and then immediately halt. To continue execution after
-    /// reset, use the [`reset`] function.
+    /// Reset the core using the given [`ResetType`], and then immediately halt.
+    /// To continue execution after reset, use the [`reset`] function.
     ///
     /// [`reset`]: Core::reset
-    fn reset_and_halt(&mut self, timeout: Duration) -> Result<CoreInformation, error::Error>;
+    fn reset_and_halt(
+        &mut self,
+        timeout: Duration,
+        reset_type: ResetType,
+    ) -> Result<CoreInformation, error::Error>;
 
     /// Steps one instruction and then enters halted state again.
     fn step(&mut self) -> Result<CoreInformation, error::Error>;
@@ -92,6 +128,34 @@ pub trait CoreInterface: MemoryInterface {
     /// Clears the breakpoint configured in unit `unit_index`.
     fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), error::Error>;
 
+    /// Returns the number of data watchpoint units of the core.
+    fn available_watchpoint_units(&mut self) -> Result<u32, error::Error>;
+
+    /// Read the hardware watchpoints configured in the comparator units of the core.
+    /// A value of None in any position of the Vector indicates that the position is unset/available.
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, error::Error>;
+
+    /// Returns which comparator units of the core are used by other functions than watchpoints,
+    /// e.g. by an active data trace, and must not be used for watchpoints.
+    ///
+    /// A missing entry means that the unit is not reserved.
+    fn reserved_watchpoint_units(&mut self) -> Result<Vec<bool>, error::Error> {
+        Ok(vec![])
+    }
+
+    /// Sets a data watchpoint on the memory range starting at `address` with the given `length`,
+    /// using unit `unit_index`.
+    fn set_hw_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        length: u64,
+        kind: WatchpointKind,
+    ) -> Result<(), error::Error>;
+
+    /// Clears the watchpoint configured in unit `unit_index`.
+    fn clear_hw_watchpoint(&mut self, unit_index: usize) -> Result<(), error::Error>;
+
     /// Returns a list of all the registers of this core.
     fn registers(&self) -> &'static registers::CoreRegisters;
 
@@ -110,6 +174,15 @@ pub trait CoreInterface: MemoryInterface {
     /// Returns `true` if hwardware breakpoints are enabled, `false` otherwise.
     fn hw_breakpoints_enabled(&self) -> bool;
 
+    /// Make the instructions in the memory range starting at `address` visible to the core,
+    /// after they were written by the debugger, e.g. for a software breakpoint.
+    ///
+    /// This is only needed for cores whose instruction cache is not coherent with the memory
+    /// accesses of the debugger.
+    fn sync_instruction_cache(&mut self, _address: u64, _length: u64) -> Result<(), error::Error> {
+        Ok(())
+    }
+
     /// Configure the target to ensure software breakpoints will enter Debug Mode.
     fn debug_on_sw_breakpoint(&mut self, _enabled: bool) -> Result<(), error::Error> {
         // This default will have override methods for architectures that require special behavior, e.g. RISV-V.
@@ -144,6 +217,12 @@ pub trait CoreInterface: MemoryInterface {
     /// This will reset the changes done by `reset_catch_set`.
     fn reset_catch_clear(&mut self) -> Result<(), Error>;
 
+    /// Configure the core to halt when `condition` occurs.
+    fn enable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error>;
+
+    /// Configure the core to no longer halt when `condition` occurs.
+    fn disable_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error>;
+
     /// Called when we stop debugging a core.
     fn debug_core_stop(&mut self) -> Result<(), Error>;
 }
@@ -161,6 +240,10 @@ impl<'probe> MemoryInterface for Core<'probe> {
         self.inner.read_word_32(address)
     }
 
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        self.inner.read_word_16(address)
+    }
+
     fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
         self.inner.read_word_8(address)
     }
@@ -173,6 +256,10 @@ impl<'probe> MemoryInterface for Core<'probe> {
         self.inner.read_32(address, data)
     }
 
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        self.inner.read_16(address, data)
+    }
+
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
         self.inner.read_8(address, data)
     }
@@ -189,6 +276,10 @@ impl<'probe> MemoryInterface for Core<'probe> {
         self.inner.write_word_32(addr, data)
     }
 
+    fn write_word_16(&mut self, addr: u64, data: u16) -> Result<(), Error> {
+        self.inner.write_word_16(addr, data)
+    }
+
     fn write_word_8(&mut self, addr: u64, data: u8) -> Result<(), Error> {
         self.inner.write_word_8(addr, data)
     }
@@ -201,6 +292,10 @@ impl<'probe> MemoryInterface for Core<'probe> {
         self.inner.write_32(addr, data)
     }
 
+    fn write_16(&mut self, addr: u64, data: &[u16]) -> Result<(), Error> {
+        self.inner.write_16(addr, data)
+    }
+
     fn write_8(&mut self, addr: u64, data: &[u8]) -> Result<(), Error> {
         self.inner.write_8(addr, data)
     }
@@ -218,6 +313,47 @@ impl<'probe> MemoryInterface for Core<'probe> {
     }
 }
 
+/// The breakpoint instruction which replaces the `original` instruction for a software breakpoint.
+fn sw_breakpoint_instruction(
+    core_type: CoreType,
+    instruction_set: InstructionSet,
+    original: &[u8],
+) -> Result<&'static [u8], error::Error> {
+    match (core_type, instruction_set) {
+        // HLT #0, which enters debug state if halting debug is enabled.
+        (CoreType::Armv8a, InstructionSet::A64) => Ok(&[0x00, 0x00, 0x40, 0xd4]),
+        (CoreType::Armv8a, InstructionSet::A32) => Ok(&[0x70, 0x00, 0x00, 0xe1]),
+        (CoreType::Armv8a, InstructionSet::Thumb2) => Ok(&[0x80, 0xba]),
+        // BKPT #0
+        (_, InstructionSet::A32) => Ok(&[0x70, 0x00, 0x20, 0xe1]),
+        (_, InstructionSet::Thumb2) => Ok(&[0x00, 0xbe]),
+        // C.EBREAK, if the original instruction is a compressed instruction.
+        (_, InstructionSet::RV32C) if original[0] & 0b11 != 0b11 => Ok(&[0x02, 0x90]),
+        // EBREAK
+        (_, InstructionSet::RV32 | InstructionSet::RV32C) => Ok(&[0x73, 0x00, 0x10, 0x00]),
+        (core_type, instruction_set) => Err(error::Error::Other(anyhow!(
+            "Software breakpoints are not supported for {:?} cores executing {:?} instructions",
+            core_type,
+            instruction_set
+        ))),
+    }
+}
+
+/// Checks if the `length` bytes at `address` are completely inside one of the `ranges`.
+fn in_ranges(ranges: &[std::ops::Range<u64>], address: u64, length: u64) -> bool {
+    ranges
+        .iter()
+        .any(|range| range.start <= address && address + length <= range.end)
+}
+
+/// The error for a software breakpoint outside of the RAM.
+fn sw_breakpoint_not_in_ram(address: u64) -> error::Error {
+    error::Error::Other(anyhow!(
+        "Unable to set a software breakpoint at address {:#010x}, the address is not in RAM",
+        address
+    ))
+}
+
 /// Generic core handle representing a physical core on an MCU.
 ///
 /// This should be considere as a temporary view of the core which locks the debug probe driver to as single consumer by borrowing it.
@@ -226,13 +362,18 @@ impl<'probe> MemoryInterface for Core<'probe> {
 /// to allow potential other shareholders of the session struct to grab a core handle too.
 pub struct Core<'probe> {
     inner: Box<dyn CoreInterface + 'probe>,
+    state: &'probe mut CoreState,
 }
 
 impl<'probe> Core<'probe> {
     /// Create a new [`Core`].
-    pub(crate) fn new(core: impl CoreInterface + 'probe) -> Core<'probe> {
+    pub(crate) fn new(
+        core: impl CoreInterface + 'probe,
+        state: &'probe mut CoreState,
+    ) -> Core<'probe> {
         Self {
             inner: Box::new(core),
+            state,
         }
     }
 
@@ -245,6 +386,16 @@ impl<'probe> Core<'probe> {
     ) -> CombinedCoreState {
         let specific_state = SpecificCoreState::from_core_type(core_type);
 
+        let core_name = &target.cores[id].name;
+        let ram_ranges: Vec<_> = target
+            .memory_map
+            .iter()
+            .filter_map(|region| match region {
+                MemoryRegion::Ram(ram) if ram.cores.contains(core_name) => Some(ram.range.clone()),
+                _ => None,
+            })
+            .collect();
+
         match options {
             CoreAccessOptions::Arm(options) => {
                 let sequence = match &target.debug_sequence {
@@ -254,7 +405,9 @@ impl<'probe> Core<'probe> {
                     ),
                 };
 
-                let core_state = CoreState::new(ResolvedCoreOptions::Arm { sequence, options });
+                let mut core_state = CoreState::new(ResolvedCoreOptions::Arm { sequence, options });
+                core_state.reset_type = target.default_reset_type;
+                core_state.ram_ranges = ram_ranges;
 
                 CombinedCoreState {
                     id,
@@ -263,7 +416,9 @@ impl<'probe> Core<'probe> {
                 }
             }
             CoreAccessOptions::Riscv(options) => {
-                let core_state = CoreState::new(ResolvedCoreOptions::Riscv { options });
+                let mut core_state = CoreState::new(ResolvedCoreOptions::Riscv { options });
+                core_state.reset_type = target.default_reset_type;
+                core_state.ram_ranges = ram_ranges;
                 CombinedCoreState {
                     id,
                     core_state,
@@ -299,39 +454,89 @@ impl<'probe> Core<'probe> {
     }
 
     /// Continue to execute instructions.
+    ///
+    /// If the core is halted on a software breakpoint, the original instruction is executed first.
     #[tracing::instrument(skip(self))]
     pub fn run(&mut self) -> Result<(), error::Error> {
+        self.step_over_sw_breakpoint()?;
         self.inner.run()
     }
 
+    /// Prepare the halted core to be restarted through its Cross Trigger Interface.
+    ///
+    /// If the core is halted on a software breakpoint, the original instruction is executed first.
+    pub(crate) fn prepare_restart(&mut self) -> Result<(), error::Error> {
+        self.step_over_sw_breakpoint()?;
+        self.inner.prepare_restart()
+    }
+
     /// Reset the core, and then continue to execute instructions. If the core
     /// should be halted after reset, use the [`reset_and_halt`] function.
     ///
+    /// The core is reset using the reset type selected with [`Core::set_reset_type`],
+    /// which defaults to the reset type of the target.
+    ///
     /// [`reset_and_halt`]: Core::reset_and_halt
     #[tracing::instrument(skip(self))]
     pub fn reset(&mut self) -> Result<(), error::Error> {
-        self.inner.reset()
+        self.inner.reset(self.state.reset_type)
     }
 
     /// Reset the core, and then immediately halt. To continue execution after
     /// reset, use the [`reset`] function.
     ///
+    /// The core is reset using the reset type selected with [`Core::set_reset_type`],
+    /// which defaults to the reset type of the target.
+    ///
     /// [`reset`]: Core::reset
     #[tracing::instrument(skip(self))]
     pub fn reset_and_halt(&mut self, timeout: Duration) -> Result<CoreInformation, error::Error> {
-        self.inner.reset_and_halt(timeout)
+        self.inner.reset_and_halt(timeout, self.state.reset_type)
+    }
+
+    /// Returns the reset type used by [`Core::reset`] and [`Core::reset_and_halt`].
+    pub fn reset_type(&self) -> ResetType {
+        self.state.reset_type
+    }
+
+    /// Selects the reset type used by [`Core::reset`] and [`Core::reset_and_halt`].
+    ///
+    /// The selection is kept for the lifetime of the [`Session`](crate::Session).
+    pub fn set_reset_type(&mut self, reset_type: ResetType) {
+        self.state.reset_type = reset_type;
     }
 
     /// Steps one instruction and then enters halted state again.
+    ///
+    /// If the core is halted on a software breakpoint, the original instruction is executed.
     #[tracing::instrument(skip(self))]
     pub fn step(&mut self) -> Result<CoreInformation, error::Error> {
-        self.inner.step()
+        match self.step_over_sw_breakpoint()? {
+            Some(core_information) => Ok(core_information),
+            None => self.inner.step(),
+        }
     }
 
     /// Returns the current status of the core.
     #[tracing::instrument(skip(self))]
     pub fn status(&mut self) -> Result<CoreStatus, error::Error> {
-        self.inner.status()
+        let status = self.inner.status()?;
+
+        // Not all architectures can tell software and hardware breakpoints apart,
+        // but we know where we placed the software breakpoints.
+        if let CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Unknown)) = status {
+            if !self.state.sw_breakpoints.is_empty() {
+                let pc: u64 = self.read_core_reg(self.program_counter())?;
+
+                if self.state.sw_breakpoints.contains_key(&pc) {
+                    return Ok(CoreStatus::Halted(HaltReason::Breakpoint(
+                        BreakpointCause::Software,
+                    )));
+                }
+            }
+        }
+
+        Ok(status)
     }
 
     /// Read the value of a core register.
@@ -428,9 +633,21 @@ impl<'probe> Core<'probe> {
 
     /// Find the index of the next available HW breakpoint comparator.
     fn find_free_breakpoint_comparator_index(&mut self) -> Result<usize, error::Error> {
+        // On RISC-V, breakpoints and watchpoints share the same trigger units.
+        let watchpoints = if self.architecture() == Architecture::Riscv {
+            self.inner.hw_watchpoints()?
+        } else {
+            vec![]
+        };
+
         let mut next_available_hw_breakpoint = 0;
         for breakpoint in self.inner.hw_breakpoints()? {
-            if breakpoint.is_none() {
+            if breakpoint.is_none()
+                && watchpoints
+                    .get(next_available_hw_breakpoint)
+                    .and_then(Option::as_ref)
+                    .is_none()
+            {
                 return Ok(next_available_hw_breakpoint);
             } else {
                 next_available_hw_breakpoint += 1;
@@ -518,6 +735,542 @@ impl<'probe> Core<'probe> {
         Ok(())
     }
 
+    /// Writes an instruction for a software breakpoint, and makes it visible to the core.
+    fn write_instruction(&mut self, address: u64, instruction: &[u8]) -> Result<(), error::Error> {
+        self.inner.write_8(address, instruction)?;
+        self.inner.flush()?;
+        self.inner
+            .sync_instruction_cache(address, instruction.len() as u64)
+    }
+
+    /// Write a breakpoint instruction, and check that it was actually written.
+    fn patch_instruction(&mut self, address: u64, instruction: &[u8]) -> Result<(), error::Error> {
+        self.write_instruction(address, instruction)?;
+
+        // Writes to flash and other read-only memory are silently ignored by some targets.
+        let mut written_instruction = vec![0u8; instruction.len()];
+        self.inner.read_8(address, &mut written_instruction)?;
+
+        if written_instruction != instruction {
+            return Err(error::Error::Other(anyhow!(
+                "Unable to set a software breakpoint at address {:#010x}, the memory is not writable",
+                address
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Set a software breakpoint
+    ///
+    /// This function will replace the instruction at `address` with a breakpoint instruction
+    /// (`BKPT`, `HLT` or `EBREAK`, depending on the architecture). The original instruction is
+    /// kept, and is executed transparently when the core is stepped or resumed from the breakpoint.
+    ///
+    /// This only works for code which is placed in the RAM of the core, according to the memory
+    /// map of the target. Other memory, like flash, is never written, as writes to it can cause
+    /// errors in its controller. If the breakpoint instruction cannot be written, an error is
+    /// returned and the memory is left unchanged. Use [`Core::set_breakpoint`] to fall back to a
+    /// hardware breakpoint in that case.
+    ///
+    /// The core has to be halted, as the instruction cache of some cores can only be
+    /// synchronized while they are halted.
+    ///
+    /// The breakpoint will be lost if the memory is overwritten by the target, e.g. after a reset.
+    #[tracing::instrument(skip(self))]
+    pub fn set_sw_breakpoint(&mut self, address: u64) -> Result<(), error::Error> {
+        if self.state.sw_breakpoints.contains_key(&address) {
+            return Ok(());
+        }
+
+        if !self.inner.core_halted()? {
+            return Err(error::Error::Other(anyhow!(
+                "Unable to set a software breakpoint at address {:#010x}, the core is not halted",
+                address
+            )));
+        }
+
+        // All breakpoint instructions are at least two bytes long, so the range can be checked
+        // before anything is read from the target.
+        if !in_ranges(&self.state.ram_ranges, address, 2) {
+            return Err(sw_breakpoint_not_in_ram(address));
+        }
+
+        // The first halfword is enough to select the breakpoint instruction.
+        let mut original_instruction = vec![0u8; 2];
+        self.inner.read_8(address, &mut original_instruction)?;
+
+        let breakpoint_instruction = sw_breakpoint_instruction(
+            self.core_type(),
+            self.instruction_set()?,
+            &original_instruction,
+        )?
+        .to_vec();
+
+        if breakpoint_instruction.len() > original_instruction.len() {
+            if !in_ranges(
+                &self.state.ram_ranges,
+                address,
+                breakpoint_instruction.len() as u64,
+            ) {
+                return Err(sw_breakpoint_not_in_ram(address));
+            }
+
+            original_instruction.resize(breakpoint_instruction.len(), 0);
+            self.inner
+                .read_8(address + 2, &mut original_instruction[2..])?;
+        }
+
+        // Ensure breakpoint instructions actually enter debug mode.
+        self.inner.debug_on_sw_breakpoint(true)?;
+
+        if let Err(error) = self.patch_instruction(address, &breakpoint_instruction) {
+            // Don't leave a breakpoint instruction behind which is not tracked.
+            if let Err(restore_error) = self.write_instruction(address, &original_instruction) {
+                tracing::warn!(
+                    "Unable to restore the instruction at address {:#010x}: {}",
+                    address,
+                    restore_error
+                );
+            }
+
+            return Err(error);
+        }
+
+        tracing::debug!(
+            "Set software breakpoint at address {:#010x}, replacing {:02x?}",
+            address,
+            original_instruction
+        );
+
+        self.state.sw_breakpoints.insert(
+            address,
+            SoftwareBreakpoint {
+                original_instruction,
+                breakpoint_instruction,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Clear a software breakpoint
+    ///
+    /// This function will restore the original instruction at `address` if there exists a software breakpoint at that address.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_sw_breakpoint(&mut self, address: u64) -> Result<(), error::Error> {
+        let breakpoint = match self.state.sw_breakpoints.get(&address) {
+            Some(breakpoint) => breakpoint.clone(),
+            None => {
+                return Err(error::Error::Other(anyhow!(
+                    "No software breakpoint found at address {:#010x}",
+                    address
+                )))
+            }
+        };
+
+        // The breakpoint is only forgotten once the original instruction is back in place.
+        self.write_instruction(address, &breakpoint.original_instruction)?;
+        self.state.sw_breakpoints.remove(&address);
+
+        Ok(())
+    }
+
+    /// Clear all software breakpoints
+    ///
+    /// This function will restore the original instructions of all software breakpoints which were set using this core.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_all_sw_breakpoints(&mut self) -> Result<(), error::Error> {
+        let addresses = self.sw_breakpoints();
+
+        for address in addresses {
+            self.clear_sw_breakpoint(address)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the addresses of all software breakpoints which are currently set.
+    pub fn sw_breakpoints(&self) -> Vec<u64> {
+        self.state.sw_breakpoints.keys().copied().collect()
+    }
+
+    /// Set a breakpoint
+    ///
+    /// This function will try to set a software breakpoint at `address`, and falls back to a
+    /// hardware breakpoint if the code at `address` can not be patched, e.g. because it is not
+    /// located in RAM.
+    #[tracing::instrument(skip(self))]
+    pub fn set_breakpoint(&mut self, address: u64) -> Result<(), error::Error> {
+        match self.set_sw_breakpoint(address) {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                tracing::debug!(
+                    "Unable to set software breakpoint, using a hardware breakpoint instead: {}",
+                    error
+                );
+                self.set_hw_breakpoint(address)
+            }
+        }
+    }
+
+    /// Clear a breakpoint
+    ///
+    /// This function will clear the software or hardware breakpoint at `address`.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_breakpoint(&mut self, address: u64) -> Result<(), error::Error> {
+        if self.state.sw_breakpoints.contains_key(&address) {
+            self.clear_sw_breakpoint(address)
+        } else {
+            self.clear_hw_breakpoint(address)
+        }
+    }
+
+    /// If the core is halted on a software breakpoint, execute the original instruction
+    /// and put the breakpoint instruction back in place afterwards.
+    ///
+    /// Returns `None` if the core was not halted on a software breakpoint.
+    fn step_over_sw_breakpoint(&mut self) -> Result<Option<CoreInformation>, error::Error> {
+        if self.state.sw_breakpoints.is_empty() || !self.inner.core_halted()? {
+            return Ok(None);
+        }
+
+        let pc: u64 = self.read_core_reg(self.program_counter())?;
+
+        let breakpoint = match self.state.sw_breakpoints.get(&pc) {
+            Some(breakpoint) => breakpoint.clone(),
+            None => return Ok(None),
+        };
+
+        tracing::debug!("Stepping over software breakpoint at address {:#010x}", pc);
+
+        self.write_instruction(pc, &breakpoint.original_instruction)?;
+
+        let step_result = self.inner.step();
+
+        // Always restore the breakpoint, even if the step failed.
+        self.write_instruction(pc, &breakpoint.breakpoint_instruction)?;
+
+        step_result.map(Some)
+    }
+
+    /// Returns the number of data watchpoint units of the core.
+    pub fn available_watchpoint_units(&mut self) -> Result<u32, error::Error> {
+        self.inner.available_watchpoint_units()
+    }
+
+    /// Returns all the hardware watchpoints which are currently configured on the core.
+    ///
+    /// A value of None in any position of the Vector indicates that the unit is unset/available.
+    pub fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, error::Error> {
+        self.inner.hw_watchpoints()
+    }
+
+    /// Find the index of the next available HW watchpoint comparator.
+    fn find_free_watchpoint_comparator_index(&mut self) -> Result<usize, error::Error> {
+        // On RISC-V, breakpoints and watchpoints share the same trigger units.
+        let breakpoints = if self.architecture() == Architecture::Riscv {
+            self.inner.hw_breakpoints()?
+        } else {
+            vec![]
+        };
+
+        let reserved = self.inner.reserved_watchpoint_units()?;
+
+        self.inner
+            .hw_watchpoints()?
+            .iter()
+            .enumerate()
+            .position(|(index, watchpoint)| {
+                watchpoint.is_none()
+                    && breakpoints.get(index).and_then(Option::as_ref).is_none()
+                    && !reserved.get(index).copied().unwrap_or(false)
+            })
+            .ok_or_else(|| error::Error::Other(anyhow!("No available hardware watchpoints")))
+    }
+
+    /// Set a hardware data watchpoint
+    ///
+    /// This function will try to set a hardware watchpoint on the memory range starting at `address`
+    /// with the given `length`. The core will halt with [`HaltReason::Watchpoint`] as soon as the range
+    /// is accessed in the way described by `kind`.
+    ///
+    /// Which lengths are supported is architecture specific. Usually, the length has to be a power
+    /// of two and `address` has to be aligned to it.
+    ///
+    /// The amount of hardware watchpoints which are supported is chip specific,
+    /// and can be queried using the [`Core::available_watchpoint_units`] function.
+    #[tracing::instrument(skip(self))]
+    pub fn set_hw_watchpoint(
+        &mut self,
+        address: u64,
+        length: u64,
+        kind: WatchpointKind,
+    ) -> Result<(), error::Error> {
+        // If there is a watchpoint set on this address already, reuse its unit, else find the next free one.
+        let watchpoint_comparator_index = match self
+            .inner
+            .hw_watchpoints()?
+            .iter()
+            .position(|wp| matches!(wp, Some(wp) if wp.address == address))
+        {
+            Some(watchpoint_comparator_index) => watchpoint_comparator_index,
+            None => self.find_free_watchpoint_comparator_index()?,
+        };
+
+        tracing::debug!(
+            "Trying to set HW watchpoint #{} on {} bytes at address {:#08x}",
+            watchpoint_comparator_index,
+            length,
+            address
+        );
+
+        self.inner
+            .set_hw_watchpoint(watchpoint_comparator_index, address, length, kind)
+    }
+
+    /// Clear a hardware data watchpoint
+    ///
+    /// This function will try to clear the hardware watchpoint at `address` if there exists a watchpoint at that address.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_hw_watchpoint(&mut self, address: u64) -> Result<(), error::Error> {
+        let wp_position = self
+            .inner
+            .hw_watchpoints()?
+            .iter()
+            .position(|wp| matches!(wp, Some(wp) if wp.address == address));
+
+        match wp_position {
+            Some(wp_position) => {
+                tracing::debug!(
+                    "Will clear HW watchpoint #{} at address {:#08x}",
+                    wp_position,
+                    address
+                );
+                self.inner.clear_hw_watchpoint(wp_position)
+            }
+            None => Err(error::Error::Other(anyhow!(
+                "No watchpoint found at address {:#010x}",
+                address
+            ))),
+        }
+    }
+
+    /// Clear all hardware watchpoints
+    ///
+    /// This function will clear all HW watchpoints which are configured on the target,
+    /// regardless if they are set by probe-rs or not.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_all_hw_watchpoints(&mut self) -> Result<(), error::Error> {
+        for (unit_index, watchpoint) in self.inner.hw_watchpoints()?.into_iter().enumerate() {
+            if watchpoint.is_some() {
+                self.inner.clear_hw_watchpoint(unit_index)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls the function at `address` on the target, and returns the value it returned.
+    ///
+    /// The `arguments` are passed in the argument registers of the core, so only functions with
+    /// register sized arguments can be called, e.g. up to four arguments on 32-bit ARM cores.
+    /// The function runs on the current stack, and returns to the current program counter, where
+    /// a breakpoint halts the core again. If the function does not return within `timeout`, the core is halted.
+    ///
+    /// The core has to be halted. All registers are restored afterwards, so the core can be resumed
+    /// as if the function was never called. Changes of the function to the memory are kept.
+    /// If the function does not return and the core cannot be halted either, the registers are
+    /// not restored and the error is returned.
+    #[tracing::instrument(skip(self))]
+    pub fn call_function(
+        &mut self,
+        address: u64,
+        arguments: &[registers::RegisterValue],
+        timeout: Duration,
+    ) -> Result<registers::RegisterValue, error::Error> {
+        if !self.core_halted()? {
+            return Err(error::Error::Other(anyhow!(
+                "The core has to be halted to call a function"
+            )));
+        }
+
+        // Save the register context. Not all registers of a register file are
+        // always available, e.g. the FPU registers of a core without FPU.
+        let mut context = Vec::new();
+        for register in self.registers().all_registers() {
+            if let Ok(value) = self.read_core_reg::<registers::RegisterValue>(register) {
+                context.push((register, value));
+            }
+        }
+
+        let result = self.call_function_with_context_saved(address, arguments, timeout);
+
+        // The registers can only be restored if the core is halted again, which it is not
+        // if the function did not return and halting the core failed as well.
+        if !matches!(self.core_halted(), Ok(true)) {
+            result?;
+
+            return Err(error::Error::Other(anyhow!(
+                "The core is not halted after calling the function at {:#010x}",
+                address
+            )));
+        }
+
+        // Restore the register context, also if the call failed. Only registers which
+        // were changed are written, as some registers can not be written at all.
+        if let Err(restore_error) = self.restore_registers(&context) {
+            match result {
+                Ok(_) => return Err(restore_error),
+                Err(_) => tracing::warn!(
+                    "Unable to restore the registers after calling the function at {:#010x}: {}",
+                    address,
+                    restore_error
+                ),
+            }
+        }
+
+        result
+    }
+
+    fn restore_registers(
+        &mut self,
+        context: &[(&CoreRegister, registers::RegisterValue)],
+    ) -> Result<(), error::Error> {
+        for (register, value) in context {
+            if self.read_core_reg::<registers::RegisterValue>(*register)? != *value {
+                self.write_core_reg(register.id(), *value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn call_function_with_context_saved(
+        &mut self,
+        address: u64,
+        arguments: &[registers::RegisterValue],
+        timeout: Duration,
+    ) -> Result<registers::RegisterValue, error::Error> {
+        // The function returns to the current program counter.
+        let return_address: u64 = self.read_core_reg(self.program_counter())?;
+
+        // The calling conventions require the stack pointer to be aligned
+        // to 8 bytes on 32-bit ARM cores, and to 16 bytes otherwise.
+        let stack_alignment = match self.instruction_set()? {
+            InstructionSet::Thumb2 | InstructionSet::A32 => 8,
+            _ => 16,
+        };
+        let stack_pointer: u64 = self.read_core_reg(self.stack_pointer())?;
+        self.write_core_reg(
+            self.stack_pointer().id(),
+            stack_pointer & !(stack_alignment - 1),
+        )?;
+
+        self.prepare_function_call(address, arguments, return_address)?;
+
+        // Use an existing breakpoint at the return address, if there is one.
+        let needs_breakpoint = !self.state.sw_breakpoints.contains_key(&return_address)
+            && !self.inner.hw_breakpoints()?.contains(&Some(return_address));
+
+        if needs_breakpoint {
+            self.set_breakpoint(return_address)?;
+        }
+
+        let result = self.run_function(address, timeout);
+
+        // Clear the temporary breakpoint on every exit path, without hiding why the call failed.
+        if needs_breakpoint {
+            if let Err(clear_error) = self.clear_breakpoint(return_address) {
+                match result {
+                    Ok(()) => return Err(clear_error),
+                    Err(_) => tracing::warn!(
+                        "Unable to clear the breakpoint at the return address {:#010x}: {}",
+                        return_address,
+                        clear_error
+                    ),
+                }
+            }
+        }
+
+        result?;
+
+        let pc: u64 = self.read_core_reg(self.program_counter())?;
+        if pc != return_address {
+            return Err(error::Error::Other(anyhow!(
+                "The core halted at {:#010x} before the function at {:#010x} returned",
+                pc,
+                address
+            )));
+        }
+
+        let result_register = self.registers().get_result_register(0).ok_or_else(|| {
+            error::Error::Other(anyhow!("The core has no register for return values"))
+        })?;
+
+        self.read_core_reg(result_register)
+    }
+
+    /// Writes the `arguments` to the argument registers and points the program counter to the
+    /// function at `address`, which returns to `return_address`.
+    ///
+    /// The function starts when the core is resumed. The stack pointer is not changed.
+    pub(crate) fn prepare_function_call(
+        &mut self,
+        address: u64,
+        arguments: &[registers::RegisterValue],
+        return_address: u64,
+    ) -> Result<(), error::Error> {
+        let registers = self.registers();
+
+        for (index, argument) in arguments.iter().enumerate() {
+            let register = registers.get_argument_register(index).ok_or_else(|| {
+                error::Error::Other(anyhow!(
+                    "Functions can only be called with up to {} arguments",
+                    index
+                ))
+            })?;
+
+            self.write_core_reg(register.id(), *argument)?;
+        }
+
+        if self.instruction_set()? == InstructionSet::Thumb2 {
+            // For ARM Thumb code, the return address has to be odd to stay in Thumb mode,
+            // while the program counter is always even.
+            self.write_core_reg(self.return_address().id(), return_address | 1)?;
+            self.write_core_reg(self.program_counter().id(), address & !1)?;
+        } else {
+            self.write_core_reg(self.return_address().id(), return_address)?;
+            self.write_core_reg(self.program_counter().id(), address)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the core until the called function returns, and halts it if that takes longer than `timeout`.
+    fn run_function(&mut self, address: u64, timeout: Duration) -> Result<(), error::Error> {
+        self.run()?;
+
+        if let Err(error) = self.wait_for_core_halted(timeout) {
+            self.halt(Duration::from_millis(100)).map_err(|halt_error| {
+                error::Error::Other(anyhow!(
+                    "The function at {:#010x} did not return within {:?}, and the core could not be halted: {}",
+                    address,
+                    timeout,
+                    halt_error
+                ))
+            })?;
+
+            return Err(error::Error::Other(anyhow!(
+                "The function at {:#010x} did not return within {:?}: {}",
+                address,
+                timeout,
+                error
+            )));
+        }
+
+        Ok(())
+    }
+
     /// Returns the architecture of the core.
     pub fn architecture(&self) -> Architecture {
         self.inner.architecture()
@@ -542,6 +1295,24 @@ impl<'probe> Core<'probe> {
         self.inner.fpu_support()
     }
 
+    /// Configure the core to halt when `condition` occurs.
+    ///
+    /// When the catch fires, [`Core::status`] reports [`HaltReason::VectorCatch`] with the condition
+    /// which caused the halt, if the core is able to tell.
+    ///
+    /// Which conditions are supported is architecture specific, an error is returned
+    /// for conditions which are not supported by the core.
+    #[tracing::instrument(skip(self))]
+    pub fn set_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.inner.enable_vector_catch(condition)
+    }
+
+    /// Configure the core to no longer halt when `condition` occurs.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_vector_catch(&mut self, condition: VectorCatchCondition) -> Result<(), Error> {
+        self.inner.disable_vector_catch(condition)
+    }
+
     pub(crate) fn reset_catch_clear(&mut self) -> Result<(), Error> {
         self.inner.reset_catch_clear()
     }
@@ -573,3 +1344,45 @@ impl std::fmt::Debug for ResolvedCoreOptions {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sw_breakpoint_instructions() {
+        assert_eq!(
+            sw_breakpoint_instruction(CoreType::Armv7m, InstructionSet::Thumb2, &[0, 0]).unwrap(),
+            &[0x00, 0xbe]
+        );
+        assert_eq!(
+            sw_breakpoint_instruction(CoreType::Armv8a, InstructionSet::A64, &[0; 4]).unwrap(),
+            &[0x00, 0x00, 0x40, 0xd4]
+        );
+        // A compressed instruction is replaced by C.EBREAK, a full one by EBREAK.
+        assert_eq!(
+            sw_breakpoint_instruction(CoreType::Riscv, InstructionSet::RV32C, &[0x01, 0x45])
+                .unwrap(),
+            &[0x02, 0x90]
+        );
+        assert_eq!(
+            sw_breakpoint_instruction(CoreType::Riscv, InstructionSet::RV32C, &[0x13, 0x05])
+                .unwrap(),
+            &[0x73, 0x00, 0x10, 0x00]
+        );
+    }
+
+    #[test]
+    fn sw_breakpoints_only_in_ram() {
+        // Two RAM regions, e.g. a DTCM and an SRAM.
+        let ram = [0x1000_0000..0x1000_8000, 0x2000_0000..0x2001_0000];
+
+        assert!(in_ranges(&ram, 0x1000_0000, 4));
+        assert!(in_ranges(&ram, 0x2000_0000, 2));
+        assert!(in_ranges(&ram, 0x2000_fffc, 4));
+        // Flash, and an instruction which crosses the end of the RAM.
+        assert!(!in_ranges(&ram, 0x0800_0000, 2));
+        assert!(!in_ranges(&ram, 0x2000_fffe, 4));
+        assert!(!in_ranges(&[], 0x2000_0000, 2));
+    }
+}
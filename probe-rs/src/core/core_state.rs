@@ -1,15 +1,18 @@
 use crate::{
     architecture::{
         arm::{
-            ap::MemoryAp,
+            ap::{AccessPort, MemoryAp},
             core::{CortexAState, CortexMState},
-            ApAddress, ArmProbeInterface, DpAddress,
+            ApAddress, ArmError, ArmProbeInterface, DpAddress,
         },
         riscv::{communication_interface::RiscvCommunicationInterface, RiscVState},
     },
-    Core, CoreType, Error,
+    core::dump::{CoreDump, DumpCore},
+    Core, CoreType, Error, ResetType,
 };
 pub use probe_rs_target::{Architecture, CoreAccessOptions};
+use std::collections::BTreeMap;
+use std::ops::Range;
 
 use super::ResolvedCoreOptions;
 
@@ -38,7 +41,7 @@ impl CombinedCoreState {
         let memory = arm_interface.memory_interface(self.arm_memory_ap())?;
 
         let (options, debug_sequence) = match &self.core_state.core_access_options {
-            ResolvedCoreOptions::Arm { options, sequence } => (options, sequence.clone()),
+            ResolvedCoreOptions::Arm { options, sequence } => (options.clone(), sequence.clone()),
             ResolvedCoreOptions::Riscv { .. } => {
                 return Err(Error::UnableToOpenProbe(
                     "Core architecture and Probe mismatch.",
@@ -49,31 +52,36 @@ impl CombinedCoreState {
         Ok(match &mut self.specific_state {
             SpecificCoreState::Armv6m(s) => Core::new(
                 crate::architecture::arm::armv6m::Armv6m::new(memory, s, debug_sequence, self.id)?,
+                &mut self.core_state,
             ),
-            SpecificCoreState::Armv7a(s) => {
-                Core::new(crate::architecture::arm::armv7a::Armv7a::new(
+            SpecificCoreState::Armv7a(s) => Core::new(
+                crate::architecture::arm::armv7a::Armv7a::new(
                     memory,
                     s,
                     options.debug_base.expect("base_address not specified"),
                     debug_sequence,
                     self.id,
-                )?)
-            }
+                )?,
+                &mut self.core_state,
+            ),
             SpecificCoreState::Armv7m(s) | SpecificCoreState::Armv7em(s) => Core::new(
                 crate::architecture::arm::armv7m::Armv7m::new(memory, s, debug_sequence, self.id)?,
+                &mut self.core_state,
             ),
-            SpecificCoreState::Armv8a(s) => {
-                Core::new(crate::architecture::arm::armv8a::Armv8a::new(
+            SpecificCoreState::Armv8a(s) => Core::new(
+                crate::architecture::arm::armv8a::Armv8a::new(
                     memory,
                     s,
                     options.debug_base.expect("base_address not specified"),
                     options.cti_base.expect("cti_address not specified"),
                     debug_sequence,
                     self.id,
-                )?)
-            }
+                )?,
+                &mut self.core_state,
+            ),
             SpecificCoreState::Armv8m(s) => Core::new(
                 crate::architecture::arm::armv8m::Armv8m::new(memory, s, debug_sequence, self.id)?,
+                &mut self.core_state,
             ),
             _ => {
                 return Err(Error::UnableToOpenProbe(
@@ -140,9 +148,10 @@ impl CombinedCoreState {
         interface: &'probe mut RiscvCommunicationInterface,
     ) -> Result<Core<'probe>, Error> {
         Ok(match &mut self.specific_state {
-            SpecificCoreState::Riscv(s) => Core::new(crate::architecture::riscv::Riscv32::new(
-                interface, s, self.id,
-            )),
+            SpecificCoreState::Riscv(s) => Core::new(
+                crate::architecture::riscv::Riscv32::new(interface, s, self.id),
+                &mut self.core_state,
+            ),
             _ => {
                 return Err(Error::UnableToOpenProbe(
                     "Core architecture and Probe mismatch.",
@@ -151,6 +160,19 @@ impl CombinedCoreState {
         })
     }
 
+    pub(crate) fn attach_core_dump<'probe>(
+        &'probe mut self,
+        dump: &'probe CoreDump,
+    ) -> Result<Core<'probe>, Error> {
+        let core = dump
+            .cores
+            .iter()
+            .find(|core| core.index == self.id)
+            .ok_or(Error::CoreNotFound(self.id))?;
+
+        Ok(Core::new(DumpCore::new(dump, core), &mut self.core_state))
+    }
+
     /// Get the memory AP for this core.
     ///
     /// ## Panic
@@ -159,6 +181,23 @@ impl CombinedCoreState {
     pub(crate) fn arm_memory_ap(&self) -> MemoryAp {
         self.core_state.memory_ap()
     }
+
+    /// Get the base address of the Cross Trigger Interface (CTI) of this core, if it has one.
+    pub(crate) fn arm_cti_base(&self) -> Option<u64> {
+        match &self.core_state.core_access_options {
+            ResolvedCoreOptions::Arm { options, .. } => options.cti_base,
+            ResolvedCoreOptions::Riscv { .. } => None,
+        }
+    }
+}
+
+/// A software breakpoint which was patched into the memory of the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SoftwareBreakpoint {
+    /// The instruction which was replaced by the breakpoint instruction.
+    pub(crate) original_instruction: Vec<u8>,
+    /// The breakpoint instruction which was written to memory.
+    pub(crate) breakpoint_instruction: Vec<u8>,
 }
 
 /// A generic core state which caches the generic parts of the core state.
@@ -166,6 +205,16 @@ impl CombinedCoreState {
 pub struct CoreState {
     /// Information needed to access the core
     core_access_options: ResolvedCoreOptions,
+
+    /// The software breakpoints which are currently set, by address.
+    pub(crate) sw_breakpoints: BTreeMap<u64, SoftwareBreakpoint>,
+
+    /// The reset used by [`Core::reset`] and [`Core::reset_and_halt`].
+    pub(crate) reset_type: ResetType,
+
+    /// The address ranges of the RAM which is accessible by the core, where software
+    /// breakpoints can be set.
+    pub(crate) ram_ranges: Vec<Range<u64>>,
 }
 
 impl CoreState {
@@ -173,6 +222,9 @@ impl CoreState {
     pub fn new(core_access_options: ResolvedCoreOptions) -> Self {
         Self {
             core_access_options,
+            sw_breakpoints: BTreeMap::new(),
+            reset_type: ResetType::default(),
+            ram_ranges: Vec::new(),
         }
     }
 
@@ -196,6 +248,29 @@ impl CoreState {
 
         MemoryAp::new(ap)
     }
+
+    /// Finds the number of the memory AP of the core, if it is given by its address.
+    ///
+    /// This is needed for cores behind ADIv6 debug ports, whose APs are numbered when they are discovered.
+    pub(crate) fn resolve_arm_ap_address(
+        &mut self,
+        interface: &mut dyn ArmProbeInterface,
+    ) -> Result<(), ArmError> {
+        if let ResolvedCoreOptions::Arm { options, .. } = &mut self.core_access_options {
+            if let Some(address) = options.ap_address {
+                let dp = match options.psel {
+                    0 => DpAddress::Default,
+                    x => DpAddress::Multidrop(x),
+                };
+
+                let ap = interface.access_port_at_address(dp, address)?;
+                tracing::debug!("The AP at {:#x} is AP {}", address, ap.ap_address().ap);
+                options.ap = ap.ap_address().ap;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// The architecture specific core state.
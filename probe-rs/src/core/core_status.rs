@@ -1,5 +1,5 @@
 /// The status of the core.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum CoreStatus {
     /// The core is currently running.
     Running,
@@ -26,7 +26,7 @@ impl CoreStatus {
 }
 
 /// When the core halts due to a breakpoint request, some architectures will allow us to distinguish between a software and hardware breakpoint.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum BreakpointCause {
     /// We encountered a hardware breakpoint.
     Hardware,
@@ -36,8 +36,32 @@ pub enum BreakpointCause {
     Unknown,
 }
 
+/// An exception on which the core can be configured to halt, using a vector catch.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub enum VectorCatchCondition {
+    /// Halt when the core is reset, before executing the first instruction.
+    CoreReset,
+    /// Halt on a HardFault exception.
+    HardFault,
+    /// Halt on a MemManage exception.
+    MemManage,
+    /// Halt on a BusFault exception.
+    BusFault,
+    /// Halt on a UsageFault exception.
+    UsageFault,
+    /// Halt on a fault occurring during exception entry or exception return.
+    InterruptError,
+    /// Halt on a SecureFault exception.
+    SecureFault,
+    /// Halt on all of the conditions above which are supported by the core.
+    ///
+    /// This is only used to configure the vector catch, and is never reported
+    /// as the reason for a halt.
+    All,
+}
+
 /// The reason why a core was halted.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum HaltReason {
     /// Multiple reasons for a halt.
     ///
@@ -50,6 +74,9 @@ pub enum HaltReason {
     /// Core halted due to an exception, e.g. an
     /// an interrupt.
     Exception,
+    /// Core halted due to a vector catch, i.e. when entering the handler of an
+    /// exception which was configured using [`Core::set_vector_catch`](crate::Core::set_vector_catch).
+    VectorCatch(VectorCatchCondition),
     /// Core halted due to a data watchpoint
     Watchpoint,
     /// Core halted after single step
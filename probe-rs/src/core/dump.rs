@@ -0,0 +1,881 @@
+//! ELF core dumps of a halted target.
+//!
+//! A core dump contains the registers of every core and a snapshot of the target memory.
+//! It is stored as an ELF core file:
+//!
+//! - Each memory range is stored in a `PT_LOAD` segment.
+//! - The registers of each core are stored in a `PT_NOTE` segment, as a `probe-rs` note with all registers
+//!   described by [`CoreInterface::registers()`](crate::CoreInterface::registers).
+//! - For 32-bit ARM cores, an additional `NT_PRSTATUS` note in the layout used by Linux allows GDB
+//!   to show the general purpose registers of the dump.
+//!
+//! A stored core dump can be loaded again with [`CoreDump::load`], and inspected without any hardware
+//! using [`Session::from_core_dump`](crate::Session::from_core_dump).
+
+use crate::{
+    architecture::{
+        arm::core::registers::{
+            aarch32::{
+                AARCH32_CORE_REGSISTERS, AARCH32_WITH_FP_16_CORE_REGSISTERS,
+                AARCH32_WITH_FP_32_CORE_REGSISTERS,
+            },
+            aarch64::AARCH64_CORE_REGSISTERS,
+            cortex_m::{CORTEX_M_CORE_REGSISTERS, CORTEX_M_WITH_FP_CORE_REGSISTERS, XPSR},
+        },
+        riscv::registers::RISCV_CORE_REGSISTERS,
+    },
+    core::{
+        Architecture, CoreInformation, CoreInterface, CoreRegister, CoreRegisters, CoreStatus,
+        HaltReason, RegisterRole, VectorCatchCondition, Watchpoint, WatchpointKind,
+    },
+    CoreType, Error, InstructionSet, MemoryInterface, RegisterId, RegisterValue, ResetType,
+};
+use object::{
+    elf::{FileHeader32, FileHeader64},
+    read::elf::{FileHeader, ProgramHeader},
+    Endianness, FileKind,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs::File, io::Write, ops::Range, path::Path, time::Duration};
+
+/// The name of the notes containing the probe-rs register sets.
+const PROBE_RS_NOTE_NAME: &[u8] = b"probe-rs";
+/// The type of the notes containing the probe-rs register sets.
+const NT_PROBE_RS_REGISTERS: u32 = 0x5052_0001;
+/// The name of the notes in the layout used by Linux.
+const CORE_NOTE_NAME: &[u8] = b"CORE";
+/// The type of the note containing the general purpose registers.
+const NT_PRSTATUS: u32 = 1;
+
+const ET_CORE: u16 = 4;
+const EM_ARM: u16 = 40;
+const EM_AARCH64: u16 = 183;
+const EM_RISCV: u16 = 243;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_R: u32 = 4;
+const PF_W: u32 = 2;
+/// The EABI version 5 flag expected by tools in 32-bit ARM ELF files.
+const EF_ARM_EABI_VER5: u32 = 0x0500_0000;
+
+/// The offset of `pr_pid` in the ARM `elf_prstatus` structure.
+const PRSTATUS_PID_OFFSET: usize = 24;
+/// The offset of `pr_reg` in the ARM `elf_prstatus` structure.
+const PRSTATUS_REG_OFFSET: usize = 72;
+/// The size of the ARM `elf_prstatus` structure.
+const PRSTATUS_SIZE: usize = 148;
+
+/// An error which occurred while storing or loading a core dump.
+#[derive(thiserror::Error, Debug)]
+pub enum CoreDumpError {
+    /// The core dump file could not be written or read.
+    #[error("Failed to access the core dump file")]
+    Io(#[from] std::io::Error),
+    /// The register set of a core could not be encoded or decoded.
+    #[error("Failed to encode or decode the registers of a core")]
+    Registers(#[from] bincode::Error),
+    /// A core dump has to contain at least one core.
+    #[error("The core dump does not contain any cores")]
+    NoCores,
+    /// The file is not a valid ELF core file.
+    #[error("The core dump is not a valid ELF core file")]
+    InvalidElf(#[from] object::read::Error),
+    /// The contents of a memory segment are missing from the file.
+    #[error(
+        "The contents of the memory segment at {address:#010x} are missing from the core dump"
+    )]
+    TruncatedSegment {
+        /// The start address of the segment.
+        address: u64,
+    },
+    /// The requested memory was not captured in the core dump.
+    #[error("The memory at {address:#010x} with length {length} is not part of the core dump")]
+    MemoryNotCaptured {
+        /// The start address of the access.
+        address: u64,
+        /// The length of the access in bytes.
+        length: usize,
+    },
+    /// The requested register was not captured in the core dump.
+    #[error("Register {0:?} is not part of the core dump")]
+    RegisterNotCaptured(RegisterId),
+    /// The target of a core dump can only be inspected.
+    #[error("The target of a core dump can not be modified or resumed")]
+    ReadOnly,
+}
+
+/// The registers of a single core, captured in a [`CoreDump`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoreDumpCore {
+    /// The index of the core in the target description.
+    pub index: usize,
+    /// The type of the core.
+    pub core_type: CoreType,
+    /// The value of every register which could be read.
+    pub registers: Vec<(RegisterId, RegisterValue)>,
+}
+
+impl CoreDumpCore {
+    /// Returns the value of a register, if it is part of the dump.
+    pub fn register(&self, id: RegisterId) -> Option<RegisterValue> {
+        self.registers
+            .iter()
+            .find(|(register, _)| *register == id)
+            .map(|(_, value)| *value)
+    }
+
+    /// Encodes the general purpose registers in an ARM `elf_prstatus` structure.
+    ///
+    /// Returns `None` for cores which are not 32-bit ARM cores.
+    fn arm_prstatus(&self) -> Option<Vec<u8>> {
+        if !matches!(
+            self.core_type,
+            CoreType::Armv6m
+                | CoreType::Armv7m
+                | CoreType::Armv7em
+                | CoreType::Armv8m
+                | CoreType::Armv7a
+        ) {
+            return None;
+        }
+
+        let mut prstatus = vec![0u8; PRSTATUS_SIZE];
+
+        // GDB uses the PID to tell the threads apart, and PID 0 is not a valid thread.
+        let pid = self.index as u32 + 1;
+        prstatus[PRSTATUS_PID_OFFSET..][..4].copy_from_slice(&pid.to_le_bytes());
+
+        // R0 to R15 and the status register, XPSR on Cortex-M and CPSR on Cortex-A, which share
+        // their register ID. The last entry, ORIG_R0, stays zero.
+        let ids = (0..16).map(RegisterId).chain(std::iter::once(XPSR.id));
+        for (slot, id) in ids.enumerate() {
+            let value = self
+                .register(id)
+                .and_then(|value| TryInto::<u32>::try_into(value).ok())
+                .unwrap_or(0);
+
+            prstatus[PRSTATUS_REG_OFFSET + 4 * slot..][..4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        Some(prstatus)
+    }
+}
+
+/// A snapshot of the registers and memory of a halted target.
+///
+/// A core dump is usually created using [`Session::create_core_dump`](crate::Session::create_core_dump).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreDump {
+    /// The registers of each core.
+    pub cores: Vec<CoreDumpCore>,
+    /// The captured memory, as a list of start addresses and contents.
+    pub memory: Vec<(u64, Vec<u8>)>,
+}
+
+impl CoreDump {
+    /// Reads a core dump from the ELF core file at `path`.
+    pub fn load(path: &Path) -> Result<Self, CoreDumpError> {
+        let data = std::fs::read(path)?;
+
+        Self::from_elf(&data)
+    }
+
+    /// Decodes a core dump from an ELF core file created by [`CoreDump::to_elf`].
+    pub fn from_elf(data: &[u8]) -> Result<Self, CoreDumpError> {
+        match FileKind::parse(data)? {
+            FileKind::Elf64 => Self::from_elf_file::<FileHeader64<Endianness>>(data),
+            _ => Self::from_elf_file::<FileHeader32<Endianness>>(data),
+        }
+    }
+
+    fn from_elf_file<Elf: FileHeader<Endian = Endianness>>(
+        data: &[u8],
+    ) -> Result<Self, CoreDumpError> {
+        let header = Elf::parse(data)?;
+        let endian = header.endian()?;
+
+        let mut cores = Vec::new();
+        let mut memory = Vec::new();
+
+        for segment in header.program_headers(endian, data)? {
+            match segment.p_type(endian) {
+                PT_LOAD => {
+                    let address = segment.p_vaddr(endian).into();
+                    let contents = segment
+                        .data(endian, data)
+                        .map_err(|()| CoreDumpError::TruncatedSegment { address })?;
+
+                    memory.push((address, contents.to_vec()));
+                }
+                PT_NOTE => {
+                    if let Some(mut notes) = segment.notes(endian, data)? {
+                        while let Some(note) = notes.next()? {
+                            if note.name() == PROBE_RS_NOTE_NAME
+                                && note.n_type(endian) == NT_PROBE_RS_REGISTERS
+                            {
+                                cores.push(bincode::deserialize(note.desc())?);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if cores.is_empty() {
+            return Err(CoreDumpError::NoCores);
+        }
+
+        Ok(Self { cores, memory })
+    }
+
+    /// Reads memory from the captured memory ranges.
+    ///
+    /// The whole range has to be part of a single captured memory range.
+    pub fn read_memory(&self, address: u64, data: &mut [u8]) -> Result<(), CoreDumpError> {
+        let end = address + data.len() as u64;
+
+        let (start, contents) = self
+            .memory
+            .iter()
+            .find(|(start, contents)| *start <= address && end <= *start + contents.len() as u64)
+            .ok_or(CoreDumpError::MemoryNotCaptured {
+                address,
+                length: data.len(),
+            })?;
+
+        let offset = (address - start) as usize;
+        data.copy_from_slice(&contents[offset..][..data.len()]);
+
+        Ok(())
+    }
+
+    /// Writes the core dump to the file at `path`, as an ELF core file.
+    pub fn store(&self, path: &Path) -> Result<(), CoreDumpError> {
+        let elf = self.to_elf()?;
+
+        let mut file = File::create(path)?;
+        file.write_all(&elf)?;
+
+        Ok(())
+    }
+
+    /// Encodes the core dump as an ELF core file.
+    pub fn to_elf(&self) -> Result<Vec<u8>, CoreDumpError> {
+        let first_core = self.cores.first().ok_or(CoreDumpError::NoCores)?;
+
+        let (machine, flags, is_64_bit) = match first_core.core_type {
+            CoreType::Armv8a => (EM_AARCH64, 0, true),
+            CoreType::Riscv => (EM_RISCV, 0, false),
+            _ => (EM_ARM, EF_ARM_EABI_VER5, false),
+        };
+
+        let mut notes = Vec::new();
+        for core in &self.cores {
+            if let Some(prstatus) = core.arm_prstatus() {
+                write_note(&mut notes, CORE_NOTE_NAME, NT_PRSTATUS, &prstatus);
+            }
+
+            let registers = bincode::serialize(core)?;
+            write_note(
+                &mut notes,
+                PROBE_RS_NOTE_NAME,
+                NT_PROBE_RS_REGISTERS,
+                &registers,
+            );
+        }
+
+        let mut elf = ElfWriter::new(is_64_bit);
+
+        let program_header_count = 1 + self.memory.len();
+        let mut offset =
+            (elf.header_size() + program_header_count * elf.program_header_size()) as u64;
+
+        elf.file_header(machine, flags, program_header_count as u16);
+
+        elf.program_header(PT_NOTE, 0, offset, 0, notes.len() as u64);
+        offset += notes.len() as u64;
+
+        for (address, data) in &self.memory {
+            elf.program_header(PT_LOAD, PF_R | PF_W, offset, *address, data.len() as u64);
+            offset += data.len() as u64;
+        }
+
+        elf.bytes(&notes);
+        for (_, data) in &self.memory {
+            elf.bytes(data);
+        }
+
+        Ok(elf.finish())
+    }
+}
+
+/// A core of a [`CoreDump`], which can be inspected like a halted core of a real target.
+///
+/// Registers and memory are read from the core dump, and all attempts to modify or resume the core fail.
+pub(crate) struct DumpCore<'dump> {
+    dump: &'dump CoreDump,
+    core: &'dump CoreDumpCore,
+    registers: &'static CoreRegisters,
+}
+
+impl<'dump> DumpCore<'dump> {
+    pub(crate) fn new(dump: &'dump CoreDump, core: &'dump CoreDumpCore) -> Self {
+        let candidates: Vec<&'static CoreRegisters> = match core.core_type {
+            CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => {
+                vec![&CORTEX_M_CORE_REGSISTERS, &CORTEX_M_WITH_FP_CORE_REGSISTERS]
+            }
+            CoreType::Armv7a => vec![
+                &AARCH32_CORE_REGSISTERS,
+                &AARCH32_WITH_FP_16_CORE_REGSISTERS,
+                &AARCH32_WITH_FP_32_CORE_REGSISTERS,
+            ],
+            CoreType::Armv8a => vec![
+                &AARCH32_CORE_REGSISTERS,
+                &AARCH32_WITH_FP_16_CORE_REGSISTERS,
+                &AARCH32_WITH_FP_32_CORE_REGSISTERS,
+                &AARCH64_CORE_REGSISTERS,
+            ],
+            CoreType::Riscv => vec![&RISCV_CORE_REGSISTERS],
+        };
+
+        // The register file the core used when the dump was created is the largest one
+        // whose registers were all captured.
+        let registers = candidates
+            .iter()
+            .rev()
+            .find(|registers| {
+                registers
+                    .all_registers()
+                    .all(|register| core.register(register.id()).is_some())
+            })
+            .unwrap_or(&candidates[0]);
+
+        Self {
+            dump,
+            core,
+            registers,
+        }
+    }
+
+    fn register_with_role(&self, role: RegisterRole) -> &'static CoreRegister {
+        self.registers
+            .all_registers()
+            .find(|register| register.register_has_role(role))
+            .expect("Every register file contains the program counter, stack pointer, frame pointer and return address registers.")
+    }
+
+    fn read_memory(&self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        Ok(self.dump.read_memory(address, data)?)
+    }
+
+    fn read_words<const N: usize>(&self, address: u64) -> Result<[u8; N], Error> {
+        let mut bytes = [0u8; N];
+        self.read_memory(address, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<'dump> MemoryInterface for DumpCore<'dump> {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        true
+    }
+
+    fn read_word_64(&mut self, address: u64) -> Result<u64, Error> {
+        self.read_words(address).map(u64::from_le_bytes)
+    }
+
+    fn read_word_32(&mut self, address: u64) -> Result<u32, Error> {
+        self.read_words(address).map(u32::from_le_bytes)
+    }
+
+    fn read_word_16(&mut self, address: u64) -> Result<u16, Error> {
+        self.read_words(address).map(u16::from_le_bytes)
+    }
+
+    fn read_word_8(&mut self, address: u64) -> Result<u8, Error> {
+        self.read_words(address).map(u8::from_le_bytes)
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), Error> {
+        for (i, word) in data.iter_mut().enumerate() {
+            *word = self.read_word_64(address + 8 * i as u64)?;
+        }
+        Ok(())
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        for (i, word) in data.iter_mut().enumerate() {
+            *word = self.read_word_32(address + 4 * i as u64)?;
+        }
+        Ok(())
+    }
+
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        for (i, word) in data.iter_mut().enumerate() {
+            *word = self.read_word_16(address + 2 * i as u64)?;
+        }
+        Ok(())
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.read_memory(address, data)
+    }
+
+    fn read(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.read_memory(address, data)
+    }
+
+    fn write_word_64(&mut self, _address: u64, _data: u64) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn write_word_32(&mut self, _address: u64, _data: u32) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn write_word_16(&mut self, _address: u64, _data: u16) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn write_word_8(&mut self, _address: u64, _data: u8) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn write_64(&mut self, _address: u64, _data: &[u64]) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn write_32(&mut self, _address: u64, _data: &[u32]) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn write_16(&mut self, _address: u64, _data: &[u16]) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn write_8(&mut self, _address: u64, _data: &[u8]) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn write(&mut self, _address: u64, _data: &[u8]) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn supports_8bit_transfers(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'dump> CoreInterface for DumpCore<'dump> {
+    fn id(&self) -> usize {
+        self.core.index
+    }
+
+    fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        Ok(CoreStatus::Halted(HaltReason::Request))
+    }
+
+    fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        let pc = self.read_core_reg(self.program_counter().id())?;
+
+        Ok(CoreInformation { pc: pc.try_into()? })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn reset(&mut self, _reset_type: ResetType) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn reset_and_halt(
+        &mut self,
+        _timeout: Duration,
+        _reset_type: ResetType,
+    ) -> Result<CoreInformation, Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn read_core_reg(&mut self, address: RegisterId) -> Result<RegisterValue, Error> {
+        Ok(self
+            .core
+            .register(address)
+            .ok_or(CoreDumpError::RegisterNotCaptured(address))?)
+    }
+
+    fn write_core_reg(&mut self, _address: RegisterId, _value: RegisterValue) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn enable_breakpoints(&mut self, _state: bool) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set_hw_breakpoint(&mut self, _unit_index: usize, _addr: u64) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn clear_hw_breakpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn available_watchpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(0)
+    }
+
+    fn hw_watchpoints(&mut self) -> Result<Vec<Option<Watchpoint>>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn set_hw_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _address: u64,
+        _length: u64,
+        _kind: WatchpointKind,
+    ) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn clear_hw_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn registers(&self) -> &'static CoreRegisters {
+        self.registers
+    }
+
+    fn program_counter(&self) -> &'static CoreRegister {
+        self.register_with_role(RegisterRole::ProgramCounter)
+    }
+
+    fn frame_pointer(&self) -> &'static CoreRegister {
+        self.register_with_role(RegisterRole::FramePointer)
+    }
+
+    fn stack_pointer(&self) -> &'static CoreRegister {
+        self.register_with_role(RegisterRole::StackPointer)
+    }
+
+    fn return_address(&self) -> &'static CoreRegister {
+        self.register_with_role(RegisterRole::ReturnAddress)
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        false
+    }
+
+    fn architecture(&self) -> Architecture {
+        self.core.core_type.architecture()
+    }
+
+    fn core_type(&self) -> CoreType {
+        self.core.core_type
+    }
+
+    fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        match self.core.core_type {
+            CoreType::Riscv => Ok(InstructionSet::RV32),
+            CoreType::Armv6m | CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => {
+                Ok(InstructionSet::Thumb2)
+            }
+            CoreType::Armv8a if std::ptr::eq(self.registers, &*AARCH64_CORE_REGSISTERS) => {
+                Ok(InstructionSet::A64)
+            }
+            CoreType::Armv7a | CoreType::Armv8a => {
+                let cpsr: u32 = self.read_core_reg(XPSR.id())?.try_into()?;
+
+                // CPSR bit 5 - T - Thumb mode
+                match (cpsr >> 5) & 1 {
+                    1 => Ok(InstructionSet::Thumb2),
+                    _ => Ok(InstructionSet::A32),
+                }
+            }
+        }
+    }
+
+    fn fpu_support(&mut self) -> Result<bool, Error> {
+        Ok(self.registers.fpu_registers().is_some())
+    }
+
+    fn reset_catch_set(&mut self) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn reset_catch_clear(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn enable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+        Err(CoreDumpError::ReadOnly.into())
+    }
+
+    fn disable_vector_catch(&mut self, _condition: VectorCatchCondition) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn debug_core_stop(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Appends an ELF note, with name and descriptor padded to 4 bytes.
+fn write_note(buffer: &mut Vec<u8>, name: &[u8], note_type: u32, descriptor: &[u8]) {
+    // The name size includes the terminating zero.
+    buffer.extend_from_slice(&(name.len() as u32 + 1).to_le_bytes());
+    buffer.extend_from_slice(&(descriptor.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&note_type.to_le_bytes());
+
+    buffer.extend_from_slice(name);
+    buffer.push(0);
+    pad_to_word(buffer);
+
+    buffer.extend_from_slice(descriptor);
+    pad_to_word(buffer);
+}
+
+fn pad_to_word(buffer: &mut Vec<u8>) {
+    let padding = (4 - buffer.len() % 4) % 4;
+    buffer.resize(buffer.len() + padding, 0);
+}
+
+/// Writes the headers of a little endian ELF file, in the 32-bit or the 64-bit format.
+struct ElfWriter {
+    is_64_bit: bool,
+    buffer: Vec<u8>,
+}
+
+impl ElfWriter {
+    fn new(is_64_bit: bool) -> Self {
+        Self {
+            is_64_bit,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn header_size(&self) -> usize {
+        if self.is_64_bit {
+            64
+        } else {
+            52
+        }
+    }
+
+    fn program_header_size(&self) -> usize {
+        if self.is_64_bit {
+            56
+        } else {
+            32
+        }
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes an address or offset, which is 32 or 64 bits wide depending on the ELF class.
+    fn word(&mut self, value: u64) {
+        if self.is_64_bit {
+            self.buffer.extend_from_slice(&value.to_le_bytes());
+        } else {
+            self.buffer.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+    }
+
+    fn bytes(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    fn file_header(&mut self, machine: u16, flags: u32, program_header_count: u16) {
+        let class = if self.is_64_bit { 2 } else { 1 };
+
+        // Magic, class, little endian data encoding, version, and padding of e_ident.
+        self.bytes(&[0x7f, b'E', b'L', b'F', class, 1, 1]);
+        self.bytes(&[0; 9]);
+
+        self.u16(ET_CORE);
+        self.u16(machine);
+        // e_version
+        self.u32(1);
+        // e_entry
+        self.word(0);
+        // e_phoff
+        self.word(self.header_size() as u64);
+        // e_shoff
+        self.word(0);
+        self.u32(flags);
+        self.u16(self.header_size() as u16);
+        self.u16(self.program_header_size() as u16);
+        self.u16(program_header_count);
+        // e_shentsize, e_shnum and e_shstrndx, there are no sections.
+        self.u16(0);
+        self.u16(0);
+        self.u16(0);
+    }
+
+    fn program_header(
+        &mut self,
+        segment_type: u32,
+        flags: u32,
+        offset: u64,
+        address: u64,
+        size: u64,
+    ) {
+        // The position of the flags differs between the two ELF classes.
+        self.u32(segment_type);
+        if self.is_64_bit {
+            self.u32(flags);
+        }
+        self.word(offset);
+        // p_vaddr and p_paddr
+        self.word(address);
+        self.word(address);
+        // p_filesz and p_memsz
+        self.word(size);
+        self.word(size);
+        if !self.is_64_bit {
+            self.u32(flags);
+        }
+        // p_align
+        self.word(if segment_type == PT_NOTE { 4 } else { 1 });
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+/// Sorts the given ranges and merges overlapping or adjacent ranges, dropping empty ones.
+pub(crate) fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::{merge_ranges, CoreDump, CoreDumpCore};
+    use crate::{CoreType, MemoryInterface, RegisterId, RegisterValue, Session};
+    use object::{Object, ObjectSegment};
+
+    fn test_dump() -> CoreDump {
+        CoreDump {
+            cores: vec![CoreDumpCore {
+                index: 0,
+                core_type: CoreType::Armv7em,
+                registers: vec![
+                    (RegisterId(0), RegisterValue::U32(0x1234_5678)),
+                    (RegisterId(15), RegisterValue::U32(0x0800_0100)),
+                    (RegisterId(0b1_0000), RegisterValue::U32(0x0100_0000)),
+                ],
+            }],
+            memory: vec![(0x2000_0000, vec![1, 2, 3, 4]), (0x2000_1000, vec![5, 6])],
+        }
+    }
+
+    #[test]
+    fn core_dump_is_valid_elf() {
+        let elf = test_dump().to_elf().unwrap();
+
+        let file = object::File::parse(elf.as_slice()).unwrap();
+
+        assert_eq!(file.architecture(), object::Architecture::Arm);
+        assert_eq!(file.kind(), object::ObjectKind::Core);
+
+        let segments = file
+            .segments()
+            .map(|segment| (segment.address(), segment.data().unwrap().to_vec()))
+            .collect::<Vec<_>>();
+
+        // Only the loadable segments are listed, not the notes.
+        assert_eq!(
+            segments,
+            vec![(0x2000_0000, vec![1, 2, 3, 4]), (0x2000_1000, vec![5, 6])]
+        );
+    }
+
+    #[test]
+    fn core_dump_contains_prstatus_registers() {
+        let dump = test_dump();
+        let prstatus = dump.cores[0].arm_prstatus().unwrap();
+
+        assert_eq!(prstatus.len(), 148);
+        // pr_pid
+        assert_eq!(prstatus[24..28], 1u32.to_le_bytes());
+        // R0, PC and CPSR
+        assert_eq!(prstatus[72..76], 0x1234_5678u32.to_le_bytes());
+        assert_eq!(prstatus[132..136], 0x0800_0100u32.to_le_bytes());
+        assert_eq!(prstatus[136..140], 0x0100_0000u32.to_le_bytes());
+    }
+
+    #[test]
+    fn core_dump_round_trip() {
+        let dump = test_dump();
+        let elf = dump.to_elf().unwrap();
+
+        assert_eq!(CoreDump::from_elf(&elf).unwrap(), dump);
+    }
+
+    #[test]
+    fn inspect_core_dump_in_session() {
+        let mut session = Session::from_core_dump(test_dump(), "nrf52840_xxAA").unwrap();
+        let mut core = session.core(0).unwrap();
+
+        assert!(core.core_halted().unwrap());
+
+        let pc: u32 = core.read_core_reg(core.program_counter()).unwrap();
+        assert_eq!(pc, 0x0800_0100);
+
+        assert_eq!(core.read_word_16(0x2000_0002).unwrap(), 0x0403);
+        assert!(core.read_word_32(0x2000_0002).is_err());
+        assert!(core.write_word_8(0x2000_0000, 0).is_err());
+        assert!(core.run().is_err());
+    }
+
+    #[test]
+    fn merge_overlapping_ranges() {
+        assert_eq!(
+            merge_ranges(vec![0x100..0x200, 0x0..0x80, 0x180..0x300, 0x400..0x400]),
+            vec![0x0..0x80, 0x100..0x300]
+        );
+    }
+}
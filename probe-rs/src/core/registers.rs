@@ -2,6 +2,7 @@
 
 use crate::Error;
 use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
     convert::Infallible,
@@ -146,7 +147,7 @@ impl From<&CoreRegister> for RegisterId {
 }
 
 /// The location of a CPU \register. This is not an actual memory address, but a core specific location that represents a specific core register.
-#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Ord, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Ord, Eq, Hash, Serialize, Deserialize)]
 pub struct RegisterId(pub u16);
 
 impl From<RegisterId> for u32 {
@@ -166,7 +167,7 @@ impl From<u16> for RegisterId {
 /// Creating a new `RegisterValue` should be done using From or Into.
 /// Converting a value back to a primitive type can be done with either
 /// a match arm or TryInto
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum RegisterValue {
     /// 32-bit unsigned integer
     U32(u32),
@@ -393,6 +394,11 @@ impl CoreRegisters {
             .cloned()
     }
 
+    /// Returns an iterator over the descriptions of all the registers of this core, including the FPU registers.
+    pub fn all_registers(&self) -> impl Iterator<Item = &CoreRegister> {
+        self.0.iter().cloned()
+    }
+
     /// Returns the nth platform register.
     ///
     /// # Panics
@@ -126,6 +126,29 @@ impl DebugInfo {
         Ok(None)
     }
 
+    /// Get the names of the function at the given address, and of the functions inlined into it.
+    ///
+    /// The names are ordered from the outermost function to the innermost inlined one, so the
+    /// last name is the one returned by [`DebugInfo::function_name`] when inlined functions are
+    /// included. If no function is found, the list is empty.
+    pub fn function_names(&self, address: u64) -> Result<Vec<String>, DebugError> {
+        let mut units = self.dwarf.units();
+
+        while let Some(unit_info) = self.get_next_unit_info(&mut units) {
+            let names: Vec<String> = unit_info
+                .get_function_dies(address, None, true)?
+                .iter()
+                .filter_map(|function| function.function_name())
+                .collect();
+
+            if !names.is_empty() {
+                return Ok(names);
+            }
+        }
+
+        Ok(Vec::new())
+    }
+
     /// Try get the [`SourceLocation`] for a given address.
     pub fn get_source_location(&self, address: u64) -> Option<SourceLocation> {
         let mut units = self.dwarf.units();
@@ -3,6 +3,7 @@
 use crate::architecture::arm::ArmError;
 use crate::architecture::riscv::communication_interface::RiscvError;
 use crate::config::RegistryError;
+use crate::core::dump::CoreDumpError;
 use crate::DebugProbeError;
 
 /// The overarching error type which contains all possible errors as variants.
@@ -35,6 +36,9 @@ pub enum Error {
     /// An error that is not architecture specific occurred.
     #[error("A generic core (not architecture specific) error occurred.")]
     GenericCoreError(String),
+    /// A core dump could not be stored or loaded.
+    #[error("A core dump could not be stored or loaded.")]
+    CoreDump(#[from] CoreDumpError),
     /// Any other error occurred.
     #[error(transparent)]
     Other(#[from] anyhow::Error),
@@ -134,6 +134,34 @@ pub struct DownloadOptions {
     pub verify: bool,
     /// Disable double buffering when loading flash.
     pub disable_double_buffering: bool,
+    /// The access width used to write data into RAM regions.
+    ///
+    /// Some memories, e.g. external memories behind an FSMC, do not accept byte writes.
+    /// Unaligned leading and trailing bytes are always written using 8 bit accesses.
+    pub ram_access_width: MemoryAccessWidth,
+}
+
+/// The width of the memory accesses used to write data to the target.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAccessWidth {
+    /// 8 bit accesses.
+    #[default]
+    Bits8,
+    /// 16 bit accesses.
+    Bits16,
+    /// 32 bit accesses.
+    Bits32,
+}
+
+impl MemoryAccessWidth {
+    /// The number of bytes transferred with a single access.
+    pub fn byte_width(self) -> usize {
+        match self {
+            MemoryAccessWidth::Bits8 => 1,
+            MemoryAccessWidth::Bits16 => 2,
+            MemoryAccessWidth::Bits32 => 4,
+        }
+    }
 }
 
 impl DownloadOptions {
@@ -6,7 +6,7 @@ use super::{
 };
 use crate::config::NvmRegion;
 use crate::memory::MemoryInterface;
-use crate::{core::CoreRegisters, session::Session, Core, InstructionSet};
+use crate::{core::CoreRegisters, session::Session, Core, RegisterValue};
 use std::time::Instant;
 use std::{fmt::Debug, time::Duration};
 
@@ -612,12 +612,17 @@ impl<'probe, O: Operation> ActiveFlasher<'probe, O> {
         let algo = &self.flash_algorithm;
         let regs: &'static CoreRegisters = self.core.registers();
 
+        // The routines are called like any other function, and return to the breakpoint
+        // instruction at the start of the flash algorithm. Their arguments are always
+        // passed in consecutive registers, starting with the first one.
+        let arguments = [registers.r0, registers.r1, registers.r2, registers.r3]
+            .into_iter()
+            .map_while(|argument| argument.map(RegisterValue::from))
+            .collect::<Vec<_>>();
+        self.core
+            .prepare_function_call(registers.pc.into(), &arguments, algo.load_address)?;
+
         let registers = [
-            (self.core.program_counter(), Some(registers.pc)),
-            (regs.argument_register(0), registers.r0),
-            (regs.argument_register(1), registers.r1),
-            (regs.argument_register(2), registers.r2),
-            (regs.argument_register(3), registers.r3),
             (
                 regs.core_register(9),
                 if init {
@@ -634,16 +639,6 @@ impl<'probe, O: Operation> ActiveFlasher<'probe, O> {
                     None
                 },
             ),
-            (
-                self.core.return_address(),
-                // For ARM Cortex-M cores, we have to add 1 to the return address,
-                // to ensure that we stay in Thumb mode.
-                if self.core.instruction_set()? == InstructionSet::Thumb2 {
-                    Some(into_reg(algo.load_address + 1)?)
-                } else {
-                    Some(into_reg(algo.load_address)?)
-                },
-            ),
             // ARMv8m chips are dependent on the MSPLIM register to avoid a UsageFault/HardFault
             if self.core.core_type() == CoreType::Armv8m {
                 (
@@ -10,7 +10,7 @@ use std::str::FromStr;
 use super::builder::FlashBuilder;
 use super::{
     extract_from_elf, BinOptions, DownloadOptions, FileDownloadError, FlashError, Flasher,
-    IdfOptions,
+    IdfOptions, MemoryAccessWidth,
 };
 use crate::memory::MemoryInterface;
 use crate::session::Session;
@@ -416,7 +416,8 @@ impl FlashLoader {
                         data.len()
                     );
                     // Write data to memory.
-                    core.write_8(address, data).map_err(FlashError::Core)?;
+                    write_ram(&mut core, address, data, options.ram_access_width)
+                        .map_err(FlashError::Core)?;
                 }
 
                 if !some {
@@ -515,3 +516,48 @@ impl FlashLoader {
             .map(|(address, data)| (*address, data.as_slice()))
     }
 }
+
+/// Writes `data` to RAM at `address` using accesses of the given `width`.
+///
+/// Bytes before the first and after the last aligned access are written using 8 bit accesses.
+fn write_ram(
+    memory: &mut impl MemoryInterface,
+    address: u64,
+    data: &[u8],
+    width: MemoryAccessWidth,
+) -> Result<(), crate::Error> {
+    let byte_width = width.byte_width();
+    let head_len = ((byte_width - (address as usize % byte_width)) % byte_width).min(data.len());
+    let (head, rest) = data.split_at(head_len);
+    let aligned_len = rest.len() - rest.len() % byte_width;
+    let (aligned, tail) = rest.split_at(aligned_len);
+
+    if !head.is_empty() {
+        memory.write_8(address, head)?;
+    }
+
+    let aligned_address = address + head_len as u64;
+    match width {
+        MemoryAccessWidth::Bits8 => memory.write_8(aligned_address, aligned)?,
+        MemoryAccessWidth::Bits16 => {
+            let words = aligned
+                .chunks_exact(2)
+                .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+                .collect::<Vec<_>>();
+            memory.write_16(aligned_address, &words)?;
+        }
+        MemoryAccessWidth::Bits32 => {
+            let words = aligned
+                .chunks_exact(4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .collect::<Vec<_>>();
+            memory.write_32(aligned_address, &words)?;
+        }
+    }
+
+    if !tail.is_empty() {
+        memory.write_8(aligned_address + aligned_len as u64, tail)?;
+    }
+
+    Ok(())
+}
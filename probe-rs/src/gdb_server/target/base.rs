@@ -0,0 +1,159 @@
+use super::{GdbErrorExt, RuntimeTarget};
+use crate::core::{RegisterId, RegisterValue};
+use crate::{Core, Error, MemoryInterface};
+
+use gdbstub::common::Tid;
+use gdbstub::target::ext::base::multithread::{MultiThreadBase, MultiThreadResumeOps};
+use gdbstub::target::ext::base::single_register_access::{
+    SingleRegisterAccess, SingleRegisterAccessOps,
+};
+use gdbstub::target::ext::thread_extra_info::ThreadExtraInfoOps;
+use gdbstub::target::TargetResult;
+
+use super::super::arch::{RuntimeRegId, RuntimeRegisters};
+
+impl MultiThreadBase for RuntimeTarget<'_> {
+    fn read_registers(&mut self, regs: &mut RuntimeRegisters, tid: Tid) -> TargetResult<(), Self> {
+        let mut session = self.session.lock().unwrap();
+        let mut core = session.core(tid.get() - 1).into_target_result()?;
+
+        regs.pc = core
+            .read_core_reg(core.program_counter())
+            .into_target_result()?;
+
+        regs.regs.clear();
+        for reg in self.target_desc.registers() {
+            let value = read_register(&mut core, reg.id()).into_target_result()?;
+
+            regs.regs
+                .extend_from_slice(&value.to_le_bytes()[..reg.size_in_bytes()]);
+        }
+
+        Ok(())
+    }
+
+    fn write_registers(&mut self, regs: &RuntimeRegisters, tid: Tid) -> TargetResult<(), Self> {
+        let mut session = self.session.lock().unwrap();
+        let mut core = session.core(tid.get() - 1).into_target_result()?;
+
+        let mut bytes = &regs.regs[..];
+        for reg in self.target_desc.registers() {
+            let size = reg.size_in_bytes();
+
+            // GDB may send less registers than it received.
+            if bytes.len() < size {
+                break;
+            }
+
+            write_register(&mut core, reg.id(), &bytes[..size]).into_target_result()?;
+            bytes = &bytes[size..];
+        }
+
+        Ok(())
+    }
+
+    fn support_single_register_access(&mut self) -> Option<SingleRegisterAccessOps<'_, Tid, Self>> {
+        Some(self)
+    }
+
+    fn read_addrs(&mut self, start_addr: u64, data: &mut [u8], tid: Tid) -> TargetResult<(), Self> {
+        let mut session = self.session.lock().unwrap();
+        let mut core = session.core(tid.get() - 1).into_target_result()?;
+
+        core.read(start_addr, data).into_target_result_non_fatal()
+    }
+
+    fn write_addrs(&mut self, start_addr: u64, data: &[u8], tid: Tid) -> TargetResult<(), Self> {
+        let mut session = self.session.lock().unwrap();
+        let mut core = session.core(tid.get() - 1).into_target_result()?;
+
+        core.write_8(start_addr, data)
+            .into_target_result_non_fatal()
+    }
+
+    fn list_active_threads(
+        &mut self,
+        thread_is_active: &mut dyn FnMut(Tid),
+    ) -> Result<(), Self::Error> {
+        for core_id in &self.cores {
+            thread_is_active(Tid::new(core_id + 1).unwrap());
+        }
+
+        Ok(())
+    }
+
+    fn support_resume(&mut self) -> Option<MultiThreadResumeOps<'_, Self>> {
+        Some(self)
+    }
+
+    fn support_thread_extra_info(&mut self) -> Option<ThreadExtraInfoOps<'_, Self>> {
+        Some(self)
+    }
+}
+
+impl SingleRegisterAccess<Tid> for RuntimeTarget<'_> {
+    fn read_register(
+        &mut self,
+        tid: Tid,
+        reg_id: RuntimeRegId,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        let reg = match self.target_desc.register(reg_id.into()) {
+            Some(reg) => reg,
+            // Unknown registers are reported as unavailable.
+            None => return Ok(0),
+        };
+
+        let mut session = self.session.lock().unwrap();
+        let mut core = session.core(tid.get() - 1).into_target_result()?;
+
+        let value = read_register(&mut core, reg.id()).into_target_result()?;
+
+        let size = reg.size_in_bytes().min(buf.len());
+        buf[..size].copy_from_slice(&value.to_le_bytes()[..size]);
+
+        Ok(size)
+    }
+
+    fn write_register(
+        &mut self,
+        tid: Tid,
+        reg_id: RuntimeRegId,
+        val: &[u8],
+    ) -> TargetResult<(), Self> {
+        let reg = match self.target_desc.register(reg_id.into()) {
+            Some(reg) => reg,
+            // EINVAL
+            None => return Err(gdbstub::target::TargetError::Errno(22)),
+        };
+
+        let mut session = self.session.lock().unwrap();
+        let mut core = session.core(tid.get() - 1).into_target_result()?;
+
+        write_register(&mut core, reg.id(), val).into_target_result()
+    }
+}
+
+/// Read a register of any size
+fn read_register(core: &mut Core<'_>, id: RegisterId) -> Result<u128, Error> {
+    core.read_core_reg(id)
+}
+
+/// Write a register from its little endian representation.
+///
+/// The value is written with the width of the data GDB sent for it.
+fn write_register(core: &mut Core<'_>, id: RegisterId, bytes: &[u8]) -> Result<(), Error> {
+    let mut buf = [0u8; 16];
+    let size = bytes.len().min(buf.len());
+    buf[..size].copy_from_slice(&bytes[..size]);
+
+    let value = u128::from_le_bytes(buf);
+
+    let value = match size {
+        0..=4 => RegisterValue::U32(value as u32),
+        5..=8 => RegisterValue::U64(value as u64),
+        _ => RegisterValue::U128(value),
+    };
+
+    core.write_core_reg(id, value)
+}
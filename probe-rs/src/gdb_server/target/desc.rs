@@ -0,0 +1,293 @@
+use std::fmt::Write;
+
+use super::utils::copy_range_to_buf;
+use super::RuntimeTarget;
+use crate::config::MemoryRegion;
+use crate::core::{CoreRegister, RegisterDataType, RegisterId, RegisterRole};
+use crate::{Core, Error, InstructionSet};
+
+use gdbstub::target::ext::memory_map::MemoryMap;
+use gdbstub::target::ext::target_description_xml_override::TargetDescriptionXmlOverride;
+use gdbstub::target::TargetResult;
+
+/// A register as it is presented to GDB
+#[derive(Debug, Clone)]
+pub(crate) struct GdbRegister {
+    /// The name GDB knows the register by
+    name: String,
+    /// The probe-rs ID of the register
+    id: RegisterId,
+    /// The size of the register in bits
+    size_in_bits: usize,
+    /// The size of the register in bytes
+    size_in_bytes: usize,
+    /// The GDB type of the register
+    gdb_type: &'static str,
+}
+
+impl GdbRegister {
+    /// The probe-rs ID of the register
+    pub fn id(&self) -> RegisterId {
+        self.id
+    }
+
+    /// The size of the register in bytes
+    pub fn size_in_bytes(&self) -> usize {
+        self.size_in_bytes
+    }
+}
+
+/// A group of registers, which GDB identifies by the name of the feature
+#[derive(Debug, Clone, Default)]
+struct Feature {
+    name: &'static str,
+    registers: Vec<GdbRegister>,
+}
+
+/// Description of the architecture and the registers of a target, in the format GDB expects.
+///
+/// The GDB register number of a register is its index in [`TargetDescription::registers`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TargetDescription {
+    architecture: &'static str,
+    features: Vec<Feature>,
+    xml: String,
+}
+
+impl TargetDescription {
+    /// Build the description of a core
+    pub fn new(core: &mut Core<'_>) -> Result<Self, Error> {
+        let instruction_set = core.instruction_set()?;
+
+        let (architecture, core_feature, fpu_feature) = match instruction_set {
+            InstructionSet::Thumb2 => (
+                "arm",
+                "org.gnu.gdb.arm.m-profile",
+                "org.gnu.gdb.arm.m-profile-fpu",
+            ),
+            InstructionSet::A32 => ("arm", "org.gnu.gdb.arm.core", "org.gnu.gdb.arm.vfp"),
+            InstructionSet::A64 => (
+                "aarch64",
+                "org.gnu.gdb.aarch64.core",
+                "org.gnu.gdb.aarch64.fpu",
+            ),
+            InstructionSet::RV32 | InstructionSet::RV32C => (
+                "riscv:rv32",
+                "org.gnu.gdb.riscv.cpu",
+                "org.gnu.gdb.riscv.fpu",
+            ),
+        };
+
+        let registers = core.registers();
+
+        let mut core_registers = registers
+            .core_registers()
+            .map(|reg| GdbRegister::new(reg, instruction_set))
+            .collect::<Vec<_>>();
+        core_registers.sort_by_key(|reg| reg.id.0);
+
+        let mut features = vec![Feature {
+            name: core_feature,
+            registers: core_registers,
+        }];
+
+        if core.fpu_support()? {
+            let fpu_registers = registers
+                .all_registers()
+                .filter(|reg| {
+                    reg.register_has_role(RegisterRole::FloatingPoint)
+                        || reg.register_has_role(RegisterRole::FloatingPointStatus)
+                })
+                .map(|reg| GdbRegister::new(reg, instruction_set))
+                .collect::<Vec<_>>();
+
+            if !fpu_registers.is_empty() {
+                features.push(Feature {
+                    name: fpu_feature,
+                    registers: fpu_registers,
+                });
+            }
+        }
+
+        let mut desc = Self {
+            architecture,
+            features,
+            xml: String::new(),
+        };
+        desc.xml = desc.build_xml();
+
+        Ok(desc)
+    }
+
+    /// All registers, in the order of their GDB register numbers
+    pub fn registers(&self) -> impl Iterator<Item = &GdbRegister> {
+        self.features
+            .iter()
+            .flat_map(|feature| feature.registers.iter())
+    }
+
+    /// The register with the given GDB register number
+    pub fn register(&self, regnum: usize) -> Option<&GdbRegister> {
+        self.registers().nth(regnum)
+    }
+
+    /// The target description XML document
+    pub fn xml(&self) -> &str {
+        &self.xml
+    }
+
+    fn build_xml(&self) -> String {
+        let mut xml = String::new();
+
+        xml.push_str(r#"<?xml version="1.0"?>"#);
+        xml.push('\n');
+        xml.push_str(r#"<!DOCTYPE target SYSTEM "gdb-target.dtd">"#);
+        xml.push('\n');
+        xml.push_str("<target version=\"1.0\">\n");
+        let _ = writeln!(xml, "<architecture>{}</architecture>", self.architecture);
+
+        let mut regnum = 0;
+        for feature in &self.features {
+            let _ = writeln!(xml, "<feature name=\"{}\">", feature.name);
+
+            for reg in &feature.registers {
+                let _ = writeln!(
+                    xml,
+                    "<reg name=\"{}\" bitsize=\"{}\" type=\"{}\" regnum=\"{}\"/>",
+                    reg.name, reg.size_in_bits, reg.gdb_type, regnum
+                );
+                regnum += 1;
+            }
+
+            xml.push_str("</feature>\n");
+        }
+
+        xml.push_str("</target>\n");
+
+        xml
+    }
+}
+
+impl GdbRegister {
+    fn new(reg: &CoreRegister, instruction_set: InstructionSet) -> Self {
+        let is_aarch32 = matches!(
+            instruction_set,
+            InstructionSet::Thumb2 | InstructionSet::A32
+        );
+
+        // GDB identifies the registers of the standard features by their name
+        let name = if reg.register_has_role(RegisterRole::ProgramCounter) {
+            "pc".to_string()
+        } else if reg.register_has_role(RegisterRole::StackPointer) {
+            "sp".to_string()
+        } else if is_aarch32 && reg.register_has_role(RegisterRole::ReturnAddress) {
+            "lr".to_string()
+        } else if instruction_set == InstructionSet::A64
+            && reg.register_has_role(RegisterRole::ProcessorStatus)
+        {
+            "cpsr".to_string()
+        } else {
+            reg.name().to_lowercase()
+        };
+
+        let gdb_type = match reg.data_type() {
+            _ if reg.register_has_role(RegisterRole::ProgramCounter) => "code_ptr",
+            _ if reg.register_has_role(RegisterRole::StackPointer) => "data_ptr",
+            RegisterDataType::FloatingPoint(32) => "ieee_single",
+            RegisterDataType::FloatingPoint(64) => "ieee_double",
+            RegisterDataType::FloatingPoint(_) => "uint128",
+            RegisterDataType::UnsignedInteger(_) => "int",
+        };
+
+        Self {
+            name,
+            id: reg.id(),
+            size_in_bits: reg.size_in_bits(),
+            size_in_bytes: reg.size_in_bytes(),
+            gdb_type,
+        }
+    }
+}
+
+impl RuntimeTarget<'_> {
+    /// Load the target description from the first core of this target
+    pub(crate) fn load_target_desc(&mut self) -> Result<(), Error> {
+        let mut session = self.session.lock().unwrap();
+        let mut core = session.core(self.cores[0])?;
+
+        self.target_desc = TargetDescription::new(&mut core)?;
+
+        Ok(())
+    }
+}
+
+impl TargetDescriptionXmlOverride for RuntimeTarget<'_> {
+    fn target_description_xml(
+        &self,
+        annex: &[u8],
+        offset: u64,
+        length: usize,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        if annex != b"target.xml" {
+            return Ok(0);
+        }
+
+        Ok(copy_range_to_buf(
+            self.target_desc.xml().as_bytes(),
+            offset,
+            length,
+            buf,
+        ))
+    }
+}
+
+impl MemoryMap for RuntimeTarget<'_> {
+    fn memory_map_xml(
+        &self,
+        offset: u64,
+        length: usize,
+        buf: &mut [u8],
+    ) -> TargetResult<usize, Self> {
+        let xml = self.memory_map();
+
+        Ok(copy_range_to_buf(xml.as_bytes(), offset, length, buf))
+    }
+}
+
+impl RuntimeTarget<'_> {
+    /// Build the memory map XML document of the target.
+    ///
+    /// Non-volatile memory is reported as read-only, as it cannot be written through GDB.
+    fn memory_map(&self) -> String {
+        let session = self.session.lock().unwrap();
+
+        let mut xml = String::new();
+
+        xml.push_str(r#"<?xml version="1.0"?>"#);
+        xml.push('\n');
+        xml.push_str(r#"<!DOCTYPE memory-map PUBLIC "+//IDN gnu.org//DTD GDB Memory Map V1.0//EN" "http://sourceware.org/gdb/gdb-memory-map.dtd">"#);
+        xml.push('\n');
+        xml.push_str("<memory-map>\n");
+
+        for region in &session.target().memory_map {
+            let (memory_type, range) = match region {
+                MemoryRegion::Ram(region) => ("ram", &region.range),
+                MemoryRegion::Generic(region) => ("ram", &region.range),
+                MemoryRegion::Nvm(region) => ("rom", &region.range),
+            };
+
+            let _ = writeln!(
+                xml,
+                "<memory type=\"{}\" start=\"{:#x}\" length=\"{:#x}\"/>",
+                memory_type,
+                range.start,
+                range.end - range.start
+            );
+        }
+
+        xml.push_str("</memory-map>\n");
+
+        xml
+    }
+}
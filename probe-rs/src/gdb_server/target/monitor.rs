@@ -0,0 +1,41 @@
+use super::RuntimeTarget;
+
+use gdbstub::target::ext::monitor_cmd::{outputln, ConsoleOutput, MonitorCmd};
+use std::time::Duration;
+
+impl MonitorCmd for RuntimeTarget<'_> {
+    fn handle_monitor_cmd(
+        &mut self,
+        cmd: &[u8],
+        mut out: ConsoleOutput<'_>,
+    ) -> Result<(), Self::Error> {
+        match String::from_utf8_lossy(cmd).trim() {
+            "help" => {
+                outputln!(out, "probe-rs monitor commands:");
+                outputln!(out, "  reset       - Reset and halt the target");
+                outputln!(out, "  reset halt  - Reset and halt the target");
+            }
+            "reset" | "reset halt" => {
+                let mut session = self.session.lock().unwrap();
+
+                // GDB expects the target to be halted after a reset
+                for core_id in &self.cores {
+                    session
+                        .core(*core_id)?
+                        .reset_and_halt(Duration::from_millis(400))?;
+                }
+
+                outputln!(out, "Target reset and halted");
+            }
+            other => {
+                outputln!(
+                    out,
+                    "Unknown command '{}', use 'monitor help' for a list of commands",
+                    other
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
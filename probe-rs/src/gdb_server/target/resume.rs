@@ -0,0 +1,61 @@
+use super::{resume_cores, ResumeAction, RuntimeTarget};
+
+use gdbstub::common::{Signal, Tid};
+use gdbstub::target::ext::base::multithread::{
+    MultiThreadResume, MultiThreadSingleStep, MultiThreadSingleStepOps,
+};
+
+impl MultiThreadResume for RuntimeTarget<'_> {
+    fn resume(&mut self) -> Result<(), Self::Error> {
+        let mut session = self.session.lock().unwrap();
+
+        match self.resume_action {
+            (_, ResumeAction::Resume) => {
+                // GDB expects all or nothing stops, so all cores are resumed
+                resume_cores(&mut session, &self.cores)?;
+            }
+            (core_id, ResumeAction::Step) => {
+                // The other cores stay halted
+                session.core(core_id)?.step()?;
+            }
+            (_, ResumeAction::Unchanged) => {}
+        }
+
+        Ok(())
+    }
+
+    fn clear_resume_actions(&mut self) -> Result<(), Self::Error> {
+        self.resume_action = (0, ResumeAction::Unchanged);
+
+        Ok(())
+    }
+
+    fn set_resume_action_continue(
+        &mut self,
+        tid: Tid,
+        _signal: Option<Signal>,
+    ) -> Result<(), Self::Error> {
+        // A step request takes precedence over resuming the other cores
+        if matches!(self.resume_action, (_, ResumeAction::Unchanged)) {
+            self.resume_action = (tid.get() - 1, ResumeAction::Resume);
+        }
+
+        Ok(())
+    }
+
+    fn support_single_step(&mut self) -> Option<MultiThreadSingleStepOps<'_, Self>> {
+        Some(self)
+    }
+}
+
+impl MultiThreadSingleStep for RuntimeTarget<'_> {
+    fn set_resume_action_step(
+        &mut self,
+        tid: Tid,
+        _signal: Option<Signal>,
+    ) -> Result<(), Self::Error> {
+        self.resume_action = (tid.get() - 1, ResumeAction::Step);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+use super::utils::copy_to_buf;
+use super::RuntimeTarget;
+
+use gdbstub::common::Tid;
+use gdbstub::target::ext::thread_extra_info::ThreadExtraInfo;
+
+impl ThreadExtraInfo for RuntimeTarget<'_> {
+    fn thread_extra_info(&self, tid: Tid, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        // Each core is presented to GDB as a thread
+        let description = format!("Core {}", tid.get() - 1);
+
+        Ok(copy_to_buf(description.as_bytes(), buf))
+    }
+}
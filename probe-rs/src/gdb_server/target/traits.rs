@@ -0,0 +1,50 @@
+use crate::Error;
+use gdbstub::stub::GdbStubError;
+use gdbstub::target::{TargetError, TargetResult};
+
+use super::RuntimeTarget;
+
+/// Extension trait to convert errors of the GDB stub into probe-rs errors
+pub(crate) trait ProbeRsErrorExt<T> {
+    /// Convert the error into a probe-rs [Error]
+    fn into_error(self) -> Result<T, Error>;
+}
+
+impl<T> ProbeRsErrorExt<T> for Result<T, std::io::Error> {
+    fn into_error(self) -> Result<T, Error> {
+        self.map_err(|e| anyhow::Error::from(e).into())
+    }
+}
+
+impl<T> ProbeRsErrorExt<T> for Result<T, GdbStubError<Error, std::io::Error>> {
+    fn into_error(self) -> Result<T, Error> {
+        self.map_err(|e| match e {
+            GdbStubError::TargetError(e) => e,
+            other => anyhow::Error::from(other).into(),
+        })
+    }
+}
+
+/// Extension trait to convert probe-rs errors into errors of the GDB stub
+pub(crate) trait GdbErrorExt<T> {
+    /// Convert the error into a fatal [TargetError]
+    fn into_target_result(self) -> TargetResult<T, RuntimeTarget<'static>>;
+
+    /// Convert the error into a non-fatal [TargetError], which is reported to GDB as `EIO`
+    fn into_target_result_non_fatal(self) -> TargetResult<T, RuntimeTarget<'static>>;
+}
+
+impl<T> GdbErrorExt<T> for Result<T, Error> {
+    fn into_target_result(self) -> TargetResult<T, RuntimeTarget<'static>> {
+        self.map_err(TargetError::Fatal)
+    }
+
+    fn into_target_result_non_fatal(self) -> TargetResult<T, RuntimeTarget<'static>> {
+        self.map_err(|e| {
+            tracing::warn!("Error while processing GDB request: {}", e);
+
+            // EIO
+            TargetError::Errno(5)
+        })
+    }
+}
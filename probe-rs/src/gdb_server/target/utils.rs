@@ -0,0 +1,43 @@
+/// Copy the part of `data` starting at `offset` into `buf`, limited to `length` bytes.
+///
+/// This is used to answer GDB `qXfer` requests, which read a document in chunks.
+///
+/// Returns the number of bytes copied.
+pub(crate) fn copy_range_to_buf(data: &[u8], offset: u64, length: usize, buf: &mut [u8]) -> usize {
+    let offset = match usize::try_from(offset) {
+        Ok(offset) if offset < data.len() => offset,
+        _ => return 0,
+    };
+
+    let length = length.min(buf.len()).min(data.len() - offset);
+
+    buf[..length].copy_from_slice(&data[offset..offset + length]);
+
+    length
+}
+
+/// Copy `data` into `buf`, truncating it if `buf` is too small.
+///
+/// Returns the number of bytes copied.
+pub(crate) fn copy_to_buf(data: &[u8], buf: &mut [u8]) -> usize {
+    copy_range_to_buf(data, 0, buf.len(), buf)
+}
+
+#[cfg(test)]
+mod test {
+    use super::copy_range_to_buf;
+
+    #[test]
+    fn copy_range_in_chunks() {
+        let data = b"0123456789";
+        let mut buf = [0u8; 4];
+
+        assert_eq!(copy_range_to_buf(data, 0, 4, &mut buf), 4);
+        assert_eq!(&buf, b"0123");
+
+        assert_eq!(copy_range_to_buf(data, 8, 4, &mut buf), 2);
+        assert_eq!(&buf[..2], b"89");
+
+        assert_eq!(copy_range_to_buf(data, 10, 4, &mut buf), 0);
+    }
+}
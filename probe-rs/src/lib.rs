@@ -69,6 +69,8 @@ extern crate serde;
 
 /// All the interface bits for the different architectures.
 pub mod architecture;
+#[warn(missing_docs)]
+pub mod bscan;
 pub mod config;
 
 #[warn(missing_docs)]
@@ -84,22 +86,27 @@ mod memory;
 #[warn(missing_docs)]
 mod probe;
 #[warn(missing_docs)]
+pub mod profiling;
+#[warn(missing_docs)]
 #[cfg(feature = "rtt")]
 pub mod rtt;
 #[warn(missing_docs)]
 mod session;
 
-pub use crate::config::{CoreType, InstructionSet, Target};
+pub use crate::config::{CoreType, InstructionSet, ResetType, Target};
+pub use crate::core::dump::{CoreDump, CoreDumpCore, CoreDumpError};
 pub use crate::core::{
     Architecture, BreakpointCause, Core, CoreInformation, CoreInterface, CoreRegister,
     CoreRegisters, CoreState, CoreStatus, HaltReason, MemoryMappedRegister, RegisterId,
-    RegisterValue, SpecificCoreState,
+    RegisterValue, SpecificCoreState, VectorCatchCondition, Watchpoint, WatchpointKind,
 };
 pub use crate::error::Error;
 pub use crate::memory::MemoryInterface;
+pub use crate::probe::remote::{ProbeServer, RemoteError, RemoteProbeError};
 pub use crate::probe::{
     AttachMethod, DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeSelector, DebugProbeType,
-    Probe, ProbeCreationError, WireProtocol,
+    JtagChainConfig, JtagTapSelector, NetworkProbeAddress, Probe, ProbeCreationError, UartAccess,
+    UartConfig, UartParity, UartStopBits, WireProtocol,
 };
 pub use crate::session::{Permissions, Session};
 
@@ -1,14 +1,20 @@
+pub(crate) mod arm_debug_interface;
 pub(crate) mod cmsisdap;
+pub(crate) mod common;
 pub(crate) mod espusbjtag;
 pub(crate) mod fake_probe;
 #[cfg(feature = "ftdi")]
 pub(crate) mod ftdi;
 pub(crate) mod jlink;
+pub(crate) mod remote;
+pub(crate) mod remote_bitbang;
 pub(crate) mod stlink;
+mod uart;
 
 use self::espusbjtag::list_espjtag_devices;
 use crate::architecture::arm::ArmError;
 use crate::architecture::riscv::communication_interface::RiscvError;
+use crate::bscan::{BoundaryScan, BoundaryScanError, Bsdl};
 use crate::error::Error;
 use crate::Session;
 use crate::{
@@ -27,7 +33,14 @@ use crate::{
     Permissions,
 };
 use jlink::list_jlink_devices;
-use std::{convert::TryFrom, fmt};
+use std::{
+    convert::TryFrom,
+    fmt,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+pub use uart::{UartAccess, UartConfig, UartParity, UartStopBits};
 
 /// Used to log warnings when the measured target voltage is
 /// lower than 1.4V, if at all measureable.
@@ -69,6 +82,65 @@ impl std::str::FromStr for WireProtocol {
     }
 }
 
+/// Selects a TAP of the JTAG scan chain.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum JtagTapSelector {
+    /// The TAP at this position of the scan chain, counted from TDO.
+    Index(usize),
+    /// The TAP with this IDCODE.
+    IdCode(u32),
+}
+
+impl fmt::Display for JtagTapSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JtagTapSelector::Index(index) => write!(f, "#{index}"),
+            JtagTapSelector::IdCode(idcode) => write!(f, "with IDCODE {idcode:#010x}"),
+        }
+    }
+}
+
+impl std::str::FromStr for JtagTapSelector {
+    type Err = String;
+
+    /// Parses an IDCODE given in hex with a `0x` prefix, or an index given in decimal.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let result = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(idcode) => u32::from_str_radix(idcode, 16).map(JtagTapSelector::IdCode),
+            None => s.parse().map(JtagTapSelector::Index),
+        };
+
+        result.map_err(|_| {
+            format!("'{s}' is not a valid TAP. Use an index, or an IDCODE like 0x4ba00477.")
+        })
+    }
+}
+
+/// The JTAG scan chain of a probe, and the TAP which is debugged.
+///
+/// By default, the scan chain is detected when attaching, and a known debug TAP is selected.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct JtagChainConfig {
+    /// The instruction register lengths of the TAPs, starting with the TAP closest to TDO.
+    ///
+    /// They have to be given for chains whose instruction register lengths cannot be detected.
+    pub ir_lengths: Option<Vec<u8>>,
+    /// The TAP which is debugged.
+    pub tap: Option<JtagTapSelector>,
+}
+
+impl From<&crate::config::Jtag> for JtagChainConfig {
+    fn from(jtag: &crate::config::Jtag) -> Self {
+        JtagChainConfig {
+            ir_lengths: jtag
+                .scan_chain
+                .as_ref()
+                .map(|chain| chain.iter().map(|tap| tap.ir_len).collect()),
+            tap: jtag.tap.map(JtagTapSelector::Index),
+        }
+    }
+}
+
 /// A command queued in a batch for later execution
 ///
 /// Mostly used internally but returned in DebugProbeError to indicate
@@ -209,6 +281,8 @@ pub enum ProbeCreationError {
 pub struct Probe {
     inner: Box<dyn DebugProbe>,
     attached: bool,
+    /// The JTAG scan chain was configured explicitly, so the one of the target description is not used.
+    jtag_chain_configured: bool,
 }
 
 impl Probe {
@@ -217,6 +291,7 @@ impl Probe {
         Self {
             inner: Box::new(probe),
             attached: false,
+            jtag_chain_configured: false,
         }
     }
 
@@ -224,6 +299,7 @@ impl Probe {
         Self {
             inner: probe,
             attached: true,
+            jtag_chain_configured: false,
         }
     }
 
@@ -232,9 +308,39 @@ impl Probe {
         Probe {
             inner: probe,
             attached: false,
+            jtag_chain_configured: false,
         }
     }
 
+    /// Records every transaction with the probe, together with its result, to the given file.
+    ///
+    /// The recording can be replayed without the probe using [`Probe::replay`]. This has to be
+    /// called before attaching to the target. Only probes which give raw access to the DAP or
+    /// JTAG registers can be recorded, an ST-Link for example cannot.
+    pub fn record(mut self, path: impl AsRef<Path>) -> Result<Self, DebugProbeError> {
+        if self.attached {
+            return Err(DebugProbeError::Attached);
+        }
+
+        remote::recording::check_recordable(self.inner.as_mut())?;
+
+        let file = File::create(path).map_err(remote::RemoteProbeError::from)?;
+        let probe = remote::recording::record(self.inner, BufWriter::new(file))?;
+
+        Ok(Probe::new(probe))
+    }
+
+    /// Creates a probe which answers from a file written by [`Probe::record`].
+    ///
+    /// The session has to make the same requests as the recorded one, the first differing
+    /// request fails.
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self, DebugProbeError> {
+        let file = File::open(path).map_err(remote::RemoteProbeError::from)?;
+        let probe = remote::recording::replay(BufReader::new(file))?;
+
+        Ok(Probe::new(probe))
+    }
+
     /// Get a list of all debug probes found.
     /// This can be used to select the debug probe which
     /// should be used.
@@ -259,6 +365,16 @@ impl Probe {
     /// about all probes available.
     #[tracing::instrument(skip_all)]
     pub fn open(selector: impl Into<DebugProbeSelector> + Clone) -> Result<Self, DebugProbeError> {
+        match remote::RemoteProbe::new_from_selector(selector.clone()) {
+            Ok(link) => return Ok(Probe::from_specific_probe(link)),
+            Err(DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound)) => {}
+            Err(e) => return Err(e),
+        };
+        match remote_bitbang::RemoteBitbang::new_from_selector(selector.clone()) {
+            Ok(link) => return Ok(Probe::from_specific_probe(link)),
+            Err(DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound)) => {}
+            Err(e) => return Err(e),
+        };
         match cmsisdap::CmsisDap::new_from_selector(selector.clone()) {
             Ok(link) => return Ok(Probe::from_specific_probe(link)),
             Err(DebugProbeError::ProbeCouldNotBeCreated(ProbeCreationError::NotFound)) => {}
@@ -371,6 +487,46 @@ impl Probe {
         }
     }
 
+    /// Configures the JTAG scan chain and the TAP which is debugged.
+    ///
+    /// This takes precedence over the scan chain in the target description.
+    pub fn set_jtag_chain(&mut self, chain: JtagChainConfig) -> Result<(), DebugProbeError> {
+        if self.attached {
+            return Err(DebugProbeError::Attached);
+        }
+
+        self.inner.set_jtag_chain(chain)?;
+        self.jtag_chain_configured = true;
+
+        Ok(())
+    }
+
+    /// Uses the JTAG scan chain of the target description, unless one was configured explicitly.
+    pub(crate) fn use_target_jtag_chain(
+        &mut self,
+        target: &crate::Target,
+    ) -> Result<(), DebugProbeError> {
+        let jtag = match &target.jtag {
+            Some(jtag) if !self.jtag_chain_configured => jtag,
+            _ => return Ok(()),
+        };
+
+        if self.inner.active_protocol() == Some(WireProtocol::Swd) {
+            return Ok(());
+        }
+
+        match self.inner.set_jtag_chain(jtag.into()) {
+            Err(DebugProbeError::NotImplemented(_)) => {
+                tracing::warn!(
+                    "The probe does not support JTAG scan chains, ignoring the scan chain of {}",
+                    target.name
+                );
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
     /// Get the currently selected protocol
     ///
     /// Depending on the probe, this might not be available.
@@ -471,6 +627,27 @@ impl Probe {
         }
     }
 
+    /// Puts the TAP selected by the JTAG chain configuration into boundary scan mode, using the
+    /// description of the device in `bsdl`.
+    ///
+    /// The probe has to be attached with the JTAG protocol selected.
+    /// If an error occurs, the probe is returned.
+    pub fn try_into_boundary_scan(
+        self,
+        bsdl: Bsdl,
+    ) -> Result<BoundaryScan, (Self, BoundaryScanError)> {
+        if !self.attached {
+            Err((self, DebugProbeError::NotAttached.into()))
+        } else {
+            BoundaryScan::new(self, bsdl)
+        }
+    }
+
+    /// Gets raw access to the JTAG registers of the target.
+    pub(crate) fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        self.inner.try_as_jtag_probe()
+    }
+
     /// Gets a SWO interface from the debug probe.
     ///
     /// This does not work on all probes.
@@ -485,6 +662,13 @@ impl Probe {
         self.inner.get_swo_interface_mut()
     }
 
+    /// Gets an interface to the UART of the target, bridged by the debug probe.
+    ///
+    /// This does not work on all probes.
+    pub fn get_uart_interface_mut(&mut self) -> Option<&mut dyn UartAccess> {
+        self.inner.get_uart_interface_mut()
+    }
+
     /// Gets a DAP interface from the debug probe.
     ///
     /// This does not work on all probes.
@@ -565,6 +749,16 @@ pub trait DebugProbe: Send + fmt::Debug {
     /// Get the transport protocol currently in active use by the debug probe.
     fn active_protocol(&self) -> Option<WireProtocol>;
 
+    /// Configures the JTAG scan chain and the TAP which is debugged, before attaching.
+    ///
+    /// Probes which only support a single TAP return [`DebugProbeError::NotImplemented`].
+    fn set_jtag_chain(&mut self, chain: JtagChainConfig) -> Result<(), DebugProbeError> {
+        let _ = chain;
+        Err(DebugProbeError::NotImplemented(
+            "JTAG scan chain configuration",
+        ))
+    }
+
     /// Check if the proble offers an interface to debug ARM chips.
     fn has_arm_interface(&self) -> bool {
         false
@@ -612,6 +806,13 @@ pub trait DebugProbe: Send + fmt::Debug {
         None
     }
 
+    /// Get a mutable interface to the UART of the target, if the probe bridges it.
+    ///
+    /// This is not available on all debug probes.
+    fn get_uart_interface_mut(&mut self) -> Option<&mut dyn UartAccess> {
+        None
+    }
+
     /// Boxes itself.
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe>;
 
@@ -622,6 +823,13 @@ pub trait DebugProbe: Send + fmt::Debug {
         None
     }
 
+    /// Get access to the JTAG registers of the target.
+    ///
+    /// This is not available on all probes.
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        None
+    }
+
     /// Reads the target voltage in Volts, if possible. Returns `Ok(None)`
     /// if the probe doesn’t support reading the target voltage.
     fn get_target_voltage(&mut self) -> Result<Option<f32>, DebugProbeError> {
@@ -642,6 +850,8 @@ pub enum DebugProbeType {
     JLink,
     /// Built in RISC-V ESP JTAG debug probe
     EspJtag,
+    /// A probe attached to another machine, served by `probe-rs serve`
+    Remote,
 }
 
 /// Gathers some information about a debug probe which was found during a scan.
@@ -709,10 +919,35 @@ impl DebugProbeInfo {
 pub enum DebugProbeSelectorParseError {
     #[error("The VID or PID could not be parsed: {0}")]
     ParseInt(#[from] std::num::ParseIntError),
-    #[error("Please use a string in the form `VID:PID:<Serial>` where Serial is optional.")]
+    #[error("Please use a string in the form `VID:PID:<Serial>` where Serial is optional, `remote-bitbang:<host>:<port>` or `tcp://<host>:<port>/<selector>`.")]
     Format,
 }
 
+/// The network address of a probe which is not connected over USB.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NetworkProbeAddress {
+    /// A server implementing the OpenOCD `remote_bitbang` protocol, given as `host:port`.
+    RemoteBitbang(String),
+    /// A probe served by `probe-rs serve`.
+    Tcp {
+        /// The address of the server, given as `host:port`.
+        address: String,
+        /// The selector of the probe on the server. If it is empty, the first probe found is used.
+        selector: String,
+    },
+}
+
+impl fmt::Display for NetworkProbeAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkProbeAddress::RemoteBitbang(address) => write!(f, "remote-bitbang:{address}"),
+            NetworkProbeAddress::Tcp { address, selector } => {
+                write!(f, "tcp://{address}/{selector}")
+            }
+        }
+    }
+}
+
 /// A struct to describe the way a probe should be selected.
 ///
 /// Construct this from a set of info or from a string. The
@@ -720,6 +955,12 @@ pub enum DebugProbeSelectorParseError {
 /// where the serialnumber is optional, and VID and PID are
 /// parsed as hexadecimal numbers.
 ///
+/// Probes which are reached over the network are selected with
+/// "remote-bitbang:HOST:PORT" for a server implementing the
+/// OpenOCD `remote_bitbang` protocol, and with "tcp://HOST:PORT/SELECTOR"
+/// for a probe served by `probe-rs serve`. The selector of the remote
+/// probe is optional.
+///
 /// ## Example:
 ///
 /// ```
@@ -739,17 +980,71 @@ pub struct DebugProbeSelector {
     pub product_id: u16,
     /// The the serial number of the debug probe to be used.
     pub serial_number: Option<String>,
+    /// The network address of the debug probe to be used, if it is not connected over USB.
+    ///
+    /// The USB vendor and product id are zero in this case.
+    pub network_address: Option<NetworkProbeAddress>,
+    /// The pin layout of an FTDI probe, which overrides the known layout of the probe.
+    ///
+    /// It is given as `<output>:<direction>:<swdio>` in hexadecimal after `,ftdi-layout=`, e.g.
+    /// `0403:6014,ftdi-layout=0008:000b:resistor`. `<swdio>` is `resistor` if SWDIO is connected to
+    /// TDI through a resistor, `none` if only JTAG can be used, or the direction pins which enable the
+    /// SWDIO driver.
+    pub ftdi_layout: Option<String>,
 }
 
 impl TryFrom<&str> for DebugProbeSelector {
     type Error = DebugProbeSelectorParseError;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if let Some(address) = value.strip_prefix("remote-bitbang:") {
+            if address.is_empty() {
+                return Err(DebugProbeSelectorParseError::Format);
+            }
+
+            return Ok(DebugProbeSelector {
+                vendor_id: 0,
+                product_id: 0,
+                serial_number: None,
+                network_address: Some(NetworkProbeAddress::RemoteBitbang(address.to_string())),
+                ftdi_layout: None,
+            });
+        }
+
+        if let Some(url) = value.strip_prefix("tcp://") {
+            let (address, selector) = url.split_once('/').unwrap_or((url, ""));
+            if address.is_empty() {
+                return Err(DebugProbeSelectorParseError::Format);
+            }
+            if !selector.is_empty() {
+                // Check the remote selector before connecting to the server.
+                DebugProbeSelector::try_from(selector)?;
+            }
+
+            return Ok(DebugProbeSelector {
+                vendor_id: 0,
+                product_id: 0,
+                serial_number: None,
+                network_address: Some(NetworkProbeAddress::Tcp {
+                    address: address.to_string(),
+                    selector: selector.to_string(),
+                }),
+                ftdi_layout: None,
+            });
+        }
+
+        let (value, ftdi_layout) = match value.split_once(",ftdi-layout=") {
+            Some((value, layout)) => (value, Some(layout.to_string())),
+            None => (value, None),
+        };
+
         let split = value.split(':').collect::<Vec<_>>();
         let mut selector = if split.len() > 1 {
             DebugProbeSelector {
                 vendor_id: u16::from_str_radix(split[0], 16)?,
                 product_id: u16::from_str_radix(split[1], 16)?,
                 serial_number: None,
+                network_address: None,
+                ftdi_layout,
             }
         } else {
             return Err(DebugProbeSelectorParseError::Format);
@@ -783,6 +1078,8 @@ impl From<DebugProbeInfo> for DebugProbeSelector {
             vendor_id: selector.vendor_id,
             product_id: selector.product_id,
             serial_number: selector.serial_number,
+            network_address: None,
+            ftdi_layout: None,
         }
     }
 }
@@ -793,16 +1090,25 @@ impl From<&DebugProbeInfo> for DebugProbeSelector {
             vendor_id: selector.vendor_id,
             product_id: selector.product_id,
             serial_number: selector.serial_number.clone(),
+            network_address: None,
+            ftdi_layout: None,
         }
     }
 }
 
 impl fmt::Display for DebugProbeSelector {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref address) = self.network_address {
+            return write!(f, "{address}");
+        }
+
         write!(f, "{:04x}:{:04x}", self.vendor_id, self.product_id)?;
         if let Some(ref sn) = self.serial_number {
             write!(f, ":{sn}")?;
         }
+        if let Some(ref layout) = self.ftdi_layout {
+            write!(f, ",ftdi-layout={layout}")?;
+        }
         Ok(())
     }
 }
@@ -1,4 +1,7 @@
-//! Implementation of the SWD and JTAG protocols for the JLink probe.
+//! Implementation of the SWD and JTAG protocols for probes with bit-level access to the debug interface.
+//!
+//! Probes only have to implement [`RawProtocolIo`] and [`JTAGAccess`] to get an implementation of
+//! [`RawDapAccess`], which handles the ARM debug port protocol, including WAIT retries and FAULT handling.
 use std::{iter, time::Duration};
 
 use crate::{
@@ -10,8 +13,6 @@ use crate::{
     DebugProbe, DebugProbeError,
 };
 
-use super::{bits_to_byte, JLink};
-
 #[derive(Debug)]
 pub struct SwdSettings {
     /// Initial number of idle cycles between consecutive writes.
@@ -106,7 +107,7 @@ impl ProbeStatistics {
         self.num_transfers += num_transfers;
     }
 
-    fn report_io(&mut self) {
+    pub(crate) fn report_io(&mut self) {
         self.num_io_calls += 1;
     }
 
@@ -366,6 +367,10 @@ fn perform_transfers<P: DebugProbe + RawProtocolIo + JTAGAccess>(
 ) -> Result<(), DebugProbeError> {
     assert!(!transfers.is_empty());
 
+    let protocol = probe
+        .active_protocol()
+        .ok_or(DebugProbeError::NotAttached)?;
+
     // Read from DebugPort  -> Nothing special needed
     // Read from AccessPort -> Response is returned in next read
     //                         -> The next transfer must be a AP Read, otherwise we need to insert a read from the RDBUFF register
@@ -450,7 +455,7 @@ fn perform_transfers<P: DebugProbe + RawProtocolIo + JTAGAccess>(
         // handled by perform_jtag_transfers
         result_indices.push(OriginalTransfer {
             index: num_transfers,
-            response_in_next: probe.active_protocol().unwrap() == crate::WireProtocol::Swd
+            response_in_next: protocol == crate::WireProtocol::Swd
                 && (need_ap_read || write_response_pending),
         });
 
@@ -490,7 +495,7 @@ fn perform_transfers<P: DebugProbe + RawProtocolIo + JTAGAccess>(
 
     probe.probe_statistics().record_transfers(num_transfers);
 
-    match probe.active_protocol().unwrap() {
+    match protocol {
         crate::WireProtocol::Swd => {
             perform_swd_transfers(probe, &mut final_transfers[..])?;
         }
@@ -822,6 +827,7 @@ fn parse_swd_response(response: &[bool], direction: TransferDirection) -> Result
     }
 }
 
+/// Bit-level access to the SWD and JTAG signals of a probe.
 pub trait RawProtocolIo {
     fn jtag_io<M, I>(&mut self, tms: M, tdi: I) -> Result<Vec<bool>, DebugProbeError>
     where
@@ -840,49 +846,16 @@ pub trait RawProtocolIo {
     /// Try to perform a SWD line reset, followed by a read of the DPIDR register.
     ///
     /// Returns Ok if the read of the DPIDR register was succesful, and Err
-    /// otherwise. In case of JLink Errors, the actual error is returned.
+    /// otherwise. In case of probe errors, the actual error is returned.
     ///
     /// If the first line reset fails, it is tried once again, as the target
     /// might be in the middle of a transfer the first time we try the reset.
     ///
     /// See section B4.3.3 in the ADIv5 Specification.
-    fn line_reset(&mut self) -> Result<(), ArmError>;
-}
-
-impl RawProtocolIo for JLink {
-    fn jtag_io<M, I>(&mut self, tms: M, tdi: I) -> Result<Vec<bool>, DebugProbeError>
+    fn line_reset(&mut self) -> Result<(), ArmError>
     where
-        M: IntoIterator<Item = bool>,
-        I: IntoIterator<Item = bool>,
+        Self: DebugProbe + JTAGAccess + Sized + 'static,
     {
-        if self.protocol.unwrap() == crate::WireProtocol::Swd {
-            panic!("Logic error, requested jtag_io when in SWD mode");
-        }
-
-        self.probe_statistics.report_io();
-
-        let iter = self.handle.jtag_io(tms, tdi)?;
-
-        Ok(iter.collect())
-    }
-
-    fn swd_io<D, S>(&mut self, dir: D, swdio: S) -> Result<Vec<bool>, DebugProbeError>
-    where
-        D: IntoIterator<Item = bool>,
-        S: IntoIterator<Item = bool>,
-    {
-        if self.protocol.unwrap() == crate::WireProtocol::Jtag {
-            panic!("Logic error, requested swd_io when in JTAG mode");
-        }
-
-        self.probe_statistics.report_io();
-
-        let iter = self.handle.swd_io(dir, swdio)?;
-
-        Ok(iter.collect())
-    }
-
-    fn line_reset(&mut self) -> Result<(), ArmError> {
         tracing::debug!("Performing line reset!");
 
         const NUM_RESET_BITS: u8 = 50;
@@ -920,14 +893,6 @@ impl RawProtocolIo for JLink {
         // No acknowledge from the target, even if after line reset
         result
     }
-
-    fn swd_settings(&self) -> &SwdSettings {
-        &self.swd_settings
-    }
-
-    fn probe_statistics(&mut self) -> &mut ProbeStatistics {
-        &mut self.probe_statistics
-    }
 }
 
 impl<Probe: DebugProbe + RawProtocolIo + JTAGAccess + 'static> RawDapAccess for Probe {
@@ -935,7 +900,7 @@ impl<Probe: DebugProbe + RawProtocolIo + JTAGAccess + 'static> RawDapAccess for
         match dp {
             DpAddress::Default => Ok(()), // nop
             DpAddress::Multidrop(_) => Err(DebugProbeError::ProbeSpecific(
-                anyhow::anyhow!("JLink doesn't support multidrop SWD yet").into(),
+                anyhow::anyhow!("Multidrop SWD is not supported by this probe yet").into(),
             )
             .into()),
         }
@@ -1328,8 +1293,8 @@ impl<Probe: DebugProbe + RawProtocolIo + JTAGAccess + 'static> RawDapAccess for
         nreset.set_nreset(true);
         let nreset_mask = nreset.0 as u32;
 
-        // If only the reset pin is selected we perform the reset.
-        // If something else is selected return an error as this is not supported on J-Links.
+        // Probes with raw protocol access can only drive the reset pin, through the reset
+        // methods of the probe. Probes which cannot drive it return an error from these.
         if pin_select == nreset_mask {
             if Pins(pin_out as u8).nreset() {
                 self.target_reset_deassert()?;
@@ -1337,15 +1302,14 @@ impl<Probe: DebugProbe + RawProtocolIo + JTAGAccess + 'static> RawDapAccess for
                 self.target_reset_assert()?;
             }
 
-            // Normally this would be the timeout we pass to the probe to settle the pins.
-            // The J-Link is not capable of this, so we just wait for this time on the host
-            // and assume it has settled until then.
+            // The probe cannot wait for the pins to settle, so wait for this time on the host
+            // and assume they have settled until then.
             std::thread::sleep(Duration::from_micros(pin_wait as u64));
 
             // We signal that we cannot read the pin state.
             Ok(0xFFFF_FFFF)
         } else {
-            // This is not supported for J-Links, unfortunately.
+            // Driving the other pins directly is not supported.
             Err(DebugProbeError::CommandNotSupportedByProbe("swj_pins"))
         }
     }
@@ -1355,7 +1319,7 @@ impl<Probe: DebugProbe + RawProtocolIo + JTAGAccess + 'static> RawDapAccess for
     }
 
     fn swj_sequence(&mut self, bit_len: u8, mut bits: u64) -> Result<(), DebugProbeError> {
-        let protocol = self.active_protocol().expect("No protocol set");
+        let protocol = self.active_protocol().ok_or(DebugProbeError::NotAttached)?;
 
         let mut io_sequence = IoSequence::new();
 
@@ -1391,13 +1355,25 @@ impl<Probe: DebugProbe + RawProtocolIo + JTAGAccess + 'static> RawDapAccess for
     }
 }
 
+pub(crate) fn bits_to_byte(bits: impl IntoIterator<Item = bool>) -> u32 {
+    let mut bit_val = 0u32;
+
+    for (index, bit) in bits.into_iter().take(32).enumerate() {
+        if bit {
+            bit_val |= 1 << index;
+        }
+    }
+
+    bit_val
+}
+
 #[cfg(test)]
 mod test {
 
     use std::iter;
 
     use crate::{
-        architecture::arm::{ArmError, PortType, RawDapAccess},
+        architecture::arm::{ArmError, Pins, PortType, RawDapAccess},
         probe::JTAGAccess,
         DebugProbe, DebugProbeError,
     };
@@ -1602,20 +1578,16 @@ mod test {
     }
 
     impl JTAGAccess for MockJaylink {
-        fn set_ir_len(&mut self, _len: u32) {
-            todo!()
-        }
+        fn set_ir_len(&mut self, _len: u32) {}
 
         fn read_register(&mut self, _address: u32, _len: u32) -> Result<Vec<u8>, DebugProbeError> {
-            todo!()
+            Err(DebugProbeError::NotImplemented("read_register"))
         }
 
-        fn set_idle_cycles(&mut self, _idle_cycles: u8) {
-            todo!()
-        }
+        fn set_idle_cycles(&mut self, _idle_cycles: u8) {}
 
         fn get_idle_cycles(&self) -> u8 {
-            todo!()
+            0
         }
 
         fn write_register(
@@ -1706,8 +1678,8 @@ mod test {
         }
     }
 
-    /// This is just a blanket impl that will crash if used (only relevant in tests,
-    /// so no problem as we do not use it) to fulfill the marker requirement.
+    /// Only the protocol selection is used by the tests, the other functions
+    /// behave like a probe without a target connected.
     impl DebugProbe for MockJaylink {
         fn new_from_selector(
             _selector: impl Into<crate::DebugProbeSelector>,
@@ -1715,39 +1687,41 @@ mod test {
         where
             Self: Sized,
         {
-            todo!()
+            Err(DebugProbeError::ProbeCouldNotBeCreated(
+                crate::probe::ProbeCreationError::NotFound,
+            ))
         }
 
         fn get_name(&self) -> &str {
-            todo!()
+            "Mock J-Link"
         }
 
         fn speed_khz(&self) -> u32 {
-            todo!()
+            0
         }
 
-        fn set_speed(&mut self, _speed_khz: u32) -> Result<u32, crate::DebugProbeError> {
-            todo!()
+        fn set_speed(&mut self, speed_khz: u32) -> Result<u32, crate::DebugProbeError> {
+            Ok(speed_khz)
         }
 
         fn attach(&mut self) -> Result<(), crate::DebugProbeError> {
-            todo!()
+            Ok(())
         }
 
         fn detach(&mut self) -> Result<(), crate::Error> {
-            todo!()
+            Ok(())
         }
 
         fn target_reset(&mut self) -> Result<(), crate::DebugProbeError> {
-            todo!()
+            Err(DebugProbeError::NotImplemented("target_reset"))
         }
 
         fn target_reset_assert(&mut self) -> Result<(), crate::DebugProbeError> {
-            todo!()
+            Err(DebugProbeError::NotImplemented("target_reset_assert"))
         }
 
         fn target_reset_deassert(&mut self) -> Result<(), crate::DebugProbeError> {
-            todo!()
+            Err(DebugProbeError::NotImplemented("target_reset_deassert"))
         }
 
         fn select_protocol(
@@ -1764,7 +1738,7 @@ mod test {
         }
 
         fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
-            todo!()
+            self
         }
     }
 
@@ -1783,6 +1757,28 @@ mod test {
         assert_eq!(result, read_value);
     }
 
+    #[test]
+    fn swj_pins_without_reset_support() {
+        let mut mock = MockJaylink::new();
+
+        let mut nreset = Pins(0);
+        nreset.set_nreset(true);
+
+        // The reset pin is driven through the reset methods of the probe, which the mock does not implement.
+        let result = mock.swj_pins(0, nreset.0 as u32, 0);
+        assert!(matches!(
+            result,
+            Err(DebugProbeError::NotImplemented("target_reset_assert"))
+        ));
+
+        // Other pins can not be driven at all.
+        let result = mock.swj_pins(0, 0x01, 0);
+        assert!(matches!(
+            result,
+            Err(DebugProbeError::CommandNotSupportedByProbe("swj_pins"))
+        ));
+    }
+
     #[test]
     fn read_register_jtag() {
         let read_value = 12;
@@ -2008,7 +2004,7 @@ mod test {
     mod transfer_handling {
         use crate::{
             architecture::arm::PortType,
-            probe::jlink::arm::{perform_transfers, DapTransfer, TransferStatus},
+            probe::arm_debug_interface::{perform_transfers, DapTransfer, TransferStatus},
         };
 
         use super::{DapAcknowledge, MockJaylink};
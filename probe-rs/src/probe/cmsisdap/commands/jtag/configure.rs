@@ -0,0 +1,35 @@
+/// Implementation of the DAP_JTAG_Configure command
+///
+use super::super::{CommandId, Request, SendError, Status};
+
+/// Sets the IR lengths of the TAPs in the JTAG chain, starting with the TAP closest to TDO.
+#[derive(Debug)]
+pub struct ConfigureRequest {
+    ir_lengths: Vec<u8>,
+}
+
+impl ConfigureRequest {
+    pub(crate) fn new(ir_lengths: Vec<u8>) -> Self {
+        ConfigureRequest { ir_lengths }
+    }
+}
+
+impl Request for ConfigureRequest {
+    const COMMAND_ID: CommandId = CommandId::JtagConfigure;
+
+    type Response = ConfigureResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        buffer[0] = self.ir_lengths.len() as u8;
+        buffer[1..][..self.ir_lengths.len()].copy_from_slice(&self.ir_lengths);
+
+        Ok(1 + self.ir_lengths.len())
+    }
+
+    fn parse_response(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        Ok(ConfigureResponse(Status::from_byte(buffer[0])?))
+    }
+}
+
+#[derive(Debug)]
+pub struct ConfigureResponse(pub(crate) Status);
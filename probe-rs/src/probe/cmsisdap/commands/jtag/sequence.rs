@@ -0,0 +1,162 @@
+/// Implementation of the DAP_JTAG_Sequence command
+///
+use super::super::{CommandId, Request, SendError, Status};
+
+/// A sequence of TCK cycles with a fixed TMS value.
+#[derive(Clone, Copy, Debug)]
+pub struct JtagSequence {
+    /// The number of TCK cycles, 1 to 64.
+    cycles: u8,
+    tms: bool,
+    /// Whether TDO is captured during the sequence.
+    capture: bool,
+    /// The TDI values, LSB first.
+    tdi: u64,
+}
+
+impl JtagSequence {
+    /// The maximum number of TCK cycles of a sequence.
+    pub const MAX_CYCLES: usize = 64;
+
+    pub(crate) fn new(tms: bool, capture: bool, tdi: &[bool]) -> Self {
+        assert!(
+            !tdi.is_empty() && tdi.len() <= Self::MAX_CYCLES,
+            "Invalid JTAG sequence length. This is a bug, please report it."
+        );
+
+        JtagSequence {
+            cycles: tdi.len() as u8,
+            tms,
+            capture,
+            tdi: tdi
+                .iter()
+                .enumerate()
+                .fold(0, |value, (i, bit)| value | (u64::from(*bit) << i)),
+        }
+    }
+
+    /// The number of bytes with TDI or TDO data.
+    pub(crate) fn data_len(&self) -> usize {
+        (usize::from(self.cycles) + 7) / 8
+    }
+
+    /// The number of bytes the sequence adds to the response.
+    pub(crate) fn response_len(&self) -> usize {
+        if self.capture {
+            self.data_len()
+        } else {
+            0
+        }
+    }
+
+    fn to_bytes(self, buffer: &mut [u8]) -> usize {
+        // A cycle count of 64 is encoded as 0.
+        buffer[0] = (self.cycles % 64) | (u8::from(self.tms) << 6) | (u8::from(self.capture) << 7);
+
+        let data_len = self.data_len();
+        buffer[1..][..data_len].copy_from_slice(&self.tdi.to_le_bytes()[..data_len]);
+
+        1 + data_len
+    }
+}
+
+#[derive(Debug)]
+pub struct SequenceRequest {
+    sequences: Vec<JtagSequence>,
+}
+
+impl SequenceRequest {
+    pub(crate) fn new(sequences: Vec<JtagSequence>) -> Self {
+        assert!(
+            sequences.len() <= u8::MAX as usize,
+            "Too many JTAG sequences. This is a bug, please report it."
+        );
+
+        SequenceRequest { sequences }
+    }
+}
+
+impl Request for SequenceRequest {
+    const COMMAND_ID: CommandId = CommandId::JtagSequence;
+
+    type Response = SequenceResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        buffer[0] = self.sequences.len() as u8;
+
+        let mut size = 1;
+        for sequence in &self.sequences {
+            size += sequence.to_bytes(&mut buffer[size..]);
+        }
+
+        Ok(size)
+    }
+
+    fn parse_response(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        let status = Status::from_byte(buffer[0])?;
+
+        let mut tdo = Vec::new();
+        let mut offset = 1;
+        for sequence in &self.sequences {
+            let len = sequence.response_len();
+            if len == 0 {
+                continue;
+            }
+
+            let data = buffer
+                .get(offset..offset + len)
+                .ok_or(SendError::NotEnoughData)?;
+            tdo.extend(
+                (0..usize::from(sequence.cycles)).map(|i| data[i / 8] & (1 << (i % 8)) != 0),
+            );
+            offset += len;
+        }
+
+        Ok(SequenceResponse { status, tdo })
+    }
+}
+
+#[derive(Debug)]
+pub struct SequenceResponse {
+    pub(crate) status: Status,
+    /// The captured TDO values of all sequences.
+    pub(crate) tdo: Vec<bool>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_sequences() {
+        let request = SequenceRequest::new(vec![
+            JtagSequence::new(true, false, &[false; 5]),
+            JtagSequence::new(false, true, &[true; 64]),
+            JtagSequence::new(true, true, &[true, false, true]),
+        ]);
+
+        let mut buffer = [0; 32];
+        let size = request.to_bytes(&mut buffer).unwrap();
+
+        assert_eq!(size, 1 + 2 + 9 + 2);
+        assert_eq!(buffer[..4], [3, 0x45, 0x00, 0x80]);
+        assert_eq!(buffer[4..12], [0xff; 8]);
+        assert_eq!(buffer[12..14], [0xc3, 0b101]);
+    }
+
+    #[test]
+    fn decode_captured_bits() {
+        let request = SequenceRequest::new(vec![
+            JtagSequence::new(false, true, &[false; 3]),
+            JtagSequence::new(true, false, &[false; 2]),
+            JtagSequence::new(false, true, &[false; 9]),
+        ]);
+
+        let response = request.parse_response(&[0x00, 0b110, 0xff, 0x01]).unwrap();
+
+        assert_eq!(
+            response.tdo,
+            [false, true, true, true, true, true, true, true, true, true, true, true]
+        );
+    }
+}
@@ -1,8 +1,10 @@
 pub mod general;
+pub mod jtag;
 pub mod swd;
 pub mod swj;
 pub mod swo;
 pub mod transfer;
+pub mod uart;
 
 use crate::probe::cmsisdap::commands::general::info::PacketSizeCommand;
 use crate::DebugProbeError;
@@ -30,6 +32,10 @@ pub enum CmsisDapError {
     SwoReadError(#[source] rusb::Error),
     #[error("Could not determine a suitable packet size for this probe")]
     NoPacketSize,
+    #[error("The probe does not bridge the UART of the target")]
+    UartNotAvailable,
+    #[error("The probe does not support the requested UART configuration")]
+    UartConfigurationNotSupported,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -223,26 +229,43 @@ impl CmsisDapDevice {
 
     /// Read from the SWO streaming endpoint.
     ///
+    /// Waits up to `timeout` for the first packet, and then drains all packets the
+    /// probe has already queued, so that its trace buffer does not overflow while
+    /// the data is being processed.
+    ///
     /// Returns SWOModeNotAvailable if this device does not support SWO streaming.
     ///
     /// On timeout, returns a zero-length buffer.
     pub(super) fn read_swo_stream(&self, timeout: Duration) -> Result<Vec<u8>, CmsisDapError> {
+        /// The maximum number of packets read in one go, to bound the latency of a read.
+        const MAX_PACKETS: usize = 64;
+
         match self {
             CmsisDapDevice::V1 { .. } => Err(CmsisDapError::SwoModeNotAvailable),
             CmsisDapDevice::V2 { handle, swo_ep, .. } => match swo_ep {
                 Some((ep, len)) => {
-                    let mut buf = vec![0u8; *len];
-                    match handle.read_bulk(*ep, &mut buf, timeout) {
-                        Ok(n) => {
-                            buf.truncate(n);
-                            Ok(buf)
+                    // Packets are read one at a time: if a larger read times out,
+                    // the data received so far is lost.
+                    let mut buf = Vec::new();
+                    let mut packet = vec![0u8; *len];
+                    let mut timeout = timeout;
+                    for _ in 0..MAX_PACKETS {
+                        match handle.read_bulk(*ep, &mut packet, timeout) {
+                            Ok(n) => {
+                                buf.extend_from_slice(&packet[..n]);
+                                // A short packet ends the data the probe had queued.
+                                if n < *len {
+                                    break;
+                                }
+                            }
+                            Err(rusb::Error::Timeout) => break,
+                            Err(e) => return Err(CmsisDapError::SwoReadError(e)),
                         }
-                        Err(rusb::Error::Timeout) => {
-                            buf.truncate(0);
-                            Ok(buf)
-                        }
-                        Err(e) => Err(CmsisDapError::SwoReadError(e)),
+
+                        // Only pick up packets which are already on their way.
+                        timeout = Duration::from_millis(1);
                     }
+                    Ok(buf)
                 }
                 None => Err(CmsisDapError::SwoModeNotAvailable),
             },
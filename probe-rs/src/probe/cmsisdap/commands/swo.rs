@@ -1,6 +1,6 @@
 use scroll::{Pread, LE};
 
-use super::{CommandId, Request, SendError, Status};
+use super::{CmsisDapError, CommandId, Request, SendError, Status};
 use std::convert::TryInto;
 
 #[repr(u8)]
@@ -132,7 +132,7 @@ impl Request for StatusRequest {
                 .map_err(|_| SendError::NotEnoughData)?,
         );
         Ok(StatusResponse {
-            _status: status,
+            status,
             _count: count,
         })
     }
@@ -142,7 +142,22 @@ impl Request for StatusRequest {
 pub struct TraceStatus {
     pub(crate) _active: bool,
     pub(crate) error: bool,
-    pub(crate) _overrun: bool,
+    pub(crate) overrun: bool,
+}
+
+impl TraceStatus {
+    /// Returns an error if the probe failed to capture trace data, and warns if it had to drop data.
+    pub(crate) fn check(&self) -> Result<(), CmsisDapError> {
+        if self.error {
+            return Err(CmsisDapError::SwoTraceStreamError);
+        }
+
+        if self.overrun {
+            tracing::warn!("SWO trace buffer of the probe overflowed, trace data was lost");
+        }
+
+        Ok(())
+    }
 }
 
 impl From<u8> for TraceStatus {
@@ -150,14 +165,14 @@ impl From<u8> for TraceStatus {
         Self {
             _active: value & (1 << 0) != 0,
             error: value & (1 << 6) != 0,
-            _overrun: value & (1 << 7) != 0,
+            overrun: value & (1 << 7) != 0,
         }
     }
 }
 
 #[derive(Debug)]
 pub struct StatusResponse {
-    pub(crate) _status: TraceStatus,
+    pub(crate) status: TraceStatus,
     pub(crate) _count: u32,
 }
 
@@ -0,0 +1,261 @@
+//! The DAP_UART_* commands of CMSIS-DAP v2.1, which bridge the UART of the target.
+
+use scroll::{Pread, LE};
+
+use super::{CommandId, Request, SendError, Status};
+
+#[repr(u8)]
+#[allow(unused)]
+#[derive(Copy, Clone, Debug)]
+pub enum TransportRequest {
+    NoTransport = 0,
+    UsbComPort = 1,
+    DapCommand = 2,
+}
+
+impl Request for TransportRequest {
+    const COMMAND_ID: CommandId = CommandId::UartTransport;
+
+    type Response = TransportResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        buffer[0] = *self as u8;
+        Ok(1)
+    }
+
+    fn parse_response(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        Ok(TransportResponse(Status::from_byte(buffer[0])?))
+    }
+}
+
+#[derive(Debug)]
+pub struct TransportResponse(pub(crate) Status);
+
+#[derive(Copy, Clone, Debug)]
+pub struct ConfigureRequest {
+    /// Data bits in bits 0..3 (0 for 8 data bits), parity in bits 4..5 and stop bits in bits 6..7.
+    pub(crate) control: u8,
+    pub(crate) baud: u32,
+}
+
+impl Request for ConfigureRequest {
+    const COMMAND_ID: CommandId = CommandId::UartConfigure;
+
+    type Response = ConfigureResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        assert!(
+            buffer.len() >= 5,
+            "Buffer for CMSIS-DAP command is too small. This is a bug, please report it."
+        );
+        buffer[0] = self.control;
+        buffer[1..5].copy_from_slice(&self.baud.to_le_bytes());
+        Ok(5)
+    }
+
+    fn parse_response(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        if buffer.len() < 5 {
+            return Err(SendError::NotEnoughData);
+        }
+
+        let baud: u32 = buffer
+            .pread_with(1, LE)
+            .map_err(|_| SendError::NotEnoughData)?;
+
+        Ok(ConfigureResponse {
+            data_bits_error: buffer[0] & (1 << 0) != 0,
+            parity_error: buffer[0] & (1 << 1) != 0,
+            stop_bits_error: buffer[0] & (1 << 2) != 0,
+            baud,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ConfigureResponse {
+    pub(crate) data_bits_error: bool,
+    pub(crate) parity_error: bool,
+    pub(crate) stop_bits_error: bool,
+    /// The configured baud rate, which may differ from the requested one.
+    pub(crate) baud: u32,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ControlRequest {
+    pub(crate) enable_rx: bool,
+    pub(crate) disable_rx: bool,
+    pub(crate) flush_rx: bool,
+    pub(crate) enable_tx: bool,
+    pub(crate) disable_tx: bool,
+    pub(crate) flush_tx: bool,
+}
+
+impl Request for ControlRequest {
+    const COMMAND_ID: CommandId = CommandId::UartControl;
+
+    type Response = ControlResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        buffer[0] = (self.enable_rx as u8)
+            | ((self.disable_rx as u8) << 1)
+            | ((self.flush_rx as u8) << 2)
+            | ((self.enable_tx as u8) << 4)
+            | ((self.disable_tx as u8) << 5)
+            | ((self.flush_tx as u8) << 6);
+        Ok(1)
+    }
+
+    fn parse_response(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        Ok(ControlResponse(Status::from_byte(buffer[0])?))
+    }
+}
+
+#[derive(Debug)]
+pub struct ControlResponse(pub(crate) Status);
+
+#[derive(Copy, Clone, Debug)]
+pub struct UartStatus {
+    pub(crate) _rx_enabled: bool,
+    pub(crate) rx_data_lost: bool,
+    pub(crate) framing_error: bool,
+    pub(crate) parity_error: bool,
+    pub(crate) _tx_enabled: bool,
+}
+
+impl From<u8> for UartStatus {
+    fn from(value: u8) -> Self {
+        Self {
+            _rx_enabled: value & (1 << 0) != 0,
+            rx_data_lost: value & (1 << 1) != 0,
+            framing_error: value & (1 << 2) != 0,
+            parity_error: value & (1 << 3) != 0,
+            _tx_enabled: value & (1 << 4) != 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StatusRequest;
+
+impl Request for StatusRequest {
+    const COMMAND_ID: CommandId = CommandId::UartStatus;
+
+    type Response = StatusResponse;
+
+    fn to_bytes(&self, _buffer: &mut [u8]) -> Result<usize, SendError> {
+        Ok(0)
+    }
+
+    fn parse_response(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        if buffer.len() < 9 {
+            return Err(SendError::NotEnoughData);
+        }
+
+        Ok(StatusResponse {
+            _status: UartStatus::from(buffer[0]),
+            _rx_count: buffer
+                .pread_with(1, LE)
+                .map_err(|_| SendError::NotEnoughData)?,
+            _tx_count: buffer
+                .pread_with(5, LE)
+                .map_err(|_| SendError::NotEnoughData)?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct StatusResponse {
+    pub(crate) _status: UartStatus,
+    /// The number of bytes in the receive buffer of the probe.
+    pub(crate) _rx_count: u32,
+    /// The number of bytes in the transmit buffer of the probe.
+    pub(crate) _tx_count: u32,
+}
+
+/// Sends data to the UART of the target, and receives the data the probe has buffered.
+#[derive(Debug)]
+pub struct TransferRequest<'a> {
+    pub(crate) data: &'a [u8],
+}
+
+impl TransferRequest<'_> {
+    /// The number of bytes in a request or response which are not data.
+    pub(crate) const OVERHEAD: usize = 6;
+}
+
+impl Request for TransferRequest<'_> {
+    const COMMAND_ID: CommandId = CommandId::UartTransfer;
+
+    type Response = TransferResponse;
+
+    fn to_bytes(&self, buffer: &mut [u8]) -> Result<usize, SendError> {
+        let len = self.data.len();
+        assert!(
+            buffer.len() >= 2 + len,
+            "Buffer for CMSIS-DAP command is too small. This is a bug, please report it."
+        );
+        buffer[0..2].copy_from_slice(&(len as u16).to_le_bytes());
+        buffer[2..2 + len].copy_from_slice(self.data);
+        Ok(2 + len)
+    }
+
+    fn parse_response(&self, buffer: &[u8]) -> Result<Self::Response, SendError> {
+        if buffer.len() < 5 {
+            return Err(SendError::NotEnoughData);
+        }
+
+        let status = UartStatus::from(buffer[0]);
+        let tx_count: u16 = buffer
+            .pread_with(1, LE)
+            .map_err(|_| SendError::NotEnoughData)?;
+        let rx_count: u16 = buffer
+            .pread_with(3, LE)
+            .map_err(|_| SendError::NotEnoughData)?;
+
+        let end = 5 + rx_count as usize;
+        if end > buffer.len() {
+            return Err(SendError::NotEnoughData);
+        }
+
+        Ok(TransferResponse {
+            status,
+            tx_count: tx_count as usize,
+            data: buffer[5..end].to_vec(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct TransferResponse {
+    pub(crate) status: UartStatus,
+    /// The number of bytes the probe accepted for transmission.
+    pub(crate) tx_count: usize,
+    /// The data received from the target.
+    pub(crate) data: Vec<u8>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transfer_request() {
+        let request = TransferRequest { data: b"ab" };
+        let mut buffer = [0; 8];
+
+        assert_eq!(request.to_bytes(&mut buffer).unwrap(), 4);
+        assert_eq!(&buffer[..4], &[2, 0, b'a', b'b']);
+
+        let response = request
+            .parse_response(&[0b0000_0011, 2, 0, 3, 0, b'x', b'y', b'z', 0])
+            .unwrap();
+        assert!(response.status.rx_data_lost);
+        assert_eq!(response.tx_count, 2);
+        assert_eq!(response.data, b"xyz");
+
+        assert!(matches!(
+            request.parse_response(&[0, 0, 0, 3, 0, b'x']),
+            Err(SendError::NotEnoughData)
+        ));
+    }
+}
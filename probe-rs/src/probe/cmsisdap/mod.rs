@@ -12,10 +12,14 @@ use crate::{
     },
     probe::{
         cmsisdap::commands::{
-            general::info::{CapabilitiesCommand, PacketCountCommand, SWOTraceBufferSizeCommand},
+            general::info::{
+                CapabilitiesCommand, PacketCountCommand, SWOTraceBufferSizeCommand,
+                UartTransmitBufferSizeCommand,
+            },
             CmsisDapError,
         },
-        BatchCommand,
+        common::{scan_chain, select_tap, JtagBitIo},
+        BatchCommand, JtagChainConfig, UartAccess, UartConfig, UartParity, UartStopBits,
     },
     CoreStatus, DebugProbe, DebugProbeError, DebugProbeSelector, WireProtocol,
 };
@@ -28,6 +32,7 @@ use commands::{
         info::Capabilities,
         reset::{ResetRequest, ResetResponse},
     },
+    jtag::{self, sequence::JtagSequence},
     swd,
     swj::{
         clock::{SWJClockRequest, SWJClockResponse},
@@ -40,10 +45,14 @@ use commands::{
         Ack, InnerTransferRequest, TransferBlockRequest, TransferBlockResponse, TransferRequest,
         RW,
     },
-    CmsisDapDevice, Status,
+    uart, CmsisDapDevice, Status,
 };
 
-use std::{result::Result, time::Duration};
+use std::{
+    io,
+    result::Result,
+    time::{Duration, Instant},
+};
 
 pub struct CmsisDap {
     pub device: CmsisDapDevice,
@@ -57,11 +66,24 @@ pub struct CmsisDap {
     swo_buffer_size: Option<usize>,
     swo_active: bool,
     swo_streaming: bool,
+    /// When the trace status was last checked while streaming SWO data.
+    swo_status_checked: Instant,
+    uart_active: bool,
+    /// Data received from the UART of the target while sending data to it.
+    uart_rx_buffer: Vec<u8>,
+    /// Size of the UART transmit buffer of the probe, which limits the data sent per transfer.
+    uart_tx_buffer_size: usize,
     connected: bool,
 
     /// Speed in kHz
     speed_khz: u32,
 
+    /// The configured JTAG scan chain.
+    jtag_chain: JtagChainConfig,
+
+    /// The index of the debugged TAP in the JTAG chain, used as the DAP index of the transfers.
+    jtag_index: u8,
+
     batch: Vec<BatchCommand>,
 }
 
@@ -75,7 +97,11 @@ impl std::fmt::Debug for CmsisDap {
             .field("swo_buffer_size", &self.swo_buffer_size)
             .field("swo_active", &self.swo_active)
             .field("swo_streaming", &self.swo_streaming)
+            .field("uart_active", &self.uart_active)
+            .field("uart_tx_buffer_size", &self.uart_tx_buffer_size)
             .field("speed_khz", &self.speed_khz)
+            .field("jtag_chain", &self.jtag_chain)
+            .field("jtag_index", &self.jtag_index)
             .finish()
     }
 }
@@ -112,8 +138,14 @@ impl CmsisDap {
             swo_buffer_size,
             swo_active: false,
             swo_streaming: false,
+            swo_status_checked: Instant::now(),
+            uart_active: false,
+            uart_rx_buffer: Vec::new(),
+            uart_tx_buffer_size: 0,
             connected: false,
             speed_khz: 1_000,
+            jtag_chain: JtagChainConfig::default(),
+            jtag_index: 0,
             batch: Vec::new(),
         })
     }
@@ -153,6 +185,60 @@ impl CmsisDap {
             })
     }
 
+    fn jtag_configure(
+        &mut self,
+        request: jtag::configure::ConfigureRequest,
+    ) -> Result<(), CmsisDapError> {
+        commands::send_command(&mut self.device, request).and_then(|v| match v {
+            jtag::configure::ConfigureResponse(Status::DAPOk) => Ok(()),
+            jtag::configure::ConfigureResponse(Status::DAPError) => {
+                Err(CmsisDapError::ErrorResponse)
+            }
+        })
+    }
+
+    /// Sends the JTAG sequences, splitting them into as many commands as necessary, and returns
+    /// the captured TDO values.
+    fn send_jtag_sequences(
+        &mut self,
+        sequences: &[JtagSequence],
+    ) -> Result<Vec<bool>, CmsisDapError> {
+        let max_len = self.packet_size as usize - 2;
+        let mut tdo = Vec::new();
+
+        let mut sequences = sequences;
+        while !sequences.is_empty() {
+            // Fill the request and the response, without exceeding the packet size.
+            let mut request_len = 0;
+            let mut response_len = 0;
+            let mut count = 0;
+            for sequence in sequences.iter().take(u8::MAX as usize) {
+                if request_len + 1 + sequence.data_len() > max_len
+                    || response_len + sequence.response_len() > max_len
+                {
+                    break;
+                }
+                request_len += 1 + sequence.data_len();
+                response_len += sequence.response_len();
+                count += 1;
+            }
+
+            let (chunk, rest) = sequences.split_at(count);
+            sequences = rest;
+
+            let response = commands::send_command(
+                &mut self.device,
+                jtag::sequence::SequenceRequest::new(chunk.to_vec()),
+            )?;
+            match response.status {
+                Status::DAPOk => tdo.extend(response.tdo),
+                Status::DAPError => return Err(CmsisDapError::ErrorResponse),
+            }
+        }
+
+        Ok(tdo)
+    }
+
     fn send_swj_sequences(&mut self, request: SequenceRequest) -> Result<(), CmsisDapError> {
         commands::send_command::<SequenceRequest>(&mut self.device, request)
             .map_err(CmsisDapError::from)
@@ -173,12 +259,15 @@ impl CmsisDap {
     fn read_ctrl_register(&mut self) -> Result<Ctrl, ArmError> {
         let response = commands::send_command(
             &mut self.device,
-            TransferRequest::new(&[InnerTransferRequest::new(
-                PortType::DebugPort,
-                RW::R,
-                Ctrl::ADDRESS,
-                None,
-            )]),
+            TransferRequest::new(
+                self.jtag_index,
+                &[InnerTransferRequest::new(
+                    PortType::DebugPort,
+                    RW::R,
+                    Ctrl::ADDRESS,
+                    None,
+                )],
+            ),
         )
         .map_err(CmsisDapError::from)
         .map_err(DebugProbeError::from)?;
@@ -245,7 +334,7 @@ impl CmsisDap {
 
             let response = commands::send_command::<TransferRequest>(
                 &mut self.device,
-                TransferRequest::new(&transfers),
+                TransferRequest::new(self.jtag_index, &transfers),
             )
             .map_err(CmsisDapError::from)
             .map_err(DebugProbeError::from)?;
@@ -397,7 +486,6 @@ impl CmsisDap {
     }
 
     /// Fetch current SWO trace status.
-    #[allow(dead_code)]
     fn get_swo_status(&mut self) -> Result<swo::StatusResponse, DebugProbeError> {
         Ok(commands::send_command(
             &mut self.device,
@@ -429,16 +517,89 @@ impl CmsisDap {
 
                 let response: swo::DataResponse =
                     commands::send_command(&mut self.device, swo::DataRequest { max_count: n })?;
-                if response.status.error {
-                    Err(CmsisDapError::SwoTraceStreamError.into())
-                } else {
-                    Ok(response.data)
-                }
+                response.status.check()?;
+                Ok(response.data)
             }
             None => Ok(Vec::new()),
         }
     }
 
+    /// Read the data the probe has sent on the SWO streaming endpoint.
+    ///
+    /// The trace status is checked every now and then, as it is not part of the stream.
+    fn read_swo_stream(&mut self, timeout: Duration) -> Result<Vec<u8>, DebugProbeError> {
+        let data = self.device.read_swo_stream(timeout)?;
+
+        if self.swo_status_checked.elapsed() >= Duration::from_secs(1) {
+            self.swo_status_checked = Instant::now();
+            let response = self.get_swo_status()?;
+            response.status.check()?;
+        }
+
+        Ok(data)
+    }
+
+    /// Route the UART of the target to the requested transport.
+    ///
+    /// This is only supported by probes which also bridge the UART to a USB COM port.
+    fn set_uart_transport(
+        &mut self,
+        transport: uart::TransportRequest,
+    ) -> Result<(), DebugProbeError> {
+        let response = commands::send_command(&mut self.device, transport)?;
+        match response {
+            uart::TransportResponse(Status::DAPOk) => Ok(()),
+            uart::TransportResponse(Status::DAPError) => Err(CmsisDapError::ErrorResponse.into()),
+        }
+    }
+
+    fn uart_control(&mut self, request: uart::ControlRequest) -> Result<(), DebugProbeError> {
+        let response = commands::send_command(&mut self.device, request)?;
+        match response {
+            uart::ControlResponse(Status::DAPOk) => Ok(()),
+            uart::ControlResponse(Status::DAPError) => Err(CmsisDapError::ErrorResponse.into()),
+        }
+    }
+
+    /// Fetch the current UART status, and the fill level of the probe buffers.
+    #[allow(dead_code)]
+    fn get_uart_status(&mut self) -> Result<uart::StatusResponse, DebugProbeError> {
+        Ok(commands::send_command(
+            &mut self.device,
+            uart::StatusRequest,
+        )?)
+    }
+
+    /// Send data to the UART of the target, and receive the data buffered by the probe.
+    fn uart_transfer(&mut self, data: &[u8]) -> Result<uart::TransferResponse, DebugProbeError> {
+        let response: uart::TransferResponse =
+            commands::send_command(&mut self.device, uart::TransferRequest { data })?;
+
+        let status = response.status;
+        if status.rx_data_lost {
+            tracing::warn!("The probe lost data received from the target UART");
+        }
+        if status.framing_error || status.parity_error {
+            tracing::warn!("Framing or parity error on the target UART, check the configuration");
+        }
+
+        Ok(response)
+    }
+
+    /// Scans the JTAG chain, tells the probe about it and selects the debugged TAP.
+    fn configure_jtag_chain(&mut self) -> Result<(), DebugProbeError> {
+        let chain = self.jtag_chain.clone();
+
+        let taps = scan_chain(self, chain.ir_lengths.as_deref())?;
+        let index = select_tap(&taps, chain.tap)?;
+
+        let ir_lengths = taps.iter().map(|tap| tap.irlen as u8).collect();
+        self.jtag_configure(jtag::configure::ConfigureRequest::new(ir_lengths))?;
+        self.jtag_index = index as u8;
+
+        Ok(())
+    }
+
     fn connect_if_needed(&mut self) -> Result<(), DebugProbeError> {
         if self.connected {
             return Ok(());
@@ -470,6 +631,31 @@ impl CmsisDap {
     }
 }
 
+impl JtagBitIo for CmsisDap {
+    fn clock_bits(&mut self, tms: &[bool], tdi: &[bool], capture: bool) -> io::Result<Vec<bool>> {
+        // Every sequence has a constant TMS value.
+        let mut sequences = Vec::new();
+        let mut start = 0;
+        while start < tms.len() {
+            let len = tms[start..]
+                .iter()
+                .take(JtagSequence::MAX_CYCLES)
+                .take_while(|&&bit| bit == tms[start])
+                .count();
+
+            sequences.push(JtagSequence::new(
+                tms[start],
+                capture,
+                &tdi[start..start + len],
+            ));
+            start += len;
+        }
+
+        self.send_jtag_sequences(&sequences)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+}
+
 impl DebugProbe for CmsisDap {
     fn new_from_selector(
         selector: impl Into<DebugProbeSelector>,
@@ -522,6 +708,10 @@ impl DebugProbe for CmsisDap {
 
         self.configure_swd(swd::configure::ConfigureRequest {})?;
 
+        if self.protocol == Some(WireProtocol::Jtag) {
+            self.configure_jtag_chain()?;
+        }
+
         // Tell the probe we are connected so it can turn on an LED.
         let _: Result<HostStatusResponse, _> =
             commands::send_command(&mut self.device, HostStatusRequest::connected(true));
@@ -556,14 +746,11 @@ impl DebugProbe for CmsisDap {
 
     fn select_protocol(&mut self, protocol: WireProtocol) -> Result<(), DebugProbeError> {
         match protocol {
-            WireProtocol::Jtag => {
-                tracing::warn!(
-                    "Support for JTAG protocol is not yet implemented for CMSIS-DAP based probes."
-                );
+            WireProtocol::Jtag if !self.capabilities.jtag_implemented => {
                 Err(DebugProbeError::UnsupportedProtocol(WireProtocol::Jtag))
             }
-            WireProtocol::Swd => {
-                self.protocol = Some(WireProtocol::Swd);
+            protocol => {
+                self.protocol = Some(protocol);
                 Ok(())
             }
         }
@@ -573,6 +760,11 @@ impl DebugProbe for CmsisDap {
         self.protocol
     }
 
+    fn set_jtag_chain(&mut self, chain: JtagChainConfig) -> Result<(), DebugProbeError> {
+        self.jtag_chain = chain;
+        Ok(())
+    }
+
     /// Asserts the nRESET pin.
     fn target_reset(&mut self) -> Result<(), DebugProbeError> {
         commands::send_command(&mut self.device, ResetRequest).map(|v: ResetResponse| {
@@ -607,6 +799,14 @@ impl DebugProbe for CmsisDap {
         Some(self as _)
     }
 
+    fn get_uart_interface_mut(&mut self) -> Option<&mut dyn UartAccess> {
+        if self.capabilities.uart_communication_port_implemented {
+            Some(self as _)
+        } else {
+            None
+        }
+    }
+
     fn try_get_arm_interface<'probe>(
         self: Box<Self>,
     ) -> Result<Box<dyn UninitializedArmProbe + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)>
@@ -725,8 +925,12 @@ impl RawDapAccess for CmsisDap {
         let data_chunk_len = max_packet_size_words as usize;
 
         for (i, chunk) in values.chunks(data_chunk_len).enumerate() {
-            let request =
-                TransferBlockRequest::write_request(register_address, port, Vec::from(chunk));
+            let request = TransferBlockRequest::write_request(
+                self.jtag_index,
+                register_address,
+                port,
+                Vec::from(chunk),
+            );
 
             tracing::debug!("Transfer block: chunk={}, len={} bytes", i, chunk.len() * 4);
 
@@ -764,8 +968,12 @@ impl RawDapAccess for CmsisDap {
         let data_chunk_len = max_packet_size_words as usize;
 
         for (i, chunk) in values.chunks_mut(data_chunk_len).enumerate() {
-            let request =
-                TransferBlockRequest::read_request(register_address, port, chunk.len() as u16);
+            let request = TransferBlockRequest::read_request(
+                self.jtag_index,
+                register_address,
+                port,
+                chunk.len() as u16,
+            );
 
             tracing::debug!("Transfer block: chunk={}, len={} bytes", i, chunk.len() * 4);
 
@@ -820,7 +1028,7 @@ impl RawDapAccess for CmsisDap {
 impl DapProbe for CmsisDap {}
 
 impl SwoAccess for CmsisDap {
-    fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError> {
+    fn enable_swo(&mut self, config: &SwoConfig) -> Result<u32, ArmError> {
         let caps = self.capabilities;
 
         // Check requested mode is available in probe capabilities
@@ -862,11 +1070,12 @@ impl SwoAccess for CmsisDap {
             SwoMode::Manchester => self.set_swo_mode(swo::ModeRequest::Manchester)?,
         }
 
-        // Set baud rate.
+        // Set baud rate. The probe picks the closest baud rate it supports, which the
+        // target then has to use.
         let baud = self.set_swo_baudrate(swo::BaudrateRequest(config.baud()))?;
         if baud != config.baud() {
-            tracing::warn!(
-                "Target SWO baud rate not met: requested {}, got {}",
+            tracing::debug!(
+                "Probe SWO baud rate differs: requested {}, got {}",
                 config.baud(),
                 baud
             );
@@ -875,7 +1084,8 @@ impl SwoAccess for CmsisDap {
         self.start_swo_capture()?;
 
         self.swo_active = true;
-        Ok(())
+        self.swo_status_checked = Instant::now();
+        Ok(baud)
     }
 
     fn disable_swo(&mut self) -> Result<(), ArmError> {
@@ -888,10 +1098,7 @@ impl SwoAccess for CmsisDap {
     fn read_swo_timeout(&mut self, timeout: Duration) -> Result<Vec<u8>, ArmError> {
         if self.swo_active {
             if self.swo_streaming {
-                let buffer = self
-                    .device
-                    .read_swo_stream(timeout)
-                    .map_err(DebugProbeError::from)?;
+                let buffer = self.read_swo_stream(timeout)?;
                 tracing::trace!("SWO streaming buffer: {:?}", buffer);
                 Ok(buffer)
             } else {
@@ -925,6 +1132,133 @@ impl SwoAccess for CmsisDap {
     }
 }
 
+impl UartAccess for CmsisDap {
+    fn enable_uart(&mut self, config: &UartConfig) -> Result<u32, DebugProbeError> {
+        if !self.capabilities.uart_communication_port_implemented {
+            return Err(CmsisDapError::UartNotAvailable.into());
+        }
+
+        // Probes which bridge the UART to a USB COM port have to be told to use the DAP commands
+        // instead.
+        if self.capabilities.uart_com_port_implemented {
+            self.set_uart_transport(uart::TransportRequest::DapCommand)?;
+        }
+
+        let data_bits = match config.data_bits() {
+            8 => 0,
+            bits @ 5..=7 => bits,
+            _ => return Err(CmsisDapError::UartConfigurationNotSupported.into()),
+        };
+        let parity = match config.parity() {
+            UartParity::None => 0,
+            UartParity::Odd => 1,
+            UartParity::Even => 2,
+        };
+        let stop_bits = match config.stop_bits() {
+            UartStopBits::One => 0,
+            UartStopBits::OneAndHalf => 1,
+            UartStopBits::Two => 2,
+        };
+
+        let response = commands::send_command(
+            &mut self.device,
+            uart::ConfigureRequest {
+                control: data_bits | (parity << 4) | (stop_bits << 6),
+                baud: config.baud(),
+            },
+        )?;
+        if response.data_bits_error
+            || response.parity_error
+            || response.stop_bits_error
+            || response.baud == 0
+        {
+            return Err(CmsisDapError::UartConfigurationNotSupported.into());
+        }
+        if response.baud != config.baud() {
+            tracing::warn!(
+                "Target UART baud rate not met: requested {}, got {}",
+                config.baud(),
+                response.baud
+            );
+        }
+
+        self.uart_control(uart::ControlRequest {
+            enable_rx: true,
+            flush_rx: true,
+            enable_tx: true,
+            flush_tx: true,
+            ..Default::default()
+        })?;
+
+        let tx_buffer_size =
+            commands::send_command(&mut self.device, UartTransmitBufferSizeCommand {})?;
+        tracing::debug!("Probe UART transmit buffer size: {}", tx_buffer_size);
+        self.uart_tx_buffer_size = tx_buffer_size as usize;
+
+        self.uart_rx_buffer.clear();
+        self.uart_active = true;
+        Ok(response.baud)
+    }
+
+    fn disable_uart(&mut self) -> Result<(), DebugProbeError> {
+        tracing::debug!("Stopping UART");
+        self.uart_control(uart::ControlRequest {
+            disable_rx: true,
+            disable_tx: true,
+            ..Default::default()
+        })?;
+
+        if self.capabilities.uart_com_port_implemented {
+            self.set_uart_transport(uart::TransportRequest::UsbComPort)?;
+        }
+
+        self.uart_active = false;
+        Ok(())
+    }
+
+    fn read_uart(&mut self) -> Result<Vec<u8>, DebugProbeError> {
+        if !self.uart_active {
+            return Ok(Vec::new());
+        }
+
+        let mut data = std::mem::take(&mut self.uart_rx_buffer);
+        data.extend(self.uart_transfer(&[])?.data);
+
+        Ok(data)
+    }
+
+    fn write_uart(&mut self, mut data: &[u8]) -> Result<(), DebugProbeError> {
+        if !self.uart_active {
+            return Err(CmsisDapError::UartNotAvailable.into());
+        }
+
+        // A chunk has to fit into a single packet, and the probe cannot accept more than fits
+        // into its transmit buffer.
+        let chunk_size = (self.packet_size as usize - uart::TransferRequest::OVERHEAD)
+            .min(self.uart_tx_buffer_size)
+            .max(1);
+        let mut last_progress = Instant::now();
+
+        while !data.is_empty() {
+            let chunk = &data[..data.len().min(chunk_size)];
+            let response = self.uart_transfer(chunk)?;
+            self.uart_rx_buffer.extend(response.data);
+
+            if response.tx_count > 0 {
+                data = &data[response.tx_count.min(data.len())..];
+                last_progress = Instant::now();
+            } else if last_progress.elapsed() > Duration::from_secs(1) {
+                // The transmit buffer of the probe does not drain.
+                return Err(DebugProbeError::Timeout);
+            } else {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl Drop for CmsisDap {
     fn drop(&mut self) {
         tracing::debug!("Detaching from CMSIS-DAP probe");
@@ -937,6 +1271,10 @@ impl Drop for CmsisDap {
             let _ = self.disable_swo();
         }
 
+        if self.uart_active {
+            let _ = self.disable_uart();
+        }
+
         let _ = self.detach();
     }
 }
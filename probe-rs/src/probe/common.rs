@@ -0,0 +1,472 @@
+//! Scan chain handling shared by the probes which drive the JTAG state machine directly.
+
+use crate::probe::{DebugProbeError, JtagChainConfig, JtagTapSelector};
+use bitvec::{order::Lsb0, slice::BitSlice, vec::BitVec};
+use std::io;
+use std::iter;
+
+/// The TMS sequence which resets all TAPs and moves to RUN-TEST/IDLE.
+const TMS_RESET: [bool; 6] = [true, true, true, true, true, false];
+/// The TMS sequence which moves from RUN-TEST/IDLE to SHIFT-IR.
+const TMS_SHIFT_IR: [bool; 4] = [true, true, false, false];
+/// The TMS sequence which moves from RUN-TEST/IDLE to SHIFT-DR.
+const TMS_SHIFT_DR: [bool; 3] = [true, false, false];
+
+/// The number of TAPs a scan of the chain looks for.
+const MAX_TAP_COUNT: usize = 8;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ScanChainError {
+    #[error("An I/O error occurred while accessing the JTAG chain")]
+    Io(#[from] io::Error),
+    #[error("Invalid IR sequence during the chain scan")]
+    InvalidIrSequence,
+    #[error("The JTAG chain contains {found} TAPs, but {configured} were configured")]
+    TapCountMismatch { found: usize, configured: usize },
+    #[error("No TAPs were found in the JTAG chain")]
+    NoTaps,
+    #[error("The JTAG chain contains no known debug TAP, one has to be selected explicitly")]
+    NoDebugTap,
+    #[error("The JTAG chain contains no TAP {0}")]
+    TapNotFound(JtagTapSelector),
+    #[error("The TAP has an IR length of {0} bits, which is not supported")]
+    UnsupportedIrLength(usize),
+}
+
+impl From<ScanChainError> for DebugProbeError {
+    fn from(e: ScanChainError) -> Self {
+        match e {
+            ScanChainError::NoTaps => DebugProbeError::TargetNotFound,
+            other => DebugProbeError::ProbeSpecific(Box::new(other)),
+        }
+    }
+}
+
+/// A TAP found while scanning the JTAG chain.
+#[derive(Debug)]
+pub(crate) struct JtagChainItem {
+    pub idcode: u32,
+    pub irlen: usize,
+}
+
+/// Describes the position of the selected TAP in the JTAG chain.
+///
+/// The TAPs before the selected one are put into bypass while it is accessed.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ChainParams {
+    pub irpre: usize,
+    pub irpost: usize,
+    pub drpre: usize,
+    pub drpost: usize,
+    pub irlen: usize,
+}
+
+impl ChainParams {
+    /// Computes the parameters for the TAP at `index`, or returns `None` if it is not part of the chain.
+    pub fn from_chain(taps: &[JtagChainItem], index: usize) -> Option<Self> {
+        let tap = taps.get(index)?;
+
+        Some(ChainParams {
+            irpre: taps[..index].iter().map(|tap| tap.irlen).sum(),
+            irpost: taps[index + 1..].iter().map(|tap| tap.irlen).sum(),
+            drpre: index,
+            drpost: taps.len() - index - 1,
+            irlen: tap.irlen,
+        })
+    }
+}
+
+/// Shifting of the IR and DR registers of the whole JTAG chain.
+///
+/// All shifts start and end in the RUN-TEST/IDLE state.
+pub(crate) trait JtagShift {
+    /// Reset and go to RUN-TEST/IDLE
+    fn reset(&mut self) -> io::Result<()>;
+
+    /// Shift to IR, return the shifted out bits and return to IDLE
+    fn transfer_ir(&mut self, data: &[u8], bits: usize) -> io::Result<Vec<u8>>;
+
+    /// Shift to IR and return to IDLE
+    fn shift_ir(&mut self, data: &[u8], bits: usize) -> io::Result<()> {
+        self.transfer_ir(data, bits)?;
+        Ok(())
+    }
+
+    /// Shift to DR, return the shifted out bits and return to IDLE
+    fn transfer_dr(&mut self, data: &[u8], bits: usize) -> io::Result<Vec<u8>>;
+}
+
+/// Probes which clock the TMS and TDI pins one bit at a time.
+pub(crate) trait JtagBitIo {
+    /// Clocks the TMS and TDI bits, and returns the sampled TDO values if `capture` is set.
+    fn clock_bits(&mut self, tms: &[bool], tdi: &[bool], capture: bool) -> io::Result<Vec<bool>>;
+
+    /// Enters a shift state with `tms_prefix`, shifts `bits` bits of `data` and returns to RUN-TEST/IDLE.
+    fn shift_register(
+        &mut self,
+        tms_prefix: &[bool],
+        data: &[u8],
+        bits: usize,
+    ) -> io::Result<Vec<u8>> {
+        if bits == 0 {
+            return Ok(Vec::new());
+        }
+
+        let data = BitSlice::<u8, Lsb0>::from_slice(data);
+
+        // The last bit is shifted while leaving the shift state, followed by UPDATE and IDLE.
+        let tms = tms_prefix
+            .iter()
+            .copied()
+            .chain(iter::repeat(false).take(bits - 1))
+            .chain([true, true, false])
+            .collect::<Vec<_>>();
+        let tdi = iter::repeat(false)
+            .take(tms_prefix.len())
+            .chain(data.iter().by_vals().chain(iter::repeat(false)).take(bits))
+            .chain([false, false])
+            .collect::<Vec<_>>();
+
+        let tdo = self.clock_bits(&tms, &tdi, true)?;
+        if tdo.len() < tms_prefix.len() + bits {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Not enough TDO bits were captured",
+            ));
+        }
+
+        let reply: BitVec<u8, Lsb0> = tdo[tms_prefix.len()..][..bits].iter().collect();
+
+        Ok(reply.into_vec())
+    }
+
+    /// Stays in RUN-TEST/IDLE for the given number of clock cycles.
+    fn idle(&mut self, cycles: usize) -> io::Result<()> {
+        if cycles > 0 {
+            let bits = vec![false; cycles];
+            self.clock_bits(&bits, &bits, false)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: JtagBitIo> JtagShift for T {
+    fn reset(&mut self) -> io::Result<()> {
+        self.clock_bits(&TMS_RESET, &[false; TMS_RESET.len()], false)?;
+        Ok(())
+    }
+
+    fn transfer_ir(&mut self, data: &[u8], bits: usize) -> io::Result<Vec<u8>> {
+        self.shift_register(&TMS_SHIFT_IR, data, bits)
+    }
+
+    fn transfer_dr(&mut self, data: &[u8], bits: usize) -> io::Result<Vec<u8>> {
+        self.shift_register(&TMS_SHIFT_DR, data, bits)
+    }
+}
+
+/// Returns true if the IDCODE belongs to an ARM JTAG debug port.
+pub(crate) fn is_arm_jtag_dp(idcode: u32) -> bool {
+    // JEP106 code of ARM Ltd.
+    const ARM_DESIGNER: u32 = 0x23b;
+    // Upper byte of the part number of the JTAG-DP
+    const JTAG_DP_PART: u32 = 0xba;
+
+    (idcode >> 1) & 0x7ff == ARM_DESIGNER && (idcode >> 20) & 0xff == JTAG_DP_PART
+}
+
+/// Returns the index of the TAP which is debugged if none was selected: the only TAP of the chain,
+/// or a known debug TAP.
+fn default_tap(taps: &[JtagChainItem]) -> Option<usize> {
+    const KNOWN_IDCODES: [u32; 1] = [
+        0x1000563d, // GD32VF103
+    ];
+
+    if taps.len() == 1 {
+        return Some(0);
+    }
+
+    taps.iter()
+        .position(|tap| KNOWN_IDCODES.contains(&tap.idcode))
+        .or_else(|| taps.iter().position(|tap| is_arm_jtag_dp(tap.idcode)))
+}
+
+/// Detects the TAPs in the JTAG chain and their IR lengths.
+///
+/// If the IR lengths are given, starting with the TAP closest to TDO, they are used instead of
+/// detecting them.
+pub(crate) fn scan_chain(
+    probe: &mut impl JtagShift,
+    ir_lengths: Option<&[u8]>,
+) -> Result<Vec<JtagChainItem>, ScanChainError> {
+    probe.reset()?;
+
+    let cmd = vec![0xff; MAX_TAP_COUNT * 4];
+    let r = probe.transfer_dr(&cmd, cmd.len() * 8)?;
+    let mut targets = vec![];
+    for i in 0..MAX_TAP_COUNT {
+        let idcode = u32::from_le_bytes(r[i * 4..(i + 1) * 4].try_into().unwrap());
+        if idcode != 0xffffffff {
+            tracing::debug!("tap found: {:08x}", idcode);
+            let target = JtagChainItem { idcode, irlen: 0 };
+            targets.push(target);
+        } else {
+            break;
+        }
+    }
+
+    probe.reset()?;
+
+    if let Some(ir_lengths) = ir_lengths {
+        if ir_lengths.len() != targets.len() {
+            return Err(ScanChainError::TapCountMismatch {
+                found: targets.len(),
+                configured: ir_lengths.len(),
+            });
+        }
+
+        for (target, irlen) in targets.iter_mut().zip(ir_lengths) {
+            target.irlen = *irlen as usize;
+        }
+
+        return Ok(targets);
+    }
+
+    // Autodetect the targets' IR lengths.
+    //
+    // For many targets, reading the IR right after a reset yields 0b00..001. This allows
+    // autodetecting the IR lengths even when we have multiple targets. For example,
+    // if we read `0b1111111111110001000001` (LSB first) we know the first target in the
+    // chain has an irlen of 6 and the next one has an irlen of 4.
+    //
+    // However, not all targets satisfy this. For example, the esp32c3 shifts out a fixed value
+    // of `0b00101`. This makes the above algorithm to incorrectly detect the IR len as 2.
+    //
+    // Fortunately, we can use a different autodetection algorithm when we only have one target
+    // in the chain, that doesn't rely on the target to shift out a particular value. The key is
+    // the fact that whatever we shift in gets shifted back out, but delayed by the number of bits
+    // in the IR shfit register. So, we shift in lots of `1` bits to fill the shift register with `1`s.
+    // Then we shift in lots of `0` bytes. The output will be something like `0b00000111`, and the
+    // number of ones is the IR length.
+    if targets.len() == 1 {
+        let r = probe.transfer_ir(&[0xFF, 0x00], 16)?;
+
+        let irlen = r[1].count_ones() as usize;
+        targets[0].irlen = irlen;
+        tracing::debug!("tap irlen: {}", irlen);
+    } else {
+        let cmd = vec![0xff; MAX_TAP_COUNT];
+        let mut r = probe.transfer_ir(&cmd, cmd.len() * 8)?;
+
+        let mut ir = 0;
+        let mut irbits = 0;
+        for (i, target) in targets.iter_mut().enumerate() {
+            if (!r.is_empty()) && irbits < 8 {
+                let byte = r[0];
+                r.remove(0);
+                ir |= (byte as u32) << irbits;
+                irbits += 8;
+            }
+            if ir & 0b11 == 0b01 {
+                ir &= !1;
+                let irlen = ir.trailing_zeros();
+                ir >>= irlen;
+                irbits -= irlen;
+                tracing::debug!("tap {} irlen: {}", i, irlen);
+                target.irlen = irlen as usize;
+            } else {
+                tracing::debug!("invalid irlen for tap {}", i);
+                return Err(ScanChainError::InvalidIrSequence);
+            }
+        }
+    }
+
+    Ok(targets)
+}
+
+/// Returns the index of the TAP which is debugged, the selected one or a known debug TAP.
+pub(crate) fn select_tap(
+    taps: &[JtagChainItem],
+    tap: Option<JtagTapSelector>,
+) -> Result<usize, ScanChainError> {
+    if taps.is_empty() {
+        tracing::warn!("no JTAG taps detected");
+        return Err(ScanChainError::NoTaps);
+    }
+
+    let index = match tap {
+        Some(JtagTapSelector::Index(index)) if index < taps.len() => index,
+        Some(JtagTapSelector::IdCode(idcode)) => {
+            taps.iter()
+                .position(|tap| tap.idcode == idcode)
+                .ok_or(ScanChainError::TapNotFound(JtagTapSelector::IdCode(idcode)))?
+        }
+        Some(selector) => return Err(ScanChainError::TapNotFound(selector)),
+        None => default_tap(taps).ok_or(ScanChainError::NoDebugTap)?,
+    };
+
+    tracing::info!(
+        "Using TAP #{} with IDCODE {:#010x} of {} TAPs in the JTAG chain",
+        index,
+        taps[index].idcode,
+        taps.len()
+    );
+
+    Ok(index)
+}
+
+/// Scans the JTAG chain, and returns the parameters of the TAP which is debugged.
+pub(crate) fn configure_chain(
+    probe: &mut impl JtagShift,
+    config: &JtagChainConfig,
+) -> Result<ChainParams, ScanChainError> {
+    let taps = scan_chain(probe, config.ir_lengths.as_deref())?;
+    let index = select_tap(&taps, config.tap)?;
+
+    // NOTE(unwrap): The index is part of the chain.
+    let params = ChainParams::from_chain(&taps, index).unwrap();
+    let irbits = params.irpre + params.irlen + params.irpost;
+    if irbits > 32 {
+        return Err(ScanChainError::UnsupportedIrLength(irbits));
+    }
+
+    tracing::debug!("Target chain params: {:?}", params);
+
+    Ok(params)
+}
+
+/// Writes `address` to the IR of the selected TAP. All other TAPs of the chain are put into bypass.
+pub(crate) fn shift_instruction(
+    probe: &mut impl JtagShift,
+    params: ChainParams,
+    address: u32,
+) -> io::Result<()> {
+    let max_address = (1 << params.irlen) - 1;
+    if address > max_address {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid register address",
+        ));
+    }
+
+    let irbits = params.irpre + params.irlen + params.irpost;
+    assert!(irbits <= 32);
+    let mut ir: u32 = (1 << params.irpre) - 1;
+    ir |= address << params.irpre;
+    ir |= ((1 << params.irpost) - 1) << (params.irpre + params.irlen);
+    probe.shift_ir(&ir.to_le_bytes(), irbits)
+}
+
+/// Transfers `len_bits` of `data` through the DR of the selected TAP, and returns the shifted out bits.
+///
+/// The other TAPs of the chain have to be in bypass. If no data is given, zeroes are shifted in.
+pub(crate) fn transfer_data(
+    probe: &mut impl JtagShift,
+    params: ChainParams,
+    data: Option<&[u8]>,
+    len_bits: usize,
+) -> io::Result<Vec<u8>> {
+    let drbits = params.drpre + len_bits + params.drpost;
+    let request = if let Some(data_slice) = data {
+        let data = BitSlice::<u8, Lsb0>::from_slice(data_slice);
+        let mut data = BitVec::<u8, Lsb0>::from_bitslice(data);
+        data.truncate(len_bits);
+
+        let mut buf = BitVec::<u8, Lsb0>::new();
+        buf.resize(params.drpre, false);
+        buf.append(&mut data);
+        buf.resize(buf.len() + params.drpost, false);
+
+        buf.into_vec()
+    } else {
+        vec![0; (drbits + 7) / 8]
+    };
+    let reply = probe.transfer_dr(&request, drbits)?;
+
+    // Process the reply
+    let mut reply = BitVec::<u8, Lsb0>::from_vec(reply);
+    if params.drpre > 0 {
+        reply = reply.split_off(params.drpre);
+    }
+    reply.truncate(len_bits);
+    let reply = reply.into_vec();
+
+    Ok(reply)
+}
+
+/// Writes `address` to the IR of the selected TAP, and then transfers `len_bits` of `data` through its DR.
+///
+/// All other TAPs of the chain are kept in bypass. If no data is given, zeroes are shifted in.
+pub(crate) fn transfer_register(
+    probe: &mut impl JtagShift,
+    params: ChainParams,
+    address: u32,
+    data: Option<&[u8]>,
+    len_bits: usize,
+) -> io::Result<Vec<u8>> {
+    shift_instruction(probe, params, address)?;
+    transfer_data(probe, params, data, len_bits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn chain() -> Vec<JtagChainItem> {
+        vec![
+            JtagChainItem {
+                idcode: 0x0362d093,
+                irlen: 6,
+            },
+            JtagChainItem {
+                idcode: 0x4ba00477,
+                irlen: 4,
+            },
+            JtagChainItem {
+                idcode: 0x06413041,
+                irlen: 5,
+            },
+        ]
+    }
+
+    #[test]
+    fn chain_params_of_middle_tap() {
+        let params = ChainParams::from_chain(&chain(), 1).unwrap();
+
+        assert_eq!(params.irpre, 6);
+        assert_eq!(params.irpost, 5);
+        assert_eq!(params.drpre, 1);
+        assert_eq!(params.drpost, 1);
+        assert_eq!(params.irlen, 4);
+        assert!(ChainParams::from_chain(&chain(), 3).is_none());
+    }
+
+    #[test]
+    fn default_tap_prefers_debug_ports() {
+        assert_eq!(default_tap(&chain()), Some(1));
+        assert_eq!(default_tap(&chain()[..1]), Some(0));
+        assert_eq!(default_tap(&[chain().remove(0), chain().remove(2)]), None);
+    }
+
+    #[test]
+    fn select_tap_by_index_or_idcode() {
+        let taps = chain();
+
+        let by_index = "2".parse().unwrap();
+        assert_eq!(select_tap(&taps, Some(by_index)).unwrap(), 2);
+
+        let by_idcode = "0x0362d093".parse().unwrap();
+        assert_eq!(select_tap(&taps, Some(by_idcode)).unwrap(), 0);
+
+        assert!(matches!(
+            select_tap(&taps, Some(JtagTapSelector::IdCode(0x12345678))),
+            Err(ScanChainError::TapNotFound(_))
+        ));
+        assert!(matches!(
+            select_tap(&taps, Some(JtagTapSelector::Index(3))),
+            Err(ScanChainError::TapNotFound(_))
+        ));
+        assert!("tap".parse::<JtagTapSelector>().is_err());
+    }
+}
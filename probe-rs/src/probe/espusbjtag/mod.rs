@@ -2,7 +2,7 @@ mod protocol;
 
 use std::{
     convert::TryInto,
-    iter,
+    io,
     time::{Duration, Instant},
 };
 
@@ -14,13 +14,15 @@ use crate::{
         },
         riscv::communication_interface::{RiscvCommunicationInterface, RiscvError},
     },
-    probe::jlink::bits_to_byte,
     DebugProbe, DebugProbeError, DebugProbeSelector, WireProtocol,
 };
 
 use self::protocol::ProtocolHandler;
 
-use super::JTAGAccess;
+use super::common::{
+    configure_chain, shift_instruction, transfer_data, ChainParams, JtagBitIo, JtagShift,
+};
+use super::{JTAGAccess, JtagChainConfig};
 
 pub use protocol::list_espjtag_devices;
 
@@ -32,7 +34,14 @@ pub(crate) struct EspUsbJtag {
     /// accesses to the DMI register
     jtag_idle_cycles: u8,
 
-    current_ir_reg: u32,
+    /// The instruction in the IR of the debugged TAP, if it is known.
+    current_ir_reg: Option<u32>,
+
+    /// The configured JTAG scan chain.
+    jtag_chain: JtagChainConfig,
+
+    /// The position of the debugged TAP in the scan chain, known after attaching.
+    chain_params: Option<ChainParams>,
 
     speed_khz: u32,
 }
@@ -42,249 +51,56 @@ impl EspUsbJtag {
         self.jtag_idle_cycles
     }
 
-    fn read_dr(&mut self, register_bits: usize) -> Result<Vec<u8>, DebugProbeError> {
-        tracing::debug!("Read {} bits from DR", register_bits);
-
-        let tms_enter_shift = [true, false, false];
-
-        // Last bit of data is shifted out when we exit the SHIFT-DR State.
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
-
-        let tms_enter_idle = [true, true, false];
-
-        let mut tms = Vec::with_capacity(register_bits + 7);
-
-        tms.extend_from_slice(&tms_enter_shift);
-        tms.extend(tms_shift_out_value);
-        tms.extend_from_slice(&tms_enter_idle);
-
-        let tdi = iter::repeat(false).take(tms.len() + self.idle_cycles() as usize);
-
-        // We have to stay in the idle cycle a bit
-        tms.extend(iter::repeat(false).take(self.idle_cycles() as usize));
-
-        let mut response = self.protocol.jtag_io(tms, tdi, true)?;
-
-        tracing::trace!("Response: {:?}", response);
-
-        let _remainder = response.split_off(tms_enter_shift.len());
-
-        let mut remaining_bits = register_bits;
-
-        let mut result = Vec::new();
-
-        while remaining_bits >= 8 {
-            let byte = bits_to_byte(response.split_off(8)) as u8;
-            result.push(byte);
-            remaining_bits -= 8;
+    /// Writes the IR of the debugged TAP if necessary, and transfers `len` bits through its DR.
+    fn transfer_register(
+        &mut self,
+        address: u32,
+        data: Option<&[u8]>,
+        len: usize,
+    ) -> Result<Vec<u8>, DebugProbeError> {
+        let params = self.chain_params.ok_or(DebugProbeError::NotAttached)?;
+
+        if self.current_ir_reg != Some(address) {
+            tracing::debug!("Write IR: {:#x}", address);
+            self.current_ir_reg = None;
+            shift_instruction(self, params, address)
+                .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
+            self.current_ir_reg = Some(address);
         }
 
-        // Handle leftover bytes
-        if remaining_bits > 0 {
-            result.push(bits_to_byte(response.split_off(remaining_bits)) as u8);
-        }
+        let result = transfer_data(self, params, data, len)
+            .and_then(|result| {
+                // We have to stay in the idle state a bit
+                self.idle(self.idle_cycles() as usize)?;
+                Ok(result)
+            })
+            .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
 
         tracing::debug!("Read from DR: {:?}", result);
 
         Ok(result)
     }
+}
 
-    /// Write IR register with the specified data. The
-    /// IR register might have an odd length, so the data
-    /// will be truncated to `len` bits. If data has less
-    /// than `len` bits, an error will be returned.
-    fn write_ir(&mut self, data: &[u8], len: usize) -> Result<(), DebugProbeError> {
-        tracing::debug!("Write IR: {:?}, len={}", data, len);
-
-        // Check the bit length, enough data has to be
-        // available
-        if data.len() * 8 < len {
-            todo!("Proper error for incorrect length");
-        }
-
-        // At least one bit has to be sent
-        if len < 1 {
-            todo!("Proper error for incorrect length");
-        }
-
-        let tms_enter_ir_shift = [true, true, false, false];
-
-        // The last bit will be transmitted when exiting the shift state,
-        // so we need to stay in the shift state for one period less than
-        // we have bits to transmit.
-        let tms_data = iter::repeat(false).take(len - 1);
-
-        let tms_enter_idle = [true, true, false];
-
-        let mut tms = Vec::with_capacity(tms_enter_ir_shift.len() + len + tms_enter_idle.len());
-
-        tms.extend_from_slice(&tms_enter_ir_shift);
-        tms.extend(tms_data);
-        tms.extend_from_slice(&tms_enter_idle);
-
-        let tdi_enter_ir_shift = [false, false, false, false];
-
-        // This is one less than the enter idle for tms, because
-        // the last bit is transmitted when exiting the IR shift state
-        let tdi_enter_idle = [false, false];
-
-        let mut tdi = Vec::with_capacity(tdi_enter_ir_shift.len() + len + tdi_enter_idle.len());
-
-        tdi.extend_from_slice(&tdi_enter_ir_shift);
-
-        let num_bytes = len / 8;
-
-        let num_bits = len - (num_bytes * 8);
-
-        for bytes in &data[..num_bytes] {
-            let mut byte = *bytes;
-
-            for _ in 0..8 {
-                tdi.push(byte & 1 == 1);
-
-                byte >>= 1;
-            }
-        }
-
-        if num_bits > 0 {
-            let mut remaining_byte = data[num_bytes];
-
-            for _ in 0..num_bits {
-                tdi.push(remaining_byte & 1 == 1);
-                remaining_byte >>= 1;
-            }
-        }
-
-        tdi.extend_from_slice(&tdi_enter_idle);
-
-        tracing::trace!("tms: {:?}", tms);
-        tracing::trace!("tdi: {:?}", tdi);
-
-        let response = self.protocol.jtag_io(tms, tdi, true)?;
-
-        tracing::trace!("Response: {:?}", response);
-
-        if len >= 8 {
-            return Err(DebugProbeError::NotImplemented(
-                "Not yet implemented for IR registers larger than 8 bit",
-            ));
-        }
-
-        // TODO: Why only store the first 8 bits?
-        self.current_ir_reg = data[0] as u32;
-
-        // Maybe we could return the previous state of the IR register here...
-
-        Ok(())
-    }
-
-    fn write_dr(&mut self, data: &[u8], register_bits: usize) -> Result<Vec<u8>, DebugProbeError> {
-        tracing::debug!("Write DR: {:?}, len={}", data, register_bits);
-
-        let tms_enter_shift = [true, false, false];
-
-        // Last bit of data is shifted out when we exi the SHIFT-DR State
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
-
-        let tms_enter_idle = [true, true, false];
-
-        let mut tms = Vec::with_capacity(register_bits + 7);
-
-        tms.extend_from_slice(&tms_enter_shift);
-        tms.extend(tms_shift_out_value);
-        tms.extend_from_slice(&tms_enter_idle);
-
-        let tdi_enter_shift = [false, false, false];
-
-        let tdi_enter_idle = [false, false];
-
-        // TODO: TDI data
-        let mut tdi =
-            Vec::with_capacity(tdi_enter_shift.len() + tdi_enter_idle.len() + register_bits);
-
-        tdi.extend_from_slice(&tdi_enter_shift);
-
-        let num_bytes = register_bits / 8;
-
-        let num_bits = register_bits - (num_bytes * 8);
-
-        for bytes in &data[..num_bytes] {
-            let mut byte = *bytes;
-
-            for _ in 0..8 {
-                tdi.push(byte & 1 == 1);
-
-                byte >>= 1;
-            }
-        }
-
-        if num_bits > 0 {
-            let mut remaining_byte = data[num_bytes];
-
-            for _ in 0..num_bits {
-                tdi.push(remaining_byte & 1 == 1);
-                remaining_byte >>= 1;
-            }
-        }
-
-        tdi.extend_from_slice(&tdi_enter_idle);
-
-        // We need to stay in the idle cycle a bit
-        tms.extend(iter::repeat(false).take(self.idle_cycles() as usize));
-        tdi.extend(iter::repeat(false).take(self.idle_cycles() as usize));
-
-        let mut response = self.protocol.jtag_io(tms, tdi, true)?;
-
-        tracing::trace!("Response: {:?}", response);
-
-        let _remainder = response.split_off(tms_enter_shift.len());
-
-        let mut remaining_bits = register_bits;
-
-        let mut result = Vec::new();
-
-        while remaining_bits >= 8 {
-            let byte = bits_to_byte(response.split_off(8)) as u8;
-            result.push(byte);
-            remaining_bits -= 8;
-        }
-
-        // Handle leftover bytes
-        if remaining_bits > 0 {
-            result.push(bits_to_byte(response.split_off(remaining_bits)) as u8);
-        }
-
-        tracing::trace!("result: {:?}", result);
+impl JtagBitIo for EspUsbJtag {
+    fn clock_bits(&mut self, tms: &[bool], tdi: &[bool], capture: bool) -> io::Result<Vec<bool>> {
+        let response = self
+            .protocol
+            .jtag_io(tms.iter().copied(), tdi.iter().copied(), capture)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
-        Ok(result)
+        Ok(response.collect())
     }
 }
 
 impl JTAGAccess for EspUsbJtag {
-    fn set_ir_len(&mut self, len: u32) {
-        if len != 5 {
-            panic!("Only IR Length of 5 is currently supported");
-        }
+    fn set_ir_len(&mut self, _len: u32) {
+        // The IR length is detected while scanning the chain.
     }
 
     /// Read the data register
     fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
-        let address_bits = address.to_le_bytes();
-
-        // TODO: This is limited to 5 bit addresses for now
-        if address > 0x1f {
-            return Err(DebugProbeError::NotImplemented(
-                "JTAG Register addresses are fixed to 5 bits",
-            ));
-        }
-
-        if self.current_ir_reg != address {
-            // Write IR register
-            self.write_ir(&address_bits[..1], 5)?;
-        }
-
-        // read DR register
-        self.read_dr(len as usize)
+        self.transfer_register(address, None, len as usize)
     }
 
     /// Write the data register
@@ -294,22 +110,8 @@ impl JTAGAccess for EspUsbJtag {
         data: &[u8],
         len: u32,
     ) -> Result<Vec<u8>, DebugProbeError> {
-        let address_bits = address.to_le_bytes();
-
-        // TODO: This is limited to 5 bit addresses for now
-        if address > 0x1f {
-            return Err(DebugProbeError::NotImplemented(
-                "JTAG Register addresses are fixed to 5 bits",
-            ));
-        }
-
-        if self.current_ir_reg != address {
-            // Write IR register
-            self.write_ir(&address_bits[..1], 5)?;
-        }
-
-        // write DR register
-        self.write_dr(data, len as usize)
+        tracing::debug!("Write DR: {:?}, len={}", data, len);
+        self.transfer_register(address, Some(data), len as usize)
     }
 
     fn set_idle_cycles(&mut self, idle_cycles: u8) {
@@ -330,7 +132,9 @@ impl DebugProbe for EspUsbJtag {
         Ok(Box::new(EspUsbJtag {
             protocol,
             jtag_idle_cycles: 0,
-            current_ir_reg: 1,
+            current_ir_reg: None,
+            jtag_chain: JtagChainConfig::default(),
+            chain_params: None,
             speed_khz: 0,
         }))
     }
@@ -347,6 +151,11 @@ impl DebugProbe for EspUsbJtag {
         Some(WireProtocol::Jtag)
     }
 
+    fn set_jtag_chain(&mut self, chain: JtagChainConfig) -> Result<(), DebugProbeError> {
+        self.jtag_chain = chain;
+        Ok(())
+    }
+
     fn get_name(&self) -> &'static str {
         "Esp USB JTAG"
     }
@@ -373,19 +182,16 @@ impl DebugProbe for EspUsbJtag {
         self.protocol.set_reset(false, false)?;
 
         tracing::debug!("Resetting JTAG chain by setting tms high for 5 bits");
-
-        // Reset JTAG chain (5 times TMS high), and enter idle state afterwards
-        let tms = vec![true, true, true, true, true, false];
-        let tdi = iter::repeat(false).take(6);
-
-        let response: Vec<_> = self.protocol.jtag_io(tms, tdi, false)?.collect();
-
-        tracing::debug!("Response to reset: {:?}", response);
+        self.chain_params = None;
+        self.current_ir_reg = None;
+        JtagShift::reset(self).map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
 
         // try to read the idcode until we have some non-zero bytes
         let start = Instant::now();
         let idcode = loop {
-            let idcode_bytes = self.read_dr(32)?;
+            let idcode_bytes = self
+                .transfer_dr(&[0; 4], 32)
+                .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
             if idcode_bytes.iter().any(|&x| x != 0)
                 || Instant::now().duration_since(start) > Duration::from_secs(1)
             {
@@ -393,7 +199,10 @@ impl DebugProbe for EspUsbJtag {
             }
         };
 
-        tracing::info!("JTAG IDCODE: {:#010x}", idcode);
+        tracing::debug!("JTAG IDCODE of the first TAP: {:#010x}", idcode);
+
+        let chain = self.jtag_chain.clone();
+        self.chain_params = Some(configure_chain(self, &chain)?);
 
         Ok(())
     }
@@ -448,6 +257,10 @@ impl DebugProbe for EspUsbJtag {
         true
     }
 
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        Some(self)
+    }
+
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
@@ -487,25 +487,6 @@ impl<'a> BitIter<'a> {
             bits_left: total_bits,
         }
     }
-
-    /// Splits off another `BitIter` from `self`s current position that will return `count` bits.
-    ///
-    /// After this call, `self` will be advanced by `count` bits.
-    pub fn split_off(&mut self, count: usize) -> BitIter<'a> {
-        assert!(count <= self.bits_left);
-        let other = Self {
-            buf: self.buf,
-            next_bit: self.next_bit,
-            bits_left: count,
-        };
-
-        // Update self
-        let next_byte = (count + self.next_bit as usize) / 8;
-        self.next_bit = (count as u8 + self.next_bit) % 8;
-        self.buf = &self.buf[next_byte..];
-        self.bits_left -= count;
-        other
-    }
 }
 
 impl Iterator for BitIter<'_> {
@@ -12,6 +12,7 @@ use crate::{
         ApAddress, ArmError, ArmProbeInterface, DapAccess, DpAddress, MemoryApInformation,
         PortType, RawDapAccess, SwoAccess,
     },
+    probe::JTAGAccess,
     DebugProbe, DebugProbeError, DebugProbeSelector, Error, Probe, WireProtocol,
 };
 
@@ -25,6 +26,10 @@ pub struct FakeProbe {
 
     dap_register_write_handler:
         Option<Box<dyn Fn(PortType, u8, u32) -> Result<(), ArmError> + Send>>,
+
+    jtag_register_handler:
+        Option<Box<dyn FnMut(u32, &[u8], u32) -> Result<Vec<u8>, DebugProbeError> + Send>>,
+    jtag_idle_cycles: u8,
 }
 
 impl Debug for FakeProbe {
@@ -45,6 +50,9 @@ impl FakeProbe {
 
             dap_register_read_handler: None,
             dap_register_write_handler: None,
+
+            jtag_register_handler: None,
+            jtag_idle_cycles: 0,
         }
     }
 
@@ -66,6 +74,17 @@ impl FakeProbe {
         self.dap_register_write_handler = Some(handler);
     }
 
+    /// This sets the handler for JTAG register accesses, which gets the instruction, the data
+    /// shifted into the register and its length in bits. It returns the data shifted out.
+    /// Can be used to simulate a TAP. Reads shift in zeroes.
+    #[allow(clippy::type_complexity)]
+    pub fn set_jtag_register_handler(
+        &mut self,
+        handler: Box<dyn FnMut(u32, &[u8], u32) -> Result<Vec<u8>, DebugProbeError> + Send>,
+    ) {
+        self.jtag_register_handler = Some(handler);
+    }
+
     /// Makes a generic probe out of the [`FakeProbe`]
     pub fn into_probe(self) -> Probe {
         Probe::from_specific_probe(Box::new(self))
@@ -149,6 +168,45 @@ impl DebugProbe for FakeProbe {
     fn has_arm_interface(&self) -> bool {
         true
     }
+
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        if self.jtag_register_handler.is_some() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl JTAGAccess for FakeProbe {
+    fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
+        let data = vec![0; (len as usize + 7) / 8];
+        self.write_register(address, &data, len)
+    }
+
+    fn set_idle_cycles(&mut self, idle_cycles: u8) {
+        self.jtag_idle_cycles = idle_cycles;
+    }
+
+    fn get_idle_cycles(&self) -> u8 {
+        self.jtag_idle_cycles
+    }
+
+    fn set_ir_len(&mut self, _len: u32) {}
+
+    fn write_register(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        len: u32,
+    ) -> Result<Vec<u8>, DebugProbeError> {
+        let handler = self
+            .jtag_register_handler
+            .as_mut()
+            .ok_or(DebugProbeError::InterfaceNotAvailable("JTAG"))?;
+
+        handler(address, data, len)
+    }
 }
 
 impl RawDapAccess for FakeProbe {
@@ -329,7 +387,7 @@ impl SwoAccess for FakeArmInterface<Initialized> {
     fn enable_swo(
         &mut self,
         _config: &crate::architecture::arm::SwoConfig,
-    ) -> Result<(), ArmError> {
+    ) -> Result<u32, ArmError> {
         unimplemented!()
     }
 
@@ -1,15 +1,20 @@
 use crate::architecture::riscv::communication_interface::RiscvError;
 use crate::architecture::{
-    arm::communication_interface::UninitializedArmProbe,
+    arm::{
+        communication_interface::{DapProbe, UninitializedArmProbe},
+        ArmCommunicationInterface,
+    },
     riscv::communication_interface::RiscvCommunicationInterface,
 };
-use crate::probe::{JTAGAccess, ProbeCreationError};
+use crate::probe::{
+    arm_debug_interface::{ProbeStatistics, RawProtocolIo, SwdSettings},
+    common::{configure_chain, transfer_register, ChainParams, JtagShift, ScanChainError},
+    JTAGAccess, JtagChainConfig, ProbeCreationError,
+};
 use crate::{
     DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeSelector, DebugProbeType, WireProtocol,
 };
-use bitvec::{order::Lsb0, slice::BitSlice, vec::BitVec};
 use rusb::UsbContext;
-use std::convert::TryInto;
 use std::io::{self, Read, Write};
 use std::time::Duration;
 
@@ -22,35 +27,78 @@ use self::commands::{JtagCommand, WriteRegisterCommand};
 
 use super::{BatchExecutionError, CommandResult};
 
-#[derive(Debug)]
-struct JtagChainItem {
-    idcode: u32,
-    irlen: usize,
+/// How SWDIO is connected to the MPSSE pins.
+///
+/// SWCLK is always connected to TCK, and SWDIO is always read through TDO.
+#[derive(Clone, Copy, Debug)]
+enum SwdioWiring {
+    /// SWDIO is connected to TDO, and to TDI through a resistor of a few hundred ohms.
+    Resistor,
+    /// SWDIO is driven from TDI through a buffer, which is enabled by setting the given pins high.
+    DirectionPin(u16),
 }
 
+/// The pin layout of an FTDI based probe.
 #[derive(Clone, Copy, Debug)]
-pub(super) struct ChainParams {
-    irpre: usize,
-    irpost: usize,
-    drpre: usize,
-    drpost: usize,
-    irlen: usize,
+struct FtdiLayout {
+    /// Initial values of the output pins, with ADBUS in the low byte and ACBUS in the high byte.
+    output: u16,
+    /// Direction of the pins, a set bit configures the pin as output.
+    direction: u16,
+    /// How SWDIO is connected, `None` if the probe can only be used for JTAG.
+    swdio: Option<SwdioWiring>,
+}
+
+impl FtdiLayout {
+    /// Minimal values, may not work with all probes
+    const DEFAULT: FtdiLayout = FtdiLayout {
+        output: 0x0008,
+        direction: 0x000b,
+        swdio: Some(SwdioWiring::Resistor),
+    };
+
+    /// Parses a layout given as `<output>:<direction>:<swdio>` in hexadecimal, where `<swdio>` is
+    /// `resistor`, `none`, or the direction pins which enable the SWDIO driver.
+    fn parse(layout: &str) -> Result<Self, ProbeCreationError> {
+        let invalid = ProbeCreationError::Other(
+            "The FTDI layout has to be given as <output>:<direction>:<resistor|none|direction pins>",
+        );
+        let parse_pins = |pins: &str| {
+            u16::from_str_radix(pins.trim_start_matches("0x"), 16)
+                .map_err(|_| ProbeCreationError::Other("The FTDI pins have to be hexadecimal"))
+        };
+
+        match layout.split(':').collect::<Vec<_>>()[..] {
+            [output, direction, swdio] => Ok(FtdiLayout {
+                output: parse_pins(output)?,
+                direction: parse_pins(direction)?,
+                swdio: match swdio {
+                    "resistor" => Some(SwdioWiring::Resistor),
+                    "none" => None,
+                    pins => Some(SwdioWiring::DirectionPin(parse_pins(pins)?)),
+                },
+            }),
+            _ => Err(invalid),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct JtagAdapter {
     device: ftdi::Device,
+    layout: FtdiLayout,
     chain_params: Option<ChainParams>,
 }
 
 impl JtagAdapter {
-    pub fn open(vid: u16, pid: u16) -> Result<Self, ftdi::Error> {
+    fn open(vid: u16, pid: u16, layout: FtdiLayout) -> Result<Self, ftdi::Error> {
         let mut builder = ftdi::Builder::new();
         builder.set_interface(ftdi::Interface::A)?;
         let device = builder.usb_open(vid, pid)?;
 
         Ok(Self {
             device,
+            layout,
             chain_params: None,
         })
     }
@@ -64,13 +112,9 @@ impl JtagAdapter {
         let mut junk = vec![];
         let _ = self.device.read_to_end(&mut junk);
 
-        // Minimal values, may not work with all probes
-        let output: u16 = 0x0008;
-        let direction: u16 = 0x000b;
-        self.device
-            .write_all(&[0x80, output as u8, direction as u8])?;
-        self.device
-            .write_all(&[0x82, (output >> 8) as u8, (direction >> 8) as u8])?;
+        let mut command = vec![];
+        self.append_set_pins(&mut command, self.layout.output);
+        self.device.write_all(&command)?;
 
         // Disable loopback
         self.device.write_all(&[0x85])?;
@@ -78,6 +122,13 @@ impl JtagAdapter {
         Ok(())
     }
 
+    /// Appends the commands setting the output values of all pins to `command`.
+    fn append_set_pins(&self, command: &mut Vec<u8>, output: u16) {
+        let direction = self.layout.direction;
+        command.extend_from_slice(&[0x80, output as u8, direction as u8]);
+        command.extend_from_slice(&[0x82, (output >> 8) as u8, (direction >> 8) as u8]);
+    }
+
     fn read_response(&mut self, size: usize) -> io::Result<Vec<u8>> {
         let timeout = Duration::from_millis(10);
         let mut result = Vec::new();
@@ -205,152 +256,115 @@ impl JtagAdapter {
         Ok(reply)
     }
 
-    /// Reset and go to RUN-TEST/IDLE
-    pub fn reset(&mut self) -> io::Result<()> {
-        self.shift_tms(&[0xff, 0xff, 0xff, 0xff, 0x7f], 40)
-    }
+    /// Clocks out the given TMS and TDI bits, and returns the bits read from TDO.
+    pub fn jtag_io(
+        &mut self,
+        tms: impl IntoIterator<Item = bool>,
+        tdi: impl IntoIterator<Item = bool>,
+    ) -> io::Result<Vec<bool>> {
+        let mut command = vec![];
+        let mut bits = 0;
 
-    /// Execute RUN-TEST/IDLE for a number of cycles
-    pub fn idle(&mut self, cycles: usize) -> io::Result<()> {
-        if cycles == 0 {
-            return Ok(());
+        for (tms, tdi) in tms.into_iter().zip(tdi) {
+            // Clock a single bit out on TMS, with TDI held at the value of bit 7, and read TDO
+            command.extend_from_slice(&[0x6b, 0x00, u8::from(tms) | (u8::from(tdi) << 7)]);
+            bits += 1;
         }
-        let mut buf = vec![];
-        buf.resize((cycles + 7) / 8, 0);
-        self.shift_tms(&buf, cycles)
-    }
 
-    /// Shift to IR and return to IDLE
-    pub fn shift_ir(&mut self, data: &[u8], bits: usize) -> io::Result<()> {
-        self.shift_tms(&[0b0011], 4)?;
-        self.shift_tdi(data, bits)?;
-        self.shift_tms(&[0b01], 2)?;
-        Ok(())
-    }
+        if bits == 0 {
+            return Ok(vec![]);
+        }
 
-    /// Shift to IR and return to IDLE
-    pub fn transfer_ir(&mut self, data: &[u8], bits: usize) -> io::Result<Vec<u8>> {
-        self.shift_tms(&[0b0011], 4)?;
-        let r = self.tranfer_tdi(data, bits)?;
-        self.shift_tms(&[0b01], 2)?;
-        Ok(r)
-    }
+        self.device.write_all(&command)?;
 
-    /// Shift to DR and return to IDLE
-    pub fn transfer_dr(&mut self, data: &[u8], bits: usize) -> io::Result<Vec<u8>> {
-        self.shift_tms(&[0b001], 3)?;
-        let r = self.tranfer_tdi(data, bits)?;
-        self.shift_tms(&[0b01], 2)?;
-        Ok(r)
-    }
+        // The bit read from TDO is shifted in at bit 7 of every response byte
+        let reply = self.read_response(bits)?;
 
-    fn scan(&mut self) -> io::Result<Vec<JtagChainItem>> {
-        let max_device_count = 8;
+        Ok(reply.iter().map(|byte| byte & 0x80 != 0).collect())
+    }
 
-        self.reset()?;
+    /// Clocks out the given bits on SWDIO, and returns the bits read from SWDIO.
+    ///
+    /// Bits with a direction of `false` are driven by the target.
+    pub fn swd_io(
+        &mut self,
+        dir: impl IntoIterator<Item = bool>,
+        swdio: impl IntoIterator<Item = bool>,
+    ) -> io::Result<Vec<bool>> {
+        let bits: Vec<(bool, bool)> = dir.into_iter().zip(swdio).collect();
 
-        let cmd = vec![0xff; max_device_count * 4];
-        let r = self.transfer_dr(&cmd, cmd.len() * 8)?;
-        let mut targets = vec![];
-        for i in 0..max_device_count {
-            let idcode = u32::from_le_bytes(r[i * 4..(i + 1) * 4].try_into().unwrap());
-            if idcode != 0xffffffff {
-                tracing::debug!("tap found: {:08x}", idcode);
-                let target = JtagChainItem { idcode, irlen: 0 };
-                targets.push(target);
-            } else {
-                break;
+        let mut command = vec![];
+        // The number of bits read back in every response byte
+        let mut chunks = vec![];
+        let mut driving = None;
+
+        let mut index = 0;
+        while index < bits.len() {
+            let output = bits[index].0;
+
+            if driving != Some(output) {
+                if let Some(SwdioWiring::DirectionPin(pins)) = self.layout.swdio {
+                    let value = if output {
+                        self.layout.output | pins
+                    } else {
+                        self.layout.output & !pins
+                    };
+                    self.append_set_pins(&mut command, value);
+                }
+                driving = Some(output);
             }
+
+            let count = bits[index..]
+                .iter()
+                .take(8)
+                .take_while(|(dir, _)| *dir == output)
+                .count();
+            let byte = bits[index..index + count]
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, (_, bit))| byte | (u8::from(*bit) << i));
+
+            // Clock the bits out on TDI on the falling edge, and in from TDO on the rising edge
+            command.extend_from_slice(&[0x3b, (count - 1) as u8, byte]);
+            chunks.push(count);
+
+            index += count;
         }
 
-        self.reset()?;
-
-        // Autodetect the targets' IR lengths.
-        //
-        // For many targets, reading the IR right after a reset yields 0b00..001. This allows
-        // autodetecting the IR lengths even when we have multiple targets. For example,
-        // if we read `0b1111111111110001000001` (LSB first) we know the first target in the
-        // chain has an irlen of 6 and the next one has an irlen of 4.
-        //
-        // However, not all targets satisfy this. For example, the esp32c3 shifts out a fixed value
-        // of `0b00101`. This makes the above algorithm to incorrectly detect the IR len as 2.
-        //
-        // Fortunately, we can use a different autodetection algorithm when we only have one target
-        // in the chain, that doesn't rely on the target to shift out a particular value. The key is
-        // the fact that whatever we shift in gets shifted back out, but delayed by the number of bits
-        // in the IR shfit register. So, we shift in lots of `1` bits to fill the shift register with `1`s.
-        // Then we shift in lots of `0` bytes. The output will be something like `0b00000111`, and the
-        // number of ones is the IR length.
-        if targets.len() == 1 {
-            let r = self.transfer_ir(&[0xFF, 0x00], 16)?;
-
-            let irlen = r[1].count_ones() as usize;
-            targets[0].irlen = irlen;
-            tracing::debug!("tap irlen: {}", irlen);
-        } else {
-            let cmd = vec![0xff; max_device_count];
-            let mut r = self.transfer_ir(&cmd, cmd.len() * 8)?;
-
-            let mut ir = 0;
-            let mut irbits = 0;
-            for (i, target) in targets.iter_mut().enumerate() {
-                if (!r.is_empty()) && irbits < 8 {
-                    let byte = r[0];
-                    r.remove(0);
-                    ir |= (byte as u32) << irbits;
-                    irbits += 8;
-                }
-                if ir & 0b11 == 0b01 {
-                    ir &= !1;
-                    let irlen = ir.trailing_zeros();
-                    ir >>= irlen;
-                    irbits -= irlen;
-                    tracing::debug!("tap {} irlen: {}", i, irlen);
-                    target.irlen = irlen as usize;
-                } else {
-                    tracing::debug!("invalid irlen for tap {}", i);
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "Invalid IR sequence during the chain scan",
-                    ));
-                }
-            }
+        if chunks.is_empty() {
+            return Ok(vec![]);
         }
 
-        Ok(targets)
-    }
+        self.device.write_all(&command)?;
 
-    pub fn select_target(&mut self, idcode: u32) -> io::Result<()> {
-        let taps = self.scan()?;
+        let reply = self.read_response(chunks.len())?;
 
-        let mut found = false;
-        let mut params = ChainParams {
-            irpre: 0,
-            irpost: 0,
-            drpre: 0,
-            drpost: 0,
-            irlen: 0,
-        };
-        for tap in taps {
-            if tap.idcode == idcode {
-                params.irlen = tap.irlen;
-                found = true;
-            } else if found {
-                params.irpost += tap.irlen;
-                params.drpost += 1;
-            } else {
-                params.irpre += tap.irlen;
-                params.drpre += 1;
-            }
+        // The bits read are shifted in from bit 7 of every response byte
+        let mut result = Vec::with_capacity(bits.len());
+        for (byte, count) in reply.iter().zip(chunks) {
+            let value = byte >> (8 - count);
+            result.extend((0..count).map(|i| value & (1 << i) != 0));
         }
 
-        if found {
-            tracing::debug!("Target chain params: {:?}", params);
-            self.chain_params = Some(params);
-            Ok(())
-        } else {
-            Err(io::Error::new(io::ErrorKind::NotFound, "target not found"))
+        Ok(result)
+    }
+
+    /// Execute RUN-TEST/IDLE for a number of cycles
+    pub fn idle(&mut self, cycles: usize) -> io::Result<()> {
+        if cycles == 0 {
+            return Ok(());
         }
+        let mut buf = vec![];
+        buf.resize((cycles + 7) / 8, 0);
+        self.shift_tms(&buf, cycles)
+    }
+
+    /// Scans the JTAG chain and selects the TAP which is accessed.
+    pub fn select_target(&mut self, chain: &JtagChainConfig) -> Result<(), ScanChainError> {
+        self.chain_params = None;
+        self.chain_params = Some(configure_chain(self, chain)?);
+
+        Ok(())
     }
 
     fn get_chain_params(&self) -> io::Result<ChainParams> {
@@ -370,56 +384,50 @@ impl JtagAdapter {
         len_bits: usize,
     ) -> io::Result<Vec<u8>> {
         let params = self.get_chain_params()?;
-        let max_address = (1 << params.irlen) - 1;
-        if address > max_address {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "invalid register address",
-            ));
-        }
+        transfer_register(self, params, address, data, len_bits)
+    }
+}
 
-        // Write IR register
-        let irbits = params.irpre + params.irlen + params.irpost;
-        assert!(irbits <= 32);
-        let mut ir: u32 = (1 << params.irpre) - 1;
-        ir |= address << params.irpre;
-        ir |= ((1 << params.irpost) - 1) << (params.irpre + params.irlen);
-        self.shift_ir(&ir.to_le_bytes(), irbits)?;
-
-        let drbits = params.drpre + len_bits + params.drpost;
-        let request = if let Some(data_slice) = data {
-            let data = BitSlice::<u8, Lsb0>::from_slice(data_slice);
-            let mut data = BitVec::<u8, Lsb0>::from_bitslice(data);
-            data.truncate(len_bits);
-
-            let mut buf = BitVec::<u8, Lsb0>::new();
-            buf.resize(params.drpre, false);
-            buf.append(&mut data);
-            buf.resize(buf.len() + params.drpost, false);
-
-            buf.into_vec()
-        } else {
-            vec![0; (drbits + 7) / 8]
-        };
-        let reply = self.transfer_dr(&request, drbits)?;
+impl JtagShift for JtagAdapter {
+    /// Reset and go to RUN-TEST/IDLE
+    fn reset(&mut self) -> io::Result<()> {
+        self.shift_tms(&[0xff, 0xff, 0xff, 0xff, 0x7f], 40)
+    }
 
-        // Process the reply
-        let mut reply = BitVec::<u8, Lsb0>::from_vec(reply);
-        if params.drpre > 0 {
-            reply = reply.split_off(params.drpre);
-        }
-        reply.truncate(len_bits);
-        let reply = reply.into_vec();
+    /// Shift to IR and return to IDLE
+    fn shift_ir(&mut self, data: &[u8], bits: usize) -> io::Result<()> {
+        self.shift_tms(&[0b0011], 4)?;
+        self.shift_tdi(data, bits)?;
+        self.shift_tms(&[0b01], 2)?;
+        Ok(())
+    }
 
-        Ok(reply)
+    /// Shift to IR, return the shifted out bits and return to IDLE
+    fn transfer_ir(&mut self, data: &[u8], bits: usize) -> io::Result<Vec<u8>> {
+        self.shift_tms(&[0b0011], 4)?;
+        let r = self.tranfer_tdi(data, bits)?;
+        self.shift_tms(&[0b01], 2)?;
+        Ok(r)
+    }
+
+    /// Shift to DR, return the shifted out bits and return to IDLE
+    fn transfer_dr(&mut self, data: &[u8], bits: usize) -> io::Result<Vec<u8>> {
+        self.shift_tms(&[0b001], 3)?;
+        let r = self.tranfer_tdi(data, bits)?;
+        self.shift_tms(&[0b01], 2)?;
+        Ok(r)
     }
 }
 
 #[derive(Debug)]
 pub struct FtdiProbe {
     adapter: JtagAdapter,
+    protocol: WireProtocol,
     speed_khz: u32,
     idle_cycles: u8,
+    jtag_chain: JtagChainConfig,
+    swd_settings: SwdSettings,
+    probe_statistics: ProbeStatistics,
 }
 
 impl DebugProbe for FtdiProbe {
@@ -432,23 +440,35 @@ impl DebugProbe for FtdiProbe {
         let DebugProbeSelector {
             vendor_id,
             product_id,
+            ftdi_layout,
             ..
         } = selector.into();
 
         // Only open FTDI-compatible probes
-        if !FTDI_COMPAT_DEVICE_IDS.contains(&(vendor_id, product_id)) {
-            return Err(DebugProbeError::ProbeCouldNotBeCreated(
+        let device = FTDI_COMPAT_DEVICES
+            .iter()
+            .find(|device| device.vendor_id == vendor_id && device.product_id == product_id)
+            .ok_or(DebugProbeError::ProbeCouldNotBeCreated(
                 ProbeCreationError::NotFound,
-            ));
-        }
+            ))?;
 
-        let adapter = JtagAdapter::open(vendor_id, product_id)
+        // The layout of the selector overrides the known one, for boards with other wirings.
+        let layout = match ftdi_layout {
+            Some(layout) => FtdiLayout::parse(&layout)?,
+            None => device.layout,
+        };
+
+        let adapter = JtagAdapter::open(vendor_id, product_id, layout)
             .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
 
         let probe = FtdiProbe {
             adapter,
+            protocol: WireProtocol::Jtag,
             speed_khz: 0,
             idle_cycles: 0,
+            jtag_chain: JtagChainConfig::default(),
+            swd_settings: SwdSettings::default(),
+            probe_statistics: ProbeStatistics::default(),
         };
         tracing::debug!("opened probe: {:?}", probe);
         Ok(Box::new(probe))
@@ -475,34 +495,13 @@ impl DebugProbe for FtdiProbe {
             .attach()
             .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
 
-        let taps = self
-            .adapter
-            .scan()
-            .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
-        if taps.is_empty() {
-            tracing::warn!("no JTAG taps detected");
-            return Err(DebugProbeError::TargetNotFound);
-        }
-        if taps.len() == 1 {
-            self.adapter
-                .select_target(taps[0].idcode)
-                .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
-        } else {
-            let known_idcodes = [
-                0x1000563d, // GD32VF103
-            ];
-            let idcode = taps
-                .iter()
-                .map(|tap| tap.idcode)
-                .find(|idcode| known_idcodes.iter().any(|v| v == idcode));
-            if let Some(idcode) = idcode {
-                self.adapter
-                    .select_target(idcode)
-                    .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
-            } else {
-                return Err(DebugProbeError::TargetNotFound);
-            }
+        if self.protocol == WireProtocol::Swd {
+            // There is no scan chain with SWD, the debug port is selected by the ARM debug sequences.
+            return Ok(());
         }
+
+        self.adapter.select_target(&self.jtag_chain)?;
+
         Ok(())
     }
 
@@ -511,31 +510,33 @@ impl DebugProbe for FtdiProbe {
     }
 
     fn target_reset(&mut self) -> Result<(), DebugProbeError> {
-        tracing::error!("FTDI target_reset");
-        unimplemented!()
+        Err(DebugProbeError::NotImplemented("target_reset"))
     }
 
     fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
-        tracing::error!("FTDI target_reset_assert");
-        unimplemented!()
+        Err(DebugProbeError::NotImplemented("target_reset_assert"))
     }
 
     fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
-        tracing::error!("FTDI target_reset_deassert");
-        unimplemented!()
+        Err(DebugProbeError::NotImplemented("target_reset_deassert"))
     }
 
     fn select_protocol(&mut self, protocol: WireProtocol) -> Result<(), DebugProbeError> {
-        if protocol != WireProtocol::Jtag {
-            Err(DebugProbeError::UnsupportedProtocol(protocol))
-        } else {
-            Ok(())
+        if protocol == WireProtocol::Swd && self.adapter.layout.swdio.is_none() {
+            return Err(DebugProbeError::UnsupportedProtocol(protocol));
         }
+
+        self.protocol = protocol;
+        Ok(())
     }
 
     fn active_protocol(&self) -> Option<WireProtocol> {
-        // Only supports JTAG
-        Some(WireProtocol::Jtag)
+        Some(self.protocol)
+    }
+
+    fn set_jtag_chain(&mut self, chain: JtagChainConfig) -> Result<(), DebugProbeError> {
+        self.jtag_chain = chain;
+        Ok(())
     }
 
     fn try_get_riscv_interface(
@@ -551,6 +552,10 @@ impl DebugProbe for FtdiProbe {
         true
     }
 
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        Some(self)
+    }
+
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
@@ -559,10 +564,52 @@ impl DebugProbe for FtdiProbe {
         self: Box<Self>,
     ) -> Result<Box<dyn UninitializedArmProbe + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)>
     {
-        todo!()
+        let uninitialized_interface = ArmCommunicationInterface::new(self, true);
+
+        Ok(Box::new(uninitialized_interface))
+    }
+
+    fn has_arm_interface(&self) -> bool {
+        true
     }
 }
 
+impl RawProtocolIo for FtdiProbe {
+    fn jtag_io<M, I>(&mut self, tms: M, tdi: I) -> Result<Vec<bool>, DebugProbeError>
+    where
+        M: IntoIterator<Item = bool>,
+        I: IntoIterator<Item = bool>,
+    {
+        self.probe_statistics.report_io();
+
+        self.adapter
+            .jtag_io(tms, tdi)
+            .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))
+    }
+
+    fn swd_io<D, S>(&mut self, dir: D, swdio: S) -> Result<Vec<bool>, DebugProbeError>
+    where
+        D: IntoIterator<Item = bool>,
+        S: IntoIterator<Item = bool>,
+    {
+        self.probe_statistics.report_io();
+
+        self.adapter
+            .swd_io(dir, swdio)
+            .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))
+    }
+
+    fn swd_settings(&self) -> &SwdSettings {
+        &self.swd_settings
+    }
+
+    fn probe_statistics(&mut self) -> &mut ProbeStatistics {
+        &mut self.probe_statistics
+    }
+}
+
+impl DapProbe for FtdiProbe {}
+
 impl JTAGAccess for FtdiProbe {
     fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
         tracing::debug!("read_register({:#x}, {})", address, len);
@@ -752,21 +799,51 @@ impl JTAGAccess for FtdiProbe {
     }
 }
 
-/// (VendorId, ProductId)
-static FTDI_COMPAT_DEVICE_IDS: &[(u16, u16)] = &[
-    (0x0403, 0x6010), // FTDI Ltd. FT2232C/D/H Dual UART/FIFO IC
-    (0x0403, 0x6011), // FTDI Ltd. FT4232H Quad HS USB-UART/FIFO IC
-    (0x0403, 0x6014), // FTDI Ltd. FT232H Single HS USB-UART/FIFO IC
-    (0x15ba, 0x002a), // Olimex Ltd. ARM-USB-TINY-H JTAG interface
+/// A known FTDI based probe.
+struct FtdiDevice {
+    vendor_id: u16,
+    product_id: u16,
+    /// How the MPSSE pins of the probe are connected.
+    layout: FtdiLayout,
+}
+
+static FTDI_COMPAT_DEVICES: &[FtdiDevice] = &[
+    // FTDI Ltd. FT2232C/D/H Dual UART/FIFO IC
+    FtdiDevice {
+        vendor_id: 0x0403,
+        product_id: 0x6010,
+        layout: FtdiLayout::DEFAULT,
+    },
+    // FTDI Ltd. FT4232H Quad HS USB-UART/FIFO IC
+    FtdiDevice {
+        vendor_id: 0x0403,
+        product_id: 0x6011,
+        layout: FtdiLayout::DEFAULT,
+    },
+    // FTDI Ltd. FT232H Single HS USB-UART/FIFO IC
+    FtdiDevice {
+        vendor_id: 0x0403,
+        product_id: 0x6014,
+        layout: FtdiLayout::DEFAULT,
+    },
+    // Olimex Ltd. ARM-USB-TINY-H JTAG interface. With the ARM-JTAG-SWD adapter,
+    // TMS enables the SWDIO driver.
+    FtdiDevice {
+        vendor_id: 0x15ba,
+        product_id: 0x002a,
+        layout: FtdiLayout {
+            swdio: Some(SwdioWiring::DirectionPin(0x0008)),
+            ..FtdiLayout::DEFAULT
+        },
+    },
 ];
 
 fn get_device_info(device: &rusb::Device<rusb::Context>) -> Option<DebugProbeInfo> {
     let d_desc = device.device_descriptor().ok()?;
 
-    if !FTDI_COMPAT_DEVICE_IDS
-        .iter()
-        .any(|(vid, pid)| d_desc.vendor_id() == *vid && d_desc.product_id() == *pid)
-    {
+    if !FTDI_COMPAT_DEVICES.iter().any(|device| {
+        d_desc.vendor_id() == device.vendor_id && d_desc.product_id() == device.product_id
+    }) {
         return None;
     }
 
@@ -805,3 +882,28 @@ pub(crate) fn list_ftdi_devices() -> Vec<DebugProbeInfo> {
         Err(_) => vec![],
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_layout() {
+        let layout = FtdiLayout::parse("0x0018:001b:0010").unwrap();
+        assert_eq!(layout.output, 0x0018);
+        assert_eq!(layout.direction, 0x001b);
+        assert!(matches!(
+            layout.swdio,
+            Some(SwdioWiring::DirectionPin(0x0010))
+        ));
+
+        assert!(matches!(
+            FtdiLayout::parse("0008:000b:resistor").unwrap().swdio,
+            Some(SwdioWiring::Resistor)
+        ));
+        assert!(FtdiLayout::parse("0008:000b:none").unwrap().swdio.is_none());
+
+        assert!(FtdiLayout::parse("0008:000b").is_err());
+        assert!(FtdiLayout::parse("0008:000b:pin").is_err());
+    }
+}
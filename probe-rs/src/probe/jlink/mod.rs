@@ -3,7 +3,7 @@
 use jaylink::{Capability, Interface, JayLink, SpeedConfig, SwoMode};
 
 use std::convert::{TryFrom, TryInto};
-use std::iter;
+use std::io;
 use std::time::{Duration, Instant};
 
 use crate::architecture::arm::{ArmError, RawDapAccess};
@@ -17,14 +17,16 @@ use crate::{
         riscv::communication_interface::RiscvCommunicationInterface,
     },
     probe::{
-        DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeType, JTAGAccess, WireProtocol,
+        DebugProbe, DebugProbeError, DebugProbeInfo, DebugProbeType, JTAGAccess, JtagChainConfig,
+        WireProtocol,
     },
     DebugProbeSelector,
 };
 
-use self::arm::{ProbeStatistics, SwdSettings};
-
-mod arm;
+use super::arm_debug_interface::{ProbeStatistics, RawProtocolIo, SwdSettings};
+use super::common::{
+    configure_chain, shift_instruction, transfer_data, ChainParams, JtagBitIo, JtagShift,
+};
 
 const SWO_BUFFER_SIZE: u16 = 128;
 
@@ -37,8 +39,11 @@ pub(crate) struct JLink {
     /// accesses to the DMI register
     jtag_idle_cycles: u8,
 
-    // JTAG IR register length
-    ir_len: usize,
+    /// The configured JTAG scan chain.
+    jtag_chain: JtagChainConfig,
+
+    /// The position of the debugged TAP in the scan chain, known after attaching with JTAG.
+    chain_params: Option<ChainParams>,
 
     /// Currently selected protocol
     protocol: Option<WireProtocol>,
@@ -46,7 +51,8 @@ pub(crate) struct JLink {
     /// Protocols supported by the connected J-Link probe.
     supported_protocols: Vec<WireProtocol>,
 
-    current_ir_reg: u32,
+    /// The instruction in the IR of the debugged TAP, if it is known.
+    current_ir_reg: Option<u32>,
 
     speed_khz: u32,
 
@@ -83,11 +89,7 @@ impl JLink {
                 // No special protocol request
                 let current_protocol = self.handle.current_interface();
 
-                match current_protocol {
-                    jaylink::Interface::Swd => Ok(WireProtocol::Swd),
-                    jaylink::Interface::Jtag => Ok(WireProtocol::Jtag),
-                    x => unimplemented!("J-Link: Protocol {} is not yet supported.", x),
-                }
+                Ok(WireProtocol::try_from(current_protocol)?)
             }
         } else {
             // Assume JTAG protocol if the probe does not support switching interfaces
@@ -99,220 +101,62 @@ impl JLink {
         }
     }
 
-    fn read_dr(&mut self, register_bits: usize) -> Result<Vec<u8>, DebugProbeError> {
-        tracing::debug!("Read {} bits from DR", register_bits);
-
-        let tms_enter_shift = [true, false, false];
-
-        // Last bit of data is shifted out when we exi the SHIFT-DR State
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
-
-        let tms_enter_idle = [true, true, false];
-
-        let mut tms = Vec::with_capacity(register_bits + 7);
-
-        tms.extend_from_slice(&tms_enter_shift);
-        tms.extend(tms_shift_out_value);
-        tms.extend_from_slice(&tms_enter_idle);
-
-        let tdi = iter::repeat(false).take(tms.len() + self.idle_cycles() as usize);
-
-        // We have to stay in the idle cycle a bit
-        tms.extend(iter::repeat(false).take(self.idle_cycles() as usize));
-
-        let mut response = self.handle.jtag_io(tms, tdi)?;
-
-        tracing::trace!("Response: {:?}", response);
-
-        let _remainder = response.split_off(tms_enter_shift.len());
-
-        let mut remaining_bits = register_bits;
-
-        let mut result = Vec::new();
-
-        while remaining_bits >= 8 {
-            let byte = bits_to_byte(response.split_off(8)) as u8;
-            result.push(byte);
-            remaining_bits -= 8;
-        }
-
-        // Handle leftover bytes
-        if remaining_bits > 0 {
-            result.push(bits_to_byte(response.split_off(remaining_bits)) as u8);
-        }
-
-        tracing::debug!("Read from DR: {:?}", result);
-
-        Ok(result)
-    }
-
-    /// Write IR register with the specified data. The
-    /// IR register might have an odd length, so the dta
-    /// will be truncated to `len` bits. If data has less
-    /// than `len` bits, an error will be returned.
-    fn write_ir(&mut self, data: &[u8], len: usize) -> Result<(), DebugProbeError> {
-        tracing::debug!("Write IR: {:?}, len={}", data, len);
-
-        // Check the bit length, enough data has to be
-        // available
-        if data.len() * 8 < len {
-            todo!("Proper error for incorrect length");
-        }
-
-        // At least one bit has to be sent
-        if len < 1 {
-            todo!("Proper error for incorrect length");
-        }
-
-        let tms_enter_ir_shift = [true, true, false, false];
-
-        // The last bit will be transmitted when exiting the shift state,
-        // so we need to stay in the shift stay for one period less than
-        // we have bits to transmit
-        let tms_data = iter::repeat(false).take(len - 1);
-
-        let tms_enter_idle = [true, true, false];
-
-        let mut tms = Vec::with_capacity(tms_enter_ir_shift.len() + len + tms_enter_ir_shift.len());
-
-        tms.extend_from_slice(&tms_enter_ir_shift);
-        tms.extend(tms_data);
-        tms.extend_from_slice(&tms_enter_idle);
-
-        let tdi_enter_ir_shift = [false, false, false, false];
-
-        // This is one less than the enter idle for tms, because
-        // the last bit is transmitted when exiting the IR shift state
-        let tdi_enter_idle = [false, false];
-
-        let mut tdi = Vec::with_capacity(tdi_enter_ir_shift.len() + tdi_enter_idle.len() + len);
-
-        tdi.extend_from_slice(&tdi_enter_ir_shift);
-
-        let num_bytes = len / 8;
-
-        let num_bits = len - (num_bytes * 8);
-
-        for bytes in &data[..num_bytes] {
-            let mut byte = *bytes;
-
-            for _ in 0..8 {
-                tdi.push(byte & 1 == 1);
-
-                byte >>= 1;
+    /// Checks that the probe is attached using the given protocol.
+    fn check_protocol(&self, protocol: WireProtocol) -> Result<(), DebugProbeError> {
+        match self.protocol {
+            Some(active) if active == protocol => Ok(()),
+            Some(active) => Err(JlinkError::WrongProtocol {
+                requested: protocol,
+                active,
             }
+            .into()),
+            None => Err(DebugProbeError::NotAttached),
         }
-
-        if num_bits > 0 {
-            let mut remaining_byte = data[num_bytes];
-
-            for _ in 0..num_bits {
-                tdi.push(remaining_byte & 1 == 1);
-                remaining_byte >>= 1;
-            }
-        }
-
-        tdi.extend_from_slice(&tdi_enter_idle);
-
-        tracing::trace!("tms: {:?}", tms);
-        tracing::trace!("tdi: {:?}", tdi);
-
-        let response = self.handle.jtag_io(tms, tdi)?;
-
-        tracing::trace!("Response: {:?}", response);
-
-        if len >= 8 {
-            return Err(DebugProbeError::NotImplemented(
-                "Not yet implemented for IR registers larger than 8 bit",
-            ));
-        }
-
-        self.current_ir_reg = data[0] as u32;
-
-        // Maybe we could return the previous state of the IR register here...
-
-        Ok(())
     }
 
-    fn write_dr(&mut self, data: &[u8], register_bits: usize) -> Result<Vec<u8>, DebugProbeError> {
-        tracing::debug!("Write DR: {:?}, len={}", data, register_bits);
-
-        let tms_enter_shift = [true, false, false];
-
-        // Last bit of data is shifted out when we exi the SHIFT-DR State
-        let tms_shift_out_value = iter::repeat(false).take(register_bits - 1);
-
-        let tms_enter_idle = [true, true, false];
-
-        let mut tms = Vec::with_capacity(register_bits + 7);
-
-        tms.extend_from_slice(&tms_enter_shift);
-        tms.extend(tms_shift_out_value);
-        tms.extend_from_slice(&tms_enter_idle);
-
-        let tdi_enter_shift = [false, false, false];
-
-        let tdi_enter_idle = [false, false];
-
-        // TODO: TDI data
-        let mut tdi =
-            Vec::with_capacity(tdi_enter_shift.len() + tdi_enter_idle.len() + register_bits);
-
-        tdi.extend_from_slice(&tdi_enter_shift);
-
-        let num_bytes = register_bits / 8;
-
-        let num_bits = register_bits - (num_bytes * 8);
-
-        for bytes in &data[..num_bytes] {
-            let mut byte = *bytes;
-
-            for _ in 0..8 {
-                tdi.push(byte & 1 == 1);
-
-                byte >>= 1;
-            }
-        }
-
-        if num_bits > 0 {
-            let mut remaining_byte = data[num_bytes];
-
-            for _ in 0..num_bits {
-                tdi.push(remaining_byte & 1 == 1);
-                remaining_byte >>= 1;
-            }
+    /// Writes the IR of the debugged TAP if necessary, and transfers `len` bits through its DR.
+    fn transfer_register(
+        &mut self,
+        address: u32,
+        data: Option<&[u8]>,
+        len: usize,
+    ) -> Result<Vec<u8>, DebugProbeError> {
+        let params = self.chain_params.ok_or(DebugProbeError::NotAttached)?;
+
+        if self.current_ir_reg != Some(address) {
+            tracing::debug!("Write IR: {:#x}", address);
+            self.current_ir_reg = None;
+            shift_instruction(self, params, address)
+                .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
+            self.current_ir_reg = Some(address);
         }
 
-        tdi.extend_from_slice(&tdi_enter_idle);
-
-        // We need to stay in the idle cycle a bit
-        tms.extend(iter::repeat(false).take(self.idle_cycles() as usize));
-        tdi.extend(iter::repeat(false).take(self.idle_cycles() as usize));
-
-        let mut response = self.handle.jtag_io(tms, tdi)?;
-
-        tracing::trace!("Response: {:?}", response);
-
-        let _remainder = response.split_off(tms_enter_shift.len());
+        let result = transfer_data(self, params, data, len)
+            .and_then(|result| {
+                // We have to stay in the idle state a bit
+                self.idle(self.idle_cycles() as usize)?;
+                Ok(result)
+            })
+            .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
 
-        let mut remaining_bits = register_bits;
+        tracing::trace!("DR: {:?}", result);
 
-        let mut result = Vec::new();
+        Ok(result)
+    }
+}
 
-        while remaining_bits >= 8 {
-            let byte = bits_to_byte(response.split_off(8)) as u8;
-            result.push(byte);
-            remaining_bits -= 8;
-        }
+impl JtagBitIo for JLink {
+    fn clock_bits(&mut self, tms: &[bool], tdi: &[bool], capture: bool) -> io::Result<Vec<bool>> {
+        let response = self
+            .handle
+            .jtag_io(tms.iter().copied(), tdi.iter().copied())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
-        // Handle leftover bytes
-        if remaining_bits > 0 {
-            result.push(bits_to_byte(response.split_off(remaining_bits)) as u8);
+        if capture {
+            Ok(response.collect())
+        } else {
+            Ok(Vec::new())
         }
-
-        tracing::trace!("result: {:?}", result);
-
-        Ok(result)
     }
 }
 
@@ -393,9 +237,10 @@ impl DebugProbe for JLink {
             swo_config: None,
             supported_protocols,
             jtag_idle_cycles: 0,
-            ir_len: 0,
+            jtag_chain: JtagChainConfig::default(),
+            chain_params: None,
             protocol: None,
-            current_ir_reg: 1,
+            current_ir_reg: None,
             speed_khz: 0,
             swd_settings: SwdSettings::default(),
             probe_statistics: ProbeStatistics::default(),
@@ -420,6 +265,11 @@ impl DebugProbe for JLink {
         self.protocol
     }
 
+    fn set_jtag_chain(&mut self, chain: JtagChainConfig) -> Result<(), DebugProbeError> {
+        self.jtag_chain = chain;
+        Ok(())
+    }
+
     fn get_name(&self) -> &'static str {
         "J-Link"
     }
@@ -512,20 +362,17 @@ impl DebugProbe for JLink {
                 tracing::debug!("Resetting JTAG chain using trst");
                 self.handle.reset_trst()?;
 
-                tracing::debug!("Resetting JTAG chain by setting tms high for 32 bits");
-
-                // Reset JTAG chain (5 times TMS high), and enter idle state afterwards
-                let tms = vec![true, true, true, true, true, false];
-                let tdi = iter::repeat(false).take(6);
-
-                let response: Vec<_> = self.handle.jtag_io(tms, tdi)?.collect();
-
-                tracing::debug!("Response to reset: {:?}", response);
+                tracing::debug!("Resetting JTAG chain by setting tms high");
+                self.chain_params = None;
+                self.current_ir_reg = None;
+                JtagShift::reset(self).map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
 
                 // try to read the idcode until we have some non-zero bytes
                 let start = Instant::now();
                 let idcode = loop {
-                    let idcode_bytes = self.read_dr(32)?;
+                    let idcode_bytes = self
+                        .transfer_dr(&[0; 4], 32)
+                        .map_err(|e| DebugProbeError::ProbeSpecific(Box::new(e)))?;
                     if idcode_bytes.iter().any(|&x| x != 0)
                         || Instant::now().duration_since(start) > Duration::from_secs(1)
                     {
@@ -533,7 +380,10 @@ impl DebugProbe for JLink {
                     }
                 };
 
-                tracing::info!("JTAG IDCODE: {:#010x}", idcode);
+                tracing::debug!("JTAG IDCODE of the first TAP: {:#010x}", idcode);
+
+                let chain = self.jtag_chain.clone();
+                self.chain_params = Some(configure_chain(self, &chain)?);
             }
             WireProtocol::Swd => {
                 // Attaching is handled in sequence
@@ -597,6 +447,10 @@ impl DebugProbe for JLink {
         self.supported_protocols.contains(&WireProtocol::Jtag)
     }
 
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        Some(self)
+    }
+
     fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
         self
     }
@@ -620,29 +474,59 @@ impl DebugProbe for JLink {
     }
 }
 
+impl RawProtocolIo for JLink {
+    fn jtag_io<M, I>(&mut self, tms: M, tdi: I) -> Result<Vec<bool>, DebugProbeError>
+    where
+        M: IntoIterator<Item = bool>,
+        I: IntoIterator<Item = bool>,
+    {
+        self.check_protocol(WireProtocol::Jtag)?;
+
+        self.probe_statistics.report_io();
+
+        // The sequence might change the state of the TAPs.
+        self.current_ir_reg = None;
+
+        let iter = self.handle.jtag_io(tms, tdi)?;
+
+        Ok(iter.collect())
+    }
+
+    fn swd_io<D, S>(&mut self, dir: D, swdio: S) -> Result<Vec<bool>, DebugProbeError>
+    where
+        D: IntoIterator<Item = bool>,
+        S: IntoIterator<Item = bool>,
+    {
+        self.check_protocol(WireProtocol::Swd)?;
+
+        self.probe_statistics.report_io();
+
+        let iter = self.handle.swd_io(dir, swdio)?;
+
+        Ok(iter.collect())
+    }
+
+    fn swd_settings(&self) -> &SwdSettings {
+        &self.swd_settings
+    }
+
+    fn probe_statistics(&mut self) -> &mut ProbeStatistics {
+        &mut self.probe_statistics
+    }
+}
+
 impl JTAGAccess for JLink {
-    fn set_ir_len(&mut self, len: u32) {
-        self.ir_len = len as usize;
+    fn set_ir_len(&mut self, _len: u32) {
+        // The IR length is detected while scanning the chain.
     }
 
     /// Read the data register
     fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
-        let address_bits = address.to_le_bytes();
-
-        // TODO: This is limited to 5 bit addresses for now
-        if address > 0x1f {
-            return Err(DebugProbeError::NotImplemented(
-                "JTAG Register addresses are fixed to 5 bits",
-            ));
-        }
-
-        if self.current_ir_reg != address {
-            // Write IR register
-            self.write_ir(&address_bits[..1], self.ir_len)?;
+        if len < 1 {
+            return Err(JlinkError::EmptyRegisterAccess.into());
         }
 
-        // read DR register
-        self.read_dr(len as usize)
+        self.transfer_register(address, None, len as usize)
     }
 
     /// Write the data register
@@ -652,22 +536,11 @@ impl JTAGAccess for JLink {
         data: &[u8],
         len: u32,
     ) -> Result<Vec<u8>, DebugProbeError> {
-        let address_bits = address.to_le_bytes();
+        tracing::debug!("Write DR: {:?}, len={}", data, len);
 
-        // TODO: This is limited to 5 bit addresses for now
-        if address > 0x1f {
-            return Err(DebugProbeError::NotImplemented(
-                "JTAG Register addresses are fixed to 5 bits",
-            ));
-        }
+        check_register_length(data, len as usize)?;
 
-        if self.current_ir_reg != address {
-            // Write IR register
-            self.write_ir(&address_bits[..1], self.ir_len)?;
-        }
-
-        // write DR register
-        self.write_dr(data, len as usize)
+        self.transfer_register(address, Some(data), len as usize)
     }
 
     fn set_idle_cycles(&mut self, idle_cycles: u8) {
@@ -682,12 +555,12 @@ impl JTAGAccess for JLink {
 impl DapProbe for JLink {}
 
 impl SwoAccess for JLink {
-    fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError> {
+    fn enable_swo(&mut self, config: &SwoConfig) -> Result<u32, ArmError> {
         self.swo_config = Some(*config);
         self.handle
             .swo_start(SwoMode::Uart, config.baud(), SWO_BUFFER_SIZE.into())
             .map_err(|e| ArmError::from(DebugProbeError::ProbeSpecific(Box::new(e))))?;
-        Ok(())
+        Ok(config.baud())
     }
 
     fn disable_swo(&mut self) -> Result<(), ArmError> {
@@ -706,9 +579,12 @@ impl SwoAccess for JLink {
         let end = std::time::Instant::now() + timeout;
         let mut buf = vec![0; SWO_BUFFER_SIZE.into()];
 
+        let swo_config = self
+            .swo_config
+            .ok_or_else(|| ArmError::from(DebugProbeError::from(JlinkError::SwoNotEnabled)))?;
         let poll_interval = self
-            .swo_poll_interval_hint(&self.swo_config.unwrap())
-            .unwrap();
+            .swo_poll_interval_hint(&swo_config)
+            .unwrap_or(Duration::from_millis(1));
 
         let mut bytes = vec![];
         loop {
@@ -728,18 +604,6 @@ impl SwoAccess for JLink {
     }
 }
 
-pub(crate) fn bits_to_byte(bits: impl IntoIterator<Item = bool>) -> u32 {
-    let mut bit_val = 0u32;
-
-    for (index, bit) in bits.into_iter().take(32).enumerate() {
-        if bit {
-            bit_val |= 1 << index;
-        }
-    }
-
-    bit_val
-}
-
 #[tracing::instrument(skip_all)]
 pub(crate) fn list_jlink_devices() -> Vec<DebugProbeInfo> {
     match jaylink::scan_usb() {
@@ -791,6 +655,44 @@ impl From<jaylink::Error> for DebugProbeError {
 pub enum JlinkError {
     #[error("Unknown interface reported by J-Link: {0:?}")]
     UnknownInterface(jaylink::Interface),
+
+    #[error("A JTAG register access has to transfer at least one bit")]
+    EmptyRegisterAccess,
+
+    #[error("A JTAG register access of {len} bits was requested, but only {available} bits of data were given")]
+    NotEnoughData { len: usize, available: usize },
+
+    #[error("{requested} IO was requested, but the J-Link is attached using {active}")]
+    WrongProtocol {
+        requested: WireProtocol,
+        active: WireProtocol,
+    },
+
+    #[error("SWO data was requested, but SWO is not enabled")]
+    SwoNotEnabled,
+}
+
+impl From<JlinkError> for DebugProbeError {
+    fn from(e: JlinkError) -> DebugProbeError {
+        DebugProbeError::ProbeSpecific(Box::new(e))
+    }
+}
+
+/// Checks that a JTAG register access of `len` bits transfers at least one bit,
+/// and that `data` contains enough bits for it.
+fn check_register_length(data: &[u8], len: usize) -> Result<(), JlinkError> {
+    if len < 1 {
+        return Err(JlinkError::EmptyRegisterAccess);
+    }
+
+    if data.len() * 8 < len {
+        return Err(JlinkError::NotEnoughData {
+            len,
+            available: data.len() * 8,
+        });
+    }
+
+    Ok(())
 }
 
 impl TryFrom<jaylink::Interface> for WireProtocol {
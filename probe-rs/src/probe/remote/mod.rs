@@ -0,0 +1,998 @@
+//! Access to debug probes attached to another machine.
+//!
+//! The probes are exposed by a [`ProbeServer`], for example one started with `probe-rs serve`.
+//! The [`RemoteProbe`] forwards the DAP and JTAG register accesses and the SWO capture to it, so
+//! that everything above the register level runs locally. DAP register writes are queued and sent together
+//! with the next read, to keep the number of round-trips low.
+//!
+//! The same requests are used to record the transactions with a local probe, see [`recording`].
+
+mod protocol;
+pub(crate) mod recording;
+mod server;
+
+pub use protocol::{RemoteError, RemoteProbeError};
+pub use server::ProbeServer;
+
+use self::protocol::{
+    read_message, write_message, DapOperation, JtagWrite, ProbeInfo, Request, Response,
+};
+use crate::architecture::arm::{
+    communication_interface::{DapProbe, UninitializedArmProbe},
+    ArmCommunicationInterface, ArmError, DpAddress, PortType, RawDapAccess, SwoAccess, SwoConfig,
+};
+use crate::architecture::riscv::communication_interface::{
+    RiscvCommunicationInterface, RiscvError,
+};
+use crate::probe::{
+    BatchExecutionError, CommandResult, DebugProbe, DebugProbeError, DebugProbeSelector,
+    JTAGAccess, JtagChainConfig, JtagWriteCommand, NetworkProbeAddress, ProbeCreationError,
+    WireProtocol,
+};
+use crate::CoreStatus;
+use std::fmt;
+use std::io::{BufReader, BufWriter};
+use std::mem;
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Queued DAP operations are sent once they transfer this many words.
+const MAX_QUEUED_WORDS: usize = 1024;
+
+/// An error of a request to the probe server.
+enum CallError {
+    Connection(RemoteProbeError),
+    Remote(RemoteError),
+}
+
+impl From<RemoteProbeError> for CallError {
+    fn from(e: RemoteProbeError) -> Self {
+        CallError::Connection(e)
+    }
+}
+
+impl From<CallError> for DebugProbeError {
+    fn from(e: CallError) -> Self {
+        match e {
+            CallError::Connection(e) => e.into(),
+            CallError::Remote(e) => e.into(),
+        }
+    }
+}
+
+impl From<CallError> for ArmError {
+    fn from(e: CallError) -> Self {
+        match e {
+            CallError::Connection(e) => ArmError::Probe(e.into()),
+            CallError::Remote(e) => e.into(),
+        }
+    }
+}
+
+/// Delivers the requests of a [`RemoteProbe`] and returns their responses.
+trait Transport: fmt::Debug + Send {
+    fn call(&mut self, request: &Request) -> Result<Response, CallError>;
+}
+
+/// The connection to a [`ProbeServer`].
+#[derive(Debug)]
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+impl Transport for Connection {
+    fn call(&mut self, request: &Request) -> Result<Response, CallError> {
+        write_message(&mut self.writer, request)?;
+
+        let response: Result<Response, RemoteError> = read_message(&mut self.reader)?;
+
+        response.map_err(CallError::Remote)
+    }
+}
+
+/// A debug probe attached to a [`ProbeServer`], or a recorded or replayed probe.
+#[derive(Debug)]
+pub struct RemoteProbe {
+    transport: Box<dyn Transport>,
+    name: String,
+    info: ProbeInfo,
+    dap_queue: Vec<DapOperation>,
+    queued_words: usize,
+}
+
+impl RemoteProbe {
+    fn connect(address: &str, selector: &str) -> Result<Self, DebugProbeError> {
+        tracing::debug!("Connecting to probe server at {}", address);
+
+        let stream = TcpStream::connect(address)
+            .map_err(|e| ProbeCreationError::ProbeSpecific(Box::new(e)))?;
+        stream
+            .set_nodelay(true)
+            .map_err(|e| ProbeCreationError::ProbeSpecific(Box::new(e)))?;
+        let reader = BufReader::new(
+            stream
+                .try_clone()
+                .map_err(|e| ProbeCreationError::ProbeSpecific(Box::new(e)))?,
+        );
+
+        let connection = Connection {
+            reader,
+            writer: BufWriter::new(stream),
+        };
+
+        let mut probe = Self::open(Box::new(connection), selector)?;
+        probe.name = format!("{} at {}", probe.info.name, address);
+
+        Ok(probe)
+    }
+
+    /// Opens the probe matching the selector through the transport.
+    fn open(mut transport: Box<dyn Transport>, selector: &str) -> Result<Self, DebugProbeError> {
+        let info = match transport.call(&Request::Open(selector.to_string())) {
+            Ok(Response::Opened(info)) => info,
+            Ok(_) => return Err(RemoteProbeError::UnexpectedResponse.into()),
+            Err(CallError::Connection(e)) => return Err(e.into()),
+            Err(CallError::Remote(e)) => {
+                return Err(ProbeCreationError::ProbeSpecific(Box::new(e)).into())
+            }
+        };
+
+        Ok(Self::new(transport, info))
+    }
+
+    fn new(transport: Box<dyn Transport>, info: ProbeInfo) -> Self {
+        RemoteProbe {
+            transport,
+            name: info.name.clone(),
+            info,
+            dap_queue: Vec::new(),
+            queued_words: 0,
+        }
+    }
+
+    /// Sends a request after the queued DAP operations and waits for its response.
+    fn call(&mut self, request: Request) -> Result<Response, CallError> {
+        self.flush_dap_queue()?;
+        self.transport.call(&request)
+    }
+
+    /// Sends a request which is answered with [`Response::Ok`].
+    fn call_ok(&mut self, request: Request) -> Result<(), CallError> {
+        match self.call(request)? {
+            Response::Ok => Ok(()),
+            _ => Err(RemoteProbeError::UnexpectedResponse.into()),
+        }
+    }
+
+    /// Executes the queued DAP operations, and returns the values read by them.
+    fn flush_dap_queue(&mut self) -> Result<Vec<u32>, CallError> {
+        if self.dap_queue.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let operations = mem::take(&mut self.dap_queue);
+        self.queued_words = 0;
+
+        match self.transport.call(&Request::Dap(operations))? {
+            Response::Dap(values) => Ok(values),
+            _ => Err(RemoteProbeError::UnexpectedResponse.into()),
+        }
+    }
+
+    fn queue_dap_operation(&mut self, operation: DapOperation) -> Result<(), ArmError> {
+        self.queued_words += operation.word_count();
+        self.dap_queue.push(operation);
+
+        if self.queued_words >= MAX_QUEUED_WORDS {
+            self.flush_dap_queue()?;
+        }
+
+        Ok(())
+    }
+
+    fn protocol_response(&mut self, response: Response) -> Result<(), DebugProbeError> {
+        match response {
+            Response::Protocol(protocol) => {
+                self.info.protocol = protocol;
+                Ok(())
+            }
+            _ => Err(RemoteProbeError::UnexpectedResponse.into()),
+        }
+    }
+}
+
+impl DebugProbe for RemoteProbe {
+    fn new_from_selector(
+        selector: impl Into<DebugProbeSelector>,
+    ) -> Result<Box<Self>, DebugProbeError>
+    where
+        Self: Sized,
+    {
+        match selector.into().network_address {
+            Some(NetworkProbeAddress::Tcp { address, selector }) => {
+                Ok(Box::new(Self::connect(&address, &selector)?))
+            }
+            _ => Err(DebugProbeError::ProbeCouldNotBeCreated(
+                ProbeCreationError::NotFound,
+            )),
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn speed_khz(&self) -> u32 {
+        self.info.speed_khz
+    }
+
+    fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
+        match self.call(Request::SetSpeed(speed_khz))? {
+            Response::Speed(speed_khz) => {
+                self.info.speed_khz = speed_khz;
+                Ok(speed_khz)
+            }
+            _ => Err(RemoteProbeError::UnexpectedResponse.into()),
+        }
+    }
+
+    fn attach(&mut self) -> Result<(), DebugProbeError> {
+        let response = self.call(Request::Attach)?;
+        self.protocol_response(response)
+    }
+
+    fn detach(&mut self) -> Result<(), crate::Error> {
+        self.call_ok(Request::Detach)
+            .map_err(DebugProbeError::from)?;
+        Ok(())
+    }
+
+    fn target_reset(&mut self) -> Result<(), DebugProbeError> {
+        Ok(self.call_ok(Request::TargetReset)?)
+    }
+
+    fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+        Ok(self.call_ok(Request::TargetResetAssert)?)
+    }
+
+    fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
+        Ok(self.call_ok(Request::TargetResetDeassert)?)
+    }
+
+    fn select_protocol(&mut self, protocol: WireProtocol) -> Result<(), DebugProbeError> {
+        let response = self.call(Request::SelectProtocol(protocol))?;
+        self.protocol_response(response)
+    }
+
+    fn active_protocol(&self) -> Option<WireProtocol> {
+        self.info.protocol
+    }
+
+    fn set_jtag_chain(&mut self, chain: JtagChainConfig) -> Result<(), DebugProbeError> {
+        Ok(self.call_ok(Request::SetJtagChain(chain))?)
+    }
+
+    fn has_arm_interface(&self) -> bool {
+        self.info.dap && self.info.has_arm_interface
+    }
+
+    fn try_get_arm_interface<'probe>(
+        self: Box<Self>,
+    ) -> Result<Box<dyn UninitializedArmProbe + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)>
+    {
+        if !self.info.dap {
+            return Err((self, DebugProbeError::InterfaceNotAvailable("ARM")));
+        }
+
+        let uninitialized_interface = ArmCommunicationInterface::new(self, true);
+
+        Ok(Box::new(uninitialized_interface))
+    }
+
+    fn has_riscv_interface(&self) -> bool {
+        self.info.jtag && self.info.has_riscv_interface
+    }
+
+    fn try_get_riscv_interface(
+        self: Box<Self>,
+    ) -> Result<RiscvCommunicationInterface, (Box<dyn DebugProbe>, RiscvError)> {
+        if !self.info.jtag {
+            return Err((self, DebugProbeError::InterfaceNotAvailable("RISCV").into()));
+        }
+
+        match RiscvCommunicationInterface::new(self) {
+            Ok(interface) => Ok(interface),
+            Err((probe, err)) => Err((probe.into_probe(), err)),
+        }
+    }
+
+    fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+        self
+    }
+
+    fn try_as_dap_probe(&mut self) -> Option<&mut dyn DapProbe> {
+        if self.info.dap {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        if self.info.jtag {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn get_target_voltage(&mut self) -> Result<Option<f32>, DebugProbeError> {
+        match self.call(Request::GetTargetVoltage)? {
+            Response::Voltage(voltage) => Ok(voltage),
+            _ => Err(RemoteProbeError::UnexpectedResponse.into()),
+        }
+    }
+
+    fn get_swo_interface(&self) -> Option<&dyn SwoAccess> {
+        if self.info.swo {
+            Some(self as _)
+        } else {
+            None
+        }
+    }
+
+    fn get_swo_interface_mut(&mut self) -> Option<&mut dyn SwoAccess> {
+        if self.info.swo {
+            Some(self as _)
+        } else {
+            None
+        }
+    }
+}
+
+impl RawDapAccess for RemoteProbe {
+    fn select_dp(&mut self, dp: DpAddress) -> Result<(), ArmError> {
+        Ok(self.call_ok(Request::SelectDp(dp))?)
+    }
+
+    fn raw_read_register(&mut self, port: PortType, address: u8) -> Result<u32, ArmError> {
+        self.dap_queue.push(DapOperation::Read { port, address });
+
+        let values = self.flush_dap_queue()?;
+
+        values
+            .last()
+            .copied()
+            .ok_or_else(|| ArmError::Probe(RemoteProbeError::UnexpectedResponse.into()))
+    }
+
+    fn raw_read_block(
+        &mut self,
+        port: PortType,
+        address: u8,
+        values: &mut [u32],
+    ) -> Result<(), ArmError> {
+        self.dap_queue.push(DapOperation::ReadBlock {
+            port,
+            address,
+            len: values.len(),
+        });
+
+        let read = self.flush_dap_queue()?;
+        if read.len() != values.len() {
+            return Err(ArmError::Probe(RemoteProbeError::UnexpectedResponse.into()));
+        }
+        values.copy_from_slice(&read);
+
+        Ok(())
+    }
+
+    fn raw_write_register(
+        &mut self,
+        port: PortType,
+        address: u8,
+        value: u32,
+    ) -> Result<(), ArmError> {
+        self.queue_dap_operation(DapOperation::Write {
+            port,
+            address,
+            value,
+        })
+    }
+
+    fn raw_write_block(
+        &mut self,
+        port: PortType,
+        address: u8,
+        values: &[u32],
+    ) -> Result<(), ArmError> {
+        self.queue_dap_operation(DapOperation::WriteBlock {
+            port,
+            address,
+            values: values.to_vec(),
+        })
+    }
+
+    fn raw_flush(&mut self) -> Result<(), ArmError> {
+        self.flush_dap_queue()?;
+        Ok(())
+    }
+
+    fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), DebugProbeError> {
+        Ok(self.call_ok(Request::SwjSequence { bit_len, bits })?)
+    }
+
+    fn swj_pins(
+        &mut self,
+        pin_out: u32,
+        pin_select: u32,
+        pin_wait: u32,
+    ) -> Result<u32, DebugProbeError> {
+        let request = Request::SwjPins {
+            pin_out,
+            pin_select,
+            pin_wait,
+        };
+
+        match self.call(request)? {
+            Response::Value(pins) => Ok(pins),
+            _ => Err(RemoteProbeError::UnexpectedResponse.into()),
+        }
+    }
+
+    fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+        self
+    }
+
+    fn core_status_notification(&mut self, status: CoreStatus) -> Result<(), DebugProbeError> {
+        Ok(self.call_ok(Request::CoreStatusNotification(status))?)
+    }
+}
+
+impl DapProbe for RemoteProbe {}
+
+impl JTAGAccess for RemoteProbe {
+    fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
+        match self.call(Request::JtagReadRegister { address, len })? {
+            Response::Data(data) => Ok(data),
+            _ => Err(RemoteProbeError::UnexpectedResponse.into()),
+        }
+    }
+
+    fn set_idle_cycles(&mut self, idle_cycles: u8) {
+        if let Err(e) = self.call_ok(Request::SetIdleCycles(idle_cycles)) {
+            tracing::warn!(
+                "Failed to set the idle cycles: {}",
+                DebugProbeError::from(e)
+            );
+            return;
+        }
+
+        self.info.idle_cycles = idle_cycles;
+    }
+
+    fn get_idle_cycles(&self) -> u8 {
+        self.info.idle_cycles
+    }
+
+    fn set_ir_len(&mut self, len: u32) {
+        if let Err(e) = self.call_ok(Request::SetIrLen(len)) {
+            tracing::warn!("Failed to set the IR length: {}", DebugProbeError::from(e));
+        }
+    }
+
+    fn write_register(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        len: u32,
+    ) -> Result<Vec<u8>, DebugProbeError> {
+        let request = Request::JtagWriteRegister(JtagWrite {
+            address,
+            data: data.to_vec(),
+            len,
+        });
+
+        match self.call(request)? {
+            Response::Data(data) => Ok(data),
+            _ => Err(RemoteProbeError::UnexpectedResponse.into()),
+        }
+    }
+
+    fn write_register_batch(
+        &mut self,
+        writes: &[JtagWriteCommand],
+    ) -> Result<Vec<CommandResult>, BatchExecutionError> {
+        let request = Request::JtagWriteRegisterBatch(
+            writes
+                .iter()
+                .map(|write| JtagWrite {
+                    address: write.address,
+                    data: write.data.clone(),
+                    len: write.len,
+                })
+                .collect(),
+        );
+
+        let (responses, error) = match self.call(request) {
+            Ok(Response::JtagBatch { results, error }) => (results, error),
+            Ok(_) => {
+                let error = DebugProbeError::from(RemoteProbeError::UnexpectedResponse);
+                return Err(BatchExecutionError::new(error.into(), Vec::new()));
+            }
+            Err(e) => {
+                let error = DebugProbeError::from(e);
+                return Err(BatchExecutionError::new(error.into(), Vec::new()));
+            }
+        };
+
+        // The writes were executed up to the first error, decode their responses like
+        // a local probe would.
+        let mut results = Vec::new();
+        for (write, response) in writes.iter().zip(responses) {
+            match (write.transform)(response) {
+                Ok(result) => results.push(result),
+                Err(e) => return Err(BatchExecutionError::new(e, results)),
+            }
+        }
+
+        match error {
+            Some(e) => Err(BatchExecutionError::new(
+                DebugProbeError::from(e).into(),
+                results,
+            )),
+            None => Ok(results),
+        }
+    }
+}
+
+impl SwoAccess for RemoteProbe {
+    fn enable_swo(&mut self, config: &SwoConfig) -> Result<u32, ArmError> {
+        match self.call(Request::EnableSwo(*config))? {
+            Response::Value(baud) => Ok(baud),
+            _ => Err(ArmError::Probe(RemoteProbeError::UnexpectedResponse.into())),
+        }
+    }
+
+    fn disable_swo(&mut self) -> Result<(), ArmError> {
+        Ok(self.call_ok(Request::DisableSwo)?)
+    }
+
+    fn read_swo_timeout(&mut self, timeout: Duration) -> Result<Vec<u8>, ArmError> {
+        match self.call(Request::ReadSwo(timeout))? {
+            Response::Data(data) => Ok(data),
+            _ => Err(ArmError::Probe(RemoteProbeError::UnexpectedResponse.into())),
+        }
+    }
+
+    fn swo_buffer_size(&mut self) -> Option<usize> {
+        self.info.swo_buffer_size
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::architecture::arm::communication_interface::DapError;
+    use std::collections::HashMap;
+    use std::io::{self, Cursor, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+
+    /// Reading this AP register fails with a FAULT response.
+    const FAULT_REGISTER: u8 = 0xfc;
+
+    /// A probe which stores the written DAP registers, and returns the previous value of JTAG registers on writes.
+    #[derive(Debug, Default)]
+    struct TestProbe {
+        /// The DAP registers, by access port flag and address.
+        dap_registers: HashMap<(bool, u8), u32>,
+        jtag_registers: HashMap<u32, Vec<u8>>,
+        idle_cycles: u8,
+        /// The probe implements the ARM interface itself, like an ST-Link.
+        no_dap_access: bool,
+        swo: Option<SwoConfig>,
+    }
+
+    impl DebugProbe for TestProbe {
+        fn new_from_selector(
+            _selector: impl Into<DebugProbeSelector>,
+        ) -> Result<Box<Self>, DebugProbeError>
+        where
+            Self: Sized,
+        {
+            Ok(Box::default())
+        }
+
+        fn get_name(&self) -> &str {
+            "Test probe"
+        }
+
+        fn speed_khz(&self) -> u32 {
+            1000
+        }
+
+        fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
+            Err(DebugProbeError::UnsupportedSpeed(speed_khz))
+        }
+
+        fn attach(&mut self) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn detach(&mut self) -> Result<(), crate::Error> {
+            Ok(())
+        }
+
+        fn target_reset(&mut self) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn select_protocol(&mut self, _protocol: WireProtocol) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn active_protocol(&self) -> Option<WireProtocol> {
+            Some(WireProtocol::Swd)
+        }
+
+        fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+            self
+        }
+
+        fn has_arm_interface(&self) -> bool {
+            true
+        }
+
+        fn try_as_dap_probe(&mut self) -> Option<&mut dyn DapProbe> {
+            if self.no_dap_access {
+                None
+            } else {
+                Some(self)
+            }
+        }
+
+        fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+            Some(self)
+        }
+
+        fn get_swo_interface(&self) -> Option<&dyn SwoAccess> {
+            Some(self as _)
+        }
+
+        fn get_swo_interface_mut(&mut self) -> Option<&mut dyn SwoAccess> {
+            Some(self as _)
+        }
+    }
+
+    impl SwoAccess for TestProbe {
+        fn enable_swo(&mut self, config: &SwoConfig) -> Result<u32, ArmError> {
+            self.swo = Some(*config);
+            Ok(config.baud() / 2)
+        }
+
+        fn disable_swo(&mut self) -> Result<(), ArmError> {
+            self.swo = None;
+            Ok(())
+        }
+
+        fn read_swo_timeout(&mut self, _timeout: Duration) -> Result<Vec<u8>, ArmError> {
+            match self.swo {
+                Some(_) => Ok(vec![1, 2, 3]),
+                None => Err(ArmError::Timeout),
+            }
+        }
+
+        fn swo_buffer_size(&mut self) -> Option<usize> {
+            Some(64)
+        }
+    }
+
+    impl RawDapAccess for TestProbe {
+        fn select_dp(&mut self, _dp: DpAddress) -> Result<(), ArmError> {
+            Ok(())
+        }
+
+        fn raw_read_register(&mut self, port: PortType, address: u8) -> Result<u32, ArmError> {
+            if port == PortType::AccessPort && address == FAULT_REGISTER {
+                return Err(DapError::FaultResponse.into());
+            }
+
+            Ok(self
+                .dap_registers
+                .get(&(port == PortType::AccessPort, address))
+                .copied()
+                .unwrap_or(0))
+        }
+
+        fn raw_write_register(
+            &mut self,
+            port: PortType,
+            address: u8,
+            value: u32,
+        ) -> Result<(), ArmError> {
+            self.dap_registers
+                .insert((port == PortType::AccessPort, address), value);
+            Ok(())
+        }
+
+        fn swj_sequence(&mut self, _bit_len: u8, _bits: u64) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn swj_pins(
+            &mut self,
+            pin_out: u32,
+            _pin_select: u32,
+            _pin_wait: u32,
+        ) -> Result<u32, DebugProbeError> {
+            Ok(pin_out)
+        }
+
+        fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+            self
+        }
+
+        fn core_status_notification(&mut self, _: CoreStatus) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+    }
+
+    impl DapProbe for TestProbe {}
+
+    impl JTAGAccess for TestProbe {
+        fn read_register(&mut self, address: u32, _len: u32) -> Result<Vec<u8>, DebugProbeError> {
+            Ok(self
+                .jtag_registers
+                .get(&address)
+                .cloned()
+                .unwrap_or_default())
+        }
+
+        fn set_idle_cycles(&mut self, idle_cycles: u8) {
+            self.idle_cycles = idle_cycles;
+        }
+
+        fn get_idle_cycles(&self) -> u8 {
+            self.idle_cycles
+        }
+
+        fn set_ir_len(&mut self, _len: u32) {}
+
+        fn write_register(
+            &mut self,
+            address: u32,
+            data: &[u8],
+            _len: u32,
+        ) -> Result<Vec<u8>, DebugProbeError> {
+            Ok(self
+                .jtag_registers
+                .insert(address, data.to_vec())
+                .unwrap_or_default())
+        }
+    }
+
+    /// Starts a server for a single client using a [`TestProbe`], and connects to it.
+    fn connect() -> (Box<RemoteProbe>, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            server::serve_client(stream, |_| Ok(Box::<TestProbe>::default())).unwrap();
+        });
+
+        let selector = DebugProbeSelector::try_from(format!("tcp://{address}/").as_str()).unwrap();
+        let probe = RemoteProbe::new_from_selector(selector).unwrap();
+
+        (probe, server)
+    }
+
+    #[test]
+    fn probe_info() {
+        let (mut probe, server) = connect();
+
+        assert!(probe.get_name().starts_with("Test probe at 127.0.0.1:"));
+        assert_eq!(probe.speed_khz(), 1000);
+        assert_eq!(probe.active_protocol(), Some(WireProtocol::Swd));
+        assert!(probe.try_as_dap_probe().is_some());
+        assert!(matches!(
+            probe.set_speed(4000),
+            Err(DebugProbeError::UnsupportedSpeed(4000))
+        ));
+
+        drop(probe);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn dap_writes_are_queued() {
+        let (mut probe, server) = connect();
+
+        probe
+            .raw_write_register(PortType::DebugPort, 0x8, 0x1234)
+            .unwrap();
+        probe
+            .raw_write_block(PortType::AccessPort, 0xc, &[1, 2, 3])
+            .unwrap();
+        assert_eq!(probe.dap_queue.len(), 2);
+        assert_eq!(probe.queued_words, 4);
+
+        assert_eq!(
+            probe.raw_read_register(PortType::DebugPort, 0x8).unwrap(),
+            0x1234
+        );
+        assert!(probe.dap_queue.is_empty());
+
+        let mut values = [0; 2];
+        probe
+            .raw_read_block(PortType::AccessPort, 0xc, &mut values)
+            .unwrap();
+        assert_eq!(values, [3, 3]);
+
+        drop(probe);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn dap_errors_are_forwarded() {
+        let (mut probe, server) = connect();
+
+        assert!(matches!(
+            probe.raw_read_register(PortType::AccessPort, FAULT_REGISTER),
+            Err(ArmError::Dap(DapError::FaultResponse))
+        ));
+
+        drop(probe);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn jtag_write_batch() {
+        let (mut probe, server) = connect();
+
+        probe.set_idle_cycles(7);
+        assert_eq!(probe.get_idle_cycles(), 7);
+
+        let write = |data: u8| JtagWriteCommand {
+            address: 0x11,
+            data: vec![data],
+            len: 8,
+            transform: |data| Ok(CommandResult::U8(data.first().copied().unwrap_or(0))),
+        };
+
+        let results = probe
+            .write_register_batch(&[write(1), write(2), write(3)])
+            .unwrap();
+        let results = results
+            .into_iter()
+            .map(|result| match result {
+                CommandResult::U8(value) => value,
+                other => panic!("unexpected result {other:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(results, [0, 1, 2]);
+
+        assert_eq!(probe.read_register(0x11, 8).unwrap(), [3]);
+
+        drop(probe);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn swo_is_forwarded() {
+        let (mut probe, server) = connect();
+
+        let swo = probe.get_swo_interface_mut().unwrap();
+        assert_eq!(swo.swo_buffer_size(), Some(64));
+        assert!(matches!(swo.read_swo(), Err(ArmError::Timeout)));
+        assert_eq!(
+            swo.enable_swo(&SwoConfig::new(8_000_000).set_baud(2_000_000))
+                .unwrap(),
+            1_000_000
+        );
+        assert_eq!(swo.read_swo().unwrap(), [1, 2, 3]);
+        swo.disable_swo().unwrap();
+
+        drop(probe);
+        server.join().unwrap();
+    }
+
+    /// A log which can still be read after the recorded probe is dropped.
+    #[derive(Clone, Default)]
+    struct SharedLog(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedLog {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Runs the same accesses on the recorded and on the replayed probe.
+    fn run_session(probe: &mut RemoteProbe) {
+        probe
+            .raw_write_register(PortType::DebugPort, 0x8, 0x1234)
+            .unwrap();
+        assert_eq!(
+            probe.raw_read_register(PortType::DebugPort, 0x8).unwrap(),
+            0x1234
+        );
+        assert!(matches!(
+            probe.raw_read_register(PortType::AccessPort, FAULT_REGISTER),
+            Err(ArmError::Dap(DapError::FaultResponse))
+        ));
+        assert_eq!(probe.write_register(0x11, &[5], 8).unwrap(), []);
+        assert_eq!(probe.read_register(0x11, 8).unwrap(), [5]);
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let log = SharedLog::default();
+        let mut probe = recording::record(Box::<TestProbe>::default(), log.clone()).unwrap();
+        assert_eq!(probe.get_name(), "Test probe");
+        run_session(&mut probe);
+        drop(probe);
+
+        let log = log.0.lock().unwrap().clone();
+        let mut replay = recording::replay(Cursor::new(log)).unwrap();
+        assert_eq!(replay.get_name(), "Test probe (replay)");
+        assert_eq!(replay.active_protocol(), Some(WireProtocol::Swd));
+        run_session(&mut replay);
+
+        assert!(matches!(
+            replay.attach(),
+            Err(DebugProbeError::ProbeSpecific(_))
+        ));
+    }
+
+    #[test]
+    fn replay_detects_different_requests() {
+        let log = SharedLog::default();
+        let mut probe = recording::record(Box::<TestProbe>::default(), log.clone()).unwrap();
+        run_session(&mut probe);
+        drop(probe);
+
+        let log = log.0.lock().unwrap().clone();
+        let mut replay = recording::replay(Cursor::new(log)).unwrap();
+        replay
+            .raw_write_register(PortType::DebugPort, 0x8, 0x4321)
+            .unwrap();
+        assert!(matches!(
+            replay.raw_read_register(PortType::DebugPort, 0x8),
+            Err(ArmError::Probe(DebugProbeError::ProbeSpecific(_)))
+        ));
+    }
+
+    #[test]
+    fn record_requires_dap_access() {
+        let probe = TestProbe {
+            no_dap_access: true,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            recording::record(Box::new(probe), SharedLog::default()),
+            Err(DebugProbeError::ProbeSpecific(_))
+        ));
+    }
+
+    #[test]
+    fn replay_rejects_other_files() {
+        assert!(recording::replay(Cursor::new(b"not a recording".to_vec())).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+//! Messages exchanged between the probe server and its clients.
+//!
+//! Every message is encoded with `bincode` and prefixed with its length as a little endian `u32`.
+//! The client sends a [`Request`] and waits for the server to answer it with a
+//! `Result<Response, RemoteError>`, there is no other traffic on the connection.
+
+use crate::architecture::arm::{
+    communication_interface::DapError, ArmError, DpAddress, PortType, SwoConfig,
+};
+use crate::probe::JtagChainConfig;
+use crate::{CoreStatus, DebugProbeError, WireProtocol};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+/// Messages larger than this are rejected, to avoid allocating huge buffers for corrupted data.
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Errors of the connection between the probe server and a client, or of a recording.
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteProbeError {
+    /// The connection failed.
+    #[error("An I/O error occurred in the connection to the probe server")]
+    Io(#[from] io::Error),
+    /// A message could not be serialized or deserialized.
+    #[error("A message could not be encoded or decoded")]
+    Encoding(#[from] bincode::Error),
+    /// A message is too large to be sent or received.
+    #[error("The message size of {0} bytes exceeds the maximum size")]
+    MessageTooLarge(usize),
+    /// The response does not match the request.
+    #[error("The probe server sent an unexpected response")]
+    UnexpectedResponse,
+    /// The file does not contain a recording of probe transactions.
+    #[error("The file is not a probe-rs recording")]
+    InvalidRecording,
+    /// The probe cannot be recorded.
+    #[error("The probe cannot be recorded, as it does not give raw access to the {0} registers")]
+    NotRecordable(&'static str),
+    /// All recorded transactions were replayed.
+    #[error("The end of the recording was reached")]
+    RecordingEnded,
+    /// A request differs from the recorded one.
+    #[error(
+        "Transaction {index} differs from the recording: expected {recorded}, got {requested}"
+    )]
+    RecordingMismatch {
+        /// The index of the transaction in the recording.
+        index: usize,
+        /// The recorded request.
+        recorded: String,
+        /// The request made during the replay.
+        requested: String,
+    },
+}
+
+impl From<RemoteProbeError> for DebugProbeError {
+    fn from(e: RemoteProbeError) -> Self {
+        DebugProbeError::ProbeSpecific(Box::new(e))
+    }
+}
+
+/// An error which occurred on the probe server while executing a request.
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize, Deserialize)]
+pub enum RemoteError {
+    /// An access port or debug port returned an error.
+    #[error("An error occurred in the communication with an access port or debug port.")]
+    Dap(DapError),
+    /// An operation timed out.
+    #[error("Timeout occurred during operation.")]
+    Timeout,
+    /// The probe is not attached to the target.
+    #[error("You need to be attached to the target to perform this action")]
+    NotAttached,
+    /// The probe is attached to the target.
+    #[error("You need to be detached from the target to perform this action")]
+    Attached,
+    /// The target could not be found.
+    #[error("Failed to find the target or attach to the target")]
+    TargetNotFound,
+    /// The probe does not support the protocol.
+    #[error("Probe does not support {0}")]
+    UnsupportedProtocol(WireProtocol),
+    /// The probe does not support the speed.
+    #[error("The requested speed setting ({0} kHz) is not supported by the probe")]
+    UnsupportedSpeed(u32),
+    /// The client did not open a probe yet.
+    #[error("No probe was opened on the server")]
+    NoProbe,
+    /// Any other error, with the messages of the error and its sources.
+    #[error("{0}")]
+    Other(String),
+}
+
+impl RemoteError {
+    fn other(error: &dyn std::error::Error) -> Self {
+        let mut message = error.to_string();
+
+        let mut source = error.source();
+        while let Some(error) = source {
+            message.push_str(": ");
+            message.push_str(&error.to_string());
+            source = error.source();
+        }
+
+        RemoteError::Other(message)
+    }
+}
+
+impl From<DebugProbeError> for RemoteError {
+    fn from(e: DebugProbeError) -> Self {
+        match e {
+            DebugProbeError::NotAttached => RemoteError::NotAttached,
+            DebugProbeError::Attached => RemoteError::Attached,
+            DebugProbeError::TargetNotFound => RemoteError::TargetNotFound,
+            DebugProbeError::UnsupportedProtocol(protocol) => {
+                RemoteError::UnsupportedProtocol(protocol)
+            }
+            DebugProbeError::UnsupportedSpeed(speed) => RemoteError::UnsupportedSpeed(speed),
+            other => RemoteError::other(&other),
+        }
+    }
+}
+
+impl From<ArmError> for RemoteError {
+    fn from(e: ArmError) -> Self {
+        match e {
+            ArmError::Dap(e) => RemoteError::Dap(e),
+            ArmError::Timeout => RemoteError::Timeout,
+            ArmError::Probe(e) => e.into(),
+            other => RemoteError::other(&other),
+        }
+    }
+}
+
+impl From<crate::Error> for RemoteError {
+    fn from(e: crate::Error) -> Self {
+        match e {
+            crate::Error::Probe(e) => e.into(),
+            crate::Error::Arm(e) => e.into(),
+            other => RemoteError::other(&other),
+        }
+    }
+}
+
+impl From<RemoteError> for DebugProbeError {
+    fn from(e: RemoteError) -> Self {
+        match e {
+            RemoteError::NotAttached => DebugProbeError::NotAttached,
+            RemoteError::Attached => DebugProbeError::Attached,
+            RemoteError::TargetNotFound => DebugProbeError::TargetNotFound,
+            RemoteError::UnsupportedProtocol(protocol) => {
+                DebugProbeError::UnsupportedProtocol(protocol)
+            }
+            RemoteError::UnsupportedSpeed(speed) => DebugProbeError::UnsupportedSpeed(speed),
+            other => DebugProbeError::ProbeSpecific(Box::new(other)),
+        }
+    }
+}
+
+impl From<RemoteError> for ArmError {
+    fn from(e: RemoteError) -> Self {
+        match e {
+            RemoteError::Dap(e) => ArmError::Dap(e),
+            RemoteError::Timeout => ArmError::Timeout,
+            other => ArmError::Probe(other.into()),
+        }
+    }
+}
+
+/// A DAP register access, executed as part of a batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DapOperation {
+    Read {
+        port: PortType,
+        address: u8,
+    },
+    ReadBlock {
+        port: PortType,
+        address: u8,
+        len: usize,
+    },
+    Write {
+        port: PortType,
+        address: u8,
+        value: u32,
+    },
+    WriteBlock {
+        port: PortType,
+        address: u8,
+        values: Vec<u32>,
+    },
+}
+
+impl DapOperation {
+    /// The number of words transferred by the operation.
+    pub fn word_count(&self) -> usize {
+        match self {
+            DapOperation::Read { .. } | DapOperation::Write { .. } => 1,
+            DapOperation::ReadBlock { len, .. } => *len,
+            DapOperation::WriteBlock { values, .. } => values.len(),
+        }
+    }
+}
+
+/// A JTAG register write, executed as part of a batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JtagWrite {
+    pub address: u32,
+    pub data: Vec<u8>,
+    pub len: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Request {
+    /// Opens the probe matching the selector, or the first probe found if it is empty.
+    Open(String),
+    SetSpeed(u32),
+    Attach,
+    Detach,
+    TargetReset,
+    TargetResetAssert,
+    TargetResetDeassert,
+    SelectProtocol(WireProtocol),
+    GetTargetVoltage,
+    SelectDp(DpAddress),
+    /// Executes the operations in order and returns the values read.
+    ///
+    /// Execution stops at the first failing operation.
+    Dap(Vec<DapOperation>),
+    SwjSequence {
+        bit_len: u8,
+        bits: u64,
+    },
+    SwjPins {
+        pin_out: u32,
+        pin_select: u32,
+        pin_wait: u32,
+    },
+    CoreStatusNotification(CoreStatus),
+    JtagReadRegister {
+        address: u32,
+        len: u32,
+    },
+    JtagWriteRegister(JtagWrite),
+    JtagWriteRegisterBatch(Vec<JtagWrite>),
+    SetIdleCycles(u8),
+    SetIrLen(u32),
+    SetJtagChain(JtagChainConfig),
+    /// Starts the SWO capture, answered with the baud rate of the probe.
+    EnableSwo(SwoConfig),
+    DisableSwo,
+    /// Reads the captured SWO data, waiting up to the given time.
+    ReadSwo(Duration),
+}
+
+/// Information about the opened probe, sent in response to [`Request::Open`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeInfo {
+    pub name: String,
+    pub speed_khz: u32,
+    pub protocol: Option<WireProtocol>,
+    pub idle_cycles: u8,
+    /// The probe gives raw access to the DAP registers.
+    pub dap: bool,
+    /// The probe gives access to the JTAG registers.
+    pub jtag: bool,
+    pub has_arm_interface: bool,
+    pub has_riscv_interface: bool,
+    /// The probe can capture SWO data.
+    pub swo: bool,
+    /// The size of the SWO buffer of the probe, if known.
+    pub swo_buffer_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Response {
+    Opened(ProbeInfo),
+    Ok,
+    /// The active protocol after attaching or selecting a protocol.
+    Protocol(Option<WireProtocol>),
+    Speed(u32),
+    Voltage(Option<f32>),
+    Value(u32),
+    Data(Vec<u8>),
+    /// The values read by a [`Request::Dap`] batch, in order.
+    Dap(Vec<u32>),
+    /// The data shifted out by the writes of a [`Request::JtagWriteRegisterBatch`], up to the first failing one.
+    JtagBatch {
+        results: Vec<Vec<u8>>,
+        error: Option<RemoteError>,
+    },
+}
+
+/// Writes a length prefixed message.
+pub fn write_message(
+    writer: &mut impl Write,
+    message: &impl Serialize,
+) -> Result<(), RemoteProbeError> {
+    let data = bincode::serialize(message)?;
+    if data.len() > MAX_MESSAGE_SIZE {
+        return Err(RemoteProbeError::MessageTooLarge(data.len()));
+    }
+
+    writer.write_all(&(data.len() as u32).to_le_bytes())?;
+    writer.write_all(&data)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads a length prefixed message.
+pub fn read_message<T: DeserializeOwned>(reader: &mut impl Read) -> Result<T, RemoteProbeError> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(RemoteProbeError::MessageTooLarge(len));
+    }
+
+    let mut data = vec![0; len];
+    reader.read_exact(&mut data)?;
+
+    Ok(bincode::deserialize(&data)?)
+}
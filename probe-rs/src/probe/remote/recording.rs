@@ -0,0 +1,163 @@
+//! Recording of the transactions with a probe, and their replay without the probe.
+//!
+//! A recorded probe is a [`RemoteProbe`] whose requests are executed on a local probe, the same
+//! way the [`ProbeServer`](super::ProbeServer) executes them. Every request is written to the log,
+//! together with its result. The replay answers the requests from the log, and fails as soon as a
+//! request differs from the recorded one. As everything above the register level runs the same code
+//! for both, a replay of a session is deterministic.
+//!
+//! The log starts with a fixed header, followed by the transactions encoded like the messages of the
+//! probe server.
+
+use super::protocol::{
+    read_message, write_message, RemoteError, RemoteProbeError, Request, Response,
+};
+use super::server::Connection;
+use super::{CallError, RemoteProbe, Transport};
+use crate::probe::DebugProbe;
+use crate::DebugProbeError;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// Identifies the log format.
+const HEADER: &[u8] = b"probe-rs recording v1\n";
+
+/// Executes the requests on a local probe and logs them.
+struct Recorder {
+    connection: Connection,
+    log: Box<dyn Write + Send>,
+}
+
+impl Recorder {
+    fn record(
+        &mut self,
+        request: &Request,
+        response: &Result<Response, RemoteError>,
+    ) -> Result<(), RemoteProbeError> {
+        write_message(&mut self.log, &(request, response))
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("connection", &self.connection)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport for Recorder {
+    fn call(&mut self, request: &Request) -> Result<Response, CallError> {
+        let response = self.connection.handle(request.clone());
+        self.record(request, &response)?;
+
+        response.map_err(CallError::Remote)
+    }
+}
+
+/// Answers the requests from a log.
+struct Replayer {
+    log: Box<dyn Read + Send>,
+    /// The number of transactions replayed so far.
+    index: usize,
+}
+
+impl fmt::Debug for Replayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replayer")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport for Replayer {
+    fn call(&mut self, request: &Request) -> Result<Response, CallError> {
+        let (recorded, response): (Request, Result<Response, RemoteError>) =
+            match read_message(&mut self.log) {
+                Ok(transaction) => transaction,
+                Err(RemoteProbeError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Err(RemoteProbeError::RecordingEnded.into())
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+        let index = self.index;
+        self.index += 1;
+
+        if recorded != *request {
+            return Err(RemoteProbeError::RecordingMismatch {
+                index,
+                recorded: format!("{recorded:?}"),
+                requested: format!("{request:?}"),
+            }
+            .into());
+        }
+
+        response.map_err(CallError::Remote)
+    }
+}
+
+/// Checks that the probe gives raw access to the registers of the interfaces it supports.
+///
+/// Probes which implement the ARM or RISC-V interface on their own, like the ST-Link, would lose
+/// the interface when they are recorded.
+pub(crate) fn check_recordable(probe: &mut dyn DebugProbe) -> Result<(), DebugProbeError> {
+    if probe.has_arm_interface() && probe.try_as_dap_probe().is_none() {
+        return Err(RemoteProbeError::NotRecordable("DAP").into());
+    }
+
+    if probe.has_riscv_interface() && probe.try_as_jtag_probe().is_none() {
+        return Err(RemoteProbeError::NotRecordable("JTAG").into());
+    }
+
+    Ok(())
+}
+
+/// Wraps the probe, so that all transactions with it are written to the log.
+pub(crate) fn record(
+    mut probe: Box<dyn DebugProbe>,
+    log: impl Write + Send + 'static,
+) -> Result<RemoteProbe, DebugProbeError> {
+    check_recordable(probe.as_mut())?;
+
+    let (connection, info) = Connection::with_probe(probe);
+    let mut recorder = Recorder {
+        connection,
+        log: Box::new(log),
+    };
+
+    recorder
+        .log
+        .write_all(HEADER)
+        .map_err(RemoteProbeError::from)?;
+    // The replay opens the probe like a client of the probe server.
+    recorder.record(
+        &Request::Open(String::new()),
+        &Ok(Response::Opened(info.clone())),
+    )?;
+
+    Ok(RemoteProbe::new(Box::new(recorder), info))
+}
+
+/// Creates a probe which answers from a log written by [`record`].
+pub(crate) fn replay(mut log: impl Read + Send + 'static) -> Result<RemoteProbe, DebugProbeError> {
+    let mut header = [0; HEADER.len()];
+    match log.read_exact(&mut header) {
+        Ok(()) if header == HEADER => {}
+        Ok(()) => return Err(RemoteProbeError::InvalidRecording.into()),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            return Err(RemoteProbeError::InvalidRecording.into())
+        }
+        Err(e) => return Err(RemoteProbeError::from(e).into()),
+    }
+
+    let replayer = Replayer {
+        log: Box::new(log),
+        index: 0,
+    };
+
+    let mut probe = RemoteProbe::open(Box::new(replayer), "")?;
+    probe.name = format!("{} (replay)", probe.info.name);
+
+    Ok(probe)
+}
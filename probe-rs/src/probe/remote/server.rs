@@ -0,0 +1,353 @@
+//! A server which gives clients access to the locally attached probes.
+
+use super::protocol::{
+    read_message, write_message, DapOperation, JtagWrite, ProbeInfo, RemoteError, RemoteProbeError,
+    Request, Response,
+};
+use crate::architecture::arm::{communication_interface::DapProbe, SwoAccess};
+use crate::probe::{
+    CommandResult, DebugProbe, DebugProbeError, DebugProbeSelector, JTAGAccess, JtagWriteCommand,
+    Probe, ProbeCreationError,
+};
+use std::io::{self, BufReader, BufWriter};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+
+/// Serves the locally attached debug probes over TCP.
+///
+/// Every client opens its own probe and is handled on its own thread. The connection is not
+/// authenticated, so the server should only be reachable from trusted networks.
+#[derive(Debug)]
+pub struct ProbeServer {
+    listener: TcpListener,
+}
+
+impl ProbeServer {
+    /// Listens for clients on the given address.
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(ProbeServer {
+            listener: TcpListener::bind(address)?,
+        })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts clients until accepting a connection fails.
+    pub fn run(self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let peer = stream.peer_addr()?;
+
+            thread::spawn(move || {
+                tracing::info!("Client {} connected", peer);
+
+                match serve_client(stream, open_probe) {
+                    Ok(()) => tracing::info!("Client {} disconnected", peer),
+                    Err(e) => tracing::warn!("Connection to client {} failed: {}", peer, e),
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Opens the probe matching the selector, or the first probe found if the selector is empty.
+fn open_probe(selector: &str) -> Result<Box<dyn DebugProbe>, DebugProbeError> {
+    let probe = if selector.is_empty() {
+        let probes = Probe::list_all();
+        let info = probes
+            .first()
+            .ok_or(DebugProbeError::ProbeCouldNotBeCreated(
+                ProbeCreationError::NotFound,
+            ))?;
+
+        Probe::open(info)?
+    } else {
+        let selector = DebugProbeSelector::try_from(selector)
+            .map_err(|e| ProbeCreationError::ProbeSpecific(Box::new(e)))?;
+
+        Probe::open(selector)?
+    };
+
+    Ok(probe.inner)
+}
+
+/// Opens a probe for a client, given the selector sent by the client.
+pub(crate) type OpenProbe = fn(&str) -> Result<Box<dyn DebugProbe>, DebugProbeError>;
+
+/// Handles the requests of a client until it disconnects.
+pub(crate) fn serve_client(stream: TcpStream, open: OpenProbe) -> Result<(), RemoteProbeError> {
+    stream.set_nodelay(true)?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    let mut connection = Connection::new(open);
+
+    loop {
+        let request: Request = match read_message(&mut reader) {
+            Ok(request) => request,
+            Err(RemoteProbeError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+
+        tracing::trace!("Request: {:?}", request);
+        let response = connection.handle(request);
+        tracing::trace!("Response: {:?}", response);
+
+        write_message(&mut writer, &response)?;
+    }
+
+    if let Some(probe) = connection.probe.as_mut() {
+        if connection.attached {
+            if let Err(e) = probe.detach() {
+                tracing::warn!("Failed to detach from the target: {}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Executes the requests of a client on the probe it opened.
+#[derive(Debug)]
+pub(crate) struct Connection {
+    probe: Option<Box<dyn DebugProbe>>,
+    attached: bool,
+    open: OpenProbe,
+}
+
+impl Connection {
+    fn new(open: OpenProbe) -> Self {
+        Connection {
+            probe: None,
+            attached: false,
+            open,
+        }
+    }
+
+    /// Creates a connection for a probe which is already open.
+    pub(crate) fn with_probe(mut probe: Box<dyn DebugProbe>) -> (Self, ProbeInfo) {
+        let info = probe_info(probe.as_mut());
+        let connection = Connection {
+            probe: Some(probe),
+            attached: false,
+            open: open_probe,
+        };
+
+        (connection, info)
+    }
+
+    fn probe(&mut self) -> Result<&mut Box<dyn DebugProbe>, RemoteError> {
+        self.probe.as_mut().ok_or(RemoteError::NoProbe)
+    }
+
+    fn dap(&mut self) -> Result<&mut dyn DapProbe, RemoteError> {
+        self.probe()?.try_as_dap_probe().ok_or_else(|| {
+            RemoteError::Other("The probe does not support raw DAP register access".to_string())
+        })
+    }
+
+    fn jtag(&mut self) -> Result<&mut dyn JTAGAccess, RemoteError> {
+        self.probe()?.try_as_jtag_probe().ok_or_else(|| {
+            RemoteError::Other("The probe does not support JTAG register access".to_string())
+        })
+    }
+
+    fn swo(&mut self) -> Result<&mut dyn SwoAccess, RemoteError> {
+        self.probe()?
+            .get_swo_interface_mut()
+            .ok_or_else(|| RemoteError::Other("The probe does not support SWO".to_string()))
+    }
+
+    pub(crate) fn handle(&mut self, request: Request) -> Result<Response, RemoteError> {
+        match request {
+            Request::Open(selector) => {
+                if self.probe.is_some() {
+                    return Err(RemoteError::Other("A probe is already open".to_string()));
+                }
+
+                let mut probe = (self.open)(&selector)?;
+                let info = probe_info(probe.as_mut());
+
+                tracing::info!("Opened probe {}", info.name);
+                self.probe = Some(probe);
+
+                Ok(Response::Opened(info))
+            }
+            Request::SetSpeed(speed_khz) => {
+                Ok(Response::Speed(self.probe()?.set_speed(speed_khz)?))
+            }
+            Request::Attach => {
+                let probe = self.probe()?;
+                probe.attach()?;
+                let protocol = probe.active_protocol();
+                self.attached = true;
+
+                Ok(Response::Protocol(protocol))
+            }
+            Request::Detach => {
+                self.probe()?.detach()?;
+                self.attached = false;
+
+                Ok(Response::Ok)
+            }
+            Request::TargetReset => {
+                self.probe()?.target_reset()?;
+                Ok(Response::Ok)
+            }
+            Request::TargetResetAssert => {
+                self.probe()?.target_reset_assert()?;
+                Ok(Response::Ok)
+            }
+            Request::TargetResetDeassert => {
+                self.probe()?.target_reset_deassert()?;
+                Ok(Response::Ok)
+            }
+            Request::SelectProtocol(protocol) => {
+                let probe = self.probe()?;
+                probe.select_protocol(protocol)?;
+
+                Ok(Response::Protocol(probe.active_protocol()))
+            }
+            Request::GetTargetVoltage => Ok(Response::Voltage(self.probe()?.get_target_voltage()?)),
+            Request::SelectDp(dp) => {
+                self.dap()?.select_dp(dp)?;
+                Ok(Response::Ok)
+            }
+            Request::Dap(operations) => {
+                let values = execute_dap_operations(self.dap()?, operations)?;
+                Ok(Response::Dap(values))
+            }
+            Request::SwjSequence { bit_len, bits } => {
+                self.dap()?.swj_sequence(bit_len, bits)?;
+                Ok(Response::Ok)
+            }
+            Request::SwjPins {
+                pin_out,
+                pin_select,
+                pin_wait,
+            } => Ok(Response::Value(
+                self.dap()?.swj_pins(pin_out, pin_select, pin_wait)?,
+            )),
+            Request::CoreStatusNotification(status) => {
+                self.dap()?.core_status_notification(status)?;
+                Ok(Response::Ok)
+            }
+            Request::JtagReadRegister { address, len } => {
+                Ok(Response::Data(self.jtag()?.read_register(address, len)?))
+            }
+            Request::JtagWriteRegister(JtagWrite { address, data, len }) => Ok(Response::Data(
+                self.jtag()?.write_register(address, &data, len)?,
+            )),
+            Request::JtagWriteRegisterBatch(writes) => {
+                Ok(execute_jtag_writes(self.jtag()?, writes))
+            }
+            Request::SetIdleCycles(idle_cycles) => {
+                self.jtag()?.set_idle_cycles(idle_cycles);
+                Ok(Response::Ok)
+            }
+            Request::SetIrLen(len) => {
+                self.jtag()?.set_ir_len(len);
+                Ok(Response::Ok)
+            }
+            Request::SetJtagChain(chain) => {
+                self.probe()?.set_jtag_chain(chain)?;
+                Ok(Response::Ok)
+            }
+            Request::EnableSwo(config) => Ok(Response::Value(self.swo()?.enable_swo(&config)?)),
+            Request::DisableSwo => {
+                self.swo()?.disable_swo()?;
+                Ok(Response::Ok)
+            }
+            Request::ReadSwo(timeout) => Ok(Response::Data(self.swo()?.read_swo_timeout(timeout)?)),
+        }
+    }
+}
+
+fn probe_info(probe: &mut dyn DebugProbe) -> ProbeInfo {
+    ProbeInfo {
+        name: probe.get_name().to_string(),
+        speed_khz: probe.speed_khz(),
+        protocol: probe.active_protocol(),
+        idle_cycles: probe
+            .try_as_jtag_probe()
+            .map(|jtag| jtag.get_idle_cycles())
+            .unwrap_or(0),
+        dap: probe.try_as_dap_probe().is_some(),
+        jtag: probe.try_as_jtag_probe().is_some(),
+        has_arm_interface: probe.has_arm_interface(),
+        has_riscv_interface: probe.has_riscv_interface(),
+        swo: probe.get_swo_interface().is_some(),
+        swo_buffer_size: probe
+            .get_swo_interface_mut()
+            .and_then(|swo| swo.swo_buffer_size()),
+    }
+}
+
+fn execute_dap_operations(
+    dap: &mut dyn DapProbe,
+    operations: Vec<DapOperation>,
+) -> Result<Vec<u32>, RemoteError> {
+    let mut values = Vec::new();
+
+    for operation in operations {
+        match operation {
+            DapOperation::Read { port, address } => {
+                values.push(dap.raw_read_register(port, address)?);
+            }
+            DapOperation::ReadBlock { port, address, len } => {
+                let start = values.len();
+                values.resize(start + len, 0);
+                dap.raw_read_block(port, address, &mut values[start..])?;
+            }
+            DapOperation::Write {
+                port,
+                address,
+                value,
+            } => dap.raw_write_register(port, address, value)?,
+            DapOperation::WriteBlock {
+                port,
+                address,
+                values,
+            } => dap.raw_write_block(port, address, &values)?,
+        }
+    }
+
+    // Writes at the end of the batch might still be queued in the probe.
+    dap.raw_flush()?;
+
+    Ok(values)
+}
+
+fn execute_jtag_writes(jtag: &mut dyn JTAGAccess, writes: Vec<JtagWrite>) -> Response {
+    // The responses are decoded by the client, so that the batch can be executed
+    // in one go by probes which support that.
+    let commands = writes
+        .into_iter()
+        .map(|write| JtagWriteCommand {
+            address: write.address,
+            data: write.data,
+            len: write.len,
+            transform: |data| Ok(CommandResult::VecU8(data)),
+        })
+        .collect::<Vec<_>>();
+
+    let (results, error) = match jtag.write_register_batch(&commands) {
+        Ok(results) => (results, None),
+        Err(e) => (e.results, Some(e.error.into())),
+    };
+
+    let results = results
+        .into_iter()
+        .map(|result| match result {
+            CommandResult::VecU8(data) => data,
+            _ => unreachable!("Only raw data is returned by the batched writes"),
+        })
+        .collect();
+
+    Response::JtagBatch { results, error }
+}
@@ -0,0 +1,591 @@
+//! Client for the OpenOCD `remote_bitbang` protocol.
+//!
+//! The protocol drives the JTAG pins of a remote adapter over a TCP connection, one ASCII
+//! character per pin change. It is implemented by simulators like Verilator and Spike, FPGA
+//! test benches and small bitbang servers running on single board computers.
+//!
+//! The following commands are used:
+//!
+//! - `'0'` to `'7'`: set TCK, TMS and TDI, encoded as `TCK << 2 | TMS << 1 | TDI`.
+//! - `'R'`: sample TDO, the server replies with `'0'` or `'1'`.
+//! - `'r'` to `'u'`: set the reset signals, encoded as `TRST << 1 | SRST`, where 1 means asserted.
+//! - `'Q'`: close the connection.
+
+use crate::architecture::arm::communication_interface::{DapProbe, UninitializedArmProbe};
+use crate::architecture::arm::ArmCommunicationInterface;
+use crate::architecture::riscv::communication_interface::{
+    RiscvCommunicationInterface, RiscvError,
+};
+use crate::probe::{
+    arm_debug_interface::{ProbeStatistics, RawProtocolIo, SwdSettings},
+    common::{configure_chain, transfer_register, ChainParams, JtagBitIo},
+    DebugProbe, DebugProbeError, DebugProbeSelector, JTAGAccess, JtagChainConfig,
+    NetworkProbeAddress, ProbeCreationError, WireProtocol,
+};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RemoteBitbangError {
+    #[error("The server sent an invalid TDO value: {0:#04x}")]
+    InvalidTdo(u8),
+    #[error("No target is selected on the JTAG chain")]
+    NoTargetSelected,
+    #[error("An I/O error occurred while talking to the server")]
+    Io(#[from] io::Error),
+}
+
+impl From<RemoteBitbangError> for DebugProbeError {
+    fn from(e: RemoteBitbangError) -> Self {
+        DebugProbeError::ProbeSpecific(Box::new(e))
+    }
+}
+
+/// A probe connected to a `remote_bitbang` server over TCP.
+#[derive(Debug)]
+pub struct RemoteBitbang {
+    stream: TcpStream,
+    jtag_chain: JtagChainConfig,
+    chain_params: Option<ChainParams>,
+    speed_khz: u32,
+    idle_cycles: u8,
+    swd_settings: SwdSettings,
+    probe_statistics: ProbeStatistics,
+}
+
+impl RemoteBitbang {
+    fn connect(address: &str) -> Result<Self, DebugProbeError> {
+        tracing::debug!("Connecting to remote_bitbang server at {}", address);
+
+        let stream = TcpStream::connect(address)
+            .map_err(|e| ProbeCreationError::ProbeSpecific(Box::new(e)))?;
+        // Every command is a single byte, so don't wait for more data before sending.
+        stream
+            .set_nodelay(true)
+            .map_err(|e| ProbeCreationError::ProbeSpecific(Box::new(e)))?;
+
+        Ok(RemoteBitbang {
+            stream,
+            jtag_chain: JtagChainConfig::default(),
+            chain_params: None,
+            speed_khz: 0,
+            idle_cycles: 0,
+            swd_settings: SwdSettings::default(),
+            probe_statistics: ProbeStatistics::default(),
+        })
+    }
+
+    /// Clocks the given TMS and TDI bits, and returns the TDO values sampled before each rising edge of TCK.
+    ///
+    /// If `capture` is false, TDO is not sampled and an empty vector is returned.
+    fn shift_bits(
+        &mut self,
+        tms: impl IntoIterator<Item = bool>,
+        tdi: impl IntoIterator<Item = bool>,
+        capture: bool,
+    ) -> Result<Vec<bool>, RemoteBitbangError> {
+        let mut command = Vec::new();
+        let mut bits = 0;
+
+        for (tms, tdi) in tms.into_iter().zip(tdi) {
+            let pins = (u8::from(tms) << 1) | u8::from(tdi);
+
+            command.push(b'0' + pins);
+            if capture {
+                command.push(b'R');
+            }
+            command.push(b'0' + (1 << 2 | pins));
+            bits += 1;
+        }
+
+        self.stream.write_all(&command)?;
+
+        if !capture {
+            return Ok(Vec::new());
+        }
+
+        let mut response = vec![0; bits];
+        self.stream.read_exact(&mut response)?;
+
+        response
+            .into_iter()
+            .map(|tdo| match tdo {
+                b'0' => Ok(false),
+                b'1' => Ok(true),
+                other => Err(RemoteBitbangError::InvalidTdo(other)),
+            })
+            .collect()
+    }
+
+    fn target_transfer(
+        &mut self,
+        address: u32,
+        data: Option<&[u8]>,
+        len: u32,
+    ) -> Result<Vec<u8>, DebugProbeError> {
+        let params = self
+            .chain_params
+            .ok_or(RemoteBitbangError::NoTargetSelected)?;
+
+        let r = transfer_register(self, params, address, data, len as usize)
+            .map_err(RemoteBitbangError::from)?;
+
+        let idle_cycles = self.idle_cycles as usize;
+        self.idle(idle_cycles).map_err(RemoteBitbangError::from)?;
+
+        Ok(r)
+    }
+
+    fn set_reset(&mut self, trst: bool, srst: bool) -> Result<(), DebugProbeError> {
+        let command = b'r' + ((u8::from(trst) << 1) | u8::from(srst));
+
+        self.stream
+            .write_all(&[command])
+            .map_err(RemoteBitbangError::from)?;
+
+        Ok(())
+    }
+}
+
+impl JtagBitIo for RemoteBitbang {
+    fn clock_bits(&mut self, tms: &[bool], tdi: &[bool], capture: bool) -> io::Result<Vec<bool>> {
+        self.shift_bits(tms.iter().copied(), tdi.iter().copied(), capture)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+}
+
+impl Drop for RemoteBitbang {
+    fn drop(&mut self) {
+        // Ask the server to close the connection, there is nothing to do if that fails.
+        let _ = self.stream.write_all(b"Q");
+    }
+}
+
+impl DebugProbe for RemoteBitbang {
+    fn new_from_selector(
+        selector: impl Into<DebugProbeSelector>,
+    ) -> Result<Box<Self>, DebugProbeError>
+    where
+        Self: Sized,
+    {
+        match selector.into().network_address {
+            Some(NetworkProbeAddress::RemoteBitbang(address)) => {
+                Ok(Box::new(Self::connect(&address)?))
+            }
+            _ => Err(DebugProbeError::ProbeCouldNotBeCreated(
+                ProbeCreationError::NotFound,
+            )),
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "remote_bitbang"
+    }
+
+    fn speed_khz(&self) -> u32 {
+        self.speed_khz
+    }
+
+    fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
+        // The clock speed is determined by the server.
+        self.speed_khz = speed_khz;
+        Ok(speed_khz)
+    }
+
+    fn attach(&mut self) -> Result<(), DebugProbeError> {
+        tracing::debug!("attaching...");
+
+        self.chain_params = None;
+        let chain = self.jtag_chain.clone();
+        self.chain_params = Some(configure_chain(self, &chain)?);
+
+        Ok(())
+    }
+
+    fn detach(&mut self) -> Result<(), crate::Error> {
+        Ok(())
+    }
+
+    fn target_reset(&mut self) -> Result<(), DebugProbeError> {
+        self.target_reset_assert()?;
+        self.target_reset_deassert()
+    }
+
+    fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+        self.set_reset(false, true)
+    }
+
+    fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
+        self.set_reset(false, false)
+    }
+
+    fn select_protocol(&mut self, protocol: WireProtocol) -> Result<(), DebugProbeError> {
+        match protocol {
+            WireProtocol::Jtag => Ok(()),
+            _ => Err(DebugProbeError::UnsupportedProtocol(protocol)),
+        }
+    }
+
+    fn active_protocol(&self) -> Option<WireProtocol> {
+        Some(WireProtocol::Jtag)
+    }
+
+    fn set_jtag_chain(&mut self, chain: JtagChainConfig) -> Result<(), DebugProbeError> {
+        self.jtag_chain = chain;
+        Ok(())
+    }
+
+    fn try_get_riscv_interface(
+        self: Box<Self>,
+    ) -> Result<RiscvCommunicationInterface, (Box<dyn DebugProbe>, RiscvError)> {
+        match RiscvCommunicationInterface::new(self) {
+            Ok(interface) => Ok(interface),
+            Err((probe, err)) => Err((probe.into_probe(), err)),
+        }
+    }
+
+    fn has_riscv_interface(&self) -> bool {
+        true
+    }
+
+    fn try_as_jtag_probe(&mut self) -> Option<&mut dyn JTAGAccess> {
+        Some(self)
+    }
+
+    fn into_probe(self: Box<Self>) -> Box<dyn DebugProbe> {
+        self
+    }
+
+    fn try_get_arm_interface<'probe>(
+        self: Box<Self>,
+    ) -> Result<Box<dyn UninitializedArmProbe + 'probe>, (Box<dyn DebugProbe>, DebugProbeError)>
+    {
+        let uninitialized_interface = ArmCommunicationInterface::new(self, true);
+
+        Ok(Box::new(uninitialized_interface))
+    }
+
+    fn has_arm_interface(&self) -> bool {
+        true
+    }
+}
+
+impl RawProtocolIo for RemoteBitbang {
+    fn jtag_io<M, I>(&mut self, tms: M, tdi: I) -> Result<Vec<bool>, DebugProbeError>
+    where
+        M: IntoIterator<Item = bool>,
+        I: IntoIterator<Item = bool>,
+    {
+        self.probe_statistics.report_io();
+
+        Ok(self.shift_bits(tms, tdi, true)?)
+    }
+
+    fn swd_io<D, S>(&mut self, _dir: D, _swdio: S) -> Result<Vec<bool>, DebugProbeError>
+    where
+        D: IntoIterator<Item = bool>,
+        S: IntoIterator<Item = bool>,
+    {
+        Err(DebugProbeError::UnsupportedProtocol(WireProtocol::Swd))
+    }
+
+    fn swd_settings(&self) -> &SwdSettings {
+        &self.swd_settings
+    }
+
+    fn probe_statistics(&mut self) -> &mut ProbeStatistics {
+        &mut self.probe_statistics
+    }
+}
+
+impl DapProbe for RemoteBitbang {}
+
+impl JTAGAccess for RemoteBitbang {
+    fn read_register(&mut self, address: u32, len: u32) -> Result<Vec<u8>, DebugProbeError> {
+        tracing::debug!("read_register({:#x}, {})", address, len);
+        let r = self.target_transfer(address, None, len)?;
+        tracing::debug!("read_register result: {:?})", r);
+        Ok(r)
+    }
+
+    fn set_idle_cycles(&mut self, idle_cycles: u8) {
+        tracing::debug!("set_idle_cycles({})", idle_cycles);
+        self.idle_cycles = idle_cycles;
+    }
+
+    fn get_idle_cycles(&self) -> u8 {
+        self.idle_cycles
+    }
+
+    fn set_ir_len(&mut self, _len: u32) {
+        // The IR length is detected while scanning the chain.
+    }
+
+    fn write_register(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        len: u32,
+    ) -> Result<Vec<u8>, DebugProbeError> {
+        tracing::debug!("write_register({:#x}, {:?}, {})", address, data, len);
+        let r = self.target_transfer(address, Some(data), len)?;
+        tracing::debug!("write_register result: {:?})", r);
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::probe::common::scan_chain;
+    use crate::probe::JtagTapSelector;
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    const IDCODE: u32 = 0x1000563d;
+    const IRLEN: usize = 5;
+    const IDCODE_INSTRUCTION: u32 = 0x01;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum TapState {
+        Reset,
+        Idle,
+        SelectDr,
+        CaptureDr,
+        ShiftDr,
+        Exit1Dr,
+        PauseDr,
+        Exit2Dr,
+        UpdateDr,
+        SelectIr,
+        CaptureIr,
+        ShiftIr,
+        Exit1Ir,
+        PauseIr,
+        Exit2Ir,
+        UpdateIr,
+    }
+
+    impl TapState {
+        fn next(self, tms: bool) -> Self {
+            use TapState::*;
+            match (self, tms) {
+                (Reset, true) => Reset,
+                (Reset, false) => Idle,
+                (Idle | UpdateDr | UpdateIr, true) => SelectDr,
+                (Idle | UpdateDr | UpdateIr, false) => Idle,
+                (SelectDr, true) => SelectIr,
+                (SelectDr, false) => CaptureDr,
+                (CaptureDr | ShiftDr | Exit2Dr, false) => ShiftDr,
+                (CaptureDr | ShiftDr, true) => Exit1Dr,
+                (Exit1Dr | Exit2Dr, true) => UpdateDr,
+                (Exit1Dr | PauseDr, false) => PauseDr,
+                (PauseDr, true) => Exit2Dr,
+                (SelectIr, true) => Reset,
+                (SelectIr, false) => CaptureIr,
+                (CaptureIr | ShiftIr | Exit2Ir, false) => ShiftIr,
+                (CaptureIr | ShiftIr, true) => Exit1Ir,
+                (Exit1Ir | Exit2Ir, true) => UpdateIr,
+                (Exit1Ir | PauseIr, false) => PauseIr,
+                (PauseIr, true) => Exit2Ir,
+            }
+        }
+    }
+
+    /// A single TAP with an IDCODE register, everything else is a bypass register.
+    struct MockTap {
+        state: TapState,
+        ir: u32,
+        ir_shift: u32,
+        dr_shift: u32,
+        dr_len: usize,
+    }
+
+    impl MockTap {
+        fn new() -> Self {
+            MockTap {
+                state: TapState::Reset,
+                ir: IDCODE_INSTRUCTION,
+                ir_shift: 0,
+                dr_shift: 0,
+                dr_len: 1,
+            }
+        }
+
+        fn tdo(&self) -> bool {
+            match self.state {
+                TapState::ShiftIr => self.ir_shift & 1 != 0,
+                TapState::ShiftDr => self.dr_shift & 1 != 0,
+                _ => false,
+            }
+        }
+
+        fn rising_edge(&mut self, tms: bool, tdi: bool) {
+            match self.state {
+                TapState::Reset => self.ir = IDCODE_INSTRUCTION,
+                TapState::CaptureIr => self.ir_shift = 0b00001,
+                TapState::ShiftIr => {
+                    self.ir_shift = (self.ir_shift >> 1) | (u32::from(tdi) << (IRLEN - 1))
+                }
+                TapState::UpdateIr => self.ir = self.ir_shift,
+                TapState::CaptureDr => {
+                    if self.ir == IDCODE_INSTRUCTION {
+                        self.dr_shift = IDCODE;
+                        self.dr_len = 32;
+                    } else {
+                        self.dr_shift = 0;
+                        self.dr_len = 1;
+                    }
+                }
+                TapState::ShiftDr => {
+                    self.dr_shift = (self.dr_shift >> 1) | (u32::from(tdi) << (self.dr_len - 1))
+                }
+                _ => {}
+            }
+            self.state = self.state.next(tms);
+        }
+    }
+
+    /// Starts a `remote_bitbang` server simulating a single TAP, and returns its address.
+    fn mock_server() -> (String, JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut tap = MockTap::new();
+            let mut tck = false;
+            let mut reset_commands = Vec::new();
+
+            let mut buf = [0; 256];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                for &command in &buf[..n] {
+                    match command {
+                        b'0'..=b'7' => {
+                            let pins = command - b'0';
+                            let new_tck = pins & 0b100 != 0;
+                            if new_tck && !tck {
+                                tap.rising_edge(pins & 0b10 != 0, pins & 0b1 != 0);
+                            }
+                            tck = new_tck;
+                        }
+                        b'R' => {
+                            let tdo = if tap.tdo() { b'1' } else { b'0' };
+                            stream.write_all(&[tdo]).unwrap();
+                        }
+                        b'r'..=b'u' => reset_commands.push(command),
+                        b'Q' => return reset_commands,
+                        _ => panic!("unexpected command {command:#04x}"),
+                    }
+                }
+            }
+            reset_commands
+        });
+
+        (address, handle)
+    }
+
+    fn open(address: String) -> Box<RemoteBitbang> {
+        let selector = DebugProbeSelector {
+            vendor_id: 0,
+            product_id: 0,
+            serial_number: None,
+            network_address: Some(NetworkProbeAddress::RemoteBitbang(address)),
+            ftdi_layout: None,
+        };
+
+        RemoteBitbang::new_from_selector(selector).unwrap()
+    }
+
+    #[test]
+    fn scan_single_tap() {
+        let (address, server) = mock_server();
+        let mut probe = open(address);
+
+        let taps = scan_chain(&mut *probe, None).unwrap();
+
+        assert_eq!(taps.len(), 1);
+        assert_eq!(taps[0].idcode, IDCODE);
+        assert_eq!(taps[0].irlen, IRLEN);
+
+        drop(probe);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn read_idcode_register() {
+        let (address, server) = mock_server();
+        let mut probe = open(address);
+
+        probe.attach().unwrap();
+        probe.set_idle_cycles(3);
+
+        let idcode = probe.read_register(IDCODE_INSTRUCTION, 32).unwrap();
+        assert_eq!(idcode, IDCODE.to_le_bytes());
+
+        drop(probe);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn explicit_chain() {
+        let (address, server) = mock_server();
+        let mut probe = open(address);
+
+        probe
+            .set_jtag_chain(JtagChainConfig {
+                ir_lengths: Some(vec![IRLEN as u8]),
+                tap: Some(JtagTapSelector::IdCode(IDCODE)),
+            })
+            .unwrap();
+        probe.attach().unwrap();
+
+        let idcode = probe.read_register(IDCODE_INSTRUCTION, 32).unwrap();
+        assert_eq!(idcode, IDCODE.to_le_bytes());
+
+        probe
+            .set_jtag_chain(JtagChainConfig {
+                ir_lengths: Some(vec![4, IRLEN as u8]),
+                tap: None,
+            })
+            .unwrap();
+        assert!(probe.attach().is_err());
+
+        drop(probe);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn reset_commands() {
+        let (address, server) = mock_server();
+        let mut probe = open(address);
+
+        probe.target_reset_assert().unwrap();
+        probe.target_reset_deassert().unwrap();
+
+        drop(probe);
+        assert_eq!(server.join().unwrap(), b"sr");
+    }
+
+    #[test]
+    fn other_selectors_are_not_opened() {
+        let selector = DebugProbeSelector {
+            vendor_id: 0x1366,
+            product_id: 0x0101,
+            serial_number: None,
+            network_address: None,
+            ftdi_layout: None,
+        };
+
+        assert!(matches!(
+            RemoteBitbang::new_from_selector(selector),
+            Err(DebugProbeError::ProbeCouldNotBeCreated(
+                ProbeCreationError::NotFound
+            ))
+        ));
+    }
+}
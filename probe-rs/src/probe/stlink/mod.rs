@@ -365,6 +365,12 @@ impl<D: StLinkUsb> StLink<D> {
     /// Firmware version that adds multiple AP support.
     const MIN_JTAG_VERSION_MULTI_AP: u8 = 28;
 
+    /// Firmware version which supports 16-bit memory transfers.
+    ///
+    /// This only applies to HW version 2, all supported firmware versions
+    /// for version 3 support them.
+    const MIN_JTAG_VERSION_16BIT_XFER: u8 = 26;
+
     /// Firmware version which supports banked DP registers.
     ///
     /// This only applies to HW version 2, for version 3 we only support
@@ -401,6 +407,29 @@ impl<D: StLinkUsb> StLink<D> {
             || self.hw_version == 3
     }
 
+    /// Check if 16-bit memory transfers are supported.
+    fn supports_16bit_transfers(&self) -> bool {
+        (self.hw_version == 2 && self.jtag_version >= Self::MIN_JTAG_VERSION_16BIT_XFER)
+            || self.hw_version >= 3
+    }
+
+    /// Checks that a 16-bit memory transfer of `length` bytes is possible.
+    fn check_16bit_transfer(&self, length: usize, max_length: usize) -> Result<(), StlinkError> {
+        if !self.supports_16bit_transfers() {
+            return Err(StlinkError::Unsupported16BitTransfers);
+        }
+
+        if length > max_length || length & 1 != 0 {
+            return Err(StlinkError::InvalidTransferLength {
+                length,
+                max_length,
+                alignment: 2,
+            });
+        }
+
+        Ok(())
+    }
+
     /// Commands the ST-Link to enter idle mode.
     /// Internal helper.
     fn enter_idle(&mut self) -> Result<(), StlinkError> {
@@ -893,6 +922,51 @@ impl<D: StLinkUsb> StLink<D> {
         Ok(())
     }
 
+    #[tracing::instrument(skip(self, data, apsel), fields(ap=apsel, length= data.len()))]
+    fn read_mem_16bit(
+        &mut self,
+        address: u32,
+        data: &mut [u8],
+        apsel: u8,
+    ) -> Result<(), DebugProbeError> {
+        self.check_16bit_transfer(data.len(), STLINK_MAX_READ_LEN)?;
+
+        self.select_ap(apsel)?;
+
+        if address & 1 != 0 {
+            return Err(DebugProbeError::from(StlinkError::UnalignedAddress));
+        }
+
+        let data_length = data.len().to_le_bytes();
+
+        let addbytes = address.to_le_bytes();
+
+        retry_on_wait(|| {
+            self.device.write(
+                &[
+                    commands::JTAG_COMMAND,
+                    commands::JTAG_READMEM_16BIT,
+                    addbytes[0],
+                    addbytes[1],
+                    addbytes[2],
+                    addbytes[3],
+                    data_length[0],
+                    data_length[1],
+                    apsel,
+                ],
+                &[],
+                data,
+                TIMEOUT,
+            )?;
+
+            self.get_last_rw_status()
+        })?;
+
+        tracing::debug!("Read ok");
+
+        Ok(())
+    }
+
     fn read_mem_8bit(
         &mut self,
         address: u32,
@@ -1010,6 +1084,49 @@ impl<D: StLinkUsb> StLink<D> {
         Ok(())
     }
 
+    fn write_mem_16bit(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        apsel: u8,
+    ) -> Result<(), DebugProbeError> {
+        self.check_16bit_transfer(data.len(), STLINK_MAX_WRITE_LEN)?;
+
+        self.select_ap(apsel)?;
+
+        tracing::trace!("write_mem_16bit");
+        let length = data.len();
+
+        if address & 1 != 0 {
+            return Err(DebugProbeError::from(StlinkError::UnalignedAddress));
+        }
+
+        let addbytes = address.to_le_bytes();
+        let lenbytes = length.to_le_bytes();
+        retry_on_wait(|| {
+            self.device.write(
+                &[
+                    commands::JTAG_COMMAND,
+                    commands::JTAG_WRITEMEM_16BIT,
+                    addbytes[0],
+                    addbytes[1],
+                    addbytes[2],
+                    addbytes[3],
+                    lenbytes[0],
+                    lenbytes[1],
+                    apsel,
+                ],
+                data,
+                &mut [],
+                TIMEOUT,
+            )?;
+
+            self.get_last_rw_status()
+        })?;
+
+        Ok(())
+    }
+
     fn write_mem_8bit(
         &mut self,
         address: u32,
@@ -1099,11 +1216,11 @@ impl<D: StLinkUsb> StLink<D> {
 }
 
 impl<D: StLinkUsb> SwoAccess for StLink<D> {
-    fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError> {
+    fn enable_swo(&mut self, config: &SwoConfig) -> Result<u32, ArmError> {
         match config.mode() {
             SwoMode::Uart => {
                 self.start_trace_reception(config)?;
-                Ok(())
+                Ok(config.baud())
             }
             SwoMode::Manchester => Err(DebugProbeError::ProbeSpecific(
                 StlinkError::ManchesterSwoNotSupported.into(),
@@ -1148,6 +1265,16 @@ pub(crate) enum StlinkError {
     MultidropNotSupported,
     #[error("Unaligned")]
     UnalignedAddress,
+    #[error(
+        "16-bit memory transfers are not supported by the firmware of the probe, please update it."
+    )]
+    Unsupported16BitTransfers,
+    #[error("Invalid transfer length of {length} bytes, it has to be a multiple of {alignment} bytes, and at most {max_length} bytes.")]
+    InvalidTransferLength {
+        length: usize,
+        max_length: usize,
+        alignment: usize,
+    },
     #[error("USB")]
     Usb(#[from] rusb::Error),
 }
@@ -1394,7 +1521,7 @@ impl SwdSequence for StlinkArmDebug {
 }
 
 impl SwoAccess for StlinkArmDebug {
-    fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError> {
+    fn enable_swo(&mut self, config: &SwoConfig) -> Result<u32, ArmError> {
         self.probe.enable_swo(config)
     }
 
@@ -1472,6 +1599,27 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
         Ok(())
     }
 
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), ArmError> {
+        let address = valid_32bit_arm_address(address)?;
+
+        // Read needs to be chunked into chunks with appropiate max length (see STLINK_MAX_READ_LEN).
+        for (index, chunk) in data.chunks_mut(STLINK_MAX_READ_LEN / 2).enumerate() {
+            let mut buff = vec![0u8; 2 * chunk.len()];
+
+            self.probe.probe.read_mem_16bit(
+                address + (index * STLINK_MAX_READ_LEN) as u32,
+                &mut buff,
+                self.current_ap.ap_address().ap,
+            )?;
+
+            for (index, half_word) in buff.chunks_exact(2).enumerate() {
+                chunk[index] = u16::from_le_bytes(half_word.try_into().unwrap());
+            }
+        }
+
+        Ok(())
+    }
+
     fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), ArmError> {
         let address = valid_32bit_arm_address(address)?;
 
@@ -1545,6 +1693,30 @@ impl ArmProbe for StLinkMemoryInterface<'_> {
         Ok(())
     }
 
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), ArmError> {
+        let address = valid_32bit_arm_address(address)?;
+
+        let mut tx_buffer = vec![0u8; data.len() * 2];
+
+        let mut offset = 0;
+
+        for half_word in data {
+            tx_buffer
+                .gwrite(half_word, &mut offset)
+                .expect("Failed to write into tx_buffer");
+        }
+
+        for (index, chunk) in tx_buffer.chunks(STLINK_MAX_WRITE_LEN).enumerate() {
+            self.probe.probe.write_mem_16bit(
+                address + (index * STLINK_MAX_WRITE_LEN) as u32,
+                chunk,
+                self.current_ap.ap_address().ap,
+            )?;
+        }
+
+        Ok(())
+    }
+
     fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), ArmError> {
         let address = valid_32bit_arm_address(address)?;
 
@@ -1846,6 +2018,59 @@ mod test {
             .expect("Selecting AP other than AP 0 should work");
     }
 
+    #[test]
+    fn firmware_without_16bit_transfers() {
+        let usb_mock = MockUsb {
+            hw_version: 2,
+            jtag_version: 24,
+            swim_version: 0,
+            target_voltage_a0: 1.0,
+            _target_voltage_a1: 2.0,
+        };
+
+        let mut probe = usb_mock.build();
+        probe.hw_version = 2;
+        probe.jtag_version = 24;
+
+        let mut data = [0; 4];
+        let error = probe.read_mem_16bit(0x2000_0000, &mut data, 0).unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                DebugProbeError::ProbeSpecific(error) if matches!(
+                    error.downcast_ref::<StlinkError>(),
+                    Some(StlinkError::Unsupported16BitTransfers)
+                )
+            ),
+            "Unexpected error {error:?}"
+        );
+
+        probe.write_mem_16bit(0x2000_0000, &data, 0).unwrap_err();
+    }
+
+    #[test]
+    fn invalid_16bit_transfer_length() {
+        let usb_mock = MockUsb {
+            hw_version: 3,
+            jtag_version: 3,
+            swim_version: 0,
+            target_voltage_a0: 1.0,
+            _target_voltage_a1: 2.0,
+        };
+
+        let mut probe = usb_mock.build();
+        probe.hw_version = 3;
+        probe.jtag_version = 3;
+
+        // Odd lengths and lengths above the maximum are errors instead of panics.
+        let mut data = [0; 3];
+        probe.read_mem_16bit(0x2000_0000, &mut data, 0).unwrap_err();
+        probe.write_mem_16bit(0x2000_0000, &data, 0).unwrap_err();
+
+        let data = vec![0; STLINK_MAX_WRITE_LEN + 2];
+        probe.write_mem_16bit(0x2000_0000, &data, 0).unwrap_err();
+    }
+
     #[test]
     fn test_is_wait_error() {
         assert!(!is_wait_error(&StlinkError::BanksNotAllowedOnDPRegister));
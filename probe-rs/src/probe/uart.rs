@@ -0,0 +1,116 @@
+//! Access to the UART of the target through the debug probe.
+
+use crate::DebugProbeError;
+
+/// The parity of the UART frames.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum UartParity {
+    /// No parity bit
+    #[default]
+    None,
+    /// Odd parity
+    Odd,
+    /// Even parity
+    Even,
+}
+
+/// The number of stop bits of the UART frames.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum UartStopBits {
+    /// One stop bit
+    #[default]
+    One,
+    /// One and a half stop bits
+    OneAndHalf,
+    /// Two stop bits
+    Two,
+}
+
+/// The config for the UART of the target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UartConfig {
+    /// Baud rate, in Hz.
+    baud: u32,
+
+    /// The number of data bits, from 5 to 8.
+    data_bits: u8,
+
+    /// The parity.
+    parity: UartParity,
+
+    /// The number of stop bits.
+    stop_bits: UartStopBits,
+}
+
+impl UartConfig {
+    /// Create a new UartConfig using the specified baud rate in Hz.
+    ///
+    /// By default 8 data bits, no parity and one stop bit are used.
+    pub fn new(baud: u32) -> Self {
+        UartConfig {
+            baud,
+            data_bits: 8,
+            parity: UartParity::None,
+            stop_bits: UartStopBits::One,
+        }
+    }
+
+    /// Set the number of data bits, from 5 to 8.
+    pub fn set_data_bits(mut self, data_bits: u8) -> Self {
+        self.data_bits = data_bits;
+        self
+    }
+
+    /// Set the parity.
+    pub fn set_parity(mut self, parity: UartParity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    /// Set the number of stop bits.
+    pub fn set_stop_bits(mut self, stop_bits: UartStopBits) -> Self {
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    /// Baud rate, in Hz.
+    pub fn baud(&self) -> u32 {
+        self.baud
+    }
+
+    /// The number of data bits.
+    pub fn data_bits(&self) -> u8 {
+        self.data_bits
+    }
+
+    /// The parity.
+    pub fn parity(&self) -> UartParity {
+        self.parity
+    }
+
+    /// The number of stop bits.
+    pub fn stop_bits(&self) -> UartStopBits {
+        self.stop_bits
+    }
+}
+
+/// An interface to the UART of the target, to be implemented on drivers of probes which bridge it.
+pub trait UartAccess {
+    /// Configure the UART and start receiving data.
+    ///
+    /// Returns the configured baud rate, which may differ from the requested one.
+    fn enable_uart(&mut self, config: &UartConfig) -> Result<u32, DebugProbeError>;
+
+    /// Stop receiving data from the UART.
+    fn disable_uart(&mut self) -> Result<(), DebugProbeError>;
+
+    /// Read any data received since the last call, without waiting.
+    ///
+    /// If no data was received, returns an empty Vec.
+    fn read_uart(&mut self) -> Result<Vec<u8>, DebugProbeError>;
+
+    /// Send data to the target.
+    ///
+    /// Blocks until all data was handed to the probe.
+    fn write_uart(&mut self, data: &[u8]) -> Result<(), DebugProbeError>;
+}
@@ -0,0 +1,279 @@
+//! Statistical profiling by sampling the program counter.
+//!
+//! On Cortex-M cores, the PC can be sampled without halting the core in two ways:
+//! - The DWT periodically emits PC samples as ITM packets, see
+//!   [`Session::enable_pc_sampling`](crate::Session::enable_pc_sampling). They are extracted from
+//!   the trace data with a [`PcSampleDecoder`].
+//! - The debugger polls the DWT_PCSR register with [`read_pcsr`].
+//!
+//! The samples are collected in a [`Profile`], which resolves them to the sampled functions and
+//! writes them as folded stacks. These can be turned into a flamegraph, e.g. with `inferno` or
+//! `flamegraph.pl`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use crate::debug::DebugInfo;
+use crate::{Core, Error, MemoryInterface};
+
+/// The address of the DWT_PCSR register, which holds a recently executed PC.
+const DWT_PCSR: u64 = 0xE000_101C;
+
+/// The header of an ITM packet with a full PC sample.
+const PC_SAMPLE_HEADER: u8 = 0x17;
+
+/// The header of an ITM packet with a one byte PC sample, which is emitted while the core sleeps.
+const SLEEP_SAMPLE_HEADER: u8 = 0x15;
+
+/// A sample of the program counter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PcSample {
+    /// The core was executing the instruction at this address.
+    Pc(u64),
+    /// The core was sleeping, e.g. waiting for an interrupt.
+    Sleeping,
+}
+
+/// Extracts the PC samples of the DWT from a stream of ITM packets.
+///
+/// The stream can be split into chunks at any point, packets which are split are decoded when
+/// their remaining data is passed to [`PcSampleDecoder::decode`].
+#[derive(Debug, Clone, Default)]
+pub struct PcSampleDecoder {
+    /// The data of an incomplete packet at the end of the last chunk.
+    buffer: Vec<u8>,
+}
+
+impl PcSampleDecoder {
+    /// Creates a decoder, which expects the stream to start with a packet header.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes the next chunk of ITM data, returning the PC samples it contains.
+    ///
+    /// All other packets, e.g. the ones of the stimulus ports, are skipped.
+    pub fn decode(&mut self, data: &[u8]) -> Vec<PcSample> {
+        self.buffer.extend_from_slice(data);
+
+        let mut samples = Vec::new();
+        let mut offset = 0;
+        while offset < self.buffer.len() {
+            let packet = &self.buffer[offset..];
+            let length = match packet_length(packet) {
+                Some(length) => length,
+                None => break,
+            };
+
+            match packet[0] {
+                PC_SAMPLE_HEADER => {
+                    let pc = u32::from_le_bytes([packet[1], packet[2], packet[3], packet[4]]);
+                    samples.push(PcSample::Pc(u64::from(pc)));
+                }
+                SLEEP_SAMPLE_HEADER if packet[1] == 0 => samples.push(PcSample::Sleeping),
+                _ => {}
+            }
+
+            offset += length;
+        }
+
+        self.buffer.drain(..offset);
+        samples
+    }
+}
+
+/// Returns the length of the ITM packet at the start of `data`, or `None` if it is incomplete.
+fn packet_length(data: &[u8]) -> Option<usize> {
+    let header = data[0];
+
+    let length = match header & 0b11 {
+        // Synchronization and overflow packets, and the end of a synchronization packet.
+        0b00 if header == 0x00 || header == 0x70 || header == 0x80 => 1,
+        // Timestamp and extension packets, which continue while the continuation bit is set.
+        0b00 if header & 0x80 != 0 => data[1..].iter().position(|byte| byte & 0x80 == 0)? + 2,
+        0b00 => 1,
+        // Stimulus port and hardware source packets, whose size is given by the header.
+        0b01 => 2,
+        0b10 => 3,
+        _ => 5,
+    };
+
+    (data.len() >= length).then_some(length)
+}
+
+/// Reads the DWT_PCSR register, which holds a recently executed PC, without halting the core.
+///
+/// Returns `None` if no PC is available, because the core is halted, or because the register is
+/// not implemented.
+pub fn read_pcsr(core: &mut Core) -> Result<Option<u64>, Error> {
+    let pc = core.read_word_32(DWT_PCSR)?;
+
+    // The register reads as all ones while the core is halted, and as zero if it is not
+    // implemented.
+    if pc == 0 || pc == 0xFFFF_FFFF {
+        Ok(None)
+    } else {
+        Ok(Some(u64::from(pc)))
+    }
+}
+
+/// A statistical profile of the program, made of PC samples.
+///
+/// Only the PC is sampled, so each sample is attributed to the function containing it and to the
+/// functions which are inlined at the PC, but not to the callers of that function.
+pub struct Profile<'a> {
+    debug_info: &'a DebugInfo,
+    /// Whether the source location of a sample is added as its innermost frame.
+    source_lines: bool,
+    /// The folded stack of every sampled PC, which is cached as resolving it is slow.
+    stacks: HashMap<u64, String>,
+    /// The number of samples of every folded stack.
+    counts: BTreeMap<String, u64>,
+    samples: u64,
+}
+
+impl<'a> Profile<'a> {
+    /// Creates an empty profile, whose samples are resolved with `debug_info`.
+    pub fn new(debug_info: &'a DebugInfo) -> Self {
+        Profile {
+            debug_info,
+            source_lines: false,
+            stacks: HashMap::new(),
+            counts: BTreeMap::new(),
+            samples: 0,
+        }
+    }
+
+    /// Set whether the source location of a sample is added as its innermost frame, so that the
+    /// hot lines of a function can be told apart.
+    pub fn set_source_lines(mut self, enabled: bool) -> Self {
+        self.source_lines = enabled;
+        self
+    }
+
+    /// Adds a PC sample to the profile.
+    pub fn add_sample(&mut self, sample: PcSample) {
+        let stack = match sample {
+            PcSample::Pc(pc) => match self.stacks.get(&pc) {
+                Some(stack) => stack.clone(),
+                None => {
+                    let stack = self.resolve(pc);
+                    self.stacks.insert(pc, stack.clone());
+                    stack
+                }
+            },
+            PcSample::Sleeping => "<sleeping>".to_string(),
+        };
+
+        *self.counts.entry(stack).or_default() += 1;
+        self.samples += 1;
+    }
+
+    /// The total number of samples in the profile.
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// The folded stacks of the profile, with the number of samples of each one.
+    ///
+    /// A folded stack consists of the names of the frames from the outermost to the innermost
+    /// one, separated by semicolons.
+    pub fn folded_stacks(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.counts
+            .iter()
+            .map(|(stack, count)| (stack.as_str(), *count))
+    }
+
+    /// Writes the profile as folded stacks, with one stack and its number of samples per line.
+    ///
+    /// This is the input format of flamegraph generators like `inferno` and `flamegraph.pl`.
+    pub fn write_folded(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for (stack, count) in self.folded_stacks() {
+            writeln!(writer, "{stack} {count}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolves a PC to the folded stack of the functions it is part of.
+    fn resolve(&self, pc: u64) -> String {
+        let mut frames = self.debug_info.function_names(pc).unwrap_or_else(|error| {
+            tracing::debug!("Failed to resolve the function at {:#010x}: {}", pc, error);
+            Vec::new()
+        });
+        if frames.is_empty() {
+            frames.push(format!("{pc:#010x}"));
+        }
+
+        if self.source_lines {
+            if let Some(location) = self
+                .debug_info
+                .get_source_location(pc)
+                .and_then(|location| Some(format!("{}:{}", location.file?, location.line?)))
+            {
+                frames.push(location);
+            }
+        }
+
+        // Semicolons separate the frames, so they must not be part of a name, as in `[u8; 4]`.
+        frames
+            .iter()
+            .map(|frame| frame.replace(';', ","))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_pc_samples() {
+        #[rustfmt::skip]
+        let data = [
+            // Synchronization
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+            // PC sample of 0x08001234
+            0x17, 0x34, 0x12, 0x00, 0x08,
+            // Local timestamp with two continuation bytes
+            0xC0, 0x81, 0x01,
+            // Stimulus port 0 with the byte 0x17
+            0x01, 0x17,
+            // Sleep sample
+            0x15, 0x00,
+            // Overflow
+            0x70,
+            // Exception trace of exception 15
+            0x0E, 0x0F, 0x10,
+            // PC sample of 0x08000100
+            0x17, 0x00, 0x01, 0x00, 0x08,
+        ];
+
+        let mut decoder = PcSampleDecoder::new();
+        assert_eq!(
+            decoder.decode(&data),
+            vec![
+                PcSample::Pc(0x0800_1234),
+                PcSample::Sleeping,
+                PcSample::Pc(0x0800_0100)
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_split_packets() {
+        let mut decoder = PcSampleDecoder::new();
+
+        assert_eq!(decoder.decode(&[0x17, 0x34, 0x12]), vec![]);
+        assert_eq!(
+            decoder.decode(&[0x00, 0x08, 0xC0]),
+            vec![PcSample::Pc(0x0800_1234)]
+        );
+        assert_eq!(decoder.decode(&[0x81]), vec![]);
+        assert_eq!(
+            decoder.decode(&[0x01, 0x15, 0x00]),
+            vec![PcSample::Sleeping]
+        );
+    }
+}
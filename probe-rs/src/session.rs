@@ -1,21 +1,33 @@
+use crate::architecture::arm::ap::{AccessPort, MemoryAp};
 use crate::architecture::arm::component::get_arm_components;
+use crate::architecture::arm::core::cti;
 use crate::architecture::arm::sequences::{ArmDebugSequence, DefaultArmSequence};
 use crate::architecture::arm::{ArmError, DpAddress};
 use crate::architecture::riscv::communication_interface::RiscvError;
-use crate::config::{ChipInfo, CoreExt, RegistryError, Target, TargetSelector};
+use crate::config::{ChipInfo, RegistryError, Target, TargetSelector};
+use crate::core::dump::{merge_ranges, CoreDump, CoreDumpCore};
 use crate::core::{Architecture, CombinedCoreState};
 use crate::{
     architecture::{
         arm::{
-            communication_interface::ArmProbeInterface, component::TraceSink,
-            memory::CoresightComponent, SwoReader,
+            communication_interface::ArmProbeInterface,
+            component::{Deformatter, EtmConfig, Mtb, TraceSink},
+            etm::DecoderConfig,
+            memory::{CoresightComponent, PeripheralType},
+            SwoReader,
         },
         riscv::communication_interface::RiscvCommunicationInterface,
     },
     config::DebugSequence,
 };
-use crate::{AttachMethod, Core, CoreType, Error, FakeProbe, Probe};
-use std::ops::DerefMut;
+use crate::{
+    AttachMethod, Core, CoreType, Error, FakeProbe, MemoryInterface, Probe, RegisterValue,
+    UartAccess,
+};
+use anyhow::anyhow;
+use probe_rs_target::MemoryRegion;
+use std::ops::{DerefMut, Range};
+use std::path::Path;
 use std::{fmt, sync::Arc, time::Duration};
 
 /// The `Session` struct represents an active debug session.
@@ -42,11 +54,24 @@ pub struct Session {
     interface: ArchitectureInterface,
     cores: Vec<CombinedCoreState>,
     configured_trace_sink: Option<TraceSink>,
+    trace_deformatter: Deformatter,
+    /// The cores which can be halted and restarted through their Cross Trigger Interface,
+    /// once they have been looked up.
+    cti_cores: Option<Vec<CtiCore>>,
+}
+
+/// A core which can be halted and restarted through its Cross Trigger Interface (CTI).
+#[derive(Debug, Clone, Copy)]
+struct CtiCore {
+    core_index: usize,
+    memory_ap: MemoryAp,
+    cti_base: u64,
 }
 
 pub(crate) enum ArchitectureInterface {
     Arm(Box<dyn ArmProbeInterface + 'static>),
     Riscv(Box<RiscvCommunicationInterface>),
+    CoreDump(Box<CoreDump>),
 }
 
 impl fmt::Debug for ArchitectureInterface {
@@ -57,6 +82,9 @@ impl fmt::Debug for ArchitectureInterface {
                 .debug_tuple("ArchitectureInterface::Riscv")
                 .field(iface)
                 .finish(),
+            ArchitectureInterface::CoreDump(..) => {
+                f.write_str("ArchitectureInterface::CoreDump(..)")
+            }
         }
     }
 }
@@ -66,6 +94,10 @@ impl From<ArchitectureInterface> for Architecture {
         match value {
             ArchitectureInterface::Arm(_) => Architecture::Arm,
             ArchitectureInterface::Riscv(_) => Architecture::Riscv,
+            ArchitectureInterface::CoreDump(dump) => dump
+                .cores
+                .first()
+                .map_or(Architecture::Arm, |core| core.core_type.architecture()),
         }
     }
 }
@@ -80,6 +112,7 @@ impl ArchitectureInterface {
             ArchitectureInterface::Riscv(riscv_interface) => {
                 combined_state.attach_riscv(riscv_interface)
             }
+            ArchitectureInterface::CoreDump(dump) => combined_state.attach_core_dump(dump),
         }
     }
 }
@@ -127,16 +160,8 @@ impl Session {
         target: Target,
         attach_method: AttachMethod,
         permissions: Permissions,
-        cores: Vec<CombinedCoreState>,
+        mut cores: Vec<CombinedCoreState>,
     ) -> Result<Self, Error> {
-        let default_core = target.default_core();
-
-        let default_memory_ap = default_core.memory_ap().ok_or_else(|| {
-            Error::Other(anyhow::anyhow!(
-                "Unable to connect to core {default_core:?}, no memory AP configured"
-            ))
-        })?;
-
         let sequence_handle = match &target.debug_sequence {
             DebugSequence::Arm(sequence) => sequence.clone(),
             DebugSequence::Riscv(_) => {
@@ -160,6 +185,7 @@ impl Session {
             }
         }
 
+        probe.use_target_jtag_chain(&target)?;
         probe.inner_attach()?;
 
         let interface = probe.try_into_arm_interface().map_err(|(_, err)| err)?;
@@ -168,6 +194,12 @@ impl Session {
             .initialize(sequence_handle.clone())
             .map_err(|(_interface, e)| e)?;
 
+        // The APs of ADIv6 debug ports are only numbered once they are discovered.
+        for core in &mut cores {
+            core.core_state.resolve_arm_ap_address(&mut *interface)?;
+        }
+        let default_memory_ap = cores[0].arm_memory_ap();
+
         let unlock_span = tracing::debug_span!("debug_device_unlock").entered();
 
         // Enable debug mode
@@ -217,6 +249,8 @@ impl Session {
                 interface: ArchitectureInterface::Arm(interface),
                 cores,
                 configured_trace_sink: None,
+                trace_deformatter: Deformatter::new(),
+                cti_cores: None,
             };
 
             {
@@ -240,6 +274,8 @@ impl Session {
                 interface: ArchitectureInterface::Arm(interface),
                 cores,
                 configured_trace_sink: None,
+                trace_deformatter: Deformatter::new(),
+                cti_cores: None,
             })
         }
     }
@@ -260,6 +296,7 @@ impl Session {
             }
         };
 
+        probe.use_target_jtag_chain(&target)?;
         probe.inner_attach()?;
 
         let interface = probe
@@ -271,6 +308,8 @@ impl Session {
             interface: ArchitectureInterface::Riscv(Box::new(interface)),
             cores,
             configured_trace_sink: None,
+            trace_deformatter: Deformatter::new(),
+            cti_cores: None,
         };
 
         {
@@ -285,6 +324,65 @@ impl Session {
         Ok(session)
     }
 
+    /// Creates a session which inspects a [`CoreDump`] instead of a real target.
+    ///
+    /// The cores of the session are permanently halted, and their registers and memory are read from the dump.
+    /// All attempts to modify the target or to resume a core fail. The target has to be given by name or description,
+    /// as it can not be detected from a core dump.
+    pub fn from_core_dump(
+        dump: CoreDump,
+        target: impl Into<TargetSelector>,
+    ) -> Result<Session, Error> {
+        let target = match target.into() {
+            TargetSelector::Unspecified(name) => crate::config::get_target_by_name(name)?,
+            TargetSelector::Specified(target) => target,
+            TargetSelector::Auto => {
+                return Err(Error::Other(anyhow!(
+                    "The target of a core dump can not be detected automatically"
+                )))
+            }
+        };
+
+        for core in &dump.cores {
+            let target_core = target
+                .cores
+                .get(core.index)
+                .ok_or(Error::CoreNotFound(core.index))?;
+
+            if target_core.core_type != core.core_type {
+                return Err(Error::Other(anyhow!(
+                    "Core {} of the core dump is a {:?} core, but the target has a {:?} core",
+                    core.index,
+                    core.core_type,
+                    target_core.core_type
+                )));
+            }
+        }
+
+        let cores = target
+            .cores
+            .iter()
+            .enumerate()
+            .map(|(id, core)| {
+                Core::create_state(
+                    id,
+                    core.core_access_options.clone(),
+                    &target,
+                    core.core_type,
+                )
+            })
+            .collect();
+
+        Ok(Session {
+            target,
+            interface: ArchitectureInterface::CoreDump(Box::new(dump)),
+            cores,
+            configured_trace_sink: None,
+            trace_deformatter: Deformatter::new(),
+            cti_cores: None,
+        })
+    }
+
     /// Automatically creates a session with the first connected probe found.
     #[tracing::instrument(skip(target))]
     pub fn auto_attach(
@@ -335,6 +433,9 @@ impl Session {
 
     /// Read available trace data from the specified data sink.
     ///
+    /// For the trace memory, only the data of the ITM is returned. For SWO, the data is returned
+    /// as received, which includes the formatting if continuous formatting is enabled.
+    ///
     /// This method is only supported for ARM-based targets, and will
     /// return [ArmError::ArchitectureRequired] otherwise.
     #[tracing::instrument(skip(self))]
@@ -344,6 +445,101 @@ impl Session {
             .as_ref()
             .ok_or(ArmError::TracingUnconfigured)?;
 
+        match sink {
+            TraceSink::Swo(_) | TraceSink::Tpiu(_) => self.read_raw_trace_data(),
+
+            TraceSink::TraceMemory => {
+                let sources = self.read_trace_sources()?;
+                Ok(source_data(
+                    sources,
+                    crate::architecture::arm::component::ITM_TRACE_ID,
+                ))
+            }
+        }
+    }
+
+    /// Read available trace data from the specified data sink, split into the data of the
+    /// trace sources.
+    ///
+    /// Returns pairs of the ATB trace ID of a source and consecutive bytes of that source, see
+    /// [`Deformatter::decode`]. The trace data has to be formatted, which is always the case
+    /// for the trace memory. For SWO, continuous formatting has to be enabled with
+    /// [`SwoConfig::set_continuous_formatting`](crate::architecture::arm::SwoConfig::set_continuous_formatting).
+    #[tracing::instrument(skip(self))]
+    pub fn read_trace_sources(&mut self) -> Result<Vec<(u8, Vec<u8>)>, ArmError> {
+        if let Some(TraceSink::Swo(config) | TraceSink::Tpiu(config)) = self.configured_trace_sink {
+            if !config.tpiu_continuous_formatting() {
+                return Err(ArmError::Other(anyhow!(
+                    "Trace sources can only be separated with continuous formatting enabled"
+                )));
+            }
+        }
+
+        let data = self.read_raw_trace_data()?;
+        Ok(self.trace_deformatter.decode(&data))
+    }
+
+    /// Read the instruction trace of the ETM.
+    ///
+    /// The trace has to be configured with [`Session::setup_tracing`], and the ETM with
+    /// [`Session::enable_etm`]. The data of other trace sources is discarded, use
+    /// [`Session::read_trace_sources`] to get the data of all of them.
+    ///
+    /// The returned data can be decoded with [`crate::architecture::arm::etm::decode_trace`].
+    #[tracing::instrument(skip(self))]
+    pub fn read_etm_trace_data(&mut self) -> Result<Vec<u8>, Error> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        let trace_id = crate::architecture::arm::component::etm_trace_id(interface, &components)?;
+
+        let sources = self.read_trace_sources()?;
+        Ok(source_data(sources, trace_id))
+    }
+
+    /// Configure the DWT to emit a PC sample every `interval` cycles.
+    ///
+    /// The trace has to be configured with [`Session::setup_tracing`] before. The samples are
+    /// extracted from the data returned by [`Session::read_trace_data`] with a
+    /// [`PcSampleDecoder`](crate::profiling::PcSampleDecoder).
+    ///
+    /// # Returns
+    /// The interval which is actually used, as the DWT only supports a few intervals.
+    pub fn enable_pc_sampling(&mut self, interval: u32) -> Result<u32, Error> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        Ok(crate::architecture::arm::component::enable_pc_sampling(
+            interface,
+            &components,
+            interval,
+        )?)
+    }
+
+    /// Configure the DWT to stop emitting PC samples.
+    pub fn disable_pc_sampling(&mut self) -> Result<(), Error> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        Ok(crate::architecture::arm::component::disable_pc_sampling(
+            interface,
+            &components,
+        )?)
+    }
+
+    /// Finds the MTB (Micro Trace Buffer) of the target in the ROM table.
+    ///
+    /// Cortex-M0+ cores have no ETM, but often an MTB, which records the branches of the core
+    /// into the SRAM.
+    pub fn mtb(&mut self) -> Result<Mtb, Error> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        Ok(crate::architecture::arm::component::find_mtb(&components)?)
+    }
+
+    /// Reads the trace data from the configured trace sink, as it is received.
+    fn read_raw_trace_data(&mut self) -> Result<Vec<u8>, ArmError> {
+        let sink = self
+            .configured_trace_sink
+            .as_ref()
+            .ok_or(ArmError::TracingUnconfigured)?;
+
         match sink {
             TraceSink::Swo(_) => {
                 let interface = self.get_arm_interface()?;
@@ -375,6 +571,17 @@ impl Session {
         Ok(SwoReader::new(interface))
     }
 
+    /// Returns the interface to the UART of the target, if the probe bridges it.
+    ///
+    /// The UART has to be configured with [`UartAccess::enable_uart`] before data is received.
+    pub fn uart_interface(&mut self) -> Option<&mut dyn UartAccess> {
+        match &mut self.interface {
+            ArchitectureInterface::Arm(interface) => interface.uart_interface(),
+            ArchitectureInterface::Riscv(interface) => interface.uart_interface(),
+            ArchitectureInterface::CoreDump(_) => None,
+        }
+    }
+
     /// Get the Arm probe interface.
     pub fn get_arm_interface(&mut self) -> Result<&mut dyn ArmProbeInterface, ArmError> {
         let interface = match &mut self.interface {
@@ -448,7 +655,7 @@ impl Session {
     pub fn sequence_erase_all(&mut self) -> Result<(), Error> {
         let interface = match &mut self.interface {
             ArchitectureInterface::Arm(interface) => interface,
-            ArchitectureInterface::Riscv(_) => {
+            ArchitectureInterface::Riscv(_) | ArchitectureInterface::CoreDump(_) => {
                 return Err(Error::Probe(crate::DebugProbeError::NotImplemented(
                     "Debug Erase Sequence",
                 )))
@@ -465,7 +672,8 @@ impl Session {
         let Some(erase_sequence) = debug_sequence.debug_erase_sequence() else {
             return Err(Error::Probe(crate::DebugProbeError::NotImplemented(
                 "Debug Erase Sequence",
-        )))};
+            )));
+        };
 
         tracing::info!("Trying Debug Erase Sequence");
         let erase_result = erase_sequence.erase_all(interface.deref_mut());
@@ -508,7 +716,7 @@ impl Session {
     pub fn setup_tracing(
         &mut self,
         core_index: usize,
-        destination: TraceSink,
+        mut destination: TraceSink,
     ) -> Result<(), Error> {
         // Enable tracing on the target
         {
@@ -529,11 +737,19 @@ impl Session {
         // Configure SWO on the probe when the trace sink is configured for a serial output. Note
         // that on some architectures, the TPIU is configured to drive SWO.
         match destination {
-            TraceSink::Swo(ref config) => {
-                interface.enable_swo(config)?;
-            }
-            TraceSink::Tpiu(ref config) => {
-                interface.enable_swo(config)?;
+            TraceSink::Swo(ref mut config) | TraceSink::Tpiu(ref mut config) => {
+                // Request a baud rate the target can generate, and then configure the target for
+                // the baud rate the probe actually receives at.
+                let requested = config.set_baud(config.achievable_baud());
+                let baud = interface.enable_swo(&requested)?;
+                if baud != config.baud() {
+                    tracing::info!(
+                        "Using SWO baud rate {} instead of the requested {}",
+                        baud,
+                        config.baud()
+                    );
+                }
+                *config = requested.set_baud(baud);
             }
             TraceSink::TraceMemory => {}
         }
@@ -541,11 +757,39 @@ impl Session {
         sequence_handle.trace_start(interface, &components, &destination)?;
         crate::architecture::arm::component::setup_tracing(interface, &components, &destination)?;
 
+        // Data in the trace memory always starts at a frame boundary, while the SWO data may
+        // start anywhere.
+        self.trace_deformatter = match destination {
+            TraceSink::TraceMemory => Deformatter::new(),
+            TraceSink::Swo(_) | TraceSink::Tpiu(_) => Deformatter::unsynchronized(),
+        };
         self.configured_trace_sink.replace(destination);
 
         Ok(())
     }
 
+    /// Configure the ETM of the core for instruction trace, and enable it.
+    ///
+    /// The trace is written to the trace sink configured with [`Session::setup_tracing`].
+    ///
+    /// Returns the configuration needed to decode the trace with
+    /// [`crate::architecture::arm::etm::decode_trace`].
+    pub fn enable_etm(
+        &mut self,
+        core_index: usize,
+        config: &EtmConfig,
+    ) -> Result<DecoderConfig, Error> {
+        // Enable tracing on the target
+        {
+            let mut core = self.core(core_index)?;
+            crate::architecture::arm::component::enable_tracing(&mut core)?;
+        }
+
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::enable_etm(interface, &components, config)
+    }
+
     /// Configure the target to stop emitting SWV trace data.
     #[tracing::instrument(skip(self))]
     pub fn disable_swv(&mut self, core_index: usize) -> Result<(), Error> {
@@ -576,7 +820,246 @@ impl Session {
         match self.interface {
             ArchitectureInterface::Arm(_) => Architecture::Arm,
             ArchitectureInterface::Riscv(_) => Architecture::Riscv,
+            ArchitectureInterface::CoreDump(_) => self.target.architecture(),
+        }
+    }
+
+    /// Halts all cores of the target.
+    ///
+    /// Cores with a Cross Trigger Interface (CTI) are halted together through the Cross Trigger Matrix,
+    /// so that they stop within a few cycles of each other. Cores without a CTI are halted one after the other.
+    #[tracing::instrument(skip(self))]
+    pub fn halt_all(&mut self, timeout: Duration) -> Result<(), Error> {
+        let cti_cores = self.cti_cores()?;
+
+        if let Some(first) = cti_cores.first() {
+            self.set_cross_halt(&cti_cores, false)?;
+
+            let interface = self.get_arm_interface()?;
+            let mut memory = interface.memory_interface(first.memory_ap)?;
+            cti::broadcast_channel(&mut *memory, first.cti_base, cti::CTI_HALT_CHANNEL)?;
         }
+
+        // This halts the cores without a CTI, and updates the cached state of the others.
+        for core_index in 0..self.cores.len() {
+            self.core(core_index)?.halt(timeout)?;
+        }
+
+        Ok(())
+    }
+
+    /// Resumes all cores of the target.
+    ///
+    /// Cores with a Cross Trigger Interface (CTI) are restarted together through the Cross Trigger Matrix.
+    /// Afterwards, they are configured to halt each other: as soon as one of them halts, e.g. because of
+    /// a breakpoint, the others are halted as well. This lasts until the next call to [`Session::halt_all`].
+    ///
+    /// Cores without a CTI are resumed one after the other. `timeout` limits the wait for each CTI
+    /// to acknowledge the halt event of its core before the restart.
+    #[tracing::instrument(skip(self))]
+    pub fn resume_all(&mut self, timeout: Duration) -> Result<(), Error> {
+        let cti_cores = self.cti_cores()?;
+
+        // Stop cross-halting, so a core which halts right after the restart does not halt the others again.
+        self.set_cross_halt(&cti_cores, false)?;
+
+        let mut restarted_cores = Vec::new();
+        for core_index in 0..self.cores.len() {
+            let mut core = self.core(core_index)?;
+
+            if !cti_cores
+                .iter()
+                .any(|cti_core| cti_core.core_index == core_index)
+            {
+                core.run()?;
+            } else if core.core_halted()? {
+                core.prepare_restart()?;
+                restarted_cores.push(core_index);
+            }
+        }
+
+        let restarted_cores = cti_cores
+            .iter()
+            .filter(|cti_core| restarted_cores.contains(&cti_core.core_index))
+            .collect::<Vec<_>>();
+
+        if let Some(first) = restarted_cores.first() {
+            let interface = self.get_arm_interface()?;
+
+            for cti_core in &restarted_cores {
+                let mut memory = interface.memory_interface(cti_core.memory_ap)?;
+                cti::acknowledge_halt(&mut *memory, cti_core.cti_base, timeout)?;
+            }
+
+            let mut memory = interface.memory_interface(first.memory_ap)?;
+            cti::broadcast_channel(&mut *memory, first.cti_base, cti::CTI_RESTART_CHANNEL)?;
+        }
+
+        // Verify that the cores were restarted.
+        for cti_core in &restarted_cores {
+            if self.core(cti_core.core_index)?.status()?.is_halted() {
+                return Err(Error::Other(anyhow!(
+                    "Core {} is still halted after the restart through its CTI",
+                    cti_core.core_index
+                )));
+            }
+        }
+
+        self.set_cross_halt(&cti_cores, true)
+    }
+
+    /// The cores of the target which have a Cross Trigger Interface (CTI).
+    ///
+    /// The CTI base address is taken from the target description. For Cortex-M cores, it is
+    /// otherwise looked up in the ROM table of the memory AP of the core.
+    fn cti_cores(&mut self) -> Result<Vec<CtiCore>, Error> {
+        if let Some(cti_cores) = &self.cti_cores {
+            return Ok(cti_cores.clone());
+        }
+
+        if !matches!(self.interface, ArchitectureInterface::Arm(_)) {
+            self.cti_cores = Some(vec![]);
+            return Ok(vec![]);
+        }
+
+        let mut cti_cores = Vec::new();
+        for core_index in 0..self.cores.len() {
+            let core = &self.cores[core_index];
+            let memory_ap = core.arm_memory_ap();
+
+            let cti_base = match core.arm_cti_base() {
+                Some(cti_base) => Some(cti_base),
+                // The CTI of a Cortex-M core can only be told apart from the CTIs of other cores
+                // if the core has a memory AP of its own.
+                None if core.core_type().is_cortex_m()
+                    && self
+                        .cores
+                        .iter()
+                        .filter(|other| {
+                            other.arm_memory_ap().ap_address() == memory_ap.ap_address()
+                        })
+                        .count()
+                        == 1 =>
+                {
+                    self.find_cti(memory_ap)?
+                }
+                None => None,
+            };
+
+            if let Some(cti_base) = cti_base {
+                tracing::debug!("Core {} has a CTI at {:#010x}", core_index, cti_base);
+                cti_cores.push(CtiCore {
+                    core_index,
+                    memory_ap,
+                    cti_base,
+                });
+            }
+        }
+
+        self.cti_cores = Some(cti_cores.clone());
+
+        Ok(cti_cores)
+    }
+
+    /// Looks up the base address of a CTI in the ROM table of the given memory AP.
+    fn find_cti(&mut self, memory_ap: MemoryAp) -> Result<Option<u64>, Error> {
+        let components = self.get_arm_components(memory_ap.ap_address().dp)?;
+
+        let cti_base = components
+            .iter()
+            .filter(|component| component.ap.ap_address() == memory_ap.ap_address())
+            .find_map(|component| component.find_component(PeripheralType::Cti))
+            .map(|component| component.component.id().component_address());
+
+        Ok(cti_base)
+    }
+
+    /// Enables or disables cross-halting on the CTI of each of the given cores.
+    fn set_cross_halt(&mut self, cti_cores: &[CtiCore], enabled: bool) -> Result<(), Error> {
+        if cti_cores.is_empty() {
+            return Ok(());
+        }
+
+        let interface = self.get_arm_interface()?;
+
+        for cti_core in cti_cores {
+            let mut memory = interface.memory_interface(cti_core.memory_ap)?;
+            cti::configure_cross_halt(&mut *memory, cti_core.cti_base, enabled)?;
+        }
+
+        Ok(())
+    }
+
+    /// Captures the registers of all cores, the contents of all RAM regions in the memory map of the target,
+    /// and the contents of the given extra memory ranges.
+    ///
+    /// All cores have to be halted, e.g. using [`Session::halt_all`].
+    #[tracing::instrument(skip(self))]
+    pub fn core_dump(&mut self, extra_ranges: &[Range<u64>]) -> Result<CoreDump, Error> {
+        let mut cores = Vec::with_capacity(self.cores.len());
+
+        for index in 0..self.cores.len() {
+            let mut core = self.core(index)?;
+
+            if !core.core_halted()? {
+                return Err(Error::Other(anyhow!(
+                    "Core {} has to be halted to create a core dump",
+                    index
+                )));
+            }
+
+            let mut registers = Vec::new();
+            for register in core.registers().all_registers() {
+                match core.read_core_reg::<RegisterValue>(register) {
+                    Ok(value) => registers.push((register.id(), value)),
+                    // E.g. the FPU registers of a core without FPU.
+                    Err(error) => tracing::debug!(
+                        "Leaving register {} out of the core dump: {:?}",
+                        register.name(),
+                        error
+                    ),
+                }
+            }
+
+            cores.push(CoreDumpCore {
+                index,
+                core_type: core.core_type(),
+                registers,
+            });
+        }
+
+        let ram_ranges = self
+            .target
+            .memory_map
+            .iter()
+            .filter_map(|region| match region {
+                MemoryRegion::Ram(ram) => Some(ram.range.clone()),
+                _ => None,
+            });
+        let ranges = merge_ranges(ram_ranges.chain(extra_ranges.iter().cloned()).collect());
+
+        let mut core = self.core(0)?;
+        let mut memory = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            let mut data = vec![0; (range.end - range.start) as usize];
+            core.read(range.start, &mut data)?;
+
+            memory.push((range.start, data));
+        }
+
+        Ok(CoreDump { cores, memory })
+    }
+
+    /// Creates a core dump using [`Session::core_dump`], and stores it as an ELF core file at `path`.
+    pub fn create_core_dump(
+        &mut self,
+        path: &Path,
+        extra_ranges: &[Range<u64>],
+    ) -> Result<(), Error> {
+        let dump = self.core_dump(extra_ranges)?;
+        dump.store(path)?;
+
+        Ok(())
     }
 
     /// Clears all hardware breakpoints on all cores
@@ -595,6 +1078,14 @@ static_assertions::assert_impl_all!(Session: Send);
 impl Drop for Session {
     #[tracing::instrument(name = "session_drop", skip(self))]
     fn drop(&mut self) {
+        // Restore the original instructions, so the target can continue to run without the debugger.
+        if let Err(err) = { 0..self.cores.len() }.try_for_each(|i| {
+            self.core(i)
+                .and_then(|mut core| core.clear_all_sw_breakpoints())
+        }) {
+            tracing::warn!("Could not clear all software breakpoints: {:?}", err);
+        }
+
         if let Err(err) = { 0..self.cores.len() }.try_for_each(|i| {
             self.core(i)
                 .and_then(|mut core| core.clear_all_hw_breakpoints())
@@ -611,6 +1102,15 @@ impl Drop for Session {
     }
 }
 
+/// Concatenates the data of the trace source with the ATB trace ID `trace_id`.
+fn source_data(sources: Vec<(u8, Vec<u8>)>, trace_id: u8) -> Vec<u8> {
+    sources
+        .into_iter()
+        .filter(|(id, _)| *id == trace_id)
+        .flat_map(|(_, data)| data)
+        .collect()
+}
+
 /// Determine the [Target] from a [TargetSelector].
 ///
 /// If the selector is [TargetSelector::Unspecified], the target will be looked up in the registry.
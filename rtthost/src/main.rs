@@ -1,6 +1,6 @@
 use probe_rs::rtt::{Channels, Rtt, RttChannel, ScanRegion};
-use probe_rs::Permissions;
 use probe_rs::{config::TargetSelector, DebugProbeInfo, Probe};
+use probe_rs::{Permissions, UartConfig};
 
 use clap::Parser;
 use std::io::prelude::*;
@@ -98,6 +98,13 @@ struct Opts {
         value_parser = parse_scan_region,
         help = "Memory region to scan for control block. You can specify either an exact starting address '0x1000' or a range such as '0x0000..0x1000'. Both decimal and hex are accepted.")]
     scan_region: ScanRegion,
+
+    #[clap(
+        long,
+        value_name = "BAUD",
+        help = "Also show the serial console of the target at the given baud rate, if the probe bridges its UART."
+    )]
+    uart: Option<u32>,
 }
 
 fn main() {
@@ -159,6 +166,21 @@ fn run() -> i32 {
         }
     };
 
+    if let Some(baud) = opts.uart {
+        let uart = match session.uart_interface() {
+            Some(uart) => uart,
+            None => {
+                eprintln!("Error: the probe does not bridge the UART of the target.");
+                return 1;
+            }
+        };
+
+        if let Err(err) = uart.enable_uart(&UartConfig::new(baud)) {
+            eprintln!("Error enabling the UART: {err}");
+            return 1;
+        }
+    }
+
     let memory_map = session.target().memory_map.clone();
 
     let mut core = match session.core(0) {
@@ -178,6 +200,7 @@ fn run() -> i32 {
             return 1;
         }
     };
+    drop(core);
 
     if opts.list {
         println!("Up channels:");
@@ -223,6 +246,14 @@ fn run() -> i32 {
     let mut down_buf = vec![];
 
     loop {
+        let mut core = match session.core(0) {
+            Ok(core) => core,
+            Err(err) => {
+                eprintln!("Error attaching to core # 0 {err}");
+                return 1;
+            }
+        };
+
         if let Some(up_channel) = up_channel.as_ref() {
             let count = match up_channel.read(&mut core, up_buf.as_mut()) {
                 Ok(count) => count,
@@ -262,6 +293,25 @@ fn run() -> i32 {
                 }
             }
         }
+        drop(core);
+
+        if opts.uart.is_some() {
+            if let Some(uart) = session.uart_interface() {
+                let data = match uart.read_uart() {
+                    Ok(data) => data,
+                    Err(err) => {
+                        eprintln!("\nError reading from the UART: {err}");
+                        return 1;
+                    }
+                };
+
+                if let Err(err) = stdout().write_all(&data) {
+                    eprintln!("Error writing to stdout: {err}");
+                    return 1;
+                }
+                stdout().flush().ok();
+            }
+        }
     }
 }
 
@@ -2,7 +2,7 @@ use anyhow::{bail, Context, Result};
 use probe_rs::CoreType;
 use probe_rs_target::{
     ArmCoreAccessOptions, Chip, ChipFamily, Core, CoreAccessOptions, MemoryRegion, NvmRegion,
-    RamRegion, TargetDescriptionSource::BuiltIn,
+    RamRegion, ResetType, TargetDescriptionSource::BuiltIn,
 };
 use std::{
     fs::{File, OpenOptions},
@@ -81,6 +81,7 @@ pub fn cmd_elf(
                     core_type: CoreType::Armv6m,
                     core_access_options: CoreAccessOptions::Arm(ArmCoreAccessOptions {
                         ap: 0,
+                        ap_address: None,
                         psel: 0,
                         debug_base: None,
                         cti_base: None,
@@ -103,6 +104,8 @@ pub fn cmd_elf(
                     }),
                 ],
                 flash_algorithms: vec![algorithm_name],
+                default_reset_type: ResetType::default(),
+                jtag: None,
             }],
             flash_algorithms: vec![algorithm],
             source: BuiltIn,
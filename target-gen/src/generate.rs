@@ -11,7 +11,7 @@ use probe_rs::config::{
     RawFlashAlgorithm,
 };
 use probe_rs::{Architecture, CoreType};
-use probe_rs_target::{ArmCoreAccessOptions, CoreAccessOptions, RiscvCoreAccessOptions};
+use probe_rs_target::{ArmCoreAccessOptions, CoreAccessOptions, ResetType, RiscvCoreAccessOptions};
 use tokio::runtime::Builder;
 
 pub(crate) enum Kind<'a, T>
@@ -145,6 +145,8 @@ where
             cores,
             memory_map: get_mem_map(&device),
             flash_algorithms: flash_algorithm_names,
+            default_reset_type: ResetType::default(),
+            jtag: None,
         });
     }
 
@@ -163,6 +165,7 @@ fn create_core(processor: &Processor) -> Result<ProbeCore> {
         core_access_options: match core_type.architecture() {
             Architecture::Arm => CoreAccessOptions::Arm(ArmCoreAccessOptions {
                 ap: processor.ap,
+                ap_address: None,
                 psel: 0,
                 debug_base: None,
                 cti_base: None,